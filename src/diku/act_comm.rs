@@ -1,15 +1,541 @@
-
+use std::rc::Rc;
 
 use diku::comm::{act, send_to_char};
+use diku::constants::{MAX_HISTORY, MAX_IGNORE, MAX_SPAM};
+use diku::handler::{fname, get_char_room_vis, get_char_vis};
+use diku::interpreter::one_argument;
 use diku::structs::*;
 
-pub fn do_say(ch: &CharData, argument: &str, _cmd: i32) {
+const CHANNEL_NAMES: &'static [(&'static str, Channel)] = &[
+    ("say", Channel::Say),
+    ("emote", Channel::Emote),
+    ("tell", Channel::Tell),
+    ("whisper", Channel::Whisper),
+    ("shout", Channel::Shout),
+    ("gossip", Channel::Gossip),
+    ];
+
+fn channel_name(channel: Channel) -> &'static str {
+    CHANNEL_NAMES.iter().find(|&&(_, c)| c == channel).unwrap().0
+}
+
+fn find_channel(arg: &str) -> Option<Channel> {
+    CHANNEL_NAMES.iter().find(|&&(name, _)| name.starts_with(arg)).map(|&(_, channel)| channel)
+}
+
+// The bit that switches a channel off; talk in the room can't be switched off
+fn channel_flag(channel: Channel) -> ChannelFlags {
+    match channel {
+        Channel::Tell => ChannelFlags::CHAN_TELL,
+        Channel::Shout => ChannelFlags::CHAN_SHOUT,
+        Channel::Gossip => ChannelFlags::CHAN_GOSSIP,
+        _ => ChannelFlags::empty(),
+    }
+}
+
+// Does to hear ch on this channel?
+fn listens(to: &CharData, ch: &CharData, channel: Channel) -> bool {
+    let channels = to.channels.borrow();
+    to.awake() && !channels.off.intersects(channel_flag(channel)) &&
+        !channels.ignore.contains(&ch.get_name().to_lowercase())
+}
+
+fn turned_off(ch: &CharData, channel: Channel) -> bool {
+    if ch.channels.borrow().off.intersects(channel_flag(channel)) {
+        send_to_char(&format!("You have {} turned off.\n\r", channel_name(channel)), ch);
+        true
+    } else {
+        false
+    }
+}
+
+// Players that keep talking on the long range channels are held back
+fn spamming(ch: &CharData) -> bool {
+    if ch.is_npc() {
+        return false;
+    }
+
+    let mut channels = ch.channels.borrow_mut();
+    if channels.spam >= MAX_SPAM {
+        send_to_char("You are talking too much, take a breather.\n\r", ch);
+        true
+    } else {
+        channels.spam += 1;
+        false
+    }
+}
+
+pub fn spam_update(game: &Game) {
+    for ch in game.character_list.borrow().iter().filter(|ch| !ch.is_npc()) {
+        let mut channels = ch.channels.borrow_mut();
+        channels.spam = channels.spam.saturating_sub(1);
+    }
+}
+
+// Passes a message on to a listener, who can review it later. The words are
+// never run through act(), so players can't slip $-codes into them.
+fn hear(to: &CharData, channel: Channel, messg: String) {
+    {
+        let mut channels = to.channels.borrow_mut();
+        let history = &mut channels.history[channel];
+        if history.len() >= MAX_HISTORY {
+            history.pop_front();
+        }
+        history.push_back(messg.clone());
+    }
+    send_to_char(&messg, to);
+}
+
+fn talk_to_room(ch: &Rc<CharData>, channel: Channel, messg: &str) {
+    let people: Vec<Rc<CharData>> = ch.in_room.borrow().people.borrow().clone();
+    for to in people.iter().filter(|to| !Rc::ptr_eq(to, ch) && listens(to, ch, channel)) {
+        hear(to, channel, format!("{} {}", to.pers(ch), messg));
+    }
+}
+
+// Everyone playing that might hear a shout or gossip
+fn listeners(ch: &Rc<CharData>, game: &Game) -> Vec<Rc<CharData>> {
+    game.descriptor_list.borrow().iter()
+        .map(|d| d.borrow())
+        .filter(|d| d.connected == ConnectionMode::Plying)
+        .filter_map(|d| d.character.clone())
+        .filter(|to| !Rc::ptr_eq(to, ch) && !to.specials.borrow().act.contains(SpecialActFlags::PLR_NOSHOUT))
+        .collect()
+}
+
+// Slur the words of someone that has had too much to drink
+fn drunk_speech(argument: &str, drunk: i8, game: &Game) -> String {
+    let mut buf = String::with_capacity(argument.len() * 2);
+
+    for c in argument.chars() {
+        match c {
+            's' | 'S' => {
+                buf.push(c);
+                buf.push('h');
+            }
+            ' ' if game.number(0, 48) < drunk as i32 => buf.push_str(" *hic* "),
+            _ if c.is_alphabetic() && game.number(0, 96) < drunk as i32 => {
+                buf.push(c);
+                buf.push(c);
+            }
+            _ => buf.push(c),
+        }
+    }
+
+    buf
+}
+
+pub fn do_say(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
     let argument = argument.trim();
 
     if argument.is_empty() {
-        send_to_char("Yes, but WHAT do you want to say?\n\r", ch);
+        send_to_char("Yes, but WHAT do you want to say?\n\r", &ch);
     } else {
-        let buf = format!("$n says '{}'", argument);
-        act(&buf, false, ch, None, None, None, None, VictimType::ToRoom);
+        let drunk = ch.get_cond(Condition::Drunk);
+        let argument = if drunk > 10 {
+            drunk_speech(argument, drunk, game)
+        } else {
+            String::from(argument)
+        };
+
+        talk_to_room(&ch, Channel::Say, &format!("says '{}'", argument));
+    }
+}
+
+pub fn do_emote(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    if !ch.is_npc() && ch.specials.borrow().act.contains(SpecialActFlags::PLR_NOEMOTE) {
+        send_to_char("You can't show your emotions!!\n\r", &ch);
+        return;
+    }
+
+    let argument = argument.trim();
+
+    if argument.is_empty() {
+        send_to_char("Yes.. But what?\n\r", &ch);
+    } else {
+        talk_to_room(&ch, Channel::Emote, argument);
+        send_to_char("Ok.\n\r", &ch);
+    }
+}
+
+pub fn do_shout(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if !ch.is_npc() && ch.specials.borrow().act.contains(SpecialActFlags::PLR_NOSHOUT) {
+        send_to_char("You can't shout!!\n\r", &ch);
+        return;
+    }
+
+    let argument = argument.trim();
+
+    if argument.is_empty() {
+        send_to_char("Shout? Yes! Fine! Shout we must, but WHAT??\n\r", &ch);
+    } else if !turned_off(&ch, Channel::Shout) && !spamming(&ch) {
+        send_to_char("Ok.\n\r", &ch);
+        // a shout carries no further than the zone
+        let zone = ch.in_room.borrow().zone;
+        for to in listeners(&ch, game) {
+            if to.in_room.borrow().zone == zone && listens(&to, &ch, Channel::Shout) {
+                hear(&to, Channel::Shout, format!("{} shouts '{}'", to.pers(&ch), argument));
+            }
+        }
+    }
+}
+
+pub fn do_gossip(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if !ch.is_npc() && ch.specials.borrow().act.contains(SpecialActFlags::PLR_NOSHOUT) {
+        send_to_char("You can't gossip!!\n\r", &ch);
+        return;
+    }
+
+    let argument = argument.trim();
+
+    if argument.is_empty() {
+        send_to_char("Gossip? Yes, but WHAT??\n\r", &ch);
+    } else if !turned_off(&ch, Channel::Gossip) && !spamming(&ch) {
+        send_to_char("Ok.\n\r", &ch);
+        for to in listeners(&ch, game) {
+            if listens(&to, &ch, Channel::Gossip) {
+                hear(&to, Channel::Gossip, format!("{} gossips '{}'", to.pers(&ch), argument));
+            }
+        }
+    }
+}
+
+pub fn do_tell(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if !ch.is_npc() && ch.specials.borrow().act.contains(SpecialActFlags::PLR_NOTELL) {
+        send_to_char("Your message didn't get through!!\n\r", &ch);
+        return;
+    }
+
+    let (name, message) = one_argument(argument);
+    let message = message.trim();
+
+    if name.is_empty() || message.is_empty() {
+        send_to_char("Who do you wish to tell what??\n\r", &ch);
+        return;
+    }
+    if turned_off(&ch, Channel::Tell) {
+        return;
+    }
+
+    match get_char_vis(&ch, &name, game) {
+        None => send_to_char("No-one by that name here..\n\r", &ch),
+        Some(ref vict) if Rc::ptr_eq(vict, &ch) => send_to_char("You try to tell yourself something.\n\r", &ch),
+        Some(ref vict) if !listens(vict, &ch, Channel::Tell) =>
+            act("$E can't hear you.", false, &ch, None, Some(vict), None, None, VictimType::ToChar),
+        Some(vict) => if !spamming(&ch) {
+            hear(&vict, Channel::Tell, format!("{} tells you '{}'", vict.pers(&ch), message));
+            vict.channels.borrow_mut().reply_to = Some(fname(&ch.get_name()).to_lowercase());
+            send_to_char("Ok.\n\r", &ch);
+        }
+    }
+}
+
+pub fn do_reply(ch: Rc<CharData>, argument: &str, cmd: i32, game: &Game) {
+    let reply_to = ch.channels.borrow().reply_to.clone();
+    match reply_to {
+        Some(name) => do_tell(ch, &format!("{} {}", name, argument.trim()), cmd, game),
+        None => send_to_char("You have no-one to reply to.\n\r", &ch),
+    }
+}
+
+pub fn do_whisper(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (name, message) = one_argument(argument);
+    let message = message.trim();
+
+    if name.is_empty() || message.is_empty() {
+        send_to_char("Who do you want to whisper to.. and what??\n\r", &ch);
+        return;
+    }
+
+    match get_char_room_vis(&ch, &name) {
+        None => send_to_char("No-one by that name here..\n\r", &ch),
+        Some(ref vict) if Rc::ptr_eq(vict, &ch) => {
+            act("$n whispers quietly to $mself.", false, &ch, None, None, None, None, VictimType::ToRoom);
+            send_to_char("You can't seem to get your mouth close enough to your ear...\n\r", &ch);
+        },
+        Some(ref vict) if !listens(vict, &ch, Channel::Whisper) =>
+            act("$E can't hear you.", false, &ch, None, Some(vict), None, None, VictimType::ToChar),
+        Some(vict) => {
+            hear(&vict, Channel::Whisper, format!("{} whispers to you, '{}'", vict.pers(&ch), message));
+            send_to_char("Ok.\n\r", &ch);
+            act("$n whispers something to $N.", false, &ch, None, Some(&vict), None, None, VictimType::ToNotVict);
+        }
+    }
+}
+
+// Switches tell, shout or gossip on or off
+pub fn do_channels(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (arg, _) = one_argument(argument);
+
+    if arg.is_empty() {
+        let mut buf = String::from("Your channels:\n\r");
+        for &channel in &[Channel::Tell, Channel::Shout, Channel::Gossip] {
+            let on = !ch.channels.borrow().off.intersects(channel_flag(channel));
+            buf.push_str(&format!("  {:<7}{}\n\r", channel_name(channel), if on { "on" } else { "off" }));
+        }
+        send_to_char(&buf, &ch);
+        return;
+    }
+
+    let channel = match find_channel(&arg) {
+        Some(channel) if !channel_flag(channel).is_empty() => channel,
+        _ => {
+            send_to_char("You can't switch that off.\n\r", &ch);
+            return;
+        }
+    };
+
+    let mut channels = ch.channels.borrow_mut();
+    channels.off.toggle(channel_flag(channel));
+    if channels.off.intersects(channel_flag(channel)) {
+        send_to_char(&format!("You will no longer hear {}.\n\r", channel_name(channel)), &ch);
+    } else {
+        send_to_char(&format!("You will now hear {} again.\n\r", channel_name(channel)), &ch);
+    }
+}
+
+pub fn do_ignore(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (name, _) = one_argument(argument);
+    let mut channels = ch.channels.borrow_mut();
+
+    if name.is_empty() {
+        if channels.ignore.is_empty() {
+            send_to_char("You are not ignoring anyone.\n\r", &ch);
+        } else {
+            send_to_char(&format!("You are ignoring: {}.\n\r", channels.ignore.join(", ")), &ch);
+        }
+    } else if name == ch.get_name().to_lowercase() {
+        send_to_char("Ignoring yourself won't help.\n\r", &ch);
+    } else if let Some(pos) = channels.ignore.iter().position(|i| *i == name) {
+        channels.ignore.remove(pos);
+        send_to_char(&format!("You no longer ignore {}.\n\r", name), &ch);
+    } else if channels.ignore.len() >= MAX_IGNORE {
+        send_to_char("You can't ignore any more people.\n\r", &ch);
+    } else {
+        send_to_char(&format!("You now ignore {}.\n\r", name), &ch);
+        channels.ignore.push(name);
+    }
+}
+
+// Shows what was heard lately on a channel
+pub fn do_history(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (arg, _) = one_argument(argument);
+
+    if arg.is_empty() {
+        send_to_char("Which channel do you want to review?\n\r", &ch);
+        return;
+    }
+
+    let channel = match find_channel(&arg) {
+        Some(channel) => channel,
+        None => {
+            send_to_char("There is no such channel.\n\r", &ch);
+            return;
+        }
+    };
+
+    let channels = ch.channels.borrow();
+    if channels.history[channel].is_empty() {
+        send_to_char("You haven't heard anything there lately.\n\r", &ch);
+    } else {
+        let mut buf = String::new();
+        for messg in channels.history[channel].iter() {
+            buf.push_str(messg);
+            buf.push_str("\n\r");
+        }
+        send_to_char(&buf, &ch);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::char_to_store;
+    use diku::testutil::*;
+
+    fn player(name: &str, sex: Sex, room: &Rc<RoomData>, game: &Game) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        ch.player.borrow_mut().sex = sex;
+        game.descriptor_list.borrow_mut().push(Rc::clone(ch.desc.borrow().as_ref().unwrap()));
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+        ch
+    }
+
+    #[test]
+    fn say_and_emote_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+        let bob = player("Bob", Sex::Male, &room, &game);
+
+        // $-codes typed by a player are not interpreted
+        do_say(Rc::clone(&alice), " I owe $N a $p", 0, &game);
+        assert!(output(&alice).is_empty());
+        assert_eq!(output(&bob), vec!["Alice says 'I owe $N a $p'"]);
+
+        do_emote(Rc::clone(&alice), " waves.", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+        assert_eq!(output(&bob), vec!["Alice waves."]);
+
+        alice.specials.borrow_mut().act.insert(SpecialActFlags::PLR_NOEMOTE);
+        do_emote(Rc::clone(&alice), " waves.", 0, &game);
+        assert_eq!(output(&alice), vec!["You can't show your emotions!!\n\r"]);
+
+        do_ignore(Rc::clone(&bob), " alice", 0, &game);
+        assert_eq!(output(&bob), vec!["You now ignore alice.\n\r"]);
+        do_say(Rc::clone(&alice), " hello?", 0, &game);
+        assert!(output(&bob).is_empty());
+    }
+
+    #[test]
+    fn tell_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+        let bob = player("Bob", Sex::Male, &room, &game);
+
+        do_tell(Rc::clone(&alice), " bob meet me at the fountain", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+        assert_eq!(output(&bob), vec!["Alice tells you 'meet me at the fountain'"]);
+
+        do_reply(Rc::clone(&bob), " on my way", 0, &game);
+        assert_eq!(output(&bob), vec!["Ok.\n\r"]);
+        assert_eq!(output(&alice), vec!["Bob tells you 'on my way'"]);
+        do_reply(Rc::clone(&alice), " good", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+        assert_eq!(output(&bob), vec!["Alice tells you 'good'"]);
+
+        do_tell(Rc::clone(&alice), " carol hi", 0, &game);
+        assert_eq!(output(&alice), vec!["No-one by that name here..\n\r"]);
+
+        bob.channels.borrow_mut().off.insert(ChannelFlags::CHAN_TELL);
+        do_tell(Rc::clone(&alice), " bob hi", 0, &game);
+        assert_eq!(output(&alice), vec!["he can't hear you."]);
+        do_tell(Rc::clone(&bob), " alice hi", 0, &game);
+        assert_eq!(output(&bob), vec!["You have tell turned off.\n\r"]);
+
+        alice.specials.borrow_mut().act.insert(SpecialActFlags::PLR_NOTELL);
+        do_tell(Rc::clone(&alice), " bob hi", 0, &game);
+        assert_eq!(output(&alice), vec!["Your message didn't get through!!\n\r"]);
+
+        let carol = test_char("Carol", &room);
+        do_reply(Rc::clone(&carol), " hi", 0, &game);
+        assert_eq!(output(&carol), vec!["You have no-one to reply to.\n\r"]);
+    }
+
+    #[test]
+    fn whisper_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+        let bob = player("Bob", Sex::Male, &room, &game);
+        let carol = player("Carol", Sex::Female, &room, &game);
+
+        do_whisper(Rc::clone(&alice), " bob the password is swordfish", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+        assert_eq!(output(&bob), vec!["Alice whispers to you, 'the password is swordfish'"]);
+        assert_eq!(output(&carol), vec!["Alice whispers something to Bob."]);
+    }
+
+    #[test]
+    fn shout_and_gossip_test() {
+        let game = test_game();
+        let here = test_room(3001);
+        let mut far = Rc::try_unwrap(test_room(4001)).ok().unwrap();
+        far.zone = 1;
+        let far = Rc::new(far);
+        let alice = player("Alice", Sex::Female, &here, &game);
+        let bob = player("Bob", Sex::Male, &test_room(3002), &game);
+        let carol = player("Carol", Sex::Female, &far, &game);
+
+        do_shout(Rc::clone(&alice), " fire!", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+        assert_eq!(output(&bob), vec!["Alice shouts 'fire!'"]);
+        assert!(output(&carol).is_empty());
+
+        do_gossip(Rc::clone(&alice), " anyone seen my cat?", 0, &game);
+        assert_eq!(output(&bob), vec!["Alice gossips 'anyone seen my cat?'"]);
+        assert_eq!(output(&carol), vec!["Alice gossips 'anyone seen my cat?'"]);
+
+        do_channels(Rc::clone(&carol), " gossip", 0, &game);
+        assert_eq!(output(&carol), vec!["You will no longer hear gossip.\n\r"]);
+        do_channels(Rc::clone(&carol), "", 0, &game);
+        assert_eq!(output(&carol), vec!["Your channels:\n\r  tell   on\n\r  shout  on\n\r  gossip off\n\r"]);
+        do_gossip(Rc::clone(&alice), " hello?", 0, &game);
+        assert!(output(&carol).is_empty());
+        output(&alice);
+        do_gossip(Rc::clone(&carol), " hello?", 0, &game);
+        assert_eq!(output(&carol), vec!["You have gossip turned off.\n\r"]);
+
+        alice.specials.borrow_mut().act.insert(SpecialActFlags::PLR_NOSHOUT);
+        do_gossip(Rc::clone(&alice), " hello?", 0, &game);
+        assert_eq!(output(&alice), vec!["You can't gossip!!\n\r"]);
+    }
+
+    #[test]
+    fn spam_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+
+        for _ in 0..MAX_SPAM {
+            do_gossip(Rc::clone(&alice), " buy my sword!", 0, &game);
+        }
+        output(&alice);
+        do_gossip(Rc::clone(&alice), " buy my sword!", 0, &game);
+        assert_eq!(output(&alice), vec!["You are talking too much, take a breather.\n\r"]);
+
+        spam_update(&game);
+        do_gossip(Rc::clone(&alice), " buy my sword!", 0, &game);
+        assert_eq!(output(&alice), vec!["Ok.\n\r"]);
+    }
+
+    #[test]
+    fn history_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+
+        do_history(Rc::clone(&alice), " gossip", 0, &game);
+        assert_eq!(output(&alice), vec!["You haven't heard anything there lately.\n\r"]);
+
+        for i in 0..MAX_HISTORY + 2 {
+            hear(&alice, Channel::Gossip, format!("Bob gossips '{}'", i));
+        }
+        hear(&alice, Channel::Tell, String::from("Bob tells you 'psst'"));
+        output(&alice);
+
+        do_history(Rc::clone(&alice), " t", 0, &game);
+        assert_eq!(output(&alice), vec!["Bob tells you 'psst'\n\r"]);
+        do_history(Rc::clone(&alice), " gossip", 0, &game);
+        let history = output(&alice).remove(0);
+        assert!(history.starts_with("Bob gossips '2'\n\r"));
+        assert!(history.ends_with("Bob gossips '21'\n\r"));
+        assert_eq!(history.matches("\n\r").count(), MAX_HISTORY);
+    }
+
+    #[test]
+    fn ignore_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let alice = player("Alice", Sex::Female, &room, &game);
+
+        do_ignore(Rc::clone(&alice), " Alice", 0, &game);
+        assert_eq!(output(&alice), vec!["Ignoring yourself won't help.\n\r"]);
+        for i in 0..MAX_IGNORE {
+            do_ignore(Rc::clone(&alice), &format!(" pest{}", i), 0, &game);
+        }
+        output(&alice);
+        do_ignore(Rc::clone(&alice), " bob", 0, &game);
+        assert_eq!(output(&alice), vec!["You can't ignore any more people.\n\r"]);
+        do_ignore(Rc::clone(&alice), " pest0", 0, &game);
+        assert_eq!(output(&alice), vec!["You no longer ignore pest0.\n\r"]);
+
+        // The ignore list and channel switches are kept in the player file
+        alice.channels.borrow_mut().off.insert(ChannelFlags::CHAN_SHOUT);
+        let st = char_to_store(&alice);
+        assert_eq!(st.channels, ChannelFlags::CHAN_SHOUT.bits());
+        assert_eq!(&st.ignore[0][..6], b"pest1\0");
+        assert_eq!(st.ignore[MAX_IGNORE - 1], [0; 20]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,304 @@
+use std::cmp::min;
+use std::io::prelude::*;
+use std::io::{BufReader, SeekFrom};
+use std::rc::Rc;
+
+use diku::comm::send_to_char;
+use diku::constants::{MONTH_NAME, WEEKDAYS};
+use diku::structs::*;
+use diku::weather::{local_sky, zone_climate};
+
+// How well a help keyword matches what was asked for; lower is better
+fn help_rank(keyword: &str, arg: &str) -> Option<usize> {
+    if keyword == arg {
+        Some(0)
+    } else if keyword.starts_with(arg) {
+        Some(1)
+    } else if keyword.split_whitespace().any(|word| word.starts_with(arg)) {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+// All help entries matching arg, best match first. An entry is listed once,
+// under its best matching keyword.
+pub fn find_help<'a>(help_index: &'a FilePosTable, arg: &str) -> Vec<(&'a str, u64)> {
+    let mut matches: Vec<(usize, &str, u64)> = help_index.iter()
+        .filter_map(|(keyword, &pos)| help_rank(keyword, arg).map(|rank| (rank, &keyword[..], pos)))
+        .collect();
+    matches.sort_by(|a, b| (a.0, a.1.len(), a.1).cmp(&(b.0, b.1.len(), b.1)));
+
+    let mut entries: Vec<(&str, u64)> = Vec::new();
+    for (_, keyword, pos) in matches {
+        if !entries.iter().any(|&(_, p)| p == pos) {
+            entries.push((keyword, pos));
+        }
+    }
+    entries
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..b.len() + 1).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = if ca == cb { 0 } else { 1 };
+            cur.push(min(min(prev[j + 1] + 1, cur[j] + 1), prev[j] + cost));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+// Keywords close enough to arg to be what was meant, nearest first
+pub fn suggest_help<'a>(help_index: &'a FilePosTable, arg: &str) -> Vec<&'a str> {
+    let limit = 1 + arg.len() / 4;
+    let mut near: Vec<(usize, &str)> = help_index.keys()
+        .map(|keyword| (edit_distance(keyword, arg), &keyword[..]))
+        .filter(|&(distance, _)| distance <= limit)
+        .collect();
+    near.sort();
+    near.into_iter().take(5).map(|(_, keyword)| keyword).collect()
+}
+
+// The topics named on a "See also" line, eg. "See Also : OPEN, CLOSE"
+fn see_also(line: &str) -> Option<Vec<String>> {
+    if !line.to_lowercase().starts_with("see also") {
+        return None;
+    }
+    let topics = match line.find(':') {
+        Some(colon) => &line[colon + 1..],
+        None => &line[8..],
+    };
+    Some(topics.split(',')
+        .flat_map(|topic| topic.split(" or "))
+        .map(|topic| topic.trim().to_string())
+        .filter(|topic| !topic.is_empty())
+        .collect())
+}
+
+// Reads the entry at pos, up to the next '#'. Cross references to topics
+// that have no entry of their own are left out.
+fn read_help_entry<R: Read + Seek>(reader: &mut BufReader<R>, pos: u64, help_index: &FilePosTable) -> String {
+    reader.seek(SeekFrom::Start(pos)).unwrap();
+
+    let mut buf = String::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 || line.starts_with('#') {
+            break;
+        }
+        let line = line.trim_end();
+
+        match see_also(line) {
+            Some(topics) => {
+                let topics: Vec<String> = topics.into_iter()
+                    .filter(|topic| help_index.contains_key(&topic.to_lowercase()))
+                    .collect();
+                if !topics.is_empty() {
+                    buf.push_str(&format!("See also: {}\n\r", topics.join(", ")));
+                }
+            },
+            None => {
+                buf.push_str(line);
+                buf.push_str("\n\r");
+            }
+        }
+    }
+    buf
+}
+
+pub fn do_help(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.desc.borrow().is_none() {
+        return;
+    }
+
+    let arg = argument.trim().trim_matches('"').trim().to_lowercase();
+    if arg.is_empty() {
+        send_to_char(&game.help, &ch);
+        return;
+    }
+
+    let help_f = match game.help_f {
+        Some(ref file) => file,
+        None => {
+            send_to_char("No help available.\n\r", &ch);
+            return;
+        }
+    };
+
+    let entries = find_help(&game.help_index, &arg);
+    if entries.is_empty() {
+        let near = suggest_help(&game.help_index, &arg);
+        if near.is_empty() {
+            send_to_char("There is no help on that word.\n\r", &ch);
+        } else {
+            send_to_char(&format!("There is no help on that word.\n\rDid you mean: {}?\n\r", near.join(", ")), &ch);
+        }
+        return;
+    }
+
+    let mut buf = read_help_entry(&mut BufReader::new(help_f), entries[0].1, &game.help_index);
+    if entries.len() > 1 {
+        let others: Vec<&str> = entries[1..].iter().take(8).map(|&(keyword, _)| keyword).collect();
+        buf.push_str(&format!("\n\rOther matching topics: {}\n\r", others.join(", ")));
+    }
+    send_to_char(&buf, &ch);
+}
+
+const SKY_LOOK: [&'static str; 4] = [
+    "cloudless",
+    "cloudy",
+    "rainy",
+    "lit by flashes of lightning",
+];
+
+pub fn do_weather(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    if !ch.outside() {
+        send_to_char("You have no feeling about the weather at all.\n\r", &ch);
+        return;
+    }
+
+    let room = Rc::clone(&ch.in_room.borrow());
+    let sky = local_sky(&room, game);
+    let look = if sky == Sky::Raining && zone_climate(&room, game) == Climate::Arctic {
+        "snowy"
+    } else {
+        SKY_LOOK[sky as usize]
+    };
+    send_to_char(&format!("The sky is {} and {}.\n\r", look, if game.weather_info.borrow().change >= 0 {
+        "you feel a warm wind from south"
+    } else {
+        "your foot tells you bad weather is due"
+    }), &ch);
+}
+
+pub fn do_time(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    let time_info = &game.time_info;
+
+    let weekday = (35 * time_info.month as usize + time_info.day as usize + 1) % 7; // 35 days in a month
+    send_to_char(&format!("It is {} o'clock {}, on {}\n\r",
+        if time_info.hours % 12 == 0 { 12 } else { time_info.hours % 12 },
+        if time_info.hours >= 12 { "pm" } else { "am" },
+        WEEKDAYS[weekday]), &ch);
+
+    let day = time_info.day + 1;   // day in [1..35]
+    let suf = match day {
+        1 => "st",
+        2 => "nd",
+        3 => "rd",
+        _ if day < 20 => "th",
+        _ if day % 10 == 1 => "st",
+        _ if day % 10 == 2 => "nd",
+        _ if day % 10 == 3 => "rd",
+        _ => "th",
+    };
+    send_to_char(&format!("The {}{} Day of the {}, Year {}.\n\r", day, suf, MONTH_NAME[time_info.month as usize],
+        time_info.year), &ch);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use diku::modify::build_help_index;
+    use diku::testutil::*;
+
+    fn help_game() -> Game {
+        let mut game = test_game();
+        let help_f = File::open("lib/help_table").expect("test help file");
+        game.help_index = build_help_index(&mut BufReader::new(&help_f));
+        game.help_f = Some(help_f);
+        game.help = String::from("Help page.\n\r");
+        game
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        assert_eq!(edit_distance("sneak", "sneak"), 0);
+        assert_eq!(edit_distance("sneek", "sneak"), 1);
+        assert_eq!(edit_distance("", "hide"), 4);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn find_help_test() {
+        let game = help_game();
+        // exact match beats a longer keyword that starts the same way
+        let entries = find_help(&game.help_index, "inn");
+        assert_eq!(entries[0].0, "inn");
+        assert!(entries.iter().all(|&(keyword, _)| keyword != "inns"));
+
+        let entries = find_help(&game.help_index, "cure");
+        assert_eq!(entries.iter().map(|&(keyword, _)| keyword).collect::<Vec<_>>(),
+            vec!["cure blind", "cure light", "cure critic"]);
+
+        assert_eq!(find_help(&game.help_index, "locks")[0].0, "pick locks");
+        assert!(find_help(&game.help_index, "xyzzy").is_empty());
+    }
+
+    #[test]
+    fn help_test() {
+        let game = help_game();
+        let room = test_room(3001);
+        let ch = test_char("Reader", &room);
+
+        do_help(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["Help page.\n\r"]);
+
+        do_help(Rc::clone(&ch), " \"pick locks\"", 0, &game);
+        let page = output(&ch).remove(0);
+        assert!(page.starts_with("\"PICK LOCKS\"\n\r\n\rThieves only.\n\r"));
+        assert!(page.ends_with("See also: OPEN, CLOSE, LOCK, UNLOCK\n\r"));
+
+        do_help(Rc::clone(&ch), " pick locks", 0, &game);
+        assert_eq!(output(&ch).len(), 1);
+
+        do_help(Rc::clone(&ch), " look", 0, &game);
+        assert!(output(&ch)[0].ends_with("See also: EXAMINE, READ\n\r"));
+
+        do_help(Rc::clone(&ch), " sneek", 0, &game);
+        assert_eq!(output(&ch), vec!["There is no help on that word.\n\rDid you mean: sneak?\n\r"]);
+
+        do_help(Rc::clone(&ch), " xyzzyplugh", 0, &game);
+        assert_eq!(output(&ch), vec!["There is no help on that word.\n\r"]);
+    }
+
+    #[test]
+    fn time_test() {
+        let mut game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Sundial", &room);
+
+        game.time_info = TimeInfoData { hours: 0, day: 0, month: 0, year: 603 };
+        do_time(Rc::clone(&ch), "", 0, &game);
+        game.time_info = TimeInfoData { hours: 15, day: 22, month: 9, year: 603 };
+        do_time(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["It is 12 o'clock am, on the Day of the Bull\n\r",
+            "The 1st Day of the Month of Winter, Year 603.\n\r",
+            "It is 3 o'clock pm, on the Day of the Deception\n\r",
+            "The 23rd Day of the Month of the Sun, Year 603.\n\r"]);
+    }
+
+    #[test]
+    fn weather_test() {
+        let game = test_game();
+        let field = test_room(3001);
+        let mut inn = Rc::try_unwrap(test_room(3002)).ok().unwrap();
+        inn.room_flags = RoomFlags::INDOORS;
+        let farmer = test_char("Farmer", &field);
+        let guest = test_char("Guest", &Rc::new(inn));
+
+        do_weather(Rc::clone(&farmer), "", 0, &game);
+        game.weather_info.borrow_mut().sky = Sky::Lightning;
+        game.weather_info.borrow_mut().change = -3;
+        do_weather(Rc::clone(&farmer), "", 0, &game);
+        do_weather(Rc::clone(&guest), "", 0, &game);
+        assert_eq!(output(&farmer), vec!["The sky is cloudless and you feel a warm wind from south.\n\r",
+            "The sky is lit by flashes of lightning and your foot tells you bad weather is due.\n\r"]);
+        assert_eq!(output(&guest), vec!["You have no feeling about the weather at all.\n\r"]);
+    }
+}
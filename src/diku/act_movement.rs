@@ -2,8 +2,9 @@ use std::rc::Rc;
 
 use diku::comm::{act, send_to_char};
 use diku::constants::*;
-use diku::handler::char_from_room;
-use diku::interpreter::special;
+use diku::handler::{char_from_room, char_to_room, get_obj_in_list_vis, isname};
+use diku::interpreter::{argument_interpreter, special};
+use diku::spells::SKILL_PICK_LOCK;
 use diku::structs::*;
 
 pub fn do_simple_move(ch: Rc<CharData>, cmd: Direction, following: bool,
@@ -20,15 +21,15 @@ game: &Game) -> bool {
         return false;
     }
     
-    let need_movement = (MOVEMENT_LOSS[ch.in_room.sector_type as usize] +
-    MOVEMENT_LOSS[game.world[&ch.in_room.dir_option[&cmd].to_room.unwrap()].sector_type as usize]) / 2;
+    let need_movement = (MOVEMENT_LOSS[ch.in_room.borrow().sector_type as usize] +
+    MOVEMENT_LOSS[game.world[&ch.in_room.borrow().dir_option[&cmd].to_room.unwrap()].sector_type as usize]) / 2;
 
-    if ch.in_room.sector_type == SectorType::WaterNoSwim ||
-    game.world[&ch.in_room.dir_option[&cmd].to_room.unwrap()].sector_type == SectorType::WaterNoSwim {
+    if ch.in_room.borrow().sector_type == SectorType::WaterNoSwim ||
+    game.world[&ch.in_room.borrow().dir_option[&cmd].to_room.unwrap()].sector_type == SectorType::WaterNoSwim {
         let mut has_boat = false;
         // See if char is carrying a boat
-        for obj in &ch.carrying {
-            if obj.obj_flags.type_flag == ItemType::Boat {
+        for obj in ch.carrying.borrow().iter() {
+            if obj.get_item_type() == ItemType::Boat {
                 has_boat = true;
             }
         }
@@ -56,9 +57,185 @@ game: &Game) -> bool {
         act(&tmp, true, &ch, None, None, None, None, VictimType::ToRoom);
     }
 
-    let was_in = &ch.in_room;
+    let to_room = ch.in_room.borrow().dir_option[&cmd].to_room.unwrap();
 
     char_from_room(&ch);
+    char_to_room(&ch, &game.world[&to_room]);
+
+    if !ch.is_affected(AffectedFlags::AFF_SNEAK) {
+        act("$n has arrived.", true, &ch, None, None, None, None, VictimType::ToRoom);
+    }
 
     return true;
-}
\ No newline at end of file
+}
+
+// The door named by type, optionally in the direction dir
+fn find_door(ch: &CharData, door_type: &str, dir: &str) -> Option<Direction> {
+    if !dir.is_empty() {
+        // a direction was specified
+        let door = match DIRS.iter().position(|d| d.starts_with(dir)) {
+            Some(door) => Direction::from(door as u8),
+            None => {
+                send_to_char("That's not a direction.\n\r", ch);
+                return None;
+            }
+        };
+
+        match ch.exit(door) {
+            Some(ref exit) if !exit.keyword.is_empty() && !isname(door_type, &exit.keyword) => {
+                send_to_char(&format!("I see no {} there.\n\r", door_type), ch);
+                None
+            }
+            Some(_) => Some(door),
+            None => {
+                send_to_char("I really don't see how you can close anything there.\n\r", ch);
+                None
+            }
+        }
+    } else {
+        // try to locate the keyword
+        let door = (0..6).map(Direction::from).find(|&door| ch.exit(door)
+            .map_or(false, |exit| !exit.keyword.is_empty() && isname(door_type, &exit.keyword)));
+        if door.is_none() {
+            send_to_char(&format!("I see no {} here.\n\r", door_type), ch);
+        }
+        door
+    }
+}
+
+pub fn do_pick(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let percent = game.number(1, 101); // 101% is a complete failure
+    if percent > ch.skills.borrow()[SKILL_PICK_LOCK as usize].learned as i32 +
+            DEX_APP_SKILL[ch.get_dex() as usize].p_locks as i32 {
+        send_to_char("You failed to pick the lock.\n\r", &ch);
+        return;
+    }
+
+    let (door_type, dir) = argument_interpreter(argument);
+
+    if door_type.is_empty() {
+        send_to_char("Pick what?\n\r", &ch);
+        return;
+    }
+
+    let obj = get_obj_in_list_vis(&ch, &door_type, &ch.carrying.borrow())
+        .or_else(|| get_obj_in_list_vis(&ch, &door_type, &ch.in_room.borrow().contents.borrow()));
+    if let Some(obj) = obj {
+        // this is an object
+        let mut flags = obj.obj_flags.borrow_mut();
+        let cont = ContainerFlags::from_bits_truncate(flags.value[1] as u8);
+        if flags.type_flag != ItemType::Container {
+            send_to_char("That's not a container.\n\r", &ch);
+        } else if !cont.contains(ContainerFlags::CONT_CLOSED) {
+            send_to_char("Silly - it isn't even closed!\n\r", &ch);
+        } else if flags.value[2] < 0 {
+            send_to_char("Odd - you can't seem to find a keyhole.\n\r", &ch);
+        } else if !cont.contains(ContainerFlags::CONT_LOCKED) {
+            send_to_char("Oho! This thing is NOT locked!\n\r", &ch);
+        } else if cont.contains(ContainerFlags::CONT_PICKPROOF) {
+            send_to_char("It resists your attempts at picking it.\n\r", &ch);
+        } else {
+            flags.value[1] = (cont - ContainerFlags::CONT_LOCKED).bits() as i32;
+            drop(flags);
+            send_to_char("*Click*\n\r", &ch);
+            act("$n fiddles with $p.", false, &ch, Some(&obj), None, None, None, VictimType::ToRoom);
+        }
+        return;
+    }
+
+    let door = match find_door(&ch, &door_type, &dir) {
+        Some(door) => door,
+        None => return,
+    };
+    let exit = ch.exit(door).unwrap();
+    let exit_info = *exit.exit_info.borrow();
+
+    if !exit_info.contains(ExitFlags::EX_ISDOOR) {
+        send_to_char("That's absurd.\n\r", &ch);
+    } else if !exit_info.contains(ExitFlags::EX_CLOSED) {
+        send_to_char("You realize that the door is already open.\n\r", &ch);
+    } else if exit.key.is_none() {
+        send_to_char("You can't seem to spot any lock to pick.\n\r", &ch);
+    } else if !exit_info.contains(ExitFlags::EX_LOCKED) {
+        send_to_char("Oh.. it wasn't locked at all.\n\r", &ch);
+    } else if exit_info.contains(ExitFlags::EX_PICKPROOF) {
+        send_to_char("You seem to be unable to pick this lock.\n\r", &ch);
+    } else {
+        exit.exit_info.borrow_mut().remove(ExitFlags::EX_LOCKED);
+        if !exit.keyword.is_empty() {
+            act("$n skillfully picks the lock of the $F.", false, &ch, None, None, None, Some(&exit.keyword),
+                VictimType::ToRoom);
+        } else {
+            act("$n picks the lock.", true, &ch, None, None, None, None, VictimType::ToRoom);
+        }
+        send_to_char("The lock quickly yields to your skills.\n\r", &ch);
+
+        // now for unlocking the other side, too
+        let back = exit.to_room.and_then(|room| game.world.get(&room))
+            .and_then(|room| room.dir_option.get(&REV_DIR[door as usize]));
+        if let Some(back) = back {
+            if back.to_room == Some(ch.in_room.borrow().number) {
+                back.exit_info.borrow_mut().remove(ExitFlags::EX_LOCKED);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::clear_object;
+    use diku::handler::obj_to_char;
+    use diku::testutil::*;
+
+    #[test]
+    fn pick_test() {
+        let mut game = test_game();
+        let door = ExitFlags::EX_ISDOOR | ExitFlags::EX_CLOSED | ExitFlags::EX_LOCKED;
+        let inside = with_exit(test_room(3001), Direction::North, 3002, door);
+        let outside = with_exit(test_room(3002), Direction::South, 3001, door);
+        game.world.insert(3001, Rc::clone(&inside));
+        game.world.insert(3002, Rc::clone(&outside));
+
+        let ch = test_char("Shadow", &inside);
+        ch.tmpabilities.borrow_mut().dex = 18;
+        ch.skills.borrow_mut()[SKILL_PICK_LOCK as usize].learned = 100;
+
+        do_pick(Rc::clone(&ch), "", 0, &game);
+        do_pick(Rc::clone(&ch), "door", 0, &game);
+        do_pick(Rc::clone(&ch), "gate up", 0, &game);
+        do_pick(Rc::clone(&ch), "gate sideways", 0, &game);
+        assert_eq!(output(&ch), vec!["Pick what?\n\r", "I see no door here.\n\r",
+            "I really don't see how you can close anything there.\n\r", "That's not a direction.\n\r"]);
+
+        do_pick(Rc::clone(&ch), "gate north", 0, &game);
+        assert_eq!(output(&ch), vec!["The lock quickly yields to your skills.\n\r"]);
+        assert_eq!(*inside.dir_option[&Direction::North].exit_info.borrow(),
+            ExitFlags::EX_ISDOOR | ExitFlags::EX_CLOSED);
+        assert_eq!(*outside.dir_option[&Direction::South].exit_info.borrow(),
+            ExitFlags::EX_ISDOOR | ExitFlags::EX_CLOSED);
+
+        do_pick(Rc::clone(&ch), "gate", 0, &game);
+        assert_eq!(output(&ch), vec!["Oh.. it wasn't locked at all.\n\r"]);
+
+        let mut chest = clear_object();
+        chest.name = String::from("chest");
+        chest.short_description = String::from("a chest");
+        chest.obj_flags.borrow_mut().type_flag = ItemType::Container;
+        chest.obj_flags.borrow_mut().value = [50, (ContainerFlags::CONT_CLOSED | ContainerFlags::CONT_LOCKED |
+            ContainerFlags::CONT_PICKPROOF).bits() as i32, 3001, 0];
+        let chest = Rc::new(chest);
+        obj_to_char(Rc::clone(&chest), &ch);
+        do_pick(Rc::clone(&ch), "chest", 0, &game);
+        chest.obj_flags.borrow_mut().value[1] = (ContainerFlags::CONT_CLOSED | ContainerFlags::CONT_LOCKED).bits() as i32;
+        do_pick(Rc::clone(&ch), "chest", 0, &game);
+        do_pick(Rc::clone(&ch), "chest", 0, &game);
+        assert_eq!(output(&ch), vec!["It resists your attempts at picking it.\n\r", "*Click*\n\r",
+            "Oho! This thing is NOT locked!\n\r"]);
+
+        // Without any training the lock always wins
+        ch.skills.borrow_mut()[SKILL_PICK_LOCK as usize].learned = 0;
+        do_pick(Rc::clone(&ch), "chest", 0, &game);
+        assert_eq!(output(&ch), vec!["You failed to pick the lock.\n\r"]);
+    }
+}
@@ -0,0 +1,496 @@
+use std::cmp::min;
+use std::ptr;
+use std::rc::Rc;
+
+use diku::comm::{act, send_to_char};
+use diku::constants::LIQUIDS;
+use diku::handler::{affect_join, extract_obj, get_obj_in_list_vis, weight_change_object};
+use diku::interpreter::{argument_interpreter, one_argument};
+use diku::limits::gain_condition;
+use diku::spells::SPELL_POISON;
+use diku::structs::*;
+
+fn liquid(obj: &ObjData) -> &'static LiquidType {
+    LIQUIDS.get(obj.obj_flags.borrow().value[2] as usize).unwrap_or(&LIQUIDS[Liquid::Water as usize])
+}
+
+// Drink containers that can't be picked up are fountains, they never run dry
+fn is_fountain(obj: &ObjData) -> bool {
+    obj.get_item_type() == ItemType::DrinkCon && !obj.can_wear(WearFlags::ITEM_TAKE)
+}
+
+// a container that has been emptied holds nothing and is no longer poisoned
+fn empty_drinkcon(obj: &ObjData) {
+    let mut flags = obj.obj_flags.borrow_mut();
+    flags.value[1] = 0;
+    flags.value[2] = 0;
+    flags.value[3] = 0;
+}
+
+fn poison(ch: &CharData, duration: i16) {
+    affect_join(ch, &AffectedType {
+        spell_type: SPELL_POISON,
+        duration,
+        modifier: 0,
+        location: AbilityModifier::None,
+        bitvector: AffectedFlags::AFF_POISON,
+    }, false, false);
+}
+
+pub fn do_drink(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (buf, _) = one_argument(argument);
+
+    let temp = get_obj_in_list_vis(&ch, &buf, &ch.carrying.borrow())
+        .or_else(|| get_obj_in_list_vis(&ch, &buf, &ch.in_room.borrow().contents.borrow()));
+    let temp = match temp {
+        Some(temp) => temp,
+        None => {
+            act("You can't find it!", false, &ch, None, None, None, None, VictimType::ToChar);
+            return;
+        }
+    };
+
+    if temp.get_item_type() != ItemType::DrinkCon {
+        act("You can't drink from that!", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    if ch.get_cond(Condition::Drunk) > 10 && ch.get_cond(Condition::Thirst) > 0 {
+        // drunk
+        act("You simply fail to reach your mouth!", false, &ch, None, None, None, None, VictimType::ToChar);
+        act("$n tried to drink but missed $s mouth!", true, &ch, None, None, None, None, VictimType::ToRoom);
+        return;
+    }
+
+    if ch.get_cond(Condition::Full) > 20 && ch.get_cond(Condition::Thirst) > 0 {
+        // Stomach full
+        act("Your stomach can't contain anymore!", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    let left = temp.obj_flags.borrow().value[1];
+    if left <= 0 {
+        act("It's empty already.", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    let liq = liquid(&temp);
+    act(&format!("$n drinks {} from $p", liq.name), true, &ch, Some(&temp), None, None, None,
+        VictimType::ToRoom);
+    send_to_char(&format!("You drink the {}.\n\r", liq.name), &ch);
+
+    let amount = if liq.drunk > 0 {
+        (25 - ch.get_cond(Condition::Thirst) as i32) / liq.drunk as i32
+    } else {
+        game.number(3, 10)
+    };
+    let amount = min(amount, left);
+
+    gain_condition(&ch, Condition::Drunk, (liq.drunk as i32 * amount / 4) as i8);
+    gain_condition(&ch, Condition::Full, (liq.full as i32 * amount / 4) as i8);
+    gain_condition(&ch, Condition::Thirst, (liq.thirst as i32 * amount / 4) as i8);
+
+    if ch.get_cond(Condition::Drunk) > 10 {
+        act("You feel drunk.", false, &ch, None, None, None, None, VictimType::ToChar);
+    }
+
+    if ch.get_cond(Condition::Thirst) > 20 {
+        act("You do not feel thirsty.", false, &ch, None, None, None, None, VictimType::ToChar);
+    }
+
+    if ch.get_cond(Condition::Full) > 20 {
+        act("You are full.", false, &ch, None, None, None, None, VictimType::ToChar);
+    }
+
+    if temp.obj_flags.borrow().value[3] != 0 {
+        // The shit was poisoned !
+        act("Ooups, it tasted rather strange ?!!?", false, &ch, None, None, None, None, VictimType::ToChar);
+        act("$n chokes and utters some strange sounds.", true, &ch, None, None, None, None,
+            VictimType::ToRoom);
+        poison(&ch, amount as i16 * 3);
+    }
+
+    if is_fountain(&temp) {
+        return;
+    }
+
+    // empty the container, and no longer poison.
+    weight_change_object(&temp, -amount);
+    temp.obj_flags.borrow_mut().value[1] -= amount;
+    if temp.obj_flags.borrow().value[1] == 0 {
+        // The last bit
+        empty_drinkcon(&temp);
+    }
+}
+
+pub fn do_eat(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (buf, _) = one_argument(argument);
+
+    let temp = match get_obj_in_list_vis(&ch, &buf, &ch.carrying.borrow()) {
+        Some(temp) => temp,
+        None => {
+            act("You can't find it!", false, &ch, None, None, None, None, VictimType::ToChar);
+            return;
+        }
+    };
+
+    if temp.get_item_type() != ItemType::Food && ch.get_level() < 22 {
+        act("Your stomach refuses to eat that!?!", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    if ch.get_cond(Condition::Full) > 20 {
+        // Stomach full
+        act("You are to full to eat more!", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    act("$n eats $p", true, &ch, Some(&temp), None, None, None, VictimType::ToRoom);
+    act("You eat the $o.", false, &ch, Some(&temp), None, None, None, VictimType::ToChar);
+
+    let (hours, poisoned) = {
+        let flags = temp.obj_flags.borrow();
+        (flags.value[0], flags.value[3] != 0)
+    };
+    gain_condition(&ch, Condition::Full, min(hours, 24) as i8);
+
+    if ch.get_cond(Condition::Full) > 20 {
+        act("You are full.", false, &ch, None, None, None, None, VictimType::ToChar);
+    }
+
+    if poisoned && ch.get_level() < 21 {
+        // The shit was poisoned !
+        act("Ooups, it tasted rather strange ?!!?", false, &ch, None, None, None, None, VictimType::ToChar);
+        act("$n coughs and utters some strange sounds.", false, &ch, None, None, None, None,
+            VictimType::ToRoom);
+        poison(&ch, hours as i16 * 2);
+    }
+
+    extract_obj(&temp, game);
+}
+
+// Move as much as fits from one drink container to another
+fn pour(ch: &CharData, from_obj: &ObjData, to_obj: &ObjData, to_name: &str) -> bool {
+    if to_obj.get_item_type() != ItemType::DrinkCon {
+        act("You can't pour anything into that.", false, ch, None, None, None, None, VictimType::ToChar);
+        return false;
+    }
+
+    if ptr::eq(from_obj, to_obj) {
+        act("A most unproductive effort.", false, ch, None, None, None, None, VictimType::ToChar);
+        return false;
+    }
+
+    let (to_capacity, to_left, to_liquid) = {
+        let flags = to_obj.obj_flags.borrow();
+        (flags.value[0], flags.value[1], flags.value[2])
+    };
+    let (from_left, from_liquid, from_poison) = {
+        let flags = from_obj.obj_flags.borrow();
+        (flags.value[1], flags.value[2], flags.value[3])
+    };
+
+    if to_left != 0 && to_liquid != from_liquid {
+        act("There is already another liquid in it!", false, ch, None, None, None, None, VictimType::ToChar);
+        return false;
+    }
+
+    if to_left >= to_capacity {
+        act("There is no room for more.", false, ch, None, None, None, None, VictimType::ToChar);
+        return false;
+    }
+
+    send_to_char(&format!("You pour the {} into the {}.\n\r", liquid(from_obj).name, to_name), ch);
+
+    // Then how much to pour, a fountain gives all that is asked for
+    let amount = if is_fountain(from_obj) {
+        to_capacity - to_left
+    } else {
+        min(to_capacity - to_left, from_left)
+    };
+
+    {
+        let mut flags = to_obj.obj_flags.borrow_mut();
+        flags.value[1] += amount;
+        flags.value[2] = from_liquid;
+        // Then the poison boogie
+        if from_poison != 0 {
+            flags.value[3] = 1;
+        }
+    }
+    weight_change_object(to_obj, amount);
+
+    if !is_fountain(from_obj) {
+        from_obj.obj_flags.borrow_mut().value[1] -= amount;
+        weight_change_object(from_obj, -amount);
+        if from_obj.obj_flags.borrow().value[1] == 0 {
+            empty_drinkcon(from_obj);
+        }
+    }
+
+    true
+}
+
+pub fn do_pour(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (arg1, arg2) = argument_interpreter(argument);
+
+    if arg1.is_empty() {
+        // No arguments
+        act("What do you want to pour from?", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    let from_obj = match get_obj_in_list_vis(&ch, &arg1, &ch.carrying.borrow()) {
+        Some(obj) => obj,
+        None => {
+            act("You can't find it!", false, &ch, None, None, None, None, VictimType::ToChar);
+            return;
+        }
+    };
+
+    if from_obj.get_item_type() != ItemType::DrinkCon {
+        act("You can't pour from that!", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    if from_obj.obj_flags.borrow().value[1] == 0 {
+        act("The $p is empty.", false, &ch, Some(&from_obj), None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    if arg2.is_empty() {
+        act("Where do you want it? Out or in what?", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    if arg2 == "out" {
+        act("$n empties $p", true, &ch, Some(&from_obj), None, None, None, VictimType::ToRoom);
+        act("You empty the $p.", false, &ch, Some(&from_obj), None, None, None, VictimType::ToChar);
+        let left = from_obj.obj_flags.borrow().value[1];
+        weight_change_object(&from_obj, -left);
+        empty_drinkcon(&from_obj);
+        return;
+    }
+
+    match get_obj_in_list_vis(&ch, &arg2, &ch.carrying.borrow()) {
+        Some(to_obj) => {
+            pour(&ch, &from_obj, &to_obj, &arg2);
+        }
+        None => act("You can't find it!", false, &ch, None, None, None, None, VictimType::ToChar),
+    }
+}
+
+// fill <container> [<fountain>], from a fountain in the room
+pub fn do_fill(ch: Rc<CharData>, argument: &str, _cmd: i32, _game: &Game) {
+    let (arg1, arg2) = argument_interpreter(argument);
+
+    if arg1.is_empty() {
+        act("What do you want to fill?", false, &ch, None, None, None, None, VictimType::ToChar);
+        return;
+    }
+
+    let to_obj = match get_obj_in_list_vis(&ch, &arg1, &ch.carrying.borrow()) {
+        Some(obj) => obj,
+        None => {
+            act("You can't find it!", false, &ch, None, None, None, None, VictimType::ToChar);
+            return;
+        }
+    };
+
+    let fountain = if arg2.is_empty() {
+        ch.in_room.borrow().contents.borrow().iter().find(|obj| is_fountain(obj)).cloned()
+    } else {
+        get_obj_in_list_vis(&ch, &arg2, &ch.in_room.borrow().contents.borrow())
+    };
+
+    match fountain {
+        Some(ref fountain) if is_fountain(fountain) => {
+            if pour(&ch, fountain, &to_obj, &arg1) {
+                act("$n fills $p.", true, &ch, Some(&to_obj), None, None, None, VictimType::ToRoom);
+            }
+        }
+        Some(_) => act("You can't fill anything from that!", false, &ch, None, None, None, None,
+            VictimType::ToChar),
+        None => act("There is nothing here to fill it from.", false, &ch, None, None, None, None,
+            VictimType::ToChar),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::act_comm::do_say;
+    use diku::game::clear_object;
+    use diku::handler::{affected_by_spell, obj_to_char, obj_to_room};
+    use diku::testutil::*;
+
+    fn drinkcon(name: &str, capacity: i32, left: i32, liquid: Liquid) -> Rc<ObjData> {
+        let mut obj = clear_object();
+        obj.name = String::from(name);
+        obj.short_description = format!("a {}", name);
+        {
+            let mut flags = obj.obj_flags.borrow_mut();
+            flags.type_flag = ItemType::DrinkCon;
+            flags.wear_flags = WearFlags::ITEM_TAKE;
+            flags.value = [capacity, left, liquid as i32, 0];
+            flags.weight = 5 + left;
+        }
+        Rc::new(obj)
+    }
+
+    #[test]
+    fn drink_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Thirsty", &room);
+        let watcher = test_char("Watcher", &room);
+        ch.specials.borrow_mut().conditions[Condition::Full] = 10;
+        ch.specials.borrow_mut().conditions[Condition::Thirst] = 2;
+
+        let cup = drinkcon("cup", 10, 10, Liquid::Water);
+        obj_to_char(Rc::clone(&cup), &ch);
+        let weight = ch.specials.borrow().carry_weight;
+
+        do_drink(Rc::clone(&ch), "cup", 0, &game);
+        let drunk = 10 - cup.obj_flags.borrow().value[1];
+        assert!(drunk >= 3 && drunk <= 10);
+        assert_eq!(ch.get_cond(Condition::Thirst) as i32, 2 + 10 * drunk / 4);
+        assert_eq!(ch.specials.borrow().carry_weight, weight - drunk);
+        assert_eq!(output(&ch)[0], "You drink the water.\n\r");
+        assert_eq!(output(&watcher), vec!["Thirsty drinks water from a cup"]);
+
+        // the last drops empty it
+        do_drink(Rc::clone(&ch), "cup", 0, &game);
+        do_drink(Rc::clone(&ch), "cup", 0, &game);
+        do_drink(Rc::clone(&ch), "cup", 0, &game);
+        assert_eq!(cup.obj_flags.borrow().value[1], 0);
+        output(&ch);
+        do_drink(Rc::clone(&ch), "cup", 0, &game);
+        assert_eq!(output(&ch), vec!["It's empty already."]);
+
+        do_drink(Rc::clone(&ch), "bottle", 0, &game);
+        assert_eq!(output(&ch), vec!["You can't find it!"]);
+    }
+
+    #[test]
+    fn drunk_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Drinker", &room);
+        let watcher = test_char("Watcher", &room);
+        ch.specials.borrow_mut().conditions[Condition::Full] = 10;
+        ch.specials.borrow_mut().conditions[Condition::Thirst] = 10;
+
+        let bottle = drinkcon("bottle", 20, 20, Liquid::Whiskey);
+        bottle.obj_flags.borrow_mut().value[3] = 1;
+        obj_to_char(Rc::clone(&bottle), &ch);
+
+        // (25 - 10) / 6 = 2 units, each 4 units give 6 drunk
+        do_drink(Rc::clone(&ch), "bottle", 0, &game);
+        assert_eq!(bottle.obj_flags.borrow().value[1], 18);
+        assert_eq!(ch.get_cond(Condition::Drunk), 3);
+        assert!(affected_by_spell(&ch, SPELL_POISON));
+        assert!(output(&ch).contains(&String::from("Ooups, it tasted rather strange ?!!?")));
+
+        ch.specials.borrow_mut().conditions[Condition::Drunk] = 11;
+        output(&watcher);
+        do_drink(Rc::clone(&ch), "bottle", 0, &game);
+        assert_eq!(output(&ch), vec!["You simply fail to reach your mouth!"]);
+        assert_eq!(output(&watcher), vec!["Drinker tried to drink but missed its mouth!"]);
+
+        // and the words come out wrong
+        do_say(Rc::clone(&ch), "see you soon", 0, &game);
+        let said = output(&watcher).pop().unwrap();
+        assert!(said.starts_with("Drinker says 'sh"), "{}", said);
+        assert!(said.len() > "Drinker says 'see you soon'".len() + 1);
+
+        // the drunkenness wears off
+        for _ in 0..11 {
+            gain_condition(&ch, Condition::Drunk, -1);
+        }
+        assert_eq!(output(&ch), vec!["You are now sober.\n\r"]);
+        do_say(Rc::clone(&ch), "see you soon", 0, &game);
+        assert_eq!(output(&watcher), vec!["Drinker says 'see you soon'"]);
+    }
+
+    #[test]
+    fn eat_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Hungry", &room);
+        ch.specials.borrow_mut().conditions[Condition::Full] = 0;
+
+        let mut bread = clear_object();
+        bread.name = String::from("bread");
+        bread.short_description = String::from("a loaf of bread");
+        bread.obj_flags.borrow_mut().type_flag = ItemType::Food;
+        bread.obj_flags.borrow_mut().value = [5, 0, 0, 1];
+        let bread = Rc::new(bread);
+        game.object_list.borrow_mut().push(Rc::clone(&bread));
+        obj_to_char(Rc::clone(&bread), &ch);
+
+        do_eat(Rc::clone(&ch), "bread", 0, &game);
+        assert_eq!(ch.get_cond(Condition::Full), 5);
+        assert!(ch.carrying.borrow().is_empty());
+        assert!(game.object_list.borrow().is_empty());
+        assert!(ch.is_affected(AffectedFlags::AFF_POISON));
+        assert_eq!(ch.affected.borrow().front().unwrap().duration, 10);
+        assert_eq!(output(&ch), vec!["You eat the bread.", "Ooups, it tasted rather strange ?!!?"]);
+
+        let cup = drinkcon("cup", 10, 10, Liquid::Water);
+        obj_to_char(Rc::clone(&cup), &ch);
+        do_eat(Rc::clone(&ch), "cup", 0, &game);
+        assert_eq!(output(&ch), vec!["Your stomach refuses to eat that!?!"]);
+
+        gain_condition(&ch, Condition::Full, -5);
+        assert_eq!(output(&ch), vec!["You are hungry.\n\r"]);
+    }
+
+    #[test]
+    fn pour_and_fill_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Pourer", &room);
+
+        let barrel = drinkcon("barrel", 30, 30, Liquid::Beer);
+        let cup = drinkcon("cup", 10, 0, Liquid::Water);
+        let flask = drinkcon("flask", 10, 5, Liquid::Wine);
+        obj_to_char(Rc::clone(&barrel), &ch);
+        obj_to_char(Rc::clone(&cup), &ch);
+        obj_to_char(Rc::clone(&flask), &ch);
+
+        do_pour(Rc::clone(&ch), "barrel cup", 0, &game);
+        assert_eq!(output(&ch), vec!["You pour the beer into the cup.\n\r"]);
+        assert_eq!(cup.obj_flags.borrow().value[1..3], [10, Liquid::Beer as i32]);
+        assert_eq!(barrel.obj_flags.borrow().value[1], 20);
+
+        do_pour(Rc::clone(&ch), "barrel flask", 0, &game);
+        do_pour(Rc::clone(&ch), "barrel barrel", 0, &game);
+        assert_eq!(output(&ch), vec!["There is already another liquid in it!", "A most unproductive effort."]);
+
+        do_pour(Rc::clone(&ch), "barrel out", 0, &game);
+        assert_eq!(barrel.obj_flags.borrow().value[1], 0);
+        assert_eq!(barrel.get_obj_weight(), 5);
+        output(&ch);
+
+        let mut fountain = clear_object();
+        fountain.name = String::from("fountain");
+        fountain.short_description = String::from("a fountain");
+        fountain.obj_flags.borrow_mut().type_flag = ItemType::DrinkCon;
+        fountain.obj_flags.borrow_mut().value = [50, 50, Liquid::Water as i32, 0];
+        let fountain = Rc::new(fountain);
+        obj_to_room(Rc::clone(&fountain), &room);
+
+        do_fill(Rc::clone(&ch), "barrel", 0, &game);
+        assert_eq!(output(&ch), vec!["You pour the water into the barrel.\n\r"]);
+        assert_eq!(barrel.obj_flags.borrow().value[1], 30);
+        assert_eq!(fountain.obj_flags.borrow().value[1], 50);
+
+        // and drinking from it leaves it full
+        ch.specials.borrow_mut().conditions[Condition::Full] = 10;
+        ch.specials.borrow_mut().conditions[Condition::Thirst] = 0;
+        do_drink(Rc::clone(&ch), "fountain", 0, &game);
+        assert!(ch.get_cond(Condition::Thirst) > 0);
+        assert_eq!(fountain.obj_flags.borrow().value[1], 50);
+    }
+}
@@ -1,20 +1,471 @@
+use std::ptr;
 use std::rc::Rc;
 
 use diku::act_movement::do_simple_move;
-use diku::comm::act;
+use diku::comm::{act, send_to_char};
+use diku::constants::PULSE_VIOLENCE;
+use diku::fight::{damage, hit, set_fighting, stop_fighting};
+use diku::handler::get_char_room_vis;
+use diku::interpreter::one_argument;
+use diku::limits::gain_exp;
+use diku::spells::{SKILL_BACKSTAB, SKILL_BASH, SKILL_KICK, SKILL_RESCUE};
 use diku::structs::*;
-use diku::utility::number;
-
-pub fn do_flee(ch: Rc<CharData>, argument: &str, cmd: i32, game: &Game) {
-    if !ch.specials.borrow().fighting.is_some() {
-        for i in 0..6 {
-            let attempt = Direction::from(number(0, 5) as u8); // Select a random direction
-            if ch.can_go(attempt) &&
-            !game.world.get(&ch.exit(attempt).unwrap().to_room.unwrap()).unwrap().room_flags.contains(RoomFlags::DEATH) {
-                act("$n panics, and attempts to flee.", true, &ch, None, None, None, None, VictimType::ToRoom);
-                let die = do_simple_move(Rc::clone(&ch), attempt, false, game);
-                
+
+// The named victim, or whoever ch is fighting when no one is named
+fn find_victim(ch: &CharData, argument: &str, who: &str) -> Option<Rc<CharData>> {
+    let (name, _) = one_argument(argument);
+
+    let victim = get_char_room_vis(ch, &name).or_else(|| ch.specials.borrow().fighting.clone());
+    match victim {
+        None => send_to_char(&format!("{} who?\n\r", who), ch),
+        Some(ref victim) if ptr::eq(victim.as_ref(), ch) => send_to_char("Aren't we funny today...\n\r", ch),
+        Some(victim) => return Some(victim),
+    }
+    None
+}
+
+// 101% is a complete failure, and every point of armor makes a hit harder
+fn armor_roll(victim: &CharData, game: &Game) -> i32 {
+    (10 - victim.get_ac() as i32 / 10) * 2 + game.number(1, 101)
+}
+
+pub fn do_backstab(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, _) = one_argument(argument);
+
+    let victim = match get_char_room_vis(&ch, &name) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("Backstab who?\n\r", &ch);
+            return;
+        }
+    };
+
+    if Rc::ptr_eq(&victim, &ch) {
+        send_to_char("How can you sneak up on yourself?\n\r", &ch);
+        return;
+    }
+
+    let pierce = match ch.get_eq(EquipmentPosition::Wield) {
+        Some(wielded) => wielded.get_item_type() == ItemType::Weapon &&
+            (8..=11).contains(&wielded.obj_flags.borrow().value[3]),
+        None => {
+            send_to_char("You need to wield a weapon, to make it a success.\n\r", &ch);
+            return;
+        }
+    };
+    if !pierce {
+        send_to_char("Only piercing weapons can be used for backstabbing.\n\r", &ch);
+        return;
+    }
+
+    if ch.specials.borrow().fighting.is_some() {
+        send_to_char("You can't backstab while fighting, too alert!\n\r", &ch);
+        return;
+    }
+
+    if victim.specials.borrow().fighting.is_some() {
+        act("$N is too alert, you can't sneak up on $M.", false, &ch, None, Some(&victim), None, None,
+            VictimType::ToChar);
+        return;
+    }
+
+    let percent = game.number(1, 101); // 101% is a complete failure
+    if victim.awake() && percent > ch.skills.borrow()[SKILL_BACKSTAB as usize].learned as i32 {
+        damage(ch, victim, 0, SKILL_BACKSTAB, game);
+    } else {
+        hit(ch, victim, SKILL_BACKSTAB, game);
+    }
+}
+
+pub fn do_flee(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    let opponent = match ch.specials.borrow().fighting.clone() {
+        Some(opponent) => opponent,
+        None => {
+            send_to_char("But you are not fighting anyone!\n\r", &ch);
+            return;
+        }
+    };
+
+    for _ in 0..6 {
+        let attempt = Direction::from(game.number(0, 5) as u8); // Select a random direction
+        if !ch.can_go(attempt) ||
+                game.world[&ch.exit(attempt).unwrap().to_room.unwrap()].room_flags.contains(RoomFlags::DEATH) {
+            continue;
+        }
+
+        act("$n panics, and attempts to flee.", true, &ch, None, None, None, None, VictimType::ToRoom);
+        if !do_simple_move(Rc::clone(&ch), attempt, false, game) {
+            act("$n tries to flee, but is too exhausted!", true, &ch, None, None, None, None, VictimType::ToRoom);
+            return;
+        }
+
+        // The escape has succeeded
+        if !ch.is_npc() {
+            let loose = (opponent.get_max_hit() - opponent.get_hit()) as i32 * opponent.get_level() as i32;
+            gain_exp(&ch, -loose, game);
+        }
+
+        send_to_char("You flee head over heels.\n\r", &ch);
+
+        if opponent.specials.borrow().fighting.as_ref().map_or(false, |f| Rc::ptr_eq(f, &ch)) {
+            stop_fighting(&opponent, game);
+        }
+        if ch.specials.borrow().fighting.is_some() {
+            stop_fighting(&ch, game);
+        }
+        return;
+    }
+
+    // No exits were found
+    send_to_char("PANIC! You couldn't escape!\n\r", &ch);
+}
+pub fn do_bash(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.get_class() != Class::Warrior {
+        send_to_char("You better leave all the martial arts to fighters.\n\r", &ch);
+        return;
+    }
+
+    let victim = match find_victim(&ch, argument, "Bash") {
+        Some(victim) => victim,
+        None => return,
+    };
+
+    if ch.get_eq(EquipmentPosition::Wield).is_none() {
+        send_to_char("You need to wield a weapon, to make it a success.\n\r", &ch);
+        return;
+    }
+
+    if armor_roll(&victim, game) > ch.skills.borrow()[SKILL_BASH as usize].learned as i32 {
+        damage(Rc::clone(&ch), victim, 0, SKILL_BASH, game);
+        ch.specials.borrow_mut().position = Position::Sitting;
+    } else {
+        damage(Rc::clone(&ch), Rc::clone(&victim), 1, SKILL_BASH, game);
+        if victim.get_pos() > Position::Sitting {
+            victim.specials.borrow_mut().position = Position::Sitting;
+            victim.wait_state(PULSE_VIOLENCE as i32 * 2);
+        }
+    }
+    ch.wait_state(PULSE_VIOLENCE as i32 * 2);
+}
+
+pub fn do_rescue(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, _) = one_argument(argument);
+
+    let victim = match get_char_room_vis(&ch, &name) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("Who do you want to rescue?\n\r", &ch);
+            return;
+        }
+    };
+
+    if Rc::ptr_eq(&victim, &ch) {
+        send_to_char("What about fleeing instead?\n\r", &ch);
+        return;
+    }
+
+    if ch.specials.borrow().fighting.as_ref().map_or(false, |f| Rc::ptr_eq(f, &victim)) {
+        send_to_char("How can you rescue someone you are trying to kill?\n\r", &ch);
+        return;
+    }
+
+    let attacker = ch.in_room.borrow().people.borrow().iter()
+        .find(|i| i.specials.borrow().fighting.as_ref().map_or(false, |f| Rc::ptr_eq(f, &victim)))
+        .cloned();
+    let attacker = match attacker {
+        Some(attacker) => attacker,
+        None => {
+            act("But nobody is fighting $M?", false, &ch, None, Some(&victim), None, None, VictimType::ToChar);
+            return;
+        }
+    };
+
+    if ch.get_class() != Class::Warrior {
+        send_to_char("But only true warriors can do this!\n\r", &ch);
+        return;
+    }
+
+    let percent = game.number(1, 101); // 101% is a complete failure
+    if percent > ch.skills.borrow()[SKILL_RESCUE as usize].learned as i32 {
+        send_to_char("You fail the rescue.\n\r", &ch);
+        return;
+    }
+
+    send_to_char("Banzai! To the rescue...\n\r", &ch);
+    act("You are rescued by $N, you are confused!", false, &victim, None, Some(&ch), None, None, VictimType::ToChar);
+    act("$n heroically rescues $N.", false, &ch, None, Some(&victim), None, None, VictimType::ToNotVict);
+
+    if victim.specials.borrow().fighting.as_ref().map_or(false, |f| Rc::ptr_eq(f, &attacker)) {
+        stop_fighting(&victim, game);
+    }
+    stop_fighting(&attacker, game);
+    if ch.specials.borrow().fighting.is_some() {
+        stop_fighting(&ch, game);
+    }
+
+    let mut combat_list = game.combat_list.borrow_mut();
+    set_fighting(Rc::clone(&ch), Rc::clone(&attacker), &mut combat_list);
+    set_fighting(attacker, ch, &mut combat_list);
+
+    victim.wait_state(PULSE_VIOLENCE as i32 * 2);
+}
+
+pub fn do_kick(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.get_class() != Class::Warrior {
+        send_to_char("You better leave all the martial arts to fighters.\n\r", &ch);
+        return;
+    }
+
+    let victim = match find_victim(&ch, argument, "Kick") {
+        Some(victim) => victim,
+        None => return,
+    };
+
+    if armor_roll(&victim, game) > ch.skills.borrow()[SKILL_KICK as usize].learned as i32 {
+        damage(Rc::clone(&ch), victim, 0, SKILL_KICK, game);
+    } else {
+        damage(Rc::clone(&ch), victim, ch.get_level() as i16 / 2, SKILL_KICK, game);
+    }
+    ch.wait_state(PULSE_VIOLENCE as i32 * 3);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::clear_object;
+    use diku::handler::{equip_char, unequip_char};
+    use diku::spells::{TYPE_HIT, TYPE_UNDEFINED};
+    use diku::testutil::*;
+
+    fn weapon(name: &str, weapon_type: i32) -> Rc<ObjData> {
+        let mut obj = clear_object();
+        obj.name = String::from(name);
+        obj.short_description = format!("a {}", name);
+        {
+            let mut flags = obj.obj_flags.borrow_mut();
+            flags.type_flag = ItemType::Weapon;
+            flags.wear_flags = WearFlags::ITEM_TAKE | WearFlags::ITEM_WIELD;
+            flags.value = [0, 1, 4, weapon_type];
+        }
+        Rc::new(obj)
+    }
+
+    #[test]
+    fn backstab_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Shadow", &room);
+        let mob = test_mob("the guard", &room);
+        mob.player.borrow_mut().name = String::from("guard");
+        mob.points.borrow_mut().hit = 500;
+        mob.points.borrow_mut().max_hit = 500;
+        mob.specials.borrow_mut().damnodice = 1;
+        mob.specials.borrow_mut().damsizedice = 2;
+
+        do_backstab(Rc::clone(&ch), "nobody", 0, &game);
+        do_backstab(Rc::clone(&ch), "shadow", 0, &game);
+        do_backstab(Rc::clone(&ch), "guard", 0, &game);
+        equip_char(&ch, weapon("club", 7), EquipmentPosition::Wield);
+        do_backstab(Rc::clone(&ch), "guard", 0, &game);
+        assert_eq!(output(&ch), vec!["Backstab who?\n\r", "How can you sneak up on yourself?\n\r",
+            "You need to wield a weapon, to make it a success.\n\r",
+            "Only piercing weapons can be used for backstabbing.\n\r"]);
+
+        // A sleeping victim never notices the dagger coming
+        unequip_char(&ch, EquipmentPosition::Wield);
+        equip_char(&ch, weapon("dagger", 11), EquipmentPosition::Wield);
+        mob.specials.borrow_mut().position = Position::Sleeping;
+        do_backstab(Rc::clone(&ch), "guard", 0, &game);
+        assert!(mob.get_hit() < 500);
+        assert!(Rc::ptr_eq(mob.specials.borrow().fighting.as_ref().unwrap(), &ch));
+        output(&ch);
+
+        do_backstab(Rc::clone(&ch), "guard", 0, &game);
+        assert_eq!(output(&ch), vec!["You can't backstab while fighting, too alert!\n\r"]);
+
+        let other = test_char("Other", &room);
+        equip_char(&other, weapon("dagger", 11), EquipmentPosition::Wield);
+        do_backstab(Rc::clone(&other), "guard", 0, &game);
+        assert_eq!(output(&other), vec!["the guard is too alert, you can't sneak up on it."]);
+    }
+
+    fn warrior(name: &str, room: &Rc<RoomData>, learned: i8) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        ch.player.borrow_mut().level = 10;
+        for skill in SKILL_KICK..SKILL_RESCUE + 1 {
+            ch.skills.borrow_mut()[skill as usize].learned = learned;
+        }
+        ch
+    }
+
+    fn ogre(room: &Rc<RoomData>) -> Rc<CharData> {
+        let mob = test_mob("the ogre", room);
+        mob.player.borrow_mut().name = String::from("ogre");
+        mob.points.borrow_mut().hit = 500;
+        mob.points.borrow_mut().max_hit = 500;
+        mob.points.borrow_mut().armor = 100;
+        mob.specials.borrow_mut().damnodice = 1;
+        mob.specials.borrow_mut().damsizedice = 2;
+        mob
+    }
+
+    fn wait(ch: &CharData) -> i32 {
+        ch.desc.borrow().as_ref().unwrap().borrow().wait
+    }
+
+    #[test]
+    fn kick_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = warrior("Fighter", &room, 100);
+        let mob = ogre(&room);
+
+        do_kick(Rc::clone(&ch), "", 0, &game);
+        do_kick(Rc::clone(&ch), "fighter", 0, &game);
+        assert_eq!(output(&ch), vec!["Kick who?\n\r", "Aren't we funny today...\n\r"]);
+
+        do_kick(Rc::clone(&ch), "ogre", 0, &game);
+        assert_eq!(mob.get_hit(), 495);
+        assert_eq!(wait(&ch), 36);
+        assert!(Rc::ptr_eq(mob.specials.borrow().fighting.as_ref().unwrap(), &ch));
+
+        // Once fighting, the opponent need not be named
+        ch.skills.borrow_mut()[SKILL_KICK as usize].learned = 0;
+        do_kick(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(mob.get_hit(), 495);
+
+        let mage = test_char("Mage", &room);
+        mage.player.borrow_mut().class = Class::MagicUser;
+        do_kick(Rc::clone(&mage), "ogre", 0, &game);
+        assert_eq!(output(&mage), vec!["You better leave all the martial arts to fighters.\n\r"]);
+    }
+
+    #[test]
+    fn bash_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = warrior("Fighter", &room, 100);
+        let mob = ogre(&room);
+
+        do_bash(Rc::clone(&ch), "ogre", 0, &game);
+        assert_eq!(output(&ch), vec!["You need to wield a weapon, to make it a success.\n\r"]);
+
+        equip_char(&ch, weapon("club", 7), EquipmentPosition::Wield);
+        let other = test_mob("the orc", &room);
+        attach_descriptor(&mob);
+        do_bash(Rc::clone(&ch), "ogre", 0, &game);
+        assert_eq!(mob.get_hit(), 499);
+        assert_eq!(mob.get_pos() as u8, Position::Sitting as u8);
+        assert_eq!(wait(&mob), 24);
+        assert_eq!(wait(&ch), 24);
+
+        // A failed bash lands the basher on the floor instead
+        ch.skills.borrow_mut()[SKILL_BASH as usize].learned = 0;
+        do_bash(Rc::clone(&ch), "orc", 0, &game);
+        assert_eq!(ch.get_pos() as u8, Position::Sitting as u8);
+        assert_eq!(other.get_hit(), 20);
+    }
+
+    #[test]
+    fn rescue_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = warrior("Fighter", &room, 100);
+        let friend = test_char("Friend", &room);
+        friend.player.borrow_mut().class = Class::Cleric;
+        let mob = ogre(&room);
+
+        do_rescue(Rc::clone(&ch), "friend", 0, &game);
+        assert_eq!(output(&ch), vec!["But nobody is fighting it?"]);
+
+        hit(Rc::clone(&mob), Rc::clone(&friend), TYPE_UNDEFINED, &game);
+        output(&friend);
+        output(&ch);
+        do_rescue(Rc::clone(&ch), "fighter", 0, &game);
+        do_rescue(Rc::clone(&ch), "friend", 0, &game);
+        assert_eq!(output(&ch), vec!["What about fleeing instead?\n\r", "Banzai! To the rescue...\n\r"]);
+        assert_eq!(output(&friend), vec!["You are rescued by Fighter, you are confused!"]);
+        assert!(friend.specials.borrow().fighting.is_none());
+        assert!(Rc::ptr_eq(mob.specials.borrow().fighting.as_ref().unwrap(), &ch));
+        assert!(Rc::ptr_eq(ch.specials.borrow().fighting.as_ref().unwrap(), &mob));
+        assert_eq!(game.combat_list.borrow().len(), 2);
+        assert_eq!(wait(&friend), 24);
+
+        do_rescue(Rc::clone(&friend), "fighter", 0, &game);
+        assert_eq!(output(&friend), vec!["But only true warriors can do this!\n\r"]);
+    }
+
+    fn start_fight(ch: &Rc<CharData>, victim: &Rc<CharData>, game: &Game) {
+        let mut combat_list = game.combat_list.borrow_mut();
+        set_fighting(Rc::clone(ch), Rc::clone(victim), &mut combat_list);
+        set_fighting(Rc::clone(victim), Rc::clone(ch), &mut combat_list);
+    }
+
+    #[test]
+    fn flee_test() {
+        let mut game = test_game();
+        let room = with_exit(test_room(3001), Direction::North, 3002, ExitFlags::empty());
+        let room = with_exit(room, Direction::East, 3003, ExitFlags::empty());
+        let safety = test_room(3002);
+        let mut pit = Rc::try_unwrap(test_room(3003)).ok().unwrap();
+        pit.room_flags = RoomFlags::DEATH;
+        game.world.insert(3001, Rc::clone(&room));
+        game.world.insert(3002, Rc::clone(&safety));
+        game.world.insert(3003, Rc::new(pit));
+
+        let ch = warrior("Coward", &room, 0);
+        ch.player.borrow_mut().level = 1;
+        ch.points.borrow_mut().exp = 1000;
+        ch.points.borrow_mut().mov = 100;
+        let mob = ogre(&room);
+        mob.player.borrow_mut().level = 5;
+        mob.points.borrow_mut().hit = 400;
+
+        do_flee(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["But you are not fighting anyone!\n\r"]);
+
+        start_fight(&ch, &mob, &game);
+        do_flee(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["You flee head over heels.\n\r"]);
+        assert!(Rc::ptr_eq(&ch.in_room.borrow(), &safety));
+        assert!(ch.specials.borrow().fighting.is_none());
+        assert!(mob.specials.borrow().fighting.is_none());
+        assert!(game.combat_list.borrow().is_empty());
+        // The damage done to the opponent is lost, times its level
+        assert_eq!(ch.get_exp(), 1000 - 100 * 5);
+
+        // There is no way out of a dead end
+        let other = ogre(&safety);
+        start_fight(&ch, &other, &game);
+        do_flee(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["PANIC! You couldn't escape!\n\r"]);
+        assert!(ch.specials.borrow().fighting.is_some());
+    }
+
+    #[test]
+    fn wimpy_test() {
+        let mut game = test_game();
+        let room = with_exit(test_room(3001), Direction::North, 3002, ExitFlags::empty());
+        let safety = test_room(3002);
+        game.world.insert(3001, Rc::clone(&room));
+        game.world.insert(3002, Rc::clone(&safety));
+
+        let ch = warrior("Bully", &room, 0);
+        let mob = test_mob("the rabbit", &room);
+        mob.specials.borrow_mut().act.insert(SpecialActFlags::ACT_WIMPY);
+
+        // Dropping below a fifth of its hit points sends it running
+        let mut fled = false;
+        for _ in 0..6 {
+            mob.points.borrow_mut().hit = 3;
+            damage(Rc::clone(&ch), Rc::clone(&mob), 1, TYPE_HIT, &game);
+            if Rc::ptr_eq(&mob.in_room.borrow(), &safety) {
+                fled = true;
+                break;
             }
         }
+        assert!(fled);
+        assert!(mob.specials.borrow().fighting.is_none());
+        assert!(ch.specials.borrow().fighting.is_none());
     }
-}
\ No newline at end of file
+}
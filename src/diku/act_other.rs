@@ -0,0 +1,496 @@
+use std::cmp::{max, min};
+use std::rc::Rc;
+
+use diku::act_comm::do_shout;
+use diku::comm::{act, send_to_char};
+use diku::constants::{DEX_APP_SKILL, INT_APP, MAX_SKILLS, SPELLS};
+use diku::fight::hit;
+use diku::handler::{affect_from_char, affect_to_char, get_char_room_vis, get_obj_in_list_vis, isname,
+    obj_from_char, obj_to_char, unequip_char};
+use diku::interpreter::argument_interpreter;
+use diku::spells::{SKILL_HIDE, SKILL_KICK, SKILL_PICK_LOCK, SKILL_RESCUE, SKILL_SNEAK, SKILL_STEAL, TYPE_UNDEFINED};
+use diku::structs::*;
+use diku::utility::log;
+
+pub fn how_good(percent: i8) -> &'static str {
+    match percent {
+        0 => " (not learned)",
+        1..=10 => " (awful)",
+        11..=20 => " (bad)",
+        21..=40 => " (poor)",
+        41..=55 => " (average)",
+        56..=70 => " (fair)",
+        71..=80 => " (good)",
+        81..=85 => " (very good)",
+        _ => " (Superb)",
+    }
+}
+
+// The spells and skills a char of this class and level may practice
+pub fn practicable(class: Class, level: u8, game: &Game) -> Vec<i32> {
+    match class {
+        Class::MagicUser | Class::Cleric => {
+            let mut spells: Vec<i32> = game.spell_info.iter()
+                .filter(|&(&nr, info)| (nr as usize) < MAX_SKILLS && info.spell_pointer.is_some() &&
+                    level >= if class == Class::MagicUser { info.min_level_magic } else { info.min_level_cleric })
+                .map(|(&nr, _)| nr)
+                .collect();
+            spells.sort();
+            spells
+        }
+        Class::Thief => (SKILL_SNEAK..SKILL_PICK_LOCK + 1).collect(),
+        Class::Warrior => (SKILL_KICK..SKILL_RESCUE + 1).collect(),
+    }
+}
+
+// How far a guild will teach, and how much is learned in one session
+fn practice_gain(class: Class, intel: u8) -> (i8, i8) {
+    let learn = INT_APP[intel as usize].learn;
+    match class {
+        Class::MagicUser | Class::Cleric => (95, max(25, learn)),
+        Class::Thief => (85, min(12, learn)),
+        Class::Warrior => (80, min(12, learn)),
+    }
+}
+
+fn what(class: Class) -> &'static str {
+    if class == Class::MagicUser || class == Class::Cleric { "spell" } else { "skill" }
+}
+
+pub fn list_practices(ch: &CharData, game: &Game) {
+    send_to_char(&format!("You have got {} practice sessions left.\n\r",
+        ch.specials.borrow().spells_to_learn), ch);
+    send_to_char(&format!("You can practise any of these {}s:\n\r", what(ch.get_class())), ch);
+
+    let skills = ch.skills.borrow();
+    for nr in practicable(ch.get_class(), ch.get_level(), game) {
+        send_to_char(&format!("{}{}\n\r", SPELLS[nr as usize - 1], how_good(skills[nr as usize].learned)), ch);
+    }
+}
+
+// One practice session in the spell or skill named by arg
+pub fn practice(ch: &CharData, arg: &str, game: &Game) {
+    let class = ch.get_class();
+    let arg = arg.trim().to_lowercase();
+
+    let number = practicable(class, ch.get_level(), game).into_iter()
+        .find(|&nr| SPELLS[nr as usize - 1].starts_with(&arg));
+    let number = match number {
+        Some(number) => number as usize,
+        None => {
+            send_to_char(&format!("You do not know of this {}...\n\r", what(class)), ch);
+            return;
+        }
+    };
+
+    if ch.specials.borrow().spells_to_learn == 0 {
+        send_to_char("You do not seem to be able to practice now.\n\r", ch);
+        return;
+    }
+
+    let (maximum, gain) = practice_gain(class, ch.get_int());
+    if ch.skills.borrow()[number].learned >= maximum {
+        send_to_char("You are already learned in this area.\n\r", ch);
+        return;
+    }
+
+    send_to_char("You Practice for a while...\n\r", ch);
+    ch.specials.borrow_mut().spells_to_learn -= 1;
+
+    let learned = min(maximum, ch.skills.borrow()[number].learned.saturating_add(gain));
+    ch.skills.borrow_mut()[number].learned = learned;
+
+    if learned >= maximum {
+        send_to_char("You are now learned in this area.\n\r", ch);
+    }
+}
+
+// Commands that only do something where a special procedure picks them up
+pub fn do_not_here(ch: Rc<CharData>, _argument: &str, _cmd: i32, _game: &Game) {
+    send_to_char("Sorry, but you cannot do that here!\n\r", &ch);
+}
+
+// Practicing needs a guild, looking at what you know can be done anywhere
+pub fn do_practice(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    if argument.trim().is_empty() {
+        list_practices(&ch, game);
+    } else {
+        send_to_char("You can only practise in a guild.\n\r", &ch);
+    }
+}
+
+pub fn do_sneak(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    send_to_char("Ok, you'll try to move silently for a while.\n\r", &ch);
+    if ch.is_affected(AffectedFlags::AFF_SNEAK) {
+        affect_from_char(&ch, SKILL_SNEAK);
+    }
+
+    let percent = game.number(1, 101); // 101% is a complete failure
+    if percent > ch.skills.borrow()[SKILL_SNEAK as usize].learned as i32 +
+            DEX_APP_SKILL[ch.get_dex() as usize].sneak as i32 {
+        return;
+    }
+
+    affect_to_char(&ch, &AffectedType {
+        spell_type: SKILL_SNEAK,
+        duration: ch.get_level() as i16,
+        modifier: 0,
+        location: AbilityModifier::None,
+        bitvector: AffectedFlags::AFF_SNEAK,
+    });
+}
+
+pub fn do_hide(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    send_to_char("You attempt to hide yourself.\n\r", &ch);
+    ch.specials.borrow_mut().affected_by.remove(AffectedFlags::AFF_HIDE);
+
+    let percent = game.number(1, 101); // 101% is a complete failure
+    if percent > ch.skills.borrow()[SKILL_HIDE as usize].learned as i32 +
+            DEX_APP_SKILL[ch.get_dex() as usize].hide as i32 {
+        return;
+    }
+
+    ch.specials.borrow_mut().affected_by.insert(AffectedFlags::AFF_HIDE);
+}
+
+pub fn do_steal(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (obj_name, victim_name) = argument_interpreter(argument);
+
+    let victim = match get_char_room_vis(&ch, &victim_name) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("Steal what from who?\n\r", &ch);
+            return;
+        }
+    };
+
+    if Rc::ptr_eq(&victim, &ch) {
+        send_to_char("Come on now, that's rather stupid!\n\r", &ch);
+        return;
+    }
+
+    if !victim.is_npc() && !game.pkill {
+        send_to_char("You can't steal from other players.\n\r", &ch);
+        return;
+    }
+
+    // 101% is a complete failure
+    let mut percent = game.number(1, 101) - DEX_APP_SKILL[ch.get_dex() as usize].p_pocket as i32;
+
+    if victim.get_pos() < Position::Sleeping {
+        percent = -1; // ALWAYS SUCCESS
+    }
+
+    // No stealing from immortals
+    if victim.get_level() > 20 {
+        percent = 101;
+    }
+
+    let learned = ch.skills.borrow()[SKILL_STEAL as usize].learned as i32;
+    let caught;
+
+    if obj_name == "coins" || obj_name == "gold" {
+        caught = victim.awake() && percent > learned;
+        if !caught {
+            // Steal some coins
+            let gold = min(100, victim.get_gold() * game.number(1, 10) / 100);
+            if gold > 0 {
+                ch.points.borrow_mut().gold += gold;
+                victim.points.borrow_mut().gold -= gold;
+                send_to_char(&format!("Bingo! You got {} gold coins.\n\r", gold), &ch);
+            } else {
+                send_to_char("You couldn't get any gold...\n\r", &ch);
+            }
+        }
+    } else {
+        let obj = get_obj_in_list_vis(&ch, &obj_name, &victim.carrying.borrow());
+        let obj = match obj {
+            Some(obj) => obj,
+            None => {
+                let worn = victim.equipment.borrow().iter().find(|&(_, eq)| eq.as_ref()
+                    .map_or(false, |eq| isname(&obj_name, &eq.name) && ch.can_see_obj(eq)))
+                    .map(|(pos, _)| pos);
+                match worn {
+                    None => act("$E has not got that item.", false, &ch, None, Some(&victim), None, None,
+                        VictimType::ToChar),
+                    Some(_) if victim.get_pos() > Position::Sleeping =>
+                        send_to_char("Steal the equipment now? Impossible!\n\r", &ch),
+                    Some(pos) => {
+                        // It is equipment, and the victim is in no state to notice
+                        let obj = unequip_char(&victim, pos).unwrap();
+                        act("You unequip $p and steal it.", false, &ch, Some(&obj), None, None, None,
+                            VictimType::ToChar);
+                        act("$n steals $p from $N.", false, &ch, Some(&obj), Some(&victim), None, None,
+                            VictimType::ToNotVict);
+                        obj_to_char(obj, &ch);
+                    }
+                }
+                return;
+            }
+        };
+
+        percent += obj.get_obj_weight(); // Make heavy harder
+        caught = victim.awake() && percent > learned;
+        if !caught {
+            if ch.can_carry_obj(&obj) {
+                obj_from_char(&obj);
+                obj_to_char(obj, &ch);
+                send_to_char("Got it!\n\r", &ch);
+            } else {
+                send_to_char("You cannot carry that much.\n\r", &ch);
+            }
+        }
+    }
+
+    if caught {
+        send_to_char("Oops..\n\r", &ch);
+        act("$n tried to steal something from you!", false, &ch, None, Some(&victim), None, None,
+            VictimType::ToVict);
+        act("$n tries to steal something from $N.", true, &ch, None, Some(&victim), None, None,
+            VictimType::ToNotVict);
+
+        if victim.is_npc() {
+            if victim.specials.borrow().act.contains(SpecialActFlags::ACT_NICE_THIEF) {
+                let buf = format!("{} is a bloody thief.", ch.get_name());
+                do_shout(Rc::clone(&victim), &buf, 0, game);
+                log(&buf);
+                send_to_char("Don't you ever do that again!\n\r", &ch);
+            } else if victim.specials.borrow().fighting.is_none() {
+                hit(victim, ch, TYPE_UNDEFINED, game);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::clear_object;
+    use diku::handler::{affected_by_spell, equip_char, obj_to_char};
+    use diku::interpreter::{command_interpreter, CMD_PRACTICE};
+    use diku::spec_procs::{guild_magic_user, guild_warrior};
+    use diku::spells::{SKILL_BASH, SPELL_MAGIC_MISSILE};
+    use diku::testutil::*;
+
+    fn thief(name: &str, room: &Rc<RoomData>, learned: i8, dex: u8) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        ch.player.borrow_mut().class = Class::Thief;
+        ch.tmpabilities.borrow_mut().dex = dex;
+        for skill in SKILL_SNEAK..SKILL_PICK_LOCK + 1 {
+            ch.skills.borrow_mut()[skill as usize].learned = learned;
+        }
+        ch
+    }
+
+    fn merchant(room: &Rc<RoomData>) -> Rc<CharData> {
+        let mob = test_mob("the merchant", room);
+        mob.player.borrow_mut().name = String::from("merchant");
+        mob.points.borrow_mut().gold = 1000;
+        mob.specials.borrow_mut().damnodice = 1;
+        mob.specials.borrow_mut().damsizedice = 4;
+        mob
+    }
+
+    fn item(name: &str) -> Rc<ObjData> {
+        let mut obj = clear_object();
+        obj.name = String::from(name);
+        obj.short_description = format!("a {}", name);
+        obj.obj_flags.borrow_mut().wear_flags = WearFlags::ITEM_TAKE;
+        Rc::new(obj)
+    }
+
+    #[test]
+    fn how_good_test() {
+        assert_eq!(how_good(0), " (not learned)");
+        assert_eq!(how_good(10), " (awful)");
+        assert_eq!(how_good(45), " (average)");
+        assert_eq!(how_good(80), " (good)");
+        assert_eq!(how_good(95), " (Superb)");
+    }
+
+    #[test]
+    fn practice_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Fighter", &room);
+        ch.specials.borrow_mut().spells_to_learn = 10;
+
+        do_practice(Rc::clone(&ch), "", 0, &game);
+        let listing = output(&ch);
+        assert_eq!(listing[0], "You have got 10 practice sessions left.\n\r");
+        assert_eq!(listing[1], "You can practise any of these skills:\n\r");
+        assert_eq!(&listing[2..], &["kick (not learned)\n\r", "bash (not learned)\n\r", "rescue (not learned)\n\r"]);
+
+        do_practice(Rc::clone(&ch), "kick", 0, &game);
+        assert_eq!(output(&ch), vec!["You can only practise in a guild.\n\r"]);
+        assert_eq!(ch.specials.borrow().spells_to_learn, 10);
+
+        // A warrior with 13 int learns 12% a session, up to 80%
+        for _ in 0..6 {
+            practice(&ch, "ba", &game);
+        }
+        assert_eq!(ch.skills.borrow()[SKILL_BASH as usize].learned, 72);
+        output(&ch);
+        practice(&ch, "bash", &game);
+        assert_eq!(ch.skills.borrow()[SKILL_BASH as usize].learned, 80);
+        assert_eq!(output(&ch), vec!["You Practice for a while...\n\r", "You are now learned in this area.\n\r"]);
+        practice(&ch, "bash", &game);
+        assert_eq!(output(&ch), vec!["You are already learned in this area.\n\r"]);
+        assert_eq!(ch.specials.borrow().spells_to_learn, 3);
+
+        practice(&ch, "magic missile", &game);
+        assert_eq!(output(&ch), vec!["You do not know of this skill...\n\r"]);
+
+        ch.specials.borrow_mut().spells_to_learn = 0;
+        practice(&ch, "kick", &game);
+        assert_eq!(output(&ch), vec!["You do not seem to be able to practice now.\n\r"]);
+    }
+
+    #[test]
+    fn practice_command_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Fighter", &room);
+        ch.specials.borrow_mut().spells_to_learn = 2;
+
+        command_interpreter(Rc::clone(&ch), "practice", &game);
+        assert_eq!(output(&ch)[0], "You have got 2 practice sessions left.\n\r");
+        command_interpreter(Rc::clone(&ch), "prac kick", &game);
+        assert_eq!(output(&ch), vec!["You can only practise in a guild.\n\r"]);
+
+        // in a guild the room takes the command before do_practice sees it
+        let mut guild = Rc::try_unwrap(test_room(3021)).ok().unwrap();
+        guild.funct = Some(guild_warrior);
+        let guild = Rc::new(guild);
+        let ch = test_char("Fighter", &guild);
+        ch.specials.borrow_mut().spells_to_learn = 2;
+        command_interpreter(Rc::clone(&ch), "practise kick", &game);
+        assert_eq!(output(&ch), vec!["You Practice for a while...\n\r"]);
+        assert_eq!(ch.skills.borrow()[SKILL_KICK as usize].learned, 12);
+        assert_eq!(ch.specials.borrow().spells_to_learn, 1);
+    }
+
+    #[test]
+    fn guild_test() {
+        let game = test_game();
+        let room = test_room(3017);
+        let ch = test_char("Mage", &room);
+        ch.player.borrow_mut().class = Class::MagicUser;
+        ch.specials.borrow_mut().spells_to_learn = 5;
+
+        assert!(!guild_magic_user(Rc::clone(&ch), 1, "", &game));
+
+        // Casters learn a quarter at a time, up to 95%
+        assert!(guild_magic_user(Rc::clone(&ch), CMD_PRACTICE, " magic missile", &game));
+        assert_eq!(ch.skills.borrow()[SPELL_MAGIC_MISSILE as usize].learned, 25);
+        for _ in 0..3 {
+            guild_magic_user(Rc::clone(&ch), CMD_PRACTICE, "magic", &game);
+        }
+        assert_eq!(ch.skills.borrow()[SPELL_MAGIC_MISSILE as usize].learned, 95);
+        output(&ch);
+
+        assert!(guild_warrior(Rc::clone(&ch), CMD_PRACTICE, "kick", &game));
+        assert_eq!(output(&ch), vec!["You will have to find your own guild to practice.\n\r"]);
+        assert_eq!(ch.specials.borrow().spells_to_learn, 1);
+    }
+
+    #[test]
+    fn sneak_and_hide_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = thief("Shadow", &room, 100, 18);
+
+        do_sneak(Rc::clone(&ch), "", 0, &game);
+        do_hide(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["Ok, you'll try to move silently for a while.\n\r",
+            "You attempt to hide yourself.\n\r"]);
+        assert!(affected_by_spell(&ch, SKILL_SNEAK));
+        assert!(ch.is_affected(AffectedFlags::AFF_SNEAK | AffectedFlags::AFF_HIDE));
+
+        // A clumsy novice never manages either, and loses what it had
+        let clumsy = thief("Clumsy", &room, 0, 0);
+        clumsy.specials.borrow_mut().affected_by.insert(AffectedFlags::AFF_HIDE);
+        do_sneak(Rc::clone(&clumsy), "", 0, &game);
+        do_hide(Rc::clone(&clumsy), "", 0, &game);
+        assert!(!clumsy.is_affected(AffectedFlags::AFF_SNEAK));
+        assert!(!clumsy.is_affected(AffectedFlags::AFF_HIDE));
+    }
+
+    #[test]
+    fn steal_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = thief("Shadow", &room, 100, 18);
+        let mob = merchant(&room);
+        obj_to_char(item("ring"), &mob);
+        equip_char(&mob, item("cloak"), EquipmentPosition::About);
+
+        do_steal(Rc::clone(&ch), "gold merchant", 0, &game);
+        let gold = ch.get_gold();
+        assert!(gold >= 10 && gold <= 100);
+        assert_eq!(mob.get_gold(), 1000 - gold);
+        assert_eq!(output(&ch), vec![format!("Bingo! You got {} gold coins.\n\r", gold)]);
+
+        do_steal(Rc::clone(&ch), "ring merchant", 0, &game);
+        assert_eq!(output(&ch), vec!["Got it!\n\r"]);
+        assert_eq!(ch.carrying.borrow().len(), 1);
+        assert!(mob.carrying.borrow().is_empty());
+
+        do_steal(Rc::clone(&ch), "cloak merchant", 0, &game);
+        do_steal(Rc::clone(&ch), "sword merchant", 0, &game);
+        do_steal(Rc::clone(&ch), "gold from nobody", 0, &game);
+        do_steal(Rc::clone(&ch), "gold shadow", 0, &game);
+        assert_eq!(output(&ch), vec!["Steal the equipment now? Impossible!\n\r",
+            "it has not got that item.", "Steal what from who?\n\r",
+            "Come on now, that's rather stupid!\n\r"]);
+
+        // Equipment only comes off a victim that is asleep or worse
+        let bystander = test_char("Bystander", &room);
+        mob.specials.borrow_mut().position = Position::Sleeping;
+        do_steal(Rc::clone(&ch), "cloak merchant", 0, &game);
+        assert_eq!(output(&ch), vec!["You unequip a cloak and steal it."]);
+        assert_eq!(output(&bystander), vec!["Shadow steals a cloak from the merchant."]);
+        assert!(mob.get_eq(EquipmentPosition::About).is_none());
+        assert_eq!(ch.carrying.borrow().len(), 2);
+
+        // Players are off limits unless player killing is allowed
+        let victim = test_char("Victim", &room);
+        do_steal(Rc::clone(&ch), "gold victim", 0, &game);
+        assert_eq!(output(&ch), vec!["You can't steal from other players.\n\r"]);
+    }
+
+    #[test]
+    fn steal_caught_test() {
+        let mut game = test_game();
+        game.pkill = true;
+        let room = test_room(3001);
+        let ch = thief("Clumsy", &room, 0, 0);
+        let victim = test_char("Victim", &room);
+        victim.points.borrow_mut().gold = 50;
+
+        do_steal(Rc::clone(&ch), "gold victim", 0, &game);
+        assert_eq!(output(&ch), vec!["Oops..\n\r"]);
+        assert_eq!(output(&victim), vec!["Clumsy tried to steal something from you!"]);
+        assert_eq!(victim.get_gold(), 50);
+
+        // A nice thief only shouts for the guards
+        let listener = test_char("Listener", &test_room(3002));
+        game.descriptor_list.borrow_mut().push(Rc::clone(listener.desc.borrow().as_ref().unwrap()));
+        let mob = merchant(&room);
+        mob.specials.borrow_mut().act.insert(SpecialActFlags::ACT_NICE_THIEF);
+        do_steal(Rc::clone(&ch), "gold merchant", 0, &game);
+        assert_eq!(output(&ch), vec!["Oops..\n\r", "Don't you ever do that again!\n\r"]);
+        assert_eq!(output(&listener), vec!["the merchant shouts 'Clumsy is a bloody thief.'"]);
+        assert!(mob.specials.borrow().fighting.is_none());
+
+        // Anyone else fights back
+        mob.specials.borrow_mut().act.remove(SpecialActFlags::ACT_NICE_THIEF);
+        do_steal(Rc::clone(&ch), "gold merchant", 0, &game);
+        assert!(Rc::ptr_eq(mob.specials.borrow().fighting.as_ref().unwrap(), &ch));
+        assert_eq!(mob.get_gold(), 1000);
+    }
+}
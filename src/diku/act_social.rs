@@ -1,29 +1,59 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Seek};
 use std::io::prelude::*;
+use std::rc::Rc;
 
+use diku::comm::{act, send_to_char};
 use diku::constants::{POSEMESS_FILE, SOCMESS_FILE};
-use diku::structs::{PoseType, SocialMessg};
-use diku::utility::read_number;
+use diku::handler::get_char_room_vis;
+use diku::interpreter::one_argument;
+use diku::structs::*;
+use diku::utility::{log, read_line_trim, read_number};
+
+// The command names of the socials, by command number
+const SOCIAL_NAMES: &'static [(i32, &'static str)] = &[
+    (9, "kiss"), (22, "bounce"), (23, "smile"), (24, "dance"), (26, "cackle"),
+    (27, "laugh"), (28, "giggle"), (29, "shake"), (30, "puke"), (31, "growl"),
+    (32, "scream"), (34, "comfort"), (35, "nod"), (36, "sigh"), (37, "sulk"),
+    (49, "hug"), (50, "snuggle"), (51, "cuddle"), (52, "nuzzle"), (53, "cry"),
+    (94, "poke"), (96, "accuse"), (97, "grin"), (98, "bow"), (104, "applaud"),
+    (105, "blush"), (106, "burp"), (107, "chuckle"), (108, "clap"), (109, "cough"),
+    (110, "curtsey"), (111, "fart"), (112, "flip"), (113, "fondle"), (114, "frown"),
+    (115, "gasp"), (116, "glare"), (117, "groan"), (118, "grope"), (119, "hiccup"),
+    (120, "lick"), (121, "love"), (122, "moan"), (123, "nibble"), (124, "pout"),
+    (125, "purr"), (126, "ruffle"), (127, "shiver"), (128, "shrug"), (129, "sing"),
+    (130, "slap"), (131, "smirk"), (132, "snap"), (133, "sneeze"), (134, "snicker"),
+    (135, "sniff"), (136, "snore"), (137, "spit"), (138, "squeeze"), (139, "stare"),
+    (140, "strut"), (141, "thank"), (142, "twiddle"), (143, "wave"), (144, "whistle"),
+    (145, "wiggle"), (146, "wink"), (147, "yawn"), (148, "snowball"), (160, "french"),
+    (161, "comb"), (162, "massage"), (163, "tickle"), (165, "pat"), (171, "curse"),
+    (176, "pray"), (178, "beg"), (180, "cringe"), (181, "daydream"), (182, "fume"),
+    (183, "grovel"),
+    ];
 
 pub fn boot_social_messages() -> Vec<SocialMessg> {
     let file = File::open(SOCMESS_FILE).expect("boot_social_messages");
-    let mut reader = BufReader::new(file);
+    read_social_messages(&mut BufReader::new(file))
+}
 
+// A social that is not in the command table has its name written after the
+// numbers, eg. "200 0 5 hmm"
+pub fn read_social_messages<R: Read + Seek>(reader: &mut BufReader<R>) -> Vec<SocialMessg> {
     let mut soc_mess_list = Vec::new();
     loop {
-        let tmp = read_number(&mut reader, true).unwrap();
+        let tmp = read_number(reader, true).unwrap();
         if tmp < 0 {
             break;
         }
-        let hide = read_number(&mut reader, true).unwrap();
-        let min_victim_position = match read_number(&mut reader, true) {
+        let hide = read_number(reader, true).unwrap();
+        let min_victim_position = match read_number(reader, false) {
             Ok(v) => v,
             Err(e) => panic!("Error in social message #{}: {}", tmp, e),
         };
-        let char_no_arg = fread_action(&mut reader);
-        let others_no_arg = fread_action(&mut reader);
-        let char_found = fread_action(&mut reader);
+        let name = read_line_trim(reader).to_lowercase();
+        let char_no_arg = fread_action(reader);
+        let others_no_arg = fread_action(reader);
+        let char_found = fread_action(reader);
 
         let mut others_found = None;
         let mut vict_found = None;
@@ -32,14 +62,27 @@ pub fn boot_social_messages() -> Vec<SocialMessg> {
         let mut others_auto = None;
 
         if char_found.is_some() {
-            others_found = fread_action(&mut reader);
-            vict_found = fread_action(&mut reader);
-            not_found = fread_action(&mut reader);
-            char_auto = fread_action(&mut reader);
-            others_auto = fread_action(&mut reader);
+            others_found = fread_action(reader);
+            vict_found = fread_action(reader);
+            not_found = fread_action(reader);
+            char_auto = fread_action(reader);
+            others_auto = fread_action(reader);
         }
 
+        let name = if !name.is_empty() {
+            name
+        } else {
+            match SOCIAL_NAMES.iter().find(|&&(nr, _)| nr == tmp) {
+                Some(&(_, name)) => String::from(name),
+                None => {
+                    log(&format!("SYSERR: Social message #{} has no name.", tmp));
+                    continue;
+                }
+            }
+        };
+
         soc_mess_list.push(SocialMessg {
+            name,
             act_nr: tmp,
             hide,
             min_victim_position,
@@ -59,11 +102,13 @@ pub fn boot_social_messages() -> Vec<SocialMessg> {
 
 pub fn boot_pose_messages() -> Vec<PoseType> {
     let file = File::open(POSEMESS_FILE).unwrap();
-    let mut reader = BufReader::new(file);
+    read_pose_messages(&mut BufReader::new(file))
+}
 
+pub fn read_pose_messages<R: Read + Seek>(reader: &mut BufReader<R>) -> Vec<PoseType> {
     let mut pose_messages = Vec::new();
     loop {
-        let level = read_number(&mut reader, true).unwrap();
+        let level = read_number(reader, true).unwrap();
         if level < 0 {
             break;
         }
@@ -73,8 +118,8 @@ pub fn boot_pose_messages() -> Vec<PoseType> {
             room_msg: Default::default(),
         };
         for class in 0..4 {
-            pose.poser_msg[class] = fread_action(&mut reader).unwrap();
-            pose.room_msg[class] = fread_action(&mut reader).unwrap();
+            pose.poser_msg[class] = fread_action(reader).unwrap();
+            pose.room_msg[class] = fread_action(reader).unwrap();
         }
         pose_messages.push(pose);
     }
@@ -92,4 +137,170 @@ fn fread_action<R: Read>(reader: &mut BufReader<R>) -> Option<String> {
     } else {
         Some(String::from(buf.trim()))
     }
-}
\ No newline at end of file
+}
+// The command number of the social called name; abbreviations are allowed
+pub fn find_action(name: &str, game: &Game) -> Option<i32> {
+    let name = name.to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    game.soc_mess_list.iter().find(|action| action.name == name)
+        .or_else(|| game.soc_mess_list.iter().find(|action| action.name.starts_with(&name)))
+        .map(|action| action.act_nr)
+}
+
+fn send_line(messg: &Option<String>, ch: &CharData) {
+    if let Some(ref messg) = *messg {
+        send_to_char(&format!("{}\n\r", messg), ch);
+    }
+}
+
+fn act_opt(messg: &Option<String>, hide: bool, ch: &CharData, vict: Option<&CharData>, type_: VictimType) {
+    if let Some(ref messg) = *messg {
+        act(messg, hide, ch, None, vict, None, None, type_);
+    }
+}
+
+pub fn do_action(ch: Rc<CharData>, argument: &str, cmd: i32, game: &Game) {
+    let action = match game.soc_mess_list.iter().find(|action| action.act_nr == cmd) {
+        Some(action) => action,
+        None => {
+            send_to_char("That action is not supported.\n\r", &ch);
+            return;
+        }
+    };
+    let hide = action.hide != 0;
+
+    let buf = if action.char_found.is_some() {
+        one_argument(argument).0
+    } else {
+        String::new()
+    };
+
+    if buf.is_empty() {
+        send_line(&action.char_no_arg, &ch);
+        act_opt(&action.others_no_arg, hide, &ch, None, VictimType::ToRoom);
+        return;
+    }
+
+    match get_char_room_vis(&ch, &buf) {
+        None => send_line(&action.not_found, &ch),
+        Some(ref vict) if Rc::ptr_eq(vict, &ch) => {
+            send_line(&action.char_auto, &ch);
+            act_opt(&action.others_auto, hide, &ch, None, VictimType::ToRoom);
+        },
+        Some(vict) => {
+            if (vict.get_pos() as i32) < action.min_victim_position {
+                act("$N is not in a proper position for that.", false, &ch, None, Some(&vict), None, None,
+                    VictimType::ToChar);
+            } else {
+                act_opt(&action.char_found, false, &ch, Some(&vict), VictimType::ToChar);
+                act_opt(&action.others_found, hide, &ch, Some(&vict), VictimType::ToNotVict);
+                act_opt(&action.vict_found, hide, &ch, Some(&vict), VictimType::ToVict);
+            }
+        }
+    }
+}
+
+pub fn do_pose(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    let level = ch.get_level() as i32;
+    let poses: Vec<&PoseType> = game.pose_messages.iter().filter(|pose| pose.level <= level).collect();
+    if ch.is_npc() || poses.is_empty() {
+        send_to_char("You can't do that.\n\r", &ch);
+        return;
+    }
+
+    let pose = poses[game.number(0, poses.len() as i32 - 1) as usize];
+    let class = ch.get_class() as usize - 1;
+    act(&pose.poser_msg[class], false, &ch, None, None, None, None, VictimType::ToChar);
+    act(&pose.room_msg[class], false, &ch, None, None, None, None, VictimType::ToRoom);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use diku::testutil::*;
+
+    fn social_game() -> Game {
+        let mut game = test_game();
+        let actions = File::open("lib/actions").expect("test actions file");
+        game.soc_mess_list = read_social_messages(&mut BufReader::new(actions));
+        let poses = File::open("lib/poses").expect("test poses file");
+        game.pose_messages = read_pose_messages(&mut BufReader::new(poses));
+        game
+    }
+
+    #[test]
+    fn read_social_messages_test() {
+        let game = social_game();
+        assert_eq!(game.soc_mess_list.len(), SOCIAL_NAMES.len());
+        assert_eq!(find_action("kiss", &game), Some(9));
+        assert_eq!(find_action("snu", &game), Some(50));
+        assert_eq!(find_action("xyzzy", &game), None);
+
+        let data = "9 0 0\nKiss whom?\n#\n#\n\n200 1 5 hmm\nYou hmm.\n$n hmms.\n#\n\n-1\n";
+        let list = read_social_messages(&mut BufReader::new(Cursor::new(data)));
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].name, "kiss");
+        assert_eq!(list[1].name, "hmm");
+        assert_eq!(list[1].hide, 1);
+        assert_eq!(list[1].min_victim_position, 5);
+        assert_eq!(list[1].others_no_arg, Some(String::from("$n hmms.")));
+    }
+
+    #[test]
+    fn action_test() {
+        let game = social_game();
+        let room = test_room(3001);
+        let ch = test_char("Romeo", &room);
+        let vict = test_char("Juliet", &room);
+        let other = test_char("Nurse", &room);
+        ch.player.borrow_mut().sex = Sex::Male;
+        vict.player.borrow_mut().sex = Sex::Female;
+        let smile = find_action("smile", &game).unwrap();
+
+        do_action(Rc::clone(&ch), "", smile, &game);
+        assert_eq!(output(&ch), vec!["You smile happily.\n\r"]);
+        assert_eq!(output(&vict), vec!["Romeo smiles happily."]);
+        output(&other);
+
+        do_action(Rc::clone(&ch), " juliet", smile, &game);
+        assert_eq!(output(&ch), vec!["You smile at her."]);
+        assert_eq!(output(&vict), vec!["Romeo smiles at you."]);
+        assert_eq!(output(&other), vec!["Romeo beams a smile at Juliet."]);
+
+        do_action(Rc::clone(&ch), " tybalt", smile, &game);
+        assert_eq!(output(&ch), vec!["There's no one by that name around.\n\r"]);
+
+        do_action(Rc::clone(&ch), " romeo", smile, &game);
+        assert_eq!(output(&ch), vec!["You smile at yourself.\n\r"]);
+        assert_eq!(output(&vict), vec!["Romeo smiles at himself."]);
+
+        // Dancing takes a partner who is standing
+        vict.specials.borrow_mut().position = Position::Sleeping;
+        do_action(Rc::clone(&ch), " juliet", find_action("dance", &game).unwrap(), &game);
+        assert_eq!(output(&ch), vec!["Juliet is not in a proper position for that."]);
+
+        do_action(Rc::clone(&ch), "", 1, &game);
+        assert_eq!(output(&ch), vec!["That action is not supported.\n\r"]);
+    }
+
+    #[test]
+    fn pose_test() {
+        let game = social_game();
+        let room = test_room(3001);
+        let ch = test_char("Poser", &room);
+        let watcher = test_char("Watcher", &room);
+        ch.player.borrow_mut().sex = Sex::Male;
+
+        do_pose(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["You can't do that.\n\r"]);
+
+        ch.player.borrow_mut().level = 5;
+        do_pose(Rc::clone(&ch), "", 0, &game);
+        assert_eq!(output(&ch), vec!["You show your bulging muscles."]);
+        assert_eq!(output(&watcher), vec!["Poser shows his bulging muscles."]);
+    }
+}
@@ -0,0 +1,1416 @@
+use std::rc::Rc;
+
+use diku::comm::{act, close_socket, send_to_all, send_to_char};
+use diku::constants::*;
+use diku::fight::update_pos;
+use diku::game::{read_mobile, read_object, save_char, secs_since_epoch};
+use diku::handler::{affect_total, char_from_room, char_to_room, extract_char, extract_obj, get_char_room_vis,
+    get_char_vis, get_obj_in_list_vis, get_obj_vis, obj_to_char, obj_to_room};
+use diku::interpreter::{argument_interpreter, command_interpreter, one_argument, CMD_REBOOT};
+use diku::limits::{gain_exp_regardless, hit_gain, mana_gain, move_gain};
+use diku::structs::*;
+use diku::utility::{log, sprintbit};
+
+pub fn do_snoop(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let desc = match *ch.desc.borrow() {
+        Some(ref desc) => Rc::clone(desc),
+        None => return,
+    };
+
+    let (arg, _) = one_argument(argument);
+    if arg.is_empty() {
+        send_to_char("Snoop who ?\n\r", &ch);
+        return;
+    }
+
+    let victim = match get_char_vis(&ch, &arg, game) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("No such person around.\n\r", &ch);
+            return;
+        }
+    };
+
+    let vdesc = match *victim.desc.borrow() {
+        Some(ref vdesc) => Rc::clone(vdesc),
+        None => {
+            send_to_char("There's no link.. nothing to snoop.\n\r", &ch);
+            return;
+        }
+    };
+
+    if Rc::ptr_eq(&victim, &ch) {
+        send_to_char("Ok, you just snoop yourself.\n\r", &ch);
+        let snooping = desc.borrow_mut().snoop.snooping.take();
+        if let Some(snooping) = snooping {
+            if let Some(ref sd) = *snooping.desc.borrow() {
+                sd.borrow_mut().snoop.snoop_by = None;
+            }
+        }
+        return;
+    }
+
+    if vdesc.borrow().snoop.snoop_by.is_some() {
+        send_to_char("Busy already. \n\r", &ch);
+        return;
+    }
+
+    // Only those below can be snooped, so snooping never goes round in circles
+    if victim.get_level() >= ch.get_level() {
+        send_to_char("You failed.\n\r", &ch);
+        return;
+    }
+
+    send_to_char("Ok. \n\r", &ch);
+    let snooping = desc.borrow_mut().snoop.snooping.take();
+    if let Some(snooping) = snooping {
+        if let Some(ref sd) = *snooping.desc.borrow() {
+            sd.borrow_mut().snoop.snoop_by = None;
+        }
+    }
+    desc.borrow_mut().snoop.snooping = Some(Rc::clone(&victim));
+    vdesc.borrow_mut().snoop.snoop_by = Some(Rc::clone(&ch));
+    wizlog(&ch, &format!("snoops {}.", victim.get_name()));
+}
+
+pub fn do_switch(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let (arg, _) = one_argument(argument);
+    if arg.is_empty() {
+        send_to_char("Switch with who?\n\r", &ch);
+        return;
+    }
+
+    let victim = match get_char_vis(&ch, &arg, game) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("They aren't here.\n\r", &ch);
+            return;
+        }
+    };
+
+    if Rc::ptr_eq(&victim, &ch) {
+        send_to_char("He he he... We are jolly funny today, eh?\n\r", &ch);
+        return;
+    }
+
+    let desc = match *ch.desc.borrow() {
+        Some(ref desc) if desc.borrow().snoop.snoop_by.is_none() && desc.borrow().snoop.snooping.is_none() =>
+            Rc::clone(desc),
+        _ => {
+            send_to_char("Mixing snoop & switch is bad for your health.\n\r", &ch);
+            return;
+        }
+    };
+
+    if victim.desc.borrow().is_some() || !victim.is_npc() {
+        send_to_char("You can't do that, the body is already in use!\n\r", &ch);
+        return;
+    }
+
+    send_to_char("Ok.\n\r", &ch);
+    wizlog(&ch, &format!("switched into {}.", victim.get_name()));
+    desc.borrow_mut().character = Some(Rc::clone(&victim));
+    desc.borrow_mut().original = Some(Rc::clone(&ch));
+    *victim.desc.borrow_mut() = Some(desc);
+    *ch.desc.borrow_mut() = None;
+}
+
+pub fn do_return(ch: Rc<CharData>, _argument: &str, _cmd: i32, _game: &Game) {
+    let desc = match *ch.desc.borrow() {
+        Some(ref desc) => Rc::clone(desc),
+        None => return,
+    };
+
+    let original = desc.borrow_mut().original.take();
+    match original {
+        None => send_to_char("Eh?\n\r", &ch),
+        Some(original) => {
+            send_to_char("You return to your original body.\n\r", &ch);
+            desc.borrow_mut().character = Some(Rc::clone(&original));
+            *original.desc.borrow_mut() = Some(desc);
+            *ch.desc.borrow_mut() = None;
+        }
+    }
+}
+
+// Every use of the powers of the gods ends up in the log
+fn wizlog(ch: &CharData, what: &str) {
+    log(&format!("(GC) {} {}", ch.get_name(), what));
+}
+
+// A room number, or the room of a character or object called arg
+fn find_location(ch: &CharData, arg: &str, game: &Game) -> Result<Rc<RoomData>, &'static str> {
+    if arg.chars().next().map_or(false, |c| c.is_ascii_digit()) {
+        return arg.parse().ok()
+            .and_then(|nr| game.world.get(&nr))
+            .cloned()
+            .ok_or("No room exists with that number.\n\r");
+    }
+
+    if let Some(target) = get_char_vis(ch, arg, game) {
+        return Ok(Rc::clone(&target.in_room.borrow()));
+    }
+
+    match get_obj_vis(ch, arg, game) {
+        Some(target) => target.in_room.borrow().clone().ok_or("The object is not available.\n\r"),
+        None => Err("No such creature or object around.\n\r"),
+    }
+}
+
+pub fn do_goto(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (arg, _) = one_argument(argument);
+    if arg.is_empty() {
+        send_to_char("You must supply a room number or a name.\n\r", &ch);
+        return;
+    }
+
+    let location = match find_location(&ch, &arg, game) {
+        Ok(location) => location,
+        Err(messg) => {
+            send_to_char(messg, &ch);
+            return;
+        }
+    };
+
+    if location.room_flags.contains(RoomFlags::PRIVATE) && location.people.borrow().len() > 1 {
+        send_to_char("There's a private conversation going on in that room.\n\r", &ch);
+        return;
+    }
+
+    act("$n disappears in a puff of smoke.", false, &ch, None, None, None, None, VictimType::ToRoom);
+    char_from_room(&ch);
+    char_to_room(&ch, &location);
+    act("$n appears with an ear-splitting bang.", false, &ch, None, None, None, None, VictimType::ToRoom);
+    wizlog(&ch, &format!("went to room {}.", location.number));
+}
+
+// Performs a command somewhere else, and comes back if still around
+pub fn do_at(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (arg, command) = one_argument(argument);
+    if arg.is_empty() {
+        send_to_char("You must supply a room number or a name.\n\r", &ch);
+        return;
+    }
+
+    let location = match find_location(&ch, &arg, game) {
+        Ok(location) => location,
+        Err(messg) => {
+            send_to_char(messg, &ch);
+            return;
+        }
+    };
+
+    wizlog(&ch, &format!("at room {}:{}", location.number, command));
+    let original_loc = Rc::clone(&ch.in_room.borrow());
+    char_from_room(&ch);
+    char_to_room(&ch, &location);
+    command_interpreter(Rc::clone(&ch), command, game);
+
+    // check if the guy's still there
+    if location.people.borrow().iter().any(|k| Rc::ptr_eq(k, &ch)) {
+        char_from_room(&ch);
+        char_to_room(&ch, &original_loc);
+    }
+}
+
+fn transfer(ch: &CharData, victim: &Rc<CharData>, location: &Rc<RoomData>) {
+    act("$n disappears in a mushroom cloud.", false, victim, None, None, None, None, VictimType::ToRoom);
+    char_from_room(victim);
+    char_to_room(victim, location);
+    act("$n arrives from a puff of smoke.", false, victim, None, None, None, None, VictimType::ToRoom);
+    act("$n has transferred you!", false, ch, None, Some(victim), None, None, VictimType::ToVict);
+}
+
+// Brings a character, or everybody, to the god or to the place given
+pub fn do_trans(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, rest) = one_argument(argument);
+    if name.is_empty() {
+        send_to_char("Who do you wich to transfer?\n\r", &ch);
+        return;
+    }
+
+    let (to, _) = one_argument(rest);
+    let location = if to.is_empty() {
+        Rc::clone(&ch.in_room.borrow())
+    } else {
+        match find_location(&ch, &to, game) {
+            Ok(location) => location,
+            Err(messg) => {
+                send_to_char(messg, &ch);
+                return;
+            }
+        }
+    };
+
+    if name != "all" {
+        let victim = match get_char_vis(&ch, &name, game) {
+            Some(victim) => victim,
+            None => {
+                send_to_char("No-one by that name around.\n\r", &ch);
+                return;
+            }
+        };
+        transfer(&ch, &victim, &location);
+        wizlog(&ch, &format!("transferred {} to room {}.", victim.get_name(), location.number));
+    } else {
+        let victims: Vec<Rc<CharData>> = game.descriptor_list.borrow().iter()
+            .filter(|d| d.borrow().connected == ConnectionMode::Plying)
+            .filter_map(|d| d.borrow().character.clone())
+            .filter(|victim| !Rc::ptr_eq(victim, &ch))
+            .collect();
+        for victim in victims {
+            transfer(&ch, &victim, &location);
+        }
+        wizlog(&ch, &format!("transferred everybody to room {}.", location.number));
+    }
+    send_to_char("Ok.\n\r", &ch);
+}
+
+pub fn do_load(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (type_, num) = argument_interpreter(argument);
+    if type_.is_empty() || !num.chars().next().map_or(false, |c| c.is_ascii_digit()) {
+        send_to_char("Syntax:\n\rload <'char' | 'obj'> <number>.\n\r", &ch);
+        return;
+    }
+    let number: u32 = match num.parse() {
+        Ok(number) => number,
+        Err(_) => {
+            send_to_char("Syntax:\n\rload <'char' | 'obj'> <number>.\n\r", &ch);
+            return;
+        }
+    };
+
+    let room = Rc::clone(&ch.in_room.borrow());
+    if "char".starts_with(&type_[..]) {
+        let mob = match read_mobile(number, &room, game) {
+            Some(mob) => mob,
+            None => {
+                send_to_char("There is no monster with that number.\n\r", &ch);
+                return;
+            }
+        };
+        act("$n makes a quaint, magical gesture with one hand.", true, &ch, None, None, None, None,
+            VictimType::ToRoom);
+        act("$n has created $N!", false, &ch, None, Some(&mob), None, None, VictimType::ToRoom);
+        send_to_char("Done.\n\r", &ch);
+        wizlog(&ch, &format!("loaded mobile {} in room {}.", number, room.number));
+    } else if "obj".starts_with(&type_[..]) {
+        let obj = match read_object(number, game) {
+            Some(obj) => obj,
+            None => {
+                send_to_char("There is no object with that number.\n\r", &ch);
+                return;
+            }
+        };
+        act("$n makes a strange magical gesture.", true, &ch, None, None, None, None, VictimType::ToRoom);
+        act("$n has created $p!", false, &ch, Some(&obj), None, None, None, VictimType::ToRoom);
+        if obj.can_wear(WearFlags::ITEM_TAKE) {
+            obj_to_char(obj, &ch);
+        } else {
+            obj_to_room(obj, &room);
+        }
+        send_to_char("Ok.\n\r", &ch);
+        wizlog(&ch, &format!("loaded object {} in room {}.", number, room.number));
+    } else {
+        send_to_char("That'll have to be either 'char' or 'obj'.\n\r", &ch);
+    }
+}
+
+// Destroys a character or object in the room, or everything but the players
+pub fn do_purge(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, _) = one_argument(argument);
+    let room = Rc::clone(&ch.in_room.borrow());
+
+    if !name.is_empty() {
+        if let Some(vict) = get_char_room_vis(&ch, &name) {
+            if !vict.is_npc() && ch.get_level() < 24 {
+                send_to_char("Fuuuuuuuuu!\n\r", &ch);
+                return;
+            }
+
+            act("$n disintegrates $N.", false, &ch, None, Some(&vict), None, None, VictimType::ToNotVict);
+            wizlog(&ch, &format!("purged {} in room {}.", vict.get_name(), room.number));
+            let desc = vict.desc.borrow().clone();
+            if let Some(desc) = desc {
+                close_socket(&desc, game);
+            }
+            extract_char(&vict, game);
+        } else if let Some(obj) = get_obj_in_list_vis(&ch, &name, &room.contents.borrow().clone()) {
+            act("$n destroys $p.", false, &ch, Some(&obj), None, None, None, VictimType::ToRoom);
+            wizlog(&ch, &format!("purged {} in room {}.", obj.name, room.number));
+            extract_obj(&obj, game);
+        } else {
+            send_to_char("I don't know anyone or anything by that name.\n\r", &ch);
+            return;
+        }
+        send_to_char("Ok.\n\r", &ch);
+    } else {
+        if ch.is_npc() {
+            send_to_char("Don't... You would only kill yourself..\n\r", &ch);
+            return;
+        }
+
+        act("$n gestures... You are surrounded by scorching flames!", false, &ch, None, None, None, None,
+            VictimType::ToRoom);
+        for k in room.people.borrow().iter() {
+            send_to_char("The world seems a little cleaner.\n\r", k);
+        }
+        wizlog(&ch, &format!("purged room {}.", room.number));
+
+        let mobs: Vec<Rc<CharData>> = room.people.borrow().iter().filter(|k| k.is_npc()).cloned().collect();
+        for vict in mobs {
+            extract_char(&vict, game);
+        }
+        let contents = room.contents.borrow().clone();
+        for obj in contents {
+            extract_obj(&obj, game);
+        }
+    }
+}
+
+fn stat_room(room: &RoomData) -> String {
+    let mut buf = format!("Room name: {}, Of zone : {}. V-Number : {}\n\r", room.name, room.zone, room.number);
+    buf.push_str(&format!("Sector type : {}, Special procedure : {}\n\r", SECTOR_TYPES[room.sector_type as usize],
+        if room.funct.is_some() { "Exists" } else { "No" }));
+    buf.push_str(&format!("Room flags: {}\n\r", sprintbit(room.room_flags.bits() as u64, &ROOM_BITS)));
+    buf.push_str(&format!("Light: {}\n\r", room.light.borrow()));
+    buf.push_str(&format!("Description:\n\r{}", room.description));
+
+    let keywords: Vec<&str> = room.ex_description.iter().map(|ex| &ex.keyword[..]).collect();
+    buf.push_str(&format!("Extra description keywords(s): {}\n\r", keywords.join(" ")));
+
+    buf.push_str("------- Chars present -------\n\r");
+    for k in room.people.borrow().iter() {
+        let kind = if !k.is_npc() { "PC" } else if k.is_mob() { "MOB" } else { "NPC" };
+        buf.push_str(&format!("{}({})\n\r", k.get_name(), kind));
+    }
+
+    buf.push_str("--------- Contents ---------\n\r");
+    for obj in room.contents.borrow().iter() {
+        buf.push_str(&format!("{}\n\r", obj.name));
+    }
+
+    buf.push_str("------- Exits defined -------\n\r");
+    for door in (0..6).map(Direction::from) {
+        if let Some(exit) = room.dir_option.get(&door) {
+            buf.push_str(&format!("Direction {} . Keyword : {}\n\r", DIRS[door as usize], exit.keyword));
+            buf.push_str(&format!("Description:\n\r  {}", exit.general_description));
+            buf.push_str(&format!("Exit flag: {}\n\r", sprintbit(exit.exit_info.borrow().bits() as u64, &EXIT_BITS)));
+            buf.push_str(&format!("Key no: {}\n\rTo room (V-Number): {}\n\r",
+                exit.key.map_or(-1, |key| key as i64), exit.to_room.map_or(-1, |to| to as i64)));
+        }
+    }
+
+    buf
+}
+
+fn stat_char(k: &CharData) -> String {
+    let kind = if !k.is_npc() { "PC" } else if k.is_mob() { "MOB" } else { "NPC" };
+    let mut buf = format!("{} - Name : {} [V-Number {}], In room [{}]\n\r", kind, k.get_name(),
+        k.nr.map_or(-1, |nr| nr as i64), k.in_room.borrow().number);
+
+    {
+        let player = k.player.borrow();
+        buf.push_str(&format!("Short description: {}\n\r", player.short_descr));
+        buf.push_str(&format!("Title: {}\n\r", player.title));
+        buf.push_str(&format!("Long description: {}", player.long_descr));
+        if !player.long_descr.ends_with('\r') {
+            buf.push_str("\n\r");
+        }
+        buf.push_str(&format!("Sex: {}, Class: {}, Hometown: {}\n\r", SEX_TYPES[player.sex as usize],
+            if k.is_npc() { "Monster" } else { PC_CLASS_TYPES[player.class as usize - 1] }, player.hometown));
+        buf.push_str(&format!("Level [{}] XP [{}] Alignment [{}]\n\r", player.level, k.get_exp(),
+            k.get_alignment()));
+        buf.push_str(&format!("Birth : [{}]secs, Logon[{}]secs, Played[{}]secs\n\r",
+            secs_since_epoch(player.time.birth), secs_since_epoch(player.time.logon), player.time.played.as_secs()));
+    }
+
+    let age = k.age();
+    buf.push_str(&format!("Age: [{}] Years,  [{}] Months,  [{}] Days,  [{}] Hours\n\r", age.year, age.month,
+        age.day, age.hours));
+    buf.push_str(&format!("Height [{}]cm  Weight [{}]pounds \n\r", k.get_height(), k.get_weight()));
+
+    let abilities = *k.abilities.borrow();
+    buf.push_str(&format!("Str:[{}/{}]  Int:[{}]  Wis:[{}]  Dex:[{}]  Con:[{}]\n\r", abilities.str,
+        abilities.str_add, abilities.intel, abilities.wis, abilities.dex, abilities.con));
+    buf.push_str(&format!("Affected Str:[{}/{}]  Int:[{}]  Wis:[{}]  Dex:[{}]  Con:[{}]\n\r", k.get_str(),
+        k.get_add(), k.get_int(), k.get_wis(), k.get_dex(), k.get_con()));
+
+    let points = *k.points.borrow();
+    buf.push_str(&format!("Mana p.:[{}/{}+{}]  Hit p.:[{}/{}+{}]  Move p.:[{}/{}+{}]\n\r",
+        points.mana, k.get_max_mana(), mana_gain(k), points.hit, k.get_max_hit(), hit_gain(k),
+        points.mov, k.get_move_max(), move_gain(k)));
+    buf.push_str(&format!("AC:[{}/10], Coins: [{}], Exp: [{}], Hitroll: [{}], Damroll: [{}]\n\r",
+        points.armor, points.gold, points.exp, points.hitroll, points.damroll));
+
+    {
+        let specials = k.specials.borrow();
+        buf.push_str(&format!("Position: {}, Fighting: {}\n\r", POSITION_TYPES[specials.position as usize],
+            specials.fighting.as_ref().map_or(String::from("Nobody"), |f| f.get_name())));
+        if k.is_npc() {
+            buf.push_str(&format!("Default position: {}, NPC flags: {}\n\r",
+                POSITION_TYPES[specials.default_pos as usize], sprintbit(specials.act.bits() as u64, &ACTION_BITS)));
+            buf.push_str(&format!("NPC Bare Hand Damage {}d{}.\n\r", specials.damnodice, specials.damsizedice));
+        } else {
+            buf.push_str(&format!("Flags (Specials Act): {}\n\r", sprintbit(specials.act.bits() as u64, &PLAYER_BITS)));
+        }
+        buf.push_str(&format!("Timer [{}], Spells to learn [{}], Was in room [{}]\n\r", specials.timer,
+            specials.spells_to_learn, specials.was_in_room.map_or(-1, |room| room as i64)));
+        buf.push_str(&format!("Carried weight: {}   Carried items: {}\n\r", specials.carry_weight,
+            specials.carry_items));
+    }
+
+    let worn = k.equipment.borrow().iter().filter(|&(_, e)| e.is_some()).count();
+    buf.push_str(&format!("Items in inventory: {}, Items in equipment: {}\n\r", k.carrying.borrow().len(), worn));
+    for (pos, obj) in k.equipment.borrow().iter() {
+        if let Some(ref obj) = *obj {
+            buf.push_str(&format!("    {}: {}\n\r", EQUIPMENT_TYPES[pos as usize], obj.short_description));
+        }
+    }
+
+    {
+        let specials = k.specials.borrow();
+        let saves: Vec<String> = specials.apply_saving_throw.iter().map(|(_, save)| format!("[{}]", save)).collect();
+        buf.push_str(&format!("Apply saving throws: {}\n\r", saves.join(" ")));
+        buf.push_str(&format!("Thirst: {}, Hunger: {}, Drunk: {}\n\r", specials.conditions[Condition::Thirst],
+            specials.conditions[Condition::Full], specials.conditions[Condition::Drunk]));
+    }
+
+    {
+        let channels = k.channels.borrow();
+        buf.push_str(&format!("Channels off: {}, Ignoring: {}\n\r",
+            sprintbit(channels.off.bits() as u64, &["tell", "shout", "gossip"]),
+            if channels.ignore.is_empty() { String::from("Nobody") } else { channels.ignore.join(" ") }));
+    }
+
+    if let Some(ref desc) = *k.desc.borrow() {
+        let desc = desc.borrow();
+        buf.push_str(&format!("Host: {}, Snooped by: {}, Original: {}\n\r", desc.host,
+            desc.snoop.snoop_by.as_ref().map_or(String::from("Nobody"), |s| s.get_name()),
+            desc.original.as_ref().map_or(String::from("Nobody"), |o| o.get_name())));
+    }
+
+    buf.push_str(&format!("Master is '{}'\n\r",
+        k.master.borrow().as_ref().map_or(String::from("NOBODY"), |m| m.get_name())));
+    buf.push_str("Followers are:\n\r");
+    for follower in k.followers.borrow().iter() {
+        buf.push_str(&format!("    {}\n\r", follower.get_name()));
+    }
+
+    buf.push_str(&format!("Affected by: {}\n\r",
+        sprintbit(k.specials.borrow().affected_by.bits() as u64, &AFFECTED_BITS)));
+    if !k.affected.borrow().is_empty() {
+        buf.push_str("Affecting Spells:\n\r--------------\n\r");
+        for aff in k.affected.borrow().iter() {
+            let spell = if aff.spell_type >= 1 { SPELLS.get(aff.spell_type as usize - 1) } else { None };
+            buf.push_str(&format!("Spell : '{}'\n\r", spell.unwrap_or(&"UNKNOWN")));
+            buf.push_str(&format!("     Modifies {} by {} points\n\r", APPLY_TYPES[aff.location as usize],
+                aff.modifier));
+            buf.push_str(&format!("     Expires in {:3} hours, Bits set {}\n\r", aff.duration,
+                sprintbit(aff.bitvector.bits() as u64, &AFFECTED_BITS)));
+        }
+    }
+
+    buf
+}
+
+fn stat_obj(j: &ObjData, game: &Game) -> String {
+    let flags = j.obj_flags.borrow();
+    let mut buf = format!("Object name: [{}], V-number: [{}] Item type: {}\n\r", j.name,
+        j.item_number.map_or(-1, |nr| nr as i64), ITEM_TYPES.get(flags.type_flag as usize).unwrap_or(&"UNDEFINED"));
+    buf.push_str(&format!("Short description: {}\n\rLong description:\n\r{}\n\r", j.short_description,
+        j.description));
+    buf.push_str(&format!("Action description: {}\n\r", j.action_description));
+
+    let keywords: Vec<&str> = j.ex_description.iter().map(|ex| &ex.keyword[..]).collect();
+    buf.push_str(&format!("Extra description keyword(s): {}\n\r", keywords.join(" ")));
+
+    buf.push_str(&format!("Can be worn on : {}\n\r", sprintbit(flags.wear_flags.bits() as u64, &WEAR_BITS)));
+    buf.push_str(&format!("Set char bits  : {}\n\r", sprintbit(flags.bitvector, &AFFECTED_BITS)));
+    buf.push_str(&format!("Extra flags: {}\n\r", sprintbit(flags.extra_flags.bits() as u64, &EXTRA_BITS)));
+    buf.push_str(&format!("Weight: {}, Value: {}, Cost/day: {}, Timer: {}\n\r", flags.weight, flags.cost,
+        flags.cost_per_day, flags.timer));
+    buf.push_str(&format!("Values 0-3 : [{}] [{}] [{}] [{}]\n\r", flags.value[0], flags.value[1], flags.value[2],
+        flags.value[3]));
+
+    buf.push_str(&format!("In room: {} ,In object: {} ,Carried by: {}\n\r",
+        j.in_room.borrow().as_ref().map_or(-1, |room| room.number as i64),
+        j.in_obj.borrow().as_ref().map_or(String::from("None"), |obj| obj.short_description.clone()),
+        j.carried_by.borrow().as_ref().map_or(String::from("Nobody"), |ch| ch.get_name())));
+
+    let func = j.item_number.and_then(|nr| game.obj_index.get(&nr)).and_then(|index| index.func);
+    buf.push_str(&format!("Special procedure : {}\n\r", if func.is_some() { "Exists" } else { "No" }));
+
+    buf.push_str("Contains :\n\r");
+    for obj in j.contains.borrow().iter() {
+        buf.push_str(&format!("    {}\n\r", obj.name));
+    }
+
+    buf.push_str("Can affect char :\n\r");
+    for aff in j.affected.borrow().iter().filter(|aff| aff.location != 0) {
+        buf.push_str(&format!("    Affects : {} By {}\n\r", APPLY_TYPES.get(aff.location as usize).unwrap_or(&"NONE"),
+            aff.modifier as i16));
+    }
+    buf
+}
+
+pub fn do_stat(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let (arg, _) = one_argument(argument);
+    if arg.is_empty() {
+        send_to_char("Stats on who or what?\n\r", &ch);
+        return;
+    }
+
+    if arg == "room" {
+        let room = Rc::clone(&ch.in_room.borrow());
+        send_to_char(&stat_room(&room), &ch);
+    } else if let Some(k) = get_char_vis(&ch, &arg, game) {
+        send_to_char(&stat_char(&k), &ch);
+    } else if let Some(j) = get_obj_vis(&ch, &arg, game) {
+        send_to_char(&stat_obj(&j, game), &ch);
+    } else {
+        send_to_char("No mobile or object by that name in the world\n\r", &ch);
+    }
+}
+
+// Changes one field of a character; value is a number, or on/off for flags
+fn set_field(vict: &CharData, field: &str, value: &str) -> Result<(), &'static str> {
+    let flag_names: &[&str] = if vict.is_npc() { &ACTION_BITS } else { &PLAYER_BITS };
+    if let Some(bit) = flag_names.iter().position(|name| name.eq_ignore_ascii_case(field)) {
+        // The bit telling players from mobiles can't be changed
+        let bit = SpecialActFlags::from_bits_truncate(1 << bit);
+        if bit == SpecialActFlags::ACT_ISNPC {
+            return Err("Unknown field.\n\r");
+        }
+        let on = match value {
+            "on" => true,
+            "off" => false,
+            _ => return Err("Value must be on or off.\n\r"),
+        };
+        vict.specials.borrow_mut().act.set(bit, on);
+        return Ok(());
+    }
+
+    let number: i32 = value.parse().map_err(|_| "Value must be a number.\n\r")?;
+    let small = || if (i16::MIN as i32..=i16::MAX as i32).contains(&number) {
+        Ok(number as i16)
+    } else {
+        Err("Value is out of range.\n\r")
+    };
+    let tiny = || if (i8::MIN as i32..=i8::MAX as i32).contains(&number) {
+        Ok(number as i8)
+    } else {
+        Err("Value is out of range.\n\r")
+    };
+    let ability = || if (3..=25).contains(&number) {
+        Ok(number as u8)
+    } else {
+        Err("Abilities range from 3 to 25.\n\r")
+    };
+
+    match field {
+        "hit" => vict.points.borrow_mut().hit = small()?,
+        "maxhit" => vict.points.borrow_mut().max_hit = small()?,
+        "mana" => vict.points.borrow_mut().mana = small()?,
+        "maxmana" => vict.points.borrow_mut().max_mana = small()?,
+        "move" => vict.points.borrow_mut().mov = small()?,
+        "maxmove" => vict.points.borrow_mut().max_mov = small()?,
+        "armor" => vict.points.borrow_mut().armor = small()?,
+        "gold" => vict.points.borrow_mut().gold = number,
+        "exp" => vict.points.borrow_mut().exp = number,
+        "hitroll" => vict.points.borrow_mut().hitroll = tiny()?,
+        "damroll" => vict.points.borrow_mut().damroll = tiny()?,
+        "align" => vict.specials.borrow_mut().alignment = number.clamp(-1000, 1000),
+        "str" => vict.abilities.borrow_mut().str = ability()?,
+        "stradd" => vict.abilities.borrow_mut().str_add = number.clamp(0, 100) as u8,
+        "int" => vict.abilities.borrow_mut().intel = ability()?,
+        "wis" => vict.abilities.borrow_mut().wis = ability()?,
+        "dex" => vict.abilities.borrow_mut().dex = ability()?,
+        "con" => vict.abilities.borrow_mut().con = ability()?,
+        _ => return Err("Unknown field.\n\r"),
+    }
+    affect_total(vict);
+    Ok(())
+}
+
+pub fn do_set(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    // The value is taken as it is, "on" would be skipped as a fill word
+    let (name, rest) = one_argument(argument);
+    let (field, rest) = one_argument(rest);
+    let value = rest.trim().to_lowercase();
+    if name.is_empty() || field.is_empty() || value.is_empty() {
+        send_to_char("Usage: set <victim> <field> <value>\n\r", &ch);
+        return;
+    }
+
+    let vict = match get_char_vis(&ch, &name, game) {
+        Some(vict) => vict,
+        None => {
+            send_to_char("No-one by that name here..\n\r", &ch);
+            return;
+        }
+    };
+
+    if !vict.is_npc() && !Rc::ptr_eq(&vict, &ch) && vict.get_level() >= ch.get_level() {
+        send_to_char("Oh no you don't!!\n\r", &ch);
+        return;
+    }
+
+    match set_field(&vict, &field, &value) {
+        Ok(()) => {
+            send_to_char("Ok.\n\r", &ch);
+            wizlog(&ch, &format!("set {} of {} to {}.", field, vict.get_name(), value));
+        },
+        Err(messg) => send_to_char(messg, &ch),
+    }
+}
+
+pub fn do_force(ch: Rc<CharData>, argument: &str, cmd: i32, game: &Game) {
+    if ch.is_npc() && cmd != 0 {
+        return;
+    }
+
+    let (name, to_force) = one_argument(argument);
+    let to_force = to_force.trim();
+    if name.is_empty() || to_force.is_empty() {
+        send_to_char("Who do you wish to force to do what?\n\r", &ch);
+        return;
+    }
+
+    // A '$' in the command would otherwise be taken as an act code
+    let buf = format!("$n has forced you to '{}'.", to_force.replace('$', "$$"));
+
+    if name != "all" {
+        let vict = match get_char_vis(&ch, &name, game) {
+            Some(vict) => vict,
+            None => {
+                send_to_char("No-one by that name here..\n\r", &ch);
+                return;
+            }
+        };
+
+        if ch.get_level() < vict.get_level() && !vict.is_npc() {
+            send_to_char("Oh no you don't!!\n\r", &ch);
+            return;
+        }
+
+        act(&buf, false, &ch, None, Some(&vict), None, None, VictimType::ToVict);
+        send_to_char("Ok.\n\r", &ch);
+        wizlog(&ch, &format!("forced {} to '{}'.", vict.get_name(), to_force));
+        command_interpreter(vict, to_force, game);
+    } else {
+        let victims: Vec<Rc<CharData>> = game.descriptor_list.borrow().iter()
+            .filter(|d| d.borrow().connected == ConnectionMode::Plying)
+            .filter_map(|d| d.borrow().character.clone())
+            .filter(|vict| !Rc::ptr_eq(vict, &ch))
+            .collect();
+        wizlog(&ch, &format!("forced everybody to '{}'.", to_force));
+        for vict in victims {
+            if ch.get_level() < vict.get_level() && !vict.is_npc() {
+                send_to_char("Oh no you don't!!\n\r", &ch);
+            } else {
+                act(&buf, false, &ch, None, Some(&vict), None, None, VictimType::ToVict);
+                command_interpreter(vict, to_force, game);
+            }
+        }
+        send_to_char("Ok.\n\r", &ch);
+    }
+}
+
+pub fn do_restore(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, _) = one_argument(argument);
+    if name.is_empty() {
+        send_to_char("Who do you wich to restore?\n\r", &ch);
+        return;
+    }
+
+    let victim = match get_char_vis(&ch, &name, game) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("No-one by that name in the world.\n\r", &ch);
+            return;
+        }
+    };
+
+    let (mana, hit, mov) = (victim.get_max_mana(), victim.get_max_hit(), victim.get_move_max());
+    {
+        let mut points = victim.points.borrow_mut();
+        points.mana = mana;
+        points.hit = hit;
+        points.mov = mov;
+    }
+
+    // Gods know all there is to know, and the greatest have no equal
+    if victim.get_level() >= 21 {
+        for skill in victim.skills.borrow_mut().iter_mut() {
+            skill.learned = 100;
+            skill.recognise = true;
+        }
+
+        if victim.get_level() >= 24 {
+            *victim.abilities.borrow_mut() = CharAbilityData {
+                str: 25, str_add: 100, intel: 25, wis: 25, dex: 25, con: 25,
+            };
+        }
+        affect_total(&victim);
+    }
+
+    update_pos(&victim);
+    send_to_char("Done.\n\r", &ch);
+    act("You have been fully healed by $N!", false, &victim, None, Some(&ch), None, None, VictimType::ToChar);
+    wizlog(&ch, &format!("restored {}.", victim.get_name()));
+}
+
+pub fn do_advance(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let (name, level) = argument_interpreter(argument);
+    if name.is_empty() {
+        send_to_char("Advance who?\n\r", &ch);
+        return;
+    }
+
+    let victim = match get_char_room_vis(&ch, &name) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("That player is not here.\n\r", &ch);
+            return;
+        }
+    };
+
+    if victim.is_npc() {
+        send_to_char("NO! Not on NPC's.\n\r", &ch);
+        return;
+    }
+
+    if level.is_empty() {
+        send_to_char("You must supply a level number.\n\r", &ch);
+        return;
+    }
+    let newlevel: u8 = match level.parse() {
+        Ok(newlevel) => newlevel,
+        Err(_) => {
+            send_to_char("Second argument must be a positive integer.\n\r", &ch);
+            return;
+        }
+    };
+    if newlevel < victim.get_level() {
+        send_to_char("Can't diminish a players status (yet).\n\r", &ch);
+        return;
+    }
+    if newlevel > 1 && ch.get_level() < 24 {
+        send_to_char("Thou art not godly enough.\n\r", &ch);
+        return;
+    }
+    if newlevel > 24 {
+        send_to_char("24 is the highest possible level.\n\r", &ch);
+        return;
+    }
+
+    send_to_char("You feel generous.\n\r", &ch);
+    act("$n makes some strange gestures.\n\rA strange feeling comes uppon you,\n\rLike a giant hand, light comes \
+        down from\n\rabove, grabbing your body, that begins\n\rto pulse with coloured lights from inside.\n\rYo head \
+        seems to be filled with deamons\n\rfrom another plane as your body dissolves\n\rinto the elements of time \
+        and space itself.\n\rSuddenly a silent explosion of light snaps\n\ryou back to reality. You feel \
+        slightly\n\rdifferent.", false, &ch, None, Some(&victim), None, None, VictimType::ToVict);
+
+    let exp = TITLES[victim.get_class() as usize - 1][newlevel as usize].exp;
+    gain_exp_regardless(&victim, exp - victim.get_exp(), game);
+    wizlog(&ch, &format!("advanced {} to level {}.", victim.get_name(), newlevel));
+}
+
+// A frozen player can't do a thing until thawed again
+pub fn do_freeze(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    let (name, _) = one_argument(argument);
+    if name.is_empty() {
+        send_to_char("Freeze who?\n\r", &ch);
+        return;
+    }
+
+    let victim = match get_char_vis(&ch, &name, game) {
+        Some(victim) => victim,
+        None => {
+            send_to_char("No-one by that name around.\n\r", &ch);
+            return;
+        }
+    };
+
+    if victim.is_npc() {
+        send_to_char("NO! Not on NPC's.\n\r", &ch);
+        return;
+    }
+
+    if victim.get_level() >= ch.get_level() {
+        send_to_char("Oh no you don't!!\n\r", &ch);
+        return;
+    }
+
+    let frozen = victim.specials.borrow().act.contains(SpecialActFlags::PLR_FREEZE);
+    victim.specials.borrow_mut().act.set(SpecialActFlags::PLR_FREEZE, !frozen);
+    if frozen {
+        send_to_char("A fireball suddenly explodes in front of you, melting the ice!\n\rYou feel thawed.\n\r",
+            &victim);
+        act("A sudden fireball conjured from nowhere thaws $n!", false, &victim, None, None, None, None,
+            VictimType::ToRoom);
+        wizlog(&ch, &format!("thawed {}.", victim.get_name()));
+    } else {
+        send_to_char("A bitter wind suddenly rises and drains every erg of heat from your body!\n\rYou feel \
+            frozen!\n\r", &victim);
+        act("A sudden cold wind conjured from nowhere freezes $n!", false, &victim, None, None, None, None,
+            VictimType::ToRoom);
+        wizlog(&ch, &format!("froze {}.", victim.get_name()));
+    }
+
+    let room = victim.in_room.borrow().number;
+    save_char(&victim, room, game);
+}
+
+pub fn do_shutdow(ch: Rc<CharData>, _argument: &str, _cmd: i32, _game: &Game) {
+    send_to_char("If you want to shut something down - say so!\n\r", &ch);
+}
+
+// Shuts the game down, or reboots it, now or after a countdown
+pub fn do_shutdown(ch: Rc<CharData>, argument: &str, cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let reboot = cmd == CMD_REBOOT;
+    let what = if reboot { "reboot" } else { "shutdown" };
+    let (arg, _) = one_argument(argument);
+    let delay = match arg.as_str() {
+        "" => SHUTDOWN_DELAY,
+        "now" => 0,
+        "cancel" => {
+            if game.shutdown_timer.borrow_mut().take().is_none() {
+                send_to_char("There is nothing to cancel.\n\r", &ch);
+            } else {
+                send_to_all(&format!("The {} has been cancelled.\n\r", if *game.reboot.borrow() {
+                    "reboot"
+                } else {
+                    "shutdown"
+                }), game);
+                wizlog(&ch, "cancelled the shutdown.");
+            }
+            return;
+        }
+        _ => match arg.parse::<u32>() {
+            Ok(delay) => delay,
+            Err(_) => {
+                send_to_char(&format!("Usage: {} [now | <seconds> | cancel]\n\r", what), &ch);
+                return;
+            }
+        },
+    };
+
+    *game.reboot.borrow_mut() = reboot;
+    if delay == 0 {
+        send_to_all(&format!("{} by {}.\n\r", if reboot { "Reboot" } else { "Shutdown" }, ch.get_name()), game);
+        wizlog(&ch, &format!("{} now.", what));
+        *game.shutdown_timer.borrow_mut() = None;
+        *game.shutdown.borrow_mut() = true;
+    } else {
+        send_to_all(&format!("{} by {} in {} second{}.\n\r", if reboot { "Reboot" } else { "Shutdown" },
+            ch.get_name(), delay, if delay == 1 { "" } else { "s" }), game);
+        wizlog(&ch, &format!("{} in {} seconds.", what, delay));
+        *game.shutdown_timer.borrow_mut() = Some(delay);
+    }
+}
+
+// Starts the game again on a new binary, without anybody losing their link
+pub fn do_copyover(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    send_to_all(&format!("Copyover by {} - please remain seated!\n\r", ch.get_name()), game);
+    wizlog(&ch, "started a copyover.");
+    *game.shutdown_timer.borrow_mut() = None;
+    *game.copyover.borrow_mut() = true;
+    *game.shutdown.borrow_mut() = true;
+}
+
+// While the game is wizlocked, only gods may enter
+pub fn do_wizlock(ch: Rc<CharData>, _argument: &str, _cmd: i32, game: &Game) {
+    let wizlock = !*game.wizlock.borrow();
+    *game.wizlock.borrow_mut() = wizlock;
+    if wizlock {
+        send_to_char("The game is now wizlocked.\n\r", &ch);
+        wizlog(&ch, "wizlocked the game.");
+    } else {
+        send_to_char("The game is no longer wizlocked.\n\r", &ch);
+        wizlog(&ch, "lifted the wizlock.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+    use diku::comm::{close_sockets, shutdown_countdown};
+    use diku::game::{clear_char, clear_object, generate_indices};
+    use diku::handler::equip_char;
+    use diku::testutil::*;
+
+    fn immortal(name: &str, level: u8, room: &Rc<RoomData>) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        ch.player.borrow_mut().level = level;
+        ch
+    }
+
+    #[test]
+    fn snoop_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let god = immortal("Odin", 23, &room);
+        let other_god = immortal("Thor", 22, &room);
+        let mortal = test_char("Mortal", &room);
+
+        do_snoop(Rc::clone(&mortal), " odin", 0, &game);
+        assert_eq!(output(&mortal), vec!["You failed.\n\r"]);
+        do_snoop(Rc::clone(&other_god), " odin", 0, &game);
+        assert_eq!(output(&other_god), vec!["You failed.\n\r"]);
+
+        do_snoop(Rc::clone(&god), " mortal", 0, &game);
+        assert_eq!(output(&god), vec!["Ok. \n\r"]);
+        send_to_char("You are hungry.\n\r", &mortal);
+        act("$n smiles.", false, &other_god, None, None, None, None, VictimType::ToRoom);
+        assert_eq!(output(&god), vec!["% You are hungry.\n\r", "Thor smiles.", "% Thor smiles."]);
+        assert_eq!(output(&mortal), vec!["You are hungry.\n\r", "Thor smiles."]);
+
+        do_snoop(Rc::clone(&other_god), " mortal", 0, &game);
+        assert_eq!(output(&other_god), vec!["Busy already. \n\r"]);
+
+        do_snoop(Rc::clone(&god), " odin", 0, &game);
+        assert_eq!(output(&god), vec!["Ok, you just snoop yourself.\n\r"]);
+        send_to_char("You are thirsty.\n\r", &mortal);
+        assert!(output(&god).is_empty());
+        do_snoop(Rc::clone(&other_god), " mortal", 0, &game);
+        assert_eq!(output(&other_god), vec!["Ok. \n\r"]);
+    }
+
+    #[test]
+    fn switch_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let god = immortal("Odin", 23, &room);
+        let mortal = test_char("Mortal", &room);
+        let mut raven = clear_char(Rc::clone(&room));
+        raven.player.borrow_mut().name = String::from("raven");
+        raven.player.borrow_mut().short_descr = String::from("the raven");
+        raven.specials.borrow_mut().act = SpecialActFlags::ACT_ISNPC;
+        let raven = Rc::new(raven);
+        room.people.borrow_mut().push(Rc::clone(&raven));
+        game.character_list.borrow_mut().push(Rc::clone(&raven));
+
+        do_switch(Rc::clone(&god), " mortal", 0, &game);
+        assert_eq!(output(&god), vec!["You can't do that, the body is already in use!\n\r"]);
+        do_return(Rc::clone(&god), "", 0, &game);
+        assert_eq!(output(&god), vec!["Eh?\n\r"]);
+
+        let desc = Rc::clone(god.desc.borrow().as_ref().unwrap());
+        do_switch(Rc::clone(&god), " raven", 0, &game);
+        assert!(god.desc.borrow().is_none());
+        assert!(Rc::ptr_eq(raven.desc.borrow().as_ref().unwrap(), &desc));
+        send_to_char("Caw!\n\r", &raven);
+        assert_eq!(output(&raven), vec!["Ok.\n\r", "Caw!\n\r"]);
+
+        do_return(Rc::clone(&raven), "", 0, &game);
+        assert_eq!(output(&god), vec!["You return to your original body.\n\r"]);
+        assert!(raven.desc.borrow().is_none());
+        assert!(Rc::ptr_eq(desc.borrow().character.as_ref().unwrap(), &god));
+
+        // A body that is killed sends the player home
+        do_switch(Rc::clone(&god), " raven", 0, &game);
+        extract_char(&raven, &game);
+        assert!(Rc::ptr_eq(god.desc.borrow().as_ref().unwrap(), &desc));
+        assert_eq!(output(&god), vec!["Ok.\n\r", "You return to your original body.\n\r"]);
+    }
+
+    // A player that can be found anywhere in the world
+    fn player(name: &str, room: &Rc<RoomData>, game: &Game) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+        ch
+    }
+
+    fn named_mob(name: &str, room: &Rc<RoomData>, game: &Game) -> Rc<CharData> {
+        let mob = test_mob(&format!("the {}", name), room);
+        mob.player.borrow_mut().name = String::from(name);
+        game.character_list.borrow_mut().push(Rc::clone(&mob));
+        mob
+    }
+
+    // Room 3001, and the private room 3002 with two people talking in it
+    fn world() -> (Game, Rc<RoomData>, Rc<RoomData>) {
+        let mut game = test_game();
+        let hall = test_room(3001);
+        let mut chamber = Rc::try_unwrap(test_room(3002)).ok().unwrap();
+        chamber.room_flags = RoomFlags::PRIVATE;
+        let chamber = Rc::new(chamber);
+        game.world.insert(3001, Rc::clone(&hall));
+        game.world.insert(3002, Rc::clone(&chamber));
+        named_mob("king", &chamber, &game);
+        named_mob("queen", &chamber, &game);
+        (game, hall, chamber)
+    }
+
+    #[test]
+    fn goto_test() {
+        let (game, hall, chamber) = world();
+        let god = immortal("Odin", 21, &hall);
+        let mortal = player("Mortal", &hall, &game);
+
+        do_goto(Rc::clone(&god), "", 0, &game);
+        do_goto(Rc::clone(&god), " 9999", 0, &game);
+        do_goto(Rc::clone(&god), " nobody", 0, &game);
+        do_goto(Rc::clone(&god), " 3002", 0, &game);
+        do_goto(Rc::clone(&god), " queen", 0, &game);
+        assert_eq!(output(&god), vec!["You must supply a room number or a name.\n\r",
+            "No room exists with that number.\n\r", "No such creature or object around.\n\r",
+            "There's a private conversation going on in that room.\n\r",
+            "There's a private conversation going on in that room.\n\r"]);
+
+        let king = chamber.people.borrow()[0].clone();
+        extract_char(&king, &game);
+        do_goto(Rc::clone(&god), " 3002", 0, &game);
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &chamber));
+        assert_eq!(output(&mortal), vec!["Odin disappears in a puff of smoke."]);
+
+        do_goto(Rc::clone(&god), " mortal", 0, &game);
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &hall));
+        assert_eq!(output(&mortal), vec!["Odin appears with an ear-splitting bang."]);
+    }
+
+    #[test]
+    fn at_test() {
+        let (game, hall, chamber) = world();
+        let god = immortal("Odin", 21, &hall);
+        let listener = player("Listener", &chamber, &game);
+        let mortal = player("Mortal", &hall, &game);
+
+        do_at(Rc::clone(&god), " listener say hello", 0, &game);
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &hall));
+        assert_eq!(output(&listener), vec!["Odin says 'hello'"]);
+        assert!(output(&mortal).is_empty());
+
+        // A command that moves the god away leaves it there
+        do_at(Rc::clone(&god), " 3002 goto 3001", 0, &game);
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &hall));
+        let talkers: Vec<Rc<CharData>> = chamber.people.borrow().iter().filter(|k| k.is_npc()).cloned().collect();
+        for k in talkers {
+            extract_char(&k, &game);
+        }
+        do_at(Rc::clone(&god), " mortal goto 3002", 0, &game);
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &chamber));
+    }
+
+    #[test]
+    fn trans_test() {
+        let (game, hall, chamber) = world();
+        let god = immortal("Odin", 22, &hall);
+        let mortal = player("Mortal", &hall, &game);
+
+        do_trans(Rc::clone(&god), " queen", 0, &game);
+        assert_eq!(output(&god), vec!["the queen arrives from a puff of smoke.", "Ok.\n\r"]);
+        assert_eq!(output(&mortal), vec!["the queen arrives from a puff of smoke."]);
+        assert_eq!(hall.people.borrow().len(), 3);
+
+        do_trans(Rc::clone(&god), " mortal 3002", 0, &game);
+        assert!(Rc::ptr_eq(&mortal.in_room.borrow(), &chamber));
+        assert_eq!(output(&mortal), vec!["Odin has transferred you!"]);
+
+        game.descriptor_list.borrow_mut().push(Rc::clone(mortal.desc.borrow().as_ref().unwrap()));
+        game.descriptor_list.borrow_mut().push(Rc::clone(god.desc.borrow().as_ref().unwrap()));
+        do_trans(Rc::clone(&god), " all", 0, &game);
+        assert!(Rc::ptr_eq(&mortal.in_room.borrow(), &hall));
+        assert!(Rc::ptr_eq(&god.in_room.borrow(), &hall));
+
+        do_trans(Rc::clone(&god), " nobody", 0, &game);
+        assert_eq!(output(&god), vec!["Mortal disappears in a mushroom cloud.", "Ok.\n\r",
+            "Mortal arrives from a puff of smoke.", "Ok.\n\r", "No-one by that name around.\n\r"]);
+    }
+
+    #[test]
+    fn load_test() {
+        let mut game = test_game();
+        game.mob_index = generate_indices(&mut BufReader::new(&game.mob_f));
+        game.obj_index = generate_indices(&mut BufReader::new(&game.obj_f));
+        let room = test_room(3001);
+        let god = immortal("Odin", 22, &room);
+        let mortal = test_char("Mortal", &room);
+
+        do_load(Rc::clone(&god), " char 3001", 0, &game);
+        assert_eq!(output(&god), vec!["Done.\n\r"]);
+        assert_eq!(output(&mortal), vec!["Odin makes a quaint, magical gesture with one hand.",
+            "Odin has created the Baker!"]);
+        assert!(room.people.borrow().iter().any(|k| k.get_name() == "baker"));
+
+        do_load(Rc::clone(&god), " obj 3001", 0, &game);
+        assert_eq!(output(&god), vec!["Ok.\n\r"]);
+        assert_eq!(god.carrying.borrow()[0].name, "beer bottle");
+
+        do_load(Rc::clone(&god), " char 1234567", 0, &game);
+        do_load(Rc::clone(&god), " obj 1234567", 0, &game);
+        do_load(Rc::clone(&god), " room 3001", 0, &game);
+        do_load(Rc::clone(&god), " char baker", 0, &game);
+        assert_eq!(output(&god), vec!["There is no monster with that number.\n\r",
+            "There is no object with that number.\n\r", "That'll have to be either 'char' or 'obj'.\n\r",
+            "Syntax:\n\rload <'char' | 'obj'> <number>.\n\r"]);
+    }
+
+    #[test]
+    fn purge_test() {
+        let (game, hall, chamber) = world();
+        let god = immortal("Odin", 22, &chamber);
+        let mortal = test_char("Mortal", &chamber);
+
+        do_purge(Rc::clone(&god), " mortal", 0, &game);
+        assert_eq!(output(&god), vec!["Fuuuuuuuuu!\n\r"]);
+
+        do_purge(Rc::clone(&god), " king", 0, &game);
+        assert_eq!(output(&god), vec!["Ok.\n\r"]);
+        assert_eq!(output(&mortal), vec!["Odin disintegrates the king."]);
+        assert_eq!(game.character_list.borrow().len(), 1);
+
+        do_purge(Rc::clone(&god), "", 0, &game);
+        assert_eq!(output(&mortal), vec!["Odin gestures... You are surrounded by scorching flames!",
+            "The world seems a little cleaner.\n\r"]);
+        assert_eq!(chamber.people.borrow().len(), 2);
+        assert!(game.character_list.borrow().is_empty());
+        assert!(hall.people.borrow().is_empty());
+    }
+
+    #[test]
+    fn stat_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 21, &hall);
+        let mortal = test_char("Mortal", &hall);
+        mortal.specials.borrow_mut().act.insert(SpecialActFlags::PLR_NOSHOUT);
+        let mut cloak = clear_object();
+        cloak.short_description = String::from("a black cloak");
+        equip_char(&mortal, Rc::new(cloak), EquipmentPosition::About);
+
+        do_stat(Rc::clone(&god), " room", 0, &game);
+        let page = output(&god).remove(0);
+        assert!(page.starts_with("Room name: Room 3001, Of zone : 0. V-Number : 3001\n\r"));
+        assert!(page.contains("Mortal(PC)\n\r"));
+
+        do_stat(Rc::clone(&god), " mortal", 0, &game);
+        let page = output(&god).remove(0);
+        assert!(page.starts_with("PC - Name : Mortal [V-Number -1], In room [3001]\n\r"));
+        assert!(page.contains("Str:[13/0]  Int:[13]  Wis:[13]  Dex:[13]  Con:[13]\n\r"));
+        assert!(page.contains("Flags (Specials Act): NOSHOUT\n\r"));
+        assert!(page.contains("Items in equipment: 1\n\r    Worn about body: a black cloak\n\r"));
+
+        do_stat(Rc::clone(&god), " king", 0, &game);
+        assert!(output(&god)[0].starts_with("NPC - Name : king"));
+
+        do_stat(Rc::clone(&god), " nothing", 0, &game);
+        assert_eq!(output(&god), vec!["No mobile or object by that name in the world\n\r"]);
+    }
+
+    #[test]
+    fn set_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 23, &hall);
+        let other_god = immortal("Thor", 23, &hall);
+        let mortal = test_char("Mortal", &hall);
+
+        do_set(Rc::clone(&god), " mortal str 18", 0, &game);
+        do_set(Rc::clone(&god), " mortal gold 500", 0, &game);
+        do_set(Rc::clone(&god), " mortal notell on", 0, &game);
+        assert_eq!(output(&god), vec!["Ok.\n\r", "Ok.\n\r", "Ok.\n\r"]);
+        assert_eq!(mortal.get_str(), 18);
+        assert_eq!(mortal.get_gold(), 500);
+        assert!(mortal.specials.borrow().act.contains(SpecialActFlags::PLR_NOTELL));
+
+        do_set(Rc::clone(&god), " king aggressive on", 0, &game);
+        let king = get_char_vis(&god, "king", &game).unwrap();
+        assert!(king.specials.borrow().act.contains(SpecialActFlags::ACT_AGGRESSIVE));
+        do_set(Rc::clone(&god), " king isnpc off", 0, &game);
+        assert!(king.is_npc());
+
+        do_set(Rc::clone(&god), " mortal str 30", 0, &game);
+        do_set(Rc::clone(&god), " mortal hit lots", 0, &game);
+        do_set(Rc::clone(&god), " mortal brief maybe", 0, &game);
+        do_set(Rc::clone(&god), " mortal colour 1", 0, &game);
+        do_set(Rc::clone(&god), " thor gold 1", 0, &game);
+        do_set(Rc::clone(&god), " mortal", 0, &game);
+        assert_eq!(output(&god), vec!["Ok.\n\r", "Unknown field.\n\r", "Abilities range from 3 to 25.\n\r",
+            "Value must be a number.\n\r", "Value must be on or off.\n\r", "Unknown field.\n\r",
+            "Oh no you don't!!\n\r", "Usage: set <victim> <field> <value>\n\r"]);
+        assert_eq!(other_god.get_gold(), 0);
+    }
+
+    #[test]
+    fn force_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 23, &hall);
+        let greater_god = immortal("Thor", 24, &hall);
+        let mortal = test_char("Mortal", &hall);
+
+        do_force(Rc::clone(&god), " mortal say I $n obey", 0, &game);
+        assert_eq!(output(&god), vec!["Ok.\n\r", "Mortal says 'I $n obey'"]);
+        assert_eq!(output(&mortal), vec!["Odin has forced you to 'say I $n obey'."]);
+
+        do_force(Rc::clone(&god), " thor say hi", 0, &game);
+        assert_eq!(output(&god), vec!["Oh no you don't!!\n\r"]);
+
+        // Forcing a mortal to use the powers of the gods does not work
+        do_force(Rc::clone(&god), " mortal goto 3002", 0, &game);
+        assert!(Rc::ptr_eq(&mortal.in_room.borrow(), &hall));
+        output(&god);
+
+        game.descriptor_list.borrow_mut().push(Rc::clone(mortal.desc.borrow().as_ref().unwrap()));
+        game.descriptor_list.borrow_mut().push(Rc::clone(greater_god.desc.borrow().as_ref().unwrap()));
+        output(&greater_god);
+        do_force(Rc::clone(&god), " all say hi", 0, &game);
+        assert_eq!(output(&god), vec!["Mortal says 'hi'", "Oh no you don't!!\n\r", "Ok.\n\r"]);
+        assert!(output(&greater_god).iter().all(|line| !line.starts_with("Odin has forced you")));
+    }
+
+    #[test]
+    fn restore_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 22, &hall);
+        let mortal = test_char("Mortal", &hall);
+        mortal.points.borrow_mut().hit = -4;
+        mortal.specials.borrow_mut().position = Position::Incap;
+
+        do_restore(Rc::clone(&god), " mortal", 0, &game);
+        assert_eq!(output(&god), vec!["Done.\n\r"]);
+        assert_eq!(output(&mortal), vec!["You have been fully healed by Odin!"]);
+        assert_eq!(mortal.get_hit(), mortal.get_max_hit());
+        assert!(mortal.get_pos() == Position::Standing);
+        assert_eq!(mortal.skills.borrow()[0].learned, 0);
+
+        let implementor = immortal("Ymir", 24, &hall);
+        do_restore(Rc::clone(&god), " ymir", 0, &game);
+        assert!(implementor.skills.borrow().iter().all(|skill| skill.learned == 100));
+        assert_eq!(implementor.abilities.borrow().str, 25);
+        assert_eq!((implementor.get_str(), implementor.get_add()), (18, 100));
+    }
+
+    #[test]
+    fn advance_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 24, &hall);
+        let mortal = test_char("Mortal", &hall);
+
+        do_advance(Rc::clone(&god), " mortal 10", 0, &game);
+        assert_eq!(mortal.get_level(), 10);
+        assert_eq!(mortal.get_exp(), TITLES[Class::Warrior as usize - 1][10].exp);
+        assert_eq!(output(&god), vec!["You feel generous.\n\r"]);
+
+        do_advance(Rc::clone(&god), " mortal 5", 0, &game);
+        do_advance(Rc::clone(&god), " mortal 25", 0, &game);
+        do_advance(Rc::clone(&god), " mortal", 0, &game);
+        named_mob("jester", &hall, &game);
+        do_advance(Rc::clone(&god), " jester 5", 0, &game);
+        assert_eq!(output(&god), vec!["Can't diminish a players status (yet).\n\r",
+            "24 is the highest possible level.\n\r", "You must supply a level number.\n\r",
+            "NO! Not on NPC's.\n\r"]);
+        assert_eq!(mortal.get_level(), 10);
+    }
+
+    #[test]
+    fn freeze_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 22, &hall);
+        let mortal = test_char("Mortal", &hall);
+
+        do_freeze(Rc::clone(&god), " mortal", 0, &game);
+        assert!(mortal.specials.borrow().act.contains(SpecialActFlags::PLR_FREEZE));
+        assert!(output(&mortal)[0].ends_with("You feel frozen!\n\r"));
+        command_interpreter(Rc::clone(&mortal), "say help", &game);
+        assert_eq!(output(&mortal), vec!["You're totally frozen!\n\r"]);
+
+        do_freeze(Rc::clone(&mortal), " odin", 0, &game);
+        assert_eq!(output(&mortal), vec!["Oh no you don't!!\n\r"]);
+        assert!(!god.specials.borrow().act.contains(SpecialActFlags::PLR_FREEZE));
+
+        do_freeze(Rc::clone(&god), " mortal", 0, &game);
+        assert!(!mortal.specials.borrow().act.contains(SpecialActFlags::PLR_FREEZE));
+        assert!(output(&mortal)[0].ends_with("You feel thawed.\n\r"));
+    }
+
+    #[test]
+    fn shutdown_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 24, &hall);
+        let mortal = test_char("Mortal", &hall);
+        for ch in &[&god, &mortal] {
+            game.descriptor_list.borrow_mut().push(Rc::clone(ch.desc.borrow().as_ref().unwrap()));
+        }
+
+        command_interpreter(Rc::clone(&god), "shut", &game);
+        assert_eq!(output(&god), vec!["If you want to shut something down - say so!\n\r"]);
+        do_shutdown(Rc::clone(&god), " soon", 66, &game);
+        assert_eq!(output(&god), vec!["Usage: shutdown [now | <seconds> | cancel]\n\r"]);
+
+        command_interpreter(Rc::clone(&god), "reboot 3", &game);
+        assert_eq!(output(&mortal), vec!["Reboot by Odin in 3 seconds.\n\r"]);
+        assert_eq!(*game.shutdown_timer.borrow(), Some(3));
+        for _ in 0..3 {
+            shutdown_countdown(&game);
+        }
+        assert_eq!(output(&mortal), vec!["The game will reboot in 3 seconds.\n\r",
+            "The game will reboot in 2 seconds.\n\r", "The game will reboot in 1 second.\n\r"]);
+        assert!(!*game.shutdown.borrow());
+
+        do_shutdown(Rc::clone(&god), " cancel", CMD_REBOOT, &game);
+        assert_eq!(output(&mortal), vec!["The reboot has been cancelled.\n\r"]);
+        shutdown_countdown(&game);
+        assert!(!*game.shutdown.borrow());
+        do_shutdown(Rc::clone(&god), " cancel", CMD_REBOOT, &game);
+        assert_eq!(output(&god).last().unwrap(), "There is nothing to cancel.\n\r");
+
+        do_shutdown(Rc::clone(&god), " 1", CMD_REBOOT, &game);
+        shutdown_countdown(&game);
+        shutdown_countdown(&game);
+        assert_eq!(output(&mortal).last().unwrap(), "The game will reboot now.\n\r");
+        assert!(*game.shutdown.borrow());
+        assert!(*game.reboot.borrow());
+
+        *game.shutdown.borrow_mut() = false;
+        command_interpreter(Rc::clone(&god), "shutdown now", &game);
+        assert_eq!(output(&mortal), vec!["Shutdown by Odin.\n\r"]);
+        assert!(*game.shutdown.borrow());
+        assert!(!*game.reboot.borrow());
+
+        // a link-dead player is saved along with the rest
+        let linkless = test_char("Linkless", &hall);
+        *linkless.desc.borrow_mut() = None;
+        game.character_list.borrow_mut().push(Rc::clone(&linkless));
+        close_sockets(&game);
+        assert!(game.descriptor_list.borrow().is_empty());
+        assert!(mortal.desc.borrow().is_none());
+    }
+
+    #[test]
+    fn wizlock_test() {
+        let (game, hall, _) = world();
+        let god = immortal("Odin", 23, &hall);
+        let mortal = test_char("Mortal", &hall);
+
+        command_interpreter(Rc::clone(&mortal), "wizlock", &game);
+        assert_eq!(output(&mortal), vec!["Huh?!?\n\r"]);
+        command_interpreter(Rc::clone(&god), "wizlock", &game);
+        assert_eq!(output(&god), vec!["The game is now wizlocked.\n\r"]);
+        assert!(*game.wizlock.borrow());
+        command_interpreter(Rc::clone(&god), "wizlock", &game);
+        assert_eq!(output(&god), vec!["The game is no longer wizlocked.\n\r"]);
+        assert!(!*game.wizlock.borrow());
+    }
+}
@@ -1,9 +1,32 @@
+use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::env;
+use std::ffi::CString;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::os::unix::ffi::OsStringExt;
 use std::os::unix::io::RawFd;
+use std::ptr;
+use std::rc::Rc;
 
-use nix::sys::socket::{bind, linger, listen, socket, setsockopt, sockopt, AddressFamily, InetAddr, IpAddr, Ipv4Addr, SockAddr, SockType, SockFlag};
+use bincode::{deserialize_from, serialize_into, Infinite};
+use libc::c_int;
 
-use diku::handler::fname;
+use nix;
+use nix::errno::Errno;
+use nix::fcntl::{fcntl, FcntlArg, FdFlag, O_NONBLOCK};
+use nix::unistd::{close, execv, read, write};
+use nix::sys::socket::{accept4, bind, getpeername, linger, listen, socket, setsockopt, sockopt, AddressFamily, InetAddr,
+    IpAddr, Ipv4Addr, SockAddr, SockType, SockFlag, SOCK_NONBLOCK};
+
+use diku::act_wizard::do_return;
+use diku::constants::{COPYOVER_FILE, GREETINGS, MAX_INPUT_LENGTH, MAX_STRING_LENGTH, START_ROOM, VOID_ROOM};
+use diku::game::{clear_char, copy_from_store, load_char, save_char, store_to_char};
+use diku::handler::{char_from_room, char_to_room, fname};
+use diku::interpreter::{command_interpreter, nanny};
+use diku::reception::{load_char_objs, save_obj};
 use diku::structs::*;
 use diku::utility::log;
 
@@ -15,6 +38,143 @@ fn write_to_q(txt: String, queue: &mut VecDeque<String>) {
     queue.push_front(txt)
 }
 
+// Everything written to a descriptor is shown to its snooper as well
+pub fn write_to_output(txt: String, d: &RefCell<DescriptorData>) {
+    let snoop_by = d.borrow().snoop.snoop_by.clone();
+    if let Some(snooper) = snoop_by {
+        if let Some(ref sd) = *snooper.desc.borrow() {
+            write_to_q(format!("% {}", txt), &mut sd.borrow_mut().output);
+        }
+    }
+    write_to_q(txt, &mut d.borrow_mut().output);
+}
+
+fn write_to_descriptor(desc: c_int, txt: &str) -> nix::Result<()> {
+    let mut txt = txt.as_bytes();
+    while !txt.is_empty() {
+        let sofar = write(desc, txt)?;
+        txt = &txt[sofar..];
+    }
+    Ok(())
+}
+
+// Sends whatever is queued for the descriptor down the line
+pub fn process_output(d: &RefCell<DescriptorData>) {
+    let descriptor = d.borrow().descriptor;
+    if descriptor < 0 {
+        return;
+    }
+
+    while let Some(txt) = get_from_q(&mut d.borrow_mut().output) {
+        if let Err(e) = write_to_descriptor(descriptor, &txt) {
+            log(&format!("Write to socket: {}", e));
+            break;
+        }
+    }
+}
+
+// Reads what has come in on the link, and queues it up a line at a time.
+// Returns false when the link is gone.
+fn process_input(d: &RefCell<DescriptorData>) -> bool {
+    let descriptor = d.borrow().descriptor;
+    let mut buf = [0u8; MAX_INPUT_LENGTH];
+    loop {
+        match read(descriptor, &mut buf) {
+            Ok(0) => {
+                log("EOF encountered on socket read.");
+                return false;
+            }
+            Ok(n) => d.borrow_mut().buf.extend_from_slice(&buf[..n]),
+            Err(nix::Error::Sys(Errno::EAGAIN)) => break,
+            Err(nix::Error::Sys(Errno::EINTR)) => (),
+            Err(e) => {
+                log(&format!("Read from socket: {}", e));
+                return false;
+            }
+        }
+    }
+
+    let mut d = d.borrow_mut();
+    while let Some(end) = d.buf.iter().position(|&b| b == b'\n') {
+        let raw: Vec<u8> = d.buf.drain(..end + 1).collect();
+        let mut line: String = String::from_utf8_lossy(&raw).chars().filter(|c| !c.is_control()).collect();
+        if line.chars().count() > MAX_INPUT_LENGTH {
+            line = line.chars().take(MAX_INPUT_LENGTH).collect();
+            write_to_q(format!("Line too long. Truncated to:\n\r{}\n\r", line), &mut d.output);
+        }
+
+        // '!' repeats the last command
+        if line == "!" {
+            line = String::from_utf8_lossy(&d.last_input).into_owned();
+        } else {
+            d.last_input = line.clone().into_bytes();
+        }
+        write_to_q(line, &mut d.input);
+    }
+
+    // nobody types this much without hitting return
+    if d.buf.len() > MAX_STRING_LENGTH {
+        log("Input overflow.");
+        return false;
+    }
+    true
+}
+
+// Reads from every link, and closes the ones that have gone
+pub fn process_inputs(game: &Game) {
+    let descriptors = game.descriptor_list.borrow().clone();
+    for d in &descriptors {
+        if d.borrow().descriptor >= 0 && !process_input(d) {
+            close_socket(d, game);
+        }
+    }
+}
+
+// One command from every link that has one waiting
+pub fn process_commands(game: &Game) {
+    let descriptors = game.descriptor_list.borrow().clone();
+    for d in &descriptors {
+        // an earlier command may have thrown the link out
+        if !game.descriptor_list.borrow().iter().any(|k| Rc::ptr_eq(k, d)) {
+            continue;
+        }
+        let comm = match get_from_q(&mut d.borrow_mut().input) {
+            Some(comm) => comm,
+            None => continue,
+        };
+
+        let character = d.borrow().character.clone();
+        let ch = match character {
+            Some(ref ch) if d.borrow().connected == ConnectionMode::Plying => Rc::clone(ch),
+            _ => {
+                nanny(d, &comm, game);
+                continue;
+            }
+        };
+
+        // back from the void the idle are kept in
+        ch.specials.borrow_mut().timer = 0;
+        let was_in_room = ch.specials.borrow_mut().was_in_room.take();
+        if let Some(room) = was_in_room {
+            char_from_room(&ch);
+            char_to_room(&ch, &game.world[&room]);
+            act("$n has returned.", true, &ch, None, None, None, None, VictimType::ToRoom);
+        }
+
+        command_interpreter(ch, &comm, game);
+        if d.borrow().connected == ConnectionMode::Plying {
+            write_to_q(String::from("> "), &mut d.borrow_mut().output);
+        }
+    }
+}
+
+pub fn process_outputs(game: &Game) {
+    let descriptors = game.descriptor_list.borrow().clone();
+    for d in &descriptors {
+        process_output(d);
+    }
+}
+
 fn flush_queues(d: &mut DescriptorData) {
     d.output.clear();
     d.input.clear();
@@ -41,13 +201,374 @@ pub fn init_socket(port: u16) -> RawFd {
     s
 }
 
+// Links are never waited on, the game loop looks in on them every pulse
+pub fn nonblock(s: RawFd) {
+    if let Err(e) = fcntl(s, FcntlArg::F_SETFL(O_NONBLOCK)) {
+        log(&format!("SYSERR: Fcntl nonblock: {}", e));
+    }
+}
+
+// Takes up everyone who knocked on the mother connection since last pulse
+pub fn new_descriptor(s: RawFd, game: &Game) {
+    loop {
+        let desc = match accept4(s, SOCK_NONBLOCK) {
+            Ok(desc) => desc,
+            Err(nix::Error::Sys(Errno::EAGAIN)) => return,
+            Err(e) => {
+                log(&format!("SYSERR: Accept: {}", e));
+                return;
+            }
+        };
+
+        let host = match getpeername(desc) {
+            Ok(SockAddr::Inet(addr)) => addr.ip().to_string(),
+            _ => String::from("unknown"),
+        };
+        log(&format!("New connection from {}.", host));
+
+        let d = Rc::new(RefCell::new(DescriptorData::new(desc, host)));
+        write_to_output(String::from(GREETINGS), &d);
+        write_to_output(String::from("By what name do you wish to be known? "), &d);
+        game.descriptor_list.borrow_mut().push(d);
+    }
+}
+
+impl DescriptorData {
+    pub fn new(descriptor: c_int, host: String) -> DescriptorData {
+        DescriptorData {
+            descriptor,
+            host,
+            pwd: String::new(),
+            pos: -1,
+            connected: ConnectionMode::Nme,
+            wait: 1,
+            prompt_mode: 0,
+            buf: Vec::new(),
+            last_input: Vec::new(),
+            output: VecDeque::new(),
+            input: VecDeque::new(),
+            character: None,
+            original: None,
+            snoop: SnoopData::default(),
+        }
+    }
+}
+
+pub fn close_socket(d: &Rc<RefCell<DescriptorData>>, game: &Game) {
+    let descriptor = d.borrow().descriptor;
+    if descriptor >= 0 {
+        if let Err(e) = close(descriptor) {
+            log(&format!("close_socket: {}", e));
+        }
+    }
+
+    flush_queues(&mut d.borrow_mut());
+    stop_snooping(d);
+
+    let character = d.borrow_mut().character.take();
+    match character {
+        // The character stays in the game until the link is back, or it
+        // has idled long enough to be thrown out
+        Some(ch) => if d.borrow().connected == ConnectionMode::Plying {
+            // a switched god loses the link in his own body, the mob is left behind
+            *ch.desc.borrow_mut() = None;
+            let original = d.borrow_mut().original.take();
+            let ch = original.unwrap_or(ch);
+            let room = ch.in_room.borrow().number;
+            save_char(&ch, room, game);
+            act("$n has lost $s link.", true, &ch, None, None, None, None, VictimType::ToRoom);
+            log(&format!("Closing link to: {}.", ch.get_name()));
+            *ch.desc.borrow_mut() = None;
+        } else {
+            log(&format!("Losing player: {}.", ch.get_name()));
+            *ch.desc.borrow_mut() = None;
+        },
+        None => log("Losing descriptor without char."),
+    }
+
+    game.descriptor_list.borrow_mut().retain(|k| !Rc::ptr_eq(k, d));
+}
+
+// Throws everybody out at shutdown, every player is saved with his objects
+pub fn close_sockets(game: &Game) {
+    log("Closing all sockets.");
+
+    let descriptors = game.descriptor_list.borrow().clone();
+    // switched gods are saved in their own bodies
+    for d in &descriptors {
+        let switched = if d.borrow().original.is_some() { d.borrow().character.clone() } else { None };
+        if let Some(ch) = switched {
+            do_return(ch, "", 0, game);
+        }
+    }
+
+    // the link-dead are still in the game too
+    let characters = game.character_list.borrow().clone();
+    for ch in characters.iter().filter(|ch| !ch.is_npc()) {
+        let room = ch.in_room.borrow().number;
+        save_char(ch, room, game);
+        save_obj(ch);
+    }
+
+    for d in &descriptors {
+        process_output(d);
+        close_socket(d, game);
+    }
+}
+
+/*******************************************************************
+*  copyover                                                        *
+********************************************************************/
+
+// Hands every link over to a freshly started game, which takes them up again
+// from the copyover file. Only comes back if that fails.
+pub fn copyover(s: RawFd, game: &Game) {
+    log("Copyover: handing over the links.");
+    if let Err(e) = write_copyover(s, game) {
+        log(&format!("SYSERR: Writing {}: {}", COPYOVER_FILE, e));
+        send_to_all("Copyover FAILED!\n\r", game);
+        return;
+    }
+
+    // the links have to survive the exec
+    let mut fds = vec![s];
+    fds.extend(game.descriptor_list.borrow().iter().map(|d| d.borrow().descriptor).filter(|&fd| fd >= 0));
+    for fd in fds {
+        if let Err(e) = fcntl(fd, FcntlArg::F_SETFD(FdFlag::empty())) {
+            log(&format!("Copyover: keeping {} open: {}", fd, e));
+        }
+    }
+
+    let result = env::current_exe().map_err(|e| e.to_string())
+        .and_then(|exe| CString::new(exe.into_os_string().into_vec()).map_err(|e| e.to_string()))
+        .and_then(|path| execv(&path, &copyover_args()).map(|_| ()).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log(&format!("SYSERR: Copyover: {}", e));
+    }
+
+    if let Err(e) = fs::remove_file(COPYOVER_FILE) {
+        log(&format!("SYSERR: Removing {}: {}", COPYOVER_FILE, e));
+    }
+    send_to_all("Copyover FAILED!\n\r", game);
+}
+
+// Saves the players and their objects, and writes down who is on which link
+fn write_copyover(s: RawFd, game: &Game) -> io::Result<()> {
+    let mut links = Vec::new();
+
+    let descriptors = game.descriptor_list.borrow().clone();
+    for d in &descriptors {
+        // switched gods come back in their own bodies
+        let switched = if d.borrow().original.is_some() { d.borrow().character.clone() } else { None };
+        if let Some(ch) = switched {
+            do_return(ch, "", 0, game);
+        }
+
+        // a character still being made is not in the player file, it is made again
+        let making = matches!(d.borrow().connected, ConnectionMode::NmeCnf | ConnectionMode::PwdGet |
+            ConnectionMode::PwdCnf | ConnectionMode::QSex | ConnectionMode::QClass);
+        if making {
+            write_to_output(String::from("\n\rThe game is rebooting, your character has to be made again.\n\r"), d);
+        }
+        process_output(d);
+
+        let character = if making { None } else { d.borrow().character.clone() };
+        let (name, room) = match character {
+            Some(ref ch) => {
+                let room = ch.in_room.borrow().number;
+                if d.borrow().connected == ConnectionMode::Plying {
+                    save_char(ch, room, game);
+                    save_obj(ch);
+                }
+                (ch.get_name(), room)
+            }
+            None => (String::new(), 0),
+        };
+
+        let d = d.borrow();
+        links.push(CopyoverLink {
+            descriptor: d.descriptor,
+            host: d.host.clone(),
+            name,
+            room,
+            connected: if making { ConnectionMode::Nme } else { d.connected },
+        });
+    }
+
+    // there is no link to take up for the link-dead, they log in again
+    let characters = game.character_list.borrow().clone();
+    for ch in characters.iter().filter(|ch| !ch.is_npc() && ch.desc.borrow().is_none()) {
+        let room = ch.in_room.borrow().number;
+        save_char(ch, room, game);
+        save_obj(ch);
+    }
+
+    let mut file = File::create(COPYOVER_FILE)?;
+    serialize_into(&mut file, &CopyoverData { mother: s, links }, Infinite)
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+}
+
+// The new game is started the way we were, only it is in the data directory already
+fn copyover_args() -> Vec<CString> {
+    let mut args = Vec::new();
+    let mut dir = false;
+    for arg in env::args() {
+        if dir {
+            dir = false;
+        } else if arg == "-d" {
+            dir = true;
+        } else if !arg.starts_with("-d") {
+            args.push(arg);
+        }
+    }
+    args.insert(1, String::from("-d"));
+    args.insert(2, String::from("."));
+
+    args.into_iter().filter_map(|arg| CString::new(arg).ok()).collect()
+}
+
+// The copyover file, if the game was started by a copyover
+pub fn read_copyover() -> Option<CopyoverData> {
+    let file = match File::open(COPYOVER_FILE) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    let data = deserialize_from(&mut BufReader::new(file), Infinite);
+
+    // never take the same links up twice
+    if let Err(e) = fs::remove_file(COPYOVER_FILE) {
+        log(&format!("SYSERR: Removing {}: {}", COPYOVER_FILE, e));
+    }
+
+    match data {
+        Ok(data) => Some(data),
+        Err(e) => {
+            log(&format!("SYSERR: Reading {}: {}", COPYOVER_FILE, e));
+            None
+        }
+    }
+}
+
+// Takes the links up again after a copyover, with everybody where they were
+pub fn copyover_recover(data: CopyoverData, game: &Game) {
+    log("Copyover recovery initiated.");
+
+    for link in data.links {
+        let d = Rc::new(RefCell::new(DescriptorData::new(link.descriptor, link.host)));
+        d.borrow_mut().connected = link.connected;
+
+        if !link.name.is_empty() {
+            let (st, nr) = match load_char(&link.name, game) {
+                Some(found) => found,
+                None => {
+                    log(&format!("Copyover recovery: {} is not in the player file.", link.name));
+                    write_to_output(String::from("Your character was lost in the copyover, sorry.\n\r"), &d);
+                    process_output(&d);
+                    close_socket(&d, game);
+                    continue;
+                }
+            };
+
+            let room = Rc::clone(game.world.get(&link.room).or_else(|| game.world.get(&START_ROOM))
+                .unwrap_or_else(|| &game.world[&VOID_ROOM]));
+            let ch = Rc::new(clear_char(Rc::clone(&room)));
+            store_to_char(&st, &ch);
+            d.borrow_mut().pos = nr as i32;
+            d.borrow_mut().pwd = copy_from_store(&st.pwd);
+            d.borrow_mut().character = Some(Rc::clone(&ch));
+            *ch.desc.borrow_mut() = Some(Rc::clone(&d));
+
+            // the ones still logging in are not in the game yet
+            if link.connected == ConnectionMode::Plying {
+                char_to_room(&ch, &room);
+                game.character_list.borrow_mut().push(Rc::clone(&ch));
+                load_char_objs(&ch, game);
+                send_to_char("Copyover complete.\n\r", &ch);
+                act("$n materializes!", true, &ch, None, None, None, None, VictimType::ToRoom);
+            }
+            log(&format!("Copyover recovery: {} is back.", link.name));
+        } else {
+            write_to_output(String::from("By what name do you wish to be known? "), &d);
+        }
+
+        game.descriptor_list.borrow_mut().push(d);
+    }
+}
+
+// Ends any snooping of or by the descriptor
+pub fn stop_snooping(d: &RefCell<DescriptorData>) {
+    let snooping = d.borrow_mut().snoop.snooping.take();
+    if let Some(victim) = snooping {
+        if let Some(ref vd) = *victim.desc.borrow() {
+            vd.borrow_mut().snoop.snoop_by = None;
+        }
+    }
+
+    let snoop_by = d.borrow_mut().snoop.snoop_by.take();
+    if let Some(snooper) = snoop_by {
+        send_to_char("Your victim is no longer among us.\n\r", &snooper);
+        if let Some(ref sd) = *snooper.desc.borrow() {
+            sd.borrow_mut().snoop.snooping = None;
+        }
+    }
+}
+
 /*****************************************************************
 *	Public routines for system-to-player-communication           *
 ******************************************************************/
 
+pub fn send_to_all(messg: &str, game: &Game) {
+    if messg.is_empty() {
+        return;
+    }
+
+    for d in game.descriptor_list.borrow().iter() {
+        if d.borrow().connected == ConnectionMode::Plying {
+            write_to_output(String::from(messg), d);
+        }
+    }
+}
+
+pub fn send_to_outdoor(messg: &str, game: &Game) {
+    if messg.is_empty() {
+        return;
+    }
+
+    for d in game.descriptor_list.borrow().iter() {
+        let outside = d.borrow().connected == ConnectionMode::Plying &&
+            d.borrow().character.as_ref().map_or(false, |ch| ch.outside());
+        if outside {
+            write_to_output(String::from(messg), d);
+        }
+    }
+}
+
+// Counts a shutdown down once a second, warning everybody on the way
+pub fn shutdown_countdown(game: &Game) {
+    let left = match *game.shutdown_timer.borrow() {
+        Some(left) => left,
+        None => return,
+    };
+
+    let what = if *game.reboot.borrow() { "reboot" } else { "shut down" };
+    if left == 0 {
+        send_to_all(&format!("The game will {} now.\n\r", what), game);
+        log("Shutdown countdown over.");
+        *game.shutdown_timer.borrow_mut() = None;
+        *game.shutdown.borrow_mut() = true;
+        return;
+    }
+
+    if left <= 5 || left == 10 || left % 30 == 0 {
+        send_to_all(&format!("The game will {} in {} second{}.\n\r", what, left,
+            if left == 1 { "" } else { "s" }), game);
+    }
+    *game.shutdown_timer.borrow_mut() = Some(left - 1);
+}
+
 pub fn send_to_char(messg: &str, ch: &CharData) {
-    match ch.desc.as_ref() {
-        Some(desc) => if !messg.is_empty() { write_to_q(String::from(messg), &mut desc.borrow_mut().output); },
+    match ch.desc.borrow().as_ref() {
+        Some(desc) => if !messg.is_empty() { write_to_output(String::from(messg), desc); },
         _ => (),
     }
 }
@@ -55,6 +576,10 @@ pub fn send_to_char(messg: &str, ch: &CharData) {
 pub fn act(string: &str, hide_invisible: bool, ch: &CharData,
         obj: Option<&ObjData>, vict: Option<&CharData>, vict_obj: Option<&ObjData>,
         vict_str: Option<&str>, vtype: VictimType) {
+    if string.is_empty() {
+        return;
+    }
+
     match vtype {
         VictimType::ToVict =>
             act_helper(vict.unwrap(), string, hide_invisible, ch, obj, vict,
@@ -62,7 +587,7 @@ pub fn act(string: &str, hide_invisible: bool, ch: &CharData,
         VictimType::ToChar =>
             act_helper(ch, string, hide_invisible, ch, obj, vict, vict_obj,
                 vict_str, vtype),
-        _ => for to in ch.in_room.people.borrow().iter() {
+        _ => for to in ch.in_room.borrow().people.borrow().iter() {
                 act_helper(to, string, hide_invisible, ch, obj, vict,
                     vict_obj, vict_str, vtype);
             },
@@ -72,9 +597,12 @@ pub fn act(string: &str, hide_invisible: bool, ch: &CharData,
 fn act_helper(to: &CharData, string: &str, hide_invisible: bool, ch: &CharData,
         obj: Option<&ObjData>, vict: Option<&CharData>, vict_obj: Option<&ObjData>,
         vict_str: Option<&str>, vtype: VictimType) {
-    if to.desc.is_some() && (to != ch || vtype == VictimType::ToChar) &&
-            (to.can_see(ch) || !hide_invisible) && to.awake() &&
-            !(vtype == VictimType::ToNotVict && Some(to) == vict) {
+    // Messages aimed directly at a char reach it even when it is not awake,
+    // otherwise a victim would never learn that it was killed.
+    let direct = vtype == VictimType::ToChar || vtype == VictimType::ToVict;
+    if to.desc.borrow().is_some() && (!ptr::eq(to, ch) || vtype == VictimType::ToChar) &&
+            (to.can_see(ch) || !hide_invisible) && (direct || to.awake()) &&
+            !(vtype == VictimType::ToNotVict && vict.map_or(false, |v| ptr::eq(to, v))) {
         let mut buf = String::with_capacity(string.len());
         let mut chars = string.chars();
         loop {
@@ -83,8 +611,8 @@ fn act_helper(to: &CharData, string: &str, hide_invisible: bool, ch: &CharData,
             };
             if c == '$' {
                 match chars.next().unwrap() {
-                    'n' => buf.push_str(&ch.pers(to)),
-                    'N' => buf.push_str(&vict.unwrap().pers(to)),
+                    'n' => buf.push_str(&to.pers(ch)),
+                    'N' => buf.push_str(&to.pers(vict.unwrap())),
                     'm' => buf.push_str(ch.hmhr()),
                     'M' => buf.push_str(vict.unwrap().hmhr()),
                     's' => buf.push_str(ch.hshr()),
@@ -106,6 +634,183 @@ fn act_helper(to: &CharData, string: &str, hide_invisible: bool, ch: &CharData,
                 buf.push(c);
             }
         }
-        write_to_q(buf, &mut to.desc.as_ref().unwrap().borrow_mut().output);
+        write_to_output(buf, to.desc.borrow().as_ref().unwrap());
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::unistd::pipe;
+    use diku::constants::VOID_ROOM;
+    use diku::handler::{char_from_room, char_to_room};
+    use diku::interpreter::reconnect;
+    use diku::testutil::*;
+
+    #[test]
+    fn process_input_test() {
+        let (rd, wr) = pipe().unwrap();
+        nonblock(rd);
+        let d = RefCell::new(DescriptorData::new(rd, String::from("localhost")));
+
+        write(wr, b"say hi\r\n!\nsmi").unwrap();
+        assert!(process_input(&d));
+        assert_eq!(get_from_q(&mut d.borrow_mut().input), Some(String::from("say hi")));
+        assert_eq!(get_from_q(&mut d.borrow_mut().input), Some(String::from("say hi")));
+        assert!(d.borrow().input.is_empty());
+
+        // the rest of a line waits for its newline
+        write(wr, b"le\n").unwrap();
+        assert!(process_input(&d));
+        assert_eq!(get_from_q(&mut d.borrow_mut().input), Some(String::from("smile")));
+
+        close(wr).unwrap();
+        assert!(!process_input(&d));
+        close(rd).unwrap();
+    }
+
+    #[test]
+    fn process_commands_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Speaker", &room);
+        let listener = test_char("Listener", &room);
+        let d = Rc::clone(ch.desc.borrow().as_ref().unwrap());
+        game.descriptor_list.borrow_mut().push(Rc::clone(&d));
+        // a link that is not in the game yet talks to the nanny
+        let newbie = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+        game.descriptor_list.borrow_mut().push(Rc::clone(&newbie));
+
+        write_to_q(String::from("say hello"), &mut d.borrow_mut().input);
+        write_to_q(String::from("4711"), &mut newbie.borrow_mut().input);
+        process_commands(&game);
+        assert_eq!(output(&listener), vec!["Speaker says 'hello'"]);
+        assert_eq!(output(&ch), vec!["> "]);
+        assert_eq!(get_from_q(&mut newbie.borrow_mut().output),
+            Some(String::from("Illegal name, please try another.\n\rName: ")));
+    }
+
+    #[test]
+    fn link_death_test() {
+        let mut game = test_game();
+        let room = test_room(3001);
+        let void = test_room(VOID_ROOM);
+        game.world.insert(3001, Rc::clone(&room));
+        game.world.insert(VOID_ROOM, Rc::clone(&void));
+        let ch = test_char("Linky", &room);
+        ch.player.borrow_mut().sex = Sex::Male;
+        let watcher = test_char("Watcher", &room);
+        watcher.player.borrow_mut().level = 22;
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        let desc = Rc::clone(ch.desc.borrow().as_ref().unwrap());
+        game.descriptor_list.borrow_mut().push(Rc::clone(&desc));
+        desc.borrow_mut().snoop.snoop_by = Some(Rc::clone(&watcher));
+        watcher.desc.borrow().as_ref().unwrap().borrow_mut().snoop.snooping = Some(Rc::clone(&ch));
+
+        close_socket(&desc, &game);
+        assert!(ch.desc.borrow().is_none());
+        assert!(game.descriptor_list.borrow().is_empty());
+        assert!(Rc::ptr_eq(&ch.in_room.borrow(), &room));
+        assert_eq!(output(&watcher), vec!["Your victim is no longer among us.\n\r", "Linky has lost his link."]);
+
+        // idled into the void meanwhile
+        char_from_room(&ch);
+        char_to_room(&ch, &void);
+        ch.specials.borrow_mut().was_in_room = Some(3001);
+        ch.specials.borrow_mut().timer = 20;
+
+        let d = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+        assert!(!reconnect(&d, "Watcher", &game));
+        assert!(reconnect(&d, "linky", &game));
+        assert!(Rc::ptr_eq(ch.desc.borrow().as_ref().unwrap(), &d));
+        assert!(Rc::ptr_eq(&ch.in_room.borrow(), &room));
+        assert_eq!(ch.specials.borrow().timer, 0);
+        assert_eq!(ch.specials.borrow().was_in_room, None);
+        assert_eq!(output(&ch), vec!["Reconnecting.\n\r"]);
+        assert_eq!(output(&watcher), vec!["Linky has reconnected."]);
+    }
+
+    #[test]
+    fn switched_link_death_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let god = test_char("Odin", &room);
+        god.player.borrow_mut().level = 23;
+        god.player.borrow_mut().sex = Sex::Male;
+        let mob = test_mob("the raven", &room);
+        let watcher = test_char("Watcher", &room);
+
+        let desc = god.desc.borrow_mut().take().unwrap();
+        *mob.desc.borrow_mut() = Some(Rc::clone(&desc));
+        desc.borrow_mut().character = Some(Rc::clone(&mob));
+        desc.borrow_mut().original = Some(Rc::clone(&god));
+
+        close_socket(&desc, &game);
+        assert!(mob.desc.borrow().is_none());
+        assert!(god.desc.borrow().is_none());
+        assert!(desc.borrow().original.is_none());
+        assert_eq!(output(&watcher), vec!["Odin has lost his link."]);
+    }
+
+    #[test]
+    fn copyover_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Staying", &room);
+        let god = test_char("Odin", &room);
+        god.player.borrow_mut().level = 23;
+        let body = test_char("Body", &room);
+        for k in &[&ch, &god] {
+            game.descriptor_list.borrow_mut().push(Rc::clone(k.desc.borrow().as_ref().unwrap()));
+        }
+        // Odin is off in another body
+        let desc = god.desc.borrow_mut().take().unwrap();
+        *body.desc.borrow_mut() = Some(Rc::clone(&desc));
+        desc.borrow_mut().character = Some(Rc::clone(&body));
+        desc.borrow_mut().original = Some(Rc::clone(&god));
+        let login = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("elsewhere"))));
+        game.descriptor_list.borrow_mut().push(Rc::clone(&login));
+        // half way through making a character
+        let making = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("newhost"))));
+        let newbie = Rc::new(clear_char(Rc::clone(&room)));
+        newbie.player.borrow_mut().name = String::from("Newbie");
+        *newbie.desc.borrow_mut() = Some(Rc::clone(&making));
+        making.borrow_mut().character = Some(newbie);
+        making.borrow_mut().connected = ConnectionMode::QSex;
+        game.descriptor_list.borrow_mut().push(Rc::clone(&making));
+        // saved, but with no link to hand over
+        let linkless = test_char("Linkless", &room);
+        *linkless.desc.borrow_mut() = None;
+        game.character_list.borrow_mut().push(Rc::clone(&linkless));
+
+        write_copyover(5, &game).unwrap();
+        assert!(Rc::ptr_eq(god.desc.borrow().as_ref().unwrap(), &desc));
+
+        let data = read_copyover().unwrap();
+        assert!(read_copyover().is_none());
+        assert_eq!(data.mother, 5);
+        assert_eq!(data.links, vec![
+            CopyoverLink { descriptor: -1, host: String::from("localhost"), name: String::from("Staying"),
+                room: 3001, connected: ConnectionMode::Plying },
+            CopyoverLink { descriptor: -1, host: String::from("localhost"), name: String::from("Odin"),
+                room: 3001, connected: ConnectionMode::Plying },
+            CopyoverLink { descriptor: -1, host: String::from("elsewhere"), name: String::new(),
+                room: 0, connected: ConnectionMode::Nme },
+            CopyoverLink { descriptor: -1, host: String::from("newhost"), name: String::new(),
+                room: 0, connected: ConnectionMode::Nme },
+        ]);
+
+        // nobody is in the player file of a new game, but the links still logging in are taken up
+        let mut game = test_game();
+        game.world.insert(3001, Rc::clone(&room));
+        copyover_recover(data, &game);
+        let descriptors = game.descriptor_list.borrow().clone();
+        let hosts: Vec<String> = descriptors.iter().map(|d| d.borrow().host.clone()).collect();
+        assert_eq!(hosts, vec!["elsewhere", "newhost"]);
+        for d in &descriptors {
+            assert!(d.borrow().connected == ConnectionMode::Nme);
+            assert_eq!(get_from_q(&mut d.borrow_mut().output),
+                Some(String::from("By what name do you wish to be known? ")));
+        }
     }
-}
\ No newline at end of file
+}
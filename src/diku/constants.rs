@@ -1,9 +1,8 @@
-use enum_map::EnumMap;
-
 use diku::structs::*;
 
 pub const PULSE_ZONE: u64 = 240;
 pub const PULSE_MOBILE: u64 = 40;
+pub const PULSE_VIOLENCE: u64 = 12;
 pub const WAIT_SEC: u64 = 4;
 pub const WAIT_ROUND: u64 = 4;
 
@@ -22,8 +21,15 @@ pub const SECS_PER_MUD_DAY: u64 = 24 * SECS_PER_MUD_HOUR;
 pub const SECS_PER_MUD_MONTH: u64 = 35 * SECS_PER_MUD_DAY;
 pub const SECS_PER_MUD_YEAR: u64 = 17 * SECS_PER_MUD_MONTH;
 
+// The epoch of the MUD calendar
+pub const BEGINNING_OF_TIME: u64 = 650336715;
+
 pub const NOWHERE: i32 = -1;
+pub const VOID_ROOM: u32 = 1;   // Idle players are kept here
+pub const START_ROOM: u32 = 3001;   // Mortals start here
 pub const MAX_OBJ_AFFECT: usize = 2;
+pub const MAX_PROD: usize = 6; // Items a shop can produce
+pub const MAX_TRADE: usize = 5; // Item types a shop can buy
 
 // For 'char_player_data'
 pub const MAX_TOUNGE: usize = 3;
@@ -31,11 +37,32 @@ pub const MAX_SKILLS: usize = 53;
 pub const MAX_WEAR: usize = 18;
 pub const MAX_AFFECT: usize = 25;
 pub const MAX_OBJ_SAVE: usize = 15; // Used in OBJ_FILE_U *DO*NOT*CHANGE*
+pub const MAX_IGNORE: usize = 10;
+pub const MAX_HISTORY: usize = 20; // Messages kept per channel
+pub const MAX_SPAM: u8 = 5; // Messages in a row before a player is held back
+
+pub const MAX_NPC_CORPSE_TIME: u32 = 5;
+pub const MAX_PC_CORPSE_TIME: u32 = 10;
 
 pub const DFLT_PORT: u16 = 4000;
 pub const MAX_NAME_LENGTH: usize = 15;
 pub const MAX_HOSTNAME: usize = 256;
 pub const OPT_USEC: u32 = 250_000;
+pub const PASSES_PER_SEC: u64 = 1_000_000 / OPT_USEC as u64;
+pub const SHUTDOWN_DELAY: u32 = 60;   // Seconds of warning before a shutdown
+
+// Exit codes, telling the script that runs the game what to do next
+pub const EXIT_SHUTDOWN: i32 = 0;   // Stay down
+pub const EXIT_REBOOT: i32 = 52;    // Start the game again
+
+// What a link is shown on its way into the game
+pub const GREETINGS: &'static str = concat!(
+    "\n\r\n\r                              DikuMUD I (GAMMA 0.0)\n\r\n\r",
+    "                            Created by\n\r",
+    "                 Hans Henrik Staerfeldt, Katja Nyboe,\n\r",
+    "           Tom Madsen, Michael Seifert, and Sebastian Hammer\n\r\n\r");
+pub const MENU: &'static str = "\n\rWelcome to DikuMUD\n\r\n\r0) Exit from DikuMud.\n\r1) Enter the game.\n\r\n\r   Make your choice: ";
+pub const WELC_MESSG: &'static str = "\n\rWelcome to the land of DikuMUD. May your visit here be... Enjoyable.\n\r\n\r";
 
 // data files used by the game system
 
@@ -44,10 +71,12 @@ pub const WORLD_FILE: &'static str = "tinyworld.wld";
 pub const MOB_FILE: &'static str = "tinyworld.mob";
 pub const OBJ_FILE: &'static str = "tinyworld.obj";
 pub const ZONE_FILE: &'static str = "tinyworld.zon";
+pub const SHOP_FILE: &'static str = "tinyworld.shp";
 pub const CREDITS_FILE: &'static str = "credits";
 pub const NEWS_FILE: &'static str = "news";
 pub const MOTD_FILE: &'static str = "motd";
 pub const PLAYER_FILE: &'static str = "players";
+pub const OBJ_SAVE_FILE: &'static str = "pcobjs.obj";
 pub const TIME_FILE: &'static str = "time";
 pub const IDEA_FILE: &'static str = "ideas";
 pub const TYPO_FILE: &'static str = "typos";
@@ -59,6 +88,9 @@ pub const HELP_PAGE_FILE: &'static str = "help";
 pub const INFO_FILE: &'static str = "info";
 pub const WIZLIST_FILE: &'static str = "wizlist";
 pub const POSEMESS_FILE: &'static str = "poses";
+pub const COPYOVER_FILE: &'static str = "copyover.dat";   // links handed over to the new game
+pub const CLIMATE_FILE: &'static str = "climate";   // zones with weather of their own
+pub const CORPSE_FILE: &'static str = "corpse";     // what make_corpse leaves behind
 
 pub const MOVEMENT_LOSS: [u8; 8] = [
     /* SectorType::Inside 		=>*/
@@ -88,6 +120,378 @@ pub const DIRS: [&'static str; 6] = [
     "down",
 ];
 
+pub const REV_DIR: [Direction; 6] = [
+    Direction::South,
+    Direction::West,
+    Direction::North,
+    Direction::East,
+    Direction::Down,
+    Direction::Up,
+];
+
+// names of the spells and skills, indexed by number - 1
+pub const SPELLS: [&'static str; 60] = [
+    "armor",
+    "teleport",
+    "bless",
+    "blindness",
+    "burning hands",
+    "call lightning",
+    "charm person",
+    "chill touch",
+    "clone",
+    "colour spray",
+    "control weather",
+    "create food",
+    "create water",
+    "cure blind",
+    "cure critic",
+    "cure light",
+    "curse",
+    "detect evil",
+    "detect invisibility",
+    "detect magic",
+    "detect poison",
+    "dispel evil",
+    "earthquake",
+    "enchant weapon",
+    "energy drain",
+    "fireball",
+    "harm",
+    "heal",
+    "invisibility",
+    "lightning bolt",
+    "locate object",
+    "magic missile",
+    "poison",
+    "protection from evil",
+    "remove curse",
+    "sanctuary",
+    "shocking grasp",
+    "sleep",
+    "strength",
+    "summon",
+    "ventriloquate",
+    "word of recall",
+    "remove poison",
+    "sense life",
+    "sneak",
+    "hide",
+    "steal",
+    "backstab",
+    "pick",
+    "kick",
+    "bash",
+    "rescue",
+    "identify",
+    "animate dead",
+    "fear",
+    "fire breath",
+    "gas breath",
+    "frost breath",
+    "acid breath",
+    "lightning breath",
+];
+
+// sent when the last affect of a spell runs out, indexed by spell number
+pub const SPELL_WEAR_OFF_MSG: [&'static str; 53] = [
+    "RESERVED DB.C",
+    "You feel less protected.",
+    "!Teleport!",
+    "You feel less righteous.",
+    "You feel a cloak of blindness disolve.",
+    "!Burning Hands!",
+    "!Call Lightning",
+    "You feel more self-confident.",
+    "You feel your strength return.",
+    "!Clone!",
+    "!Color Spray!",
+    "!Control Weather!",
+    "!Create Food!",
+    "!Create Water!",
+    "!Cure Blind!",
+    "!Cure Critic!",
+    "!Cure Light!",
+    "You feel better.",
+    "You feel less aware.",
+    "You feel less aware.",
+    "You feel less aware.",
+    "You feel less aware.",
+    "!Dispel Evil!",
+    "!Earthquake!",
+    "!Enchant Weapon!",
+    "!Energy Drain!",
+    "!Fireball!",
+    "!Harm!",
+    "!Heal",
+    "You feel yourself exposed.",
+    "!Lightning Bolt!",
+    "!Locate object!",
+    "!Magic Missile!",
+    "You feel less sick.",
+    "You feel less protected.",
+    "!Remove Curse!",
+    "The white aura around your body fades.",
+    "!Shocking Grasp!",
+    "You feel less tired.",
+    "You feel weaker.",
+    "!Summon!",
+    "!Ventriloquate!",
+    "!Word of Recall!",
+    "!Remove Poison!",
+    "You feel less aware of your suroundings.",
+    "",
+    "!Hide!",
+    "!Steal!",
+    "!Backstab!",
+    "!Pick Lock!",
+    "!Kick!",
+    "!Bash!",
+    "!Rescue!",
+];
+
+pub const ITEM_TYPES: [&'static str; 21] = [
+    "LIGHT",
+    "SCROLL",
+    "WAND",
+    "STAFF",
+    "WEAPON",
+    "FIRE WEAPON",
+    "MISSILE",
+    "TREASURE",
+    "ARMOR",
+    "POTION",
+    "WORN",
+    "OTHER",
+    "TRASH",
+    "CONTAINER",
+    "NOTE",
+    "LIQUID CONTAINER",
+    "KEY",
+    "FOOD",
+    "MONEY",
+    "PEN",
+    "BOAT",
+];
+
+pub const EXTRA_BITS: [&'static str; 12] = [
+    "GLOW",
+    "HUM",
+    "DARK",
+    "LOCK",
+    "EVIL",
+    "INVISIBLE",
+    "MAGIC",
+    "NODROP",
+    "BLESS",
+    "ANTI-GOOD",
+    "ANTI-EVIL",
+    "ANTI-NEUTRAL",
+];
+
+pub const AFFECTED_BITS: [&'static str; 23] = [
+    "BLIND",
+    "INVISIBLE",
+    "DETECT-EVIL",
+    "DETECT-INVISIBLE",
+    "DETECT-MAGIC",
+    "SENCE-LIFE",
+    "HOLD",
+    "SANCTUARY",
+    "GROUP",
+    "CURSE",
+    "FLAMING-HANDS",
+    "POISON",
+    "PROTECT-EVIL",
+    "PARALYSIS",
+    "MORDENS-SWORD",
+    "FLAMING-SWORD",
+    "SLEEP",
+    "DODGE",
+    "SNEAK",
+    "HIDE",
+    "FEAR",
+    "CHARM",
+    "FOLLOW",
+];
+
+pub const APPLY_TYPES: [&'static str; 25] = [
+    "NONE",
+    "STR",
+    "DEX",
+    "INT",
+    "WIS",
+    "CON",
+    "SEX",
+    "CLASS",
+    "LEVEL",
+    "AGE",
+    "CHAR_WEIGHT",
+    "CHAR_HEIGHT",
+    "MANA",
+    "HIT",
+    "MOVE",
+    "GOLD",
+    "EXP",
+    "ARMOR",
+    "HITROLL",
+    "DAMROLL",
+    "SAVING_PARA",
+    "SAVING_ROD",
+    "SAVING_PETRI",
+    "SAVING_BREATH",
+    "SAVING_SPELL",
+];
+
+pub const WEAR_BITS: [&'static str; 15] = [
+    "TAKE",
+    "FINGER",
+    "NECK",
+    "BODY",
+    "HEAD",
+    "LEGS",
+    "FEET",
+    "HANDS",
+    "ARMS",
+    "SHIELD",
+    "ABOUT",
+    "WAISTE",
+    "WIELD",
+    "HOLD",
+    "THROW",
+];
+
+pub const EQUIPMENT_TYPES: [&'static str; 18] = [
+    "Special",
+    "Worn on right finger",
+    "Worn on left finger",
+    "First worn around Neck",
+    "Second worn around Neck",
+    "Worn on body",
+    "Worn on head",
+    "Worn on legs",
+    "Worn on feet",
+    "Worn on hands",
+    "Worn on arms",
+    "Worn as shield",
+    "Worn about body",
+    "Worn around waiste",
+    "Worn around right wrist",
+    "Worn around left wrist",
+    "Wielded",
+    "Held",
+];
+
+pub const ROOM_BITS: [&'static str; 10] = [
+    "DARK",
+    "DEATH",
+    "NO_MOB",
+    "INDOORS",
+    "LAWFUL",
+    "NEUTRAL",
+    "CHAOTIC",
+    "NO_MAGIC",
+    "TUNNEL",
+    "PRIVATE",
+];
+
+pub const EXIT_BITS: [&'static str; 6] = [
+    "IS-DOOR",
+    "CLOSED",
+    "LOCKED",
+    "RSCLOSED",
+    "RSLOCKED",
+    "PICKPROOF",
+];
+
+pub const SECTOR_TYPES: [&'static str; 8] = [
+    "Inside",
+    "City",
+    "Field",
+    "Forest",
+    "Hills",
+    "Mountains",
+    "Water Swim",
+    "Water NoSwim",
+];
+
+pub const PC_CLASS_TYPES: [&'static str; 4] = [
+    "Magic User",
+    "Cleric",
+    "Thief",
+    "Warrior",
+];
+
+pub const ACTION_BITS: [&'static str; 8] = [
+    "SPEC",
+    "SENTINEL",
+    "SCAVENGER",
+    "ISNPC",
+    "NICE-THIEF",
+    "AGGRESSIVE",
+    "STAY-ZONE",
+    "WIMPY",
+];
+
+pub const PLAYER_BITS: [&'static str; 8] = [
+    "BRIEF",
+    "NOSHOUT",
+    "COMPACT",
+    "DONTSET",
+    "NOTELL",
+    "NOEMOTE",
+    "LOG",
+    "FREEZE",
+];
+
+pub const POSITION_TYPES: [&'static str; 9] = [
+    "Dead",
+    "Mortally wounded",
+    "Incapasitated",
+    "Stunned",
+    "Sleeping",
+    "Resting",
+    "Sitting",
+    "Fighting",
+    "Standing",
+];
+
+pub const SEX_TYPES: [&'static str; 3] = [
+    "Neutral",
+    "Male",
+    "Female",
+];
+
+pub const WEEKDAYS: [&'static str; 7] = [
+    "the Day of the Moon",
+    "the Day of the Bull",
+    "the Day of the Deception",
+    "the Day of Thunder",
+    "the Day of Freedom",
+    "the day of the Great Gods",
+    "the Day of the Sun",
+];
+
+pub const MONTH_NAME: [&'static str; 17] = [
+    "Month of Winter",           // 0
+    "Month of the Winter Wolf",
+    "Month of the Frost Giant",
+    "Month of the Old Forces",
+    "Month of the Grand Struggle",
+    "Month of the Spring",
+    "Month of Nature",
+    "Month of Futility",
+    "Month of the Dragon",
+    "Month of the Sun",
+    "Month of the Heat",
+    "Month of the Battle",
+    "Month of the Dark Shades",
+    "Month of the Shadows",
+    "Month of the Long Shadows",
+    "Month of the Ancient Darkness",
+    "Month of the Great Evil",
+];
+
 pub const TITLES: [[TitleType; 25]; 4] = [
     /*Class::MagicUser =>*/
     [
@@ -604,6 +1008,38 @@ pub const TITLES: [[TitleType; 25]; 4] = [
 ];
 
 
+/* [class], [saving throw type], [level] (levels 0 to 20, immortals always save) */
+pub const SAVING_THROWS: [[[i16; 21]; 5]; 4] = [
+    [ // Magic User
+        [16, 14, 14, 14, 14, 14, 13, 13, 13, 13, 13, 11, 11, 11, 11, 11, 10, 10, 10, 10, 10],
+        [13, 11, 11, 11, 11, 11,  9,  9,  9,  9,  9,  7,  7,  7,  7,  7,  5,  5,  5,  5,  5],
+        [15, 13, 13, 13, 13, 13, 11, 11, 11, 11, 11,  9,  9,  9,  9,  9,  7,  7,  7,  7,  7],
+        [17, 15, 15, 15, 15, 15, 13, 13, 13, 13, 13, 11, 11, 11, 11, 11,  9,  9,  9,  9,  9],
+        [14, 12, 12, 12, 12, 12, 10, 10, 10, 10, 10,  8,  8,  8,  8,  8,  6,  6,  6,  6,  6],
+    ],
+    [ // Cleric
+        [11, 10, 10, 10,  9,  9,  9,  7,  7,  7,  6,  6,  6,  5,  5,  5,  4,  4,  4,  2,  2],
+        [16, 14, 14, 14, 13, 13, 13, 11, 11, 11, 10, 10, 10,  9,  9,  9,  8,  8,  8,  6,  6],
+        [15, 13, 13, 13, 12, 12, 12, 10, 10, 10,  9,  9,  9,  8,  8,  8,  7,  7,  7,  5,  5],
+        [18, 16, 16, 16, 15, 15, 15, 13, 13, 13, 12, 12, 12, 11, 11, 11, 10, 10, 10,  8,  8],
+        [17, 15, 15, 15, 14, 14, 14, 12, 12, 12, 11, 11, 11, 10, 10, 10,  9,  9,  9,  7,  7],
+    ],
+    [ // Thief
+        [15, 13, 13, 13, 13, 12, 12, 12, 12, 11, 11, 11, 11, 10, 10, 10, 10,  9,  9,  9,  9],
+        [16, 14, 14, 14, 14, 12, 12, 12, 12, 10, 10, 10, 10,  8,  8,  8,  8,  6,  6,  6,  6],
+        [14, 12, 12, 12, 12, 11, 11, 11, 11, 10, 10, 10, 10,  9,  9,  9,  9,  8,  8,  8,  8],
+        [18, 16, 16, 16, 16, 15, 15, 15, 15, 14, 14, 14, 14, 13, 13, 13, 13, 12, 12, 12, 12],
+        [17, 15, 15, 15, 15, 13, 13, 13, 13, 11, 11, 11, 11,  9,  9,  9,  9,  7,  7,  7,  7],
+    ],
+    [ // Warrior
+        [16, 14, 14, 13, 13, 11, 11, 10, 10,  8,  8,  7,  7,  5,  5,  4,  4,  3,  3,  3,  3],
+        [18, 16, 16, 15, 15, 13, 13, 12, 12, 10, 10,  9,  9,  7,  7,  6,  6,  5,  5,  5,  5],
+        [17, 15, 15, 14, 14, 12, 12, 11, 11,  9,  9,  8,  8,  6,  6,  5,  5,  4,  4,  4,  4],
+        [20, 17, 17, 16, 16, 13, 13, 12, 12,  9,  9,  8,  8,  5,  5,  4,  4,  3,  3,  3,  3],
+        [18, 16, 16, 15, 15, 13, 13, 12, 12, 10, 10,  9,  9,  7,  7,  6,  6,  5,  5,  5,  5],
+    ],
+];
+
 /* [class], [level] (all) */
 pub const THACO: [[i16; 25]; 4] = [
     [
@@ -934,6 +1370,35 @@ pub const BACKSTAB_MULT: [u8; 25] = [
     5 /* 25 */,
 ];
 
+pub const DEX_APP_SKILL: [DexSkillType; 26] = [
+    DexSkillType { p_pocket: -99, p_locks: -99, sneak: -99, hide: -60 }, /* 0 */
+    DexSkillType { p_pocket: -90, p_locks: -90, sneak: -90, hide: -50 }, /* 1 */
+    DexSkillType { p_pocket: -80, p_locks: -80, sneak: -80, hide: -45 }, /* 2 */
+    DexSkillType { p_pocket: -70, p_locks: -70, sneak: -70, hide: -40 }, /* 3 */
+    DexSkillType { p_pocket: -60, p_locks: -60, sneak: -60, hide: -35 }, /* 4 */
+    DexSkillType { p_pocket: -50, p_locks: -50, sneak: -50, hide: -30 }, /* 5 */
+    DexSkillType { p_pocket: -40, p_locks: -40, sneak: -40, hide: -25 }, /* 6 */
+    DexSkillType { p_pocket: -30, p_locks: -30, sneak: -30, hide: -20 }, /* 7 */
+    DexSkillType { p_pocket: -20, p_locks: -20, sneak: -20, hide: -15 }, /* 8 */
+    DexSkillType { p_pocket: -15, p_locks: -10, sneak: -20, hide: -10 }, /* 9 */
+    DexSkillType { p_pocket: -10, p_locks: -5, sneak: -15, hide: -5 }, /* 10 */
+    DexSkillType { p_pocket: -5, p_locks: 0, sneak: -10, hide: 0 }, /* 11 */
+    DexSkillType { p_pocket: 0, p_locks: 0, sneak: -5, hide: 0 }, /* 12 */
+    DexSkillType { p_pocket: 0, p_locks: 0, sneak: 0, hide: 0 }, /* 13 */
+    DexSkillType { p_pocket: 0, p_locks: 0, sneak: 0, hide: 0 }, /* 14 */
+    DexSkillType { p_pocket: 0, p_locks: 0, sneak: 0, hide: 0 }, /* 15 */
+    DexSkillType { p_pocket: 0, p_locks: 5, sneak: 0, hide: 0 }, /* 16 */
+    DexSkillType { p_pocket: 5, p_locks: 10, sneak: 5, hide: 5 }, /* 17 */
+    DexSkillType { p_pocket: 10, p_locks: 15, sneak: 10, hide: 10 }, /* 18 */
+    DexSkillType { p_pocket: 15, p_locks: 20, sneak: 15, hide: 15 }, /* 19 */
+    DexSkillType { p_pocket: 15, p_locks: 20, sneak: 15, hide: 15 }, /* 20 */
+    DexSkillType { p_pocket: 20, p_locks: 25, sneak: 15, hide: 20 }, /* 21 */
+    DexSkillType { p_pocket: 20, p_locks: 25, sneak: 20, hide: 20 }, /* 22 */
+    DexSkillType { p_pocket: 25, p_locks: 25, sneak: 20, hide: 20 }, /* 23 */
+    DexSkillType { p_pocket: 25, p_locks: 30, sneak: 25, hide: 25 }, /* 24 */
+    DexSkillType { p_pocket: 25, p_locks: 30, sneak: 25, hide: 25 }, /* 25 */
+];
+
 pub const DEX_APP: [DexAppType; 26] = [
     DexAppType {
         reaction: -7,
@@ -1067,6 +1532,84 @@ pub const DEX_APP: [DexAppType; 26] = [
     } /* 25 */,
 ];
 
+// indexed by Liquid, the effects are given per 4 units drunk
+pub const LIQUIDS: [LiquidType; 16] = [
+    LiquidType { name: "water", drunk: 0, full: 1, thirst: 10 },
+    LiquidType { name: "beer", drunk: 3, full: 2, thirst: 5 },
+    LiquidType { name: "wine", drunk: 5, full: 2, thirst: 5 },
+    LiquidType { name: "ale", drunk: 2, full: 2, thirst: 5 },
+    LiquidType { name: "dark ale", drunk: 1, full: 2, thirst: 5 },
+    LiquidType { name: "whisky", drunk: 6, full: 1, thirst: 4 },
+    LiquidType { name: "lemonade", drunk: 0, full: 1, thirst: 8 },
+    LiquidType { name: "firebreather", drunk: 10, full: 0, thirst: 0 },
+    LiquidType { name: "local speciality", drunk: 3, full: 3, thirst: 3 },
+    LiquidType { name: "slime mold juice", drunk: 0, full: 4, thirst: -8 },
+    LiquidType { name: "milk", drunk: 0, full: 3, thirst: 6 },
+    LiquidType { name: "tea", drunk: 0, full: 1, thirst: 6 },
+    LiquidType { name: "coffee", drunk: 0, full: 1, thirst: 6 },
+    LiquidType { name: "blood", drunk: 0, full: 2, thirst: -1 },
+    LiquidType { name: "salt water", drunk: 0, full: 1, thirst: -2 },
+    LiquidType { name: "coca cola", drunk: 0, full: 1, thirst: 5 },
+];
+
+pub const INT_APP: [IntAppType; 26] = [
+    IntAppType { learn: 3 }, /* 0 */
+    IntAppType { learn: 5 }, /* 1 */
+    IntAppType { learn: 7 }, /* 2 */
+    IntAppType { learn: 8 }, /* 3 */
+    IntAppType { learn: 9 }, /* 4 */
+    IntAppType { learn: 10 }, /* 5 */
+    IntAppType { learn: 11 }, /* 6 */
+    IntAppType { learn: 12 }, /* 7 */
+    IntAppType { learn: 13 }, /* 8 */
+    IntAppType { learn: 15 }, /* 9 */
+    IntAppType { learn: 17 }, /* 10 */
+    IntAppType { learn: 19 }, /* 11 */
+    IntAppType { learn: 22 }, /* 12 */
+    IntAppType { learn: 25 }, /* 13 */
+    IntAppType { learn: 30 }, /* 14 */
+    IntAppType { learn: 35 }, /* 15 */
+    IntAppType { learn: 40 }, /* 16 */
+    IntAppType { learn: 45 }, /* 17 */
+    IntAppType { learn: 50 }, /* 18 */
+    IntAppType { learn: 53 }, /* 19 */
+    IntAppType { learn: 55 }, /* 20 */
+    IntAppType { learn: 56 }, /* 21 */
+    IntAppType { learn: 57 }, /* 22 */
+    IntAppType { learn: 58 }, /* 23 */
+    IntAppType { learn: 59 }, /* 24 */
+    IntAppType { learn: 60 }, /* 25 */
+];
+
+pub const WIS_APP: [WisAppType; 26] = [
+    WisAppType { bonus: 0 }, /* 0 */
+    WisAppType { bonus: 0 }, /* 1 */
+    WisAppType { bonus: 0 }, /* 2 */
+    WisAppType { bonus: 0 }, /* 3 */
+    WisAppType { bonus: 0 }, /* 4 */
+    WisAppType { bonus: 0 }, /* 5 */
+    WisAppType { bonus: 0 }, /* 6 */
+    WisAppType { bonus: 0 }, /* 7 */
+    WisAppType { bonus: 0 }, /* 8 */
+    WisAppType { bonus: 0 }, /* 9 */
+    WisAppType { bonus: 0 }, /* 10 */
+    WisAppType { bonus: 0 }, /* 11 */
+    WisAppType { bonus: 2 }, /* 12 */
+    WisAppType { bonus: 2 }, /* 13 */
+    WisAppType { bonus: 3 }, /* 14 */
+    WisAppType { bonus: 3 }, /* 15 */
+    WisAppType { bonus: 3 }, /* 16 */
+    WisAppType { bonus: 4 }, /* 17 */
+    WisAppType { bonus: 5 }, /* 18 */
+    WisAppType { bonus: 6 }, /* 19 */
+    WisAppType { bonus: 6 }, /* 20 */
+    WisAppType { bonus: 6 }, /* 21 */
+    WisAppType { bonus: 6 }, /* 22 */
+    WisAppType { bonus: 7 }, /* 23 */
+    WisAppType { bonus: 7 }, /* 24 */
+    WisAppType { bonus: 7 }, /* 25 */
+];
+
 pub const CON_APP: [ConAppType; 26] = [
     ConAppType {
         hitp: -4,
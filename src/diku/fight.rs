@@ -1,20 +1,21 @@
 use std::cmp::{max, min};
 use std::collections::HashMap;
-use std::fs::File;
 use std::io::BufReader;
 use std::io::prelude::*;
 use std::ops::DerefMut;
+use std::ptr;
 use std::rc::Rc;
 
 use diku::act_offensive::do_flee;
 use diku::comm::act;
 use diku::constants::*;
-use diku::handler::{affect_from_char, affected_by_spell};
+use diku::game::{clear_object, save_char};
+use diku::handler::{affect_from_char, affected_by_spell, create_money, extract_char, obj_from_char, obj_to_obj, obj_to_room, unequip_char};
 use diku::limits::{gain_exp, hit_limit};
 use diku::spell_parser::stop_follower;
 use diku::spells::*;
 use diku::structs::*;
-use diku::utility::{dice, fread_string, log, number, read_line_trim, read_number};
+use diku::utility::{fread_string, log, read_line_trim, read_number};
 
 fn appear(ch: &CharData) {
     act("$n slowly fade into existence.", false, ch, None, None, None, None,
@@ -27,20 +28,17 @@ fn appear(ch: &CharData) {
     ch.specials.borrow_mut().affected_by.remove(AffectedFlags::AFF_INVISIBLE)
 }
 
-pub fn load_messages() -> HashMap<i32, Vec<MessageType>> {
-    let file = File::open(MESS_FILE).expect("read messages");
-    let mut reader = BufReader::new(file);
-    
+pub fn load_messages<R: Read + Seek>(reader: &mut BufReader<R>) -> HashMap<i32, Vec<MessageType>> {
     let mut fight_messages = HashMap::new();
 
-    while read_line_trim(&mut reader).bytes().nth(0) == Some(b'M') {
-        let a_type = read_number(&mut reader, true).unwrap();
+    while read_line_trim(reader).bytes().nth(0) == Some(b'M') {
+        let a_type = read_number(reader, true).unwrap();
         let message_list = fight_messages.entry(a_type).or_insert(Vec::new());
 
-        let die_msg = read_msgs(&mut reader);
-        let miss_msg = read_msgs(&mut reader);
-        let hit_msg = read_msgs(&mut reader);
-        let god_msg = read_msgs(&mut reader);
+        let die_msg = read_msgs(reader);
+        let miss_msg = read_msgs(reader);
+        let hit_msg = read_msgs(reader);
+        let god_msg = read_msgs(reader);
 
         message_list.push(MessageType {
             die_msg,
@@ -53,7 +51,7 @@ pub fn load_messages() -> HashMap<i32, Vec<MessageType>> {
     fight_messages
 }
 
-fn update_pos(victim: &CharData) {
+pub fn update_pos(victim: &CharData) {
     if victim.get_hit() > 0 && victim.get_pos() > Position::Stunned { return; }
     else if victim.get_hit() > 0 { victim.specials.borrow_mut().position = Position::Standing; }
     else if victim.get_hit() <= -11 { victim.specials.borrow_mut().position = Position::Dead; }
@@ -75,7 +73,7 @@ fn read_msgs<R: Read>(reader: &mut BufReader<R>) -> MsgType {
 }
 
 // start one char fighting another (yes, it is horrible, I know... )
-fn set_fighting(ch: Rc<CharData>, vict: Rc<CharData>,
+pub fn set_fighting(ch: Rc<CharData>, vict: Rc<CharData>,
         combat_list: &mut Vec<Rc<CharData>>) {
     assert!(!ch.specials.borrow().fighting.is_some());
 
@@ -89,25 +87,92 @@ fn set_fighting(ch: Rc<CharData>, vict: Rc<CharData>,
     ch.specials.borrow_mut().position = Position::Fighting;
 }
 
+// remove a char from the list of fighting chars
+pub fn stop_fighting(ch: &CharData, game: &Game) {
+    assert!(ch.specials.borrow().fighting.is_some());
+
+    game.combat_list.borrow_mut().retain(|k| !ptr::eq(k.as_ref(), ch));
+
+    ch.specials.borrow_mut().fighting = None;
+    ch.specials.borrow_mut().position = Position::Standing;
+    update_pos(ch);
+}
+
+fn make_corpse(ch: &Rc<CharData>, game: &Game) {
+    let proto = &game.corpse_proto;
+    let name = if ch.is_npc() { ch.player.borrow().short_descr.clone() } else { ch.get_name() };
+
+    let mut corpse = clear_object();
+    corpse.name = proto.name.clone();
+    corpse.description = proto.description.replace("%s", &name);
+    corpse.short_description = proto.short_description.replace("%s", &name);
+    {
+        let mut flags = corpse.obj_flags.borrow_mut();
+        flags.type_flag = ItemType::Container;
+        flags.wear_flags = WearFlags::ITEM_TAKE;
+        flags.value[0] = 0; // You can't store stuff in a corpse
+        flags.value[3] = 1; // corpse identifier
+        flags.weight = ch.get_weight() as i32;
+        flags.cost_per_day = 100000;
+        flags.timer = if ch.is_npc() { proto.npc_timer } else { proto.pc_timer };
+    }
+    let corpse = Rc::new(corpse);
+    game.object_list.borrow_mut().push(Rc::clone(&corpse));
+
+    let carrying = ch.carrying.borrow().clone();
+    for obj in carrying {
+        obj_from_char(&obj);
+        obj_to_obj(obj, &corpse);
+    }
+
+    if ch.get_gold() > 0 {
+        let money = create_money(ch.get_gold(), game);
+        ch.points.borrow_mut().gold = 0;
+        obj_to_obj(money, &corpse);
+    }
+
+    let positions: Vec<EquipmentPosition> = ch.equipment.borrow().iter()
+        .filter(|&(_, e)| e.is_some()).map(|(pos, _)| pos).collect();
+    for pos in positions {
+        if let Some(obj) = unequip_char(ch, pos) {
+            obj_to_obj(obj, &corpse);
+        }
+    }
+
+    obj_to_room(corpse, &ch.in_room.borrow());
+}
+
+fn raw_kill(ch: &Rc<CharData>, game: &Game) {
+    if ch.specials.borrow().fighting.is_some() {
+        stop_fighting(ch, game);
+    }
+
+    make_corpse(ch, game);
+    extract_char(ch, game);
+}
+
+fn die(ch: &Rc<CharData>, game: &Game) {
+    gain_exp(ch, -(ch.get_exp() / 2), game);
+    raw_kill(ch, game);
+    // a dead player starts over in the temple
+    save_char(ch, START_ROOM, game);
+}
+
 // changing to add 'w' to mean singular and 'W' to be plural -sproctor
 fn replace_string(string: &str, weapon: &AttackHitType) -> String {
     let mut buf = String::with_capacity(string.len());
     let mut chars = string.chars();
-    let mut i = 0;
-    while chars.nth(i).is_some() {
-        let c = chars.nth(i).unwrap();
+    while let Some(c) = chars.next() {
         if c == '#' {
-            i += 1;
-            match chars.nth(i).unwrap() {
-                'W' => buf.push_str(weapon.plural),
-                'w' => buf.push_str(weapon.singular),
-                '#' => buf.push('#'),
+            match chars.next() {
+                Some('W') => buf.push_str(weapon.plural),
+                Some('w') => buf.push_str(weapon.singular),
+                Some('#') => buf.push('#'),
                 _ => (),
             }
         } else {
             buf.push(c);
         }
-        i += 1;
     }
     buf
 }
@@ -127,8 +192,8 @@ fn dam_message(dam: i16, ch: &CharData, victim: &CharData, w_type: i32) {
 
     let w_type = w_type - TYPE_HIT; // Change to base of table with text
 
-    let wield = ch.equipment[EquipmentPosition::Wield].as_ref()
-        .map(|o| Rc::as_ref(o));
+    let wield = ch.get_eq(EquipmentPosition::Wield);
+    let wield = wield.as_ref().map(|o| Rc::as_ref(o));
 
     let indices = [0, 2, 4, 6, 10, 15, 20, 100];
     for i in 0..indices.len() {
@@ -141,8 +206,8 @@ fn dam_message(dam: i16, ch: &CharData, victim: &CharData, w_type: i32) {
 }
 
 fn send_msg(message: &MsgType, ch: &CharData, victim: &CharData) {
-    let obj = ch.equipment[EquipmentPosition::Wield].as_ref()
-        .map(|o| Rc::as_ref(o));
+    let obj = ch.get_eq(EquipmentPosition::Wield);
+    let obj = obj.as_ref().map(|o| Rc::as_ref(o));
     act(&message.attacker_msg, false, ch, obj, Some(victim), None, None,
         VictimType::ToChar);
     act(&message.victim_msg, false, ch, obj, Some(victim), None, None,
@@ -154,7 +219,6 @@ fn send_msg(message: &MsgType, ch: &CharData, victim: &CharData) {
 pub fn damage(ch: Rc<CharData>, victim: Rc<CharData>, dam: i16,
         attacktype: i32, game: &Game) {
 
-    let mut combat_list = game.combat_list.borrow_mut();
     let mut dam = dam;
 
     assert!(victim.get_pos() > Position::Dead);
@@ -164,6 +228,7 @@ pub fn damage(ch: Rc<CharData>, victim: Rc<CharData>, dam: i16,
     }
 
     if victim != ch {
+        let mut combat_list = game.combat_list.borrow_mut();
         if victim.get_pos() > Position::Stunned {
             if victim.specials.borrow().fighting.is_none() {
                 set_fighting(Rc::clone(&victim), Rc::clone(&ch), combat_list.deref_mut());
@@ -197,13 +262,13 @@ pub fn damage(ch: Rc<CharData>, victim: Rc<CharData>, dam: i16,
     victim.points.borrow_mut().hit -= dam;
 
     if ch != victim {
-        gain_exp(&ch, victim.get_level() as i32 * dam as i32);
+        gain_exp(&ch, victim.get_level() as i32 * dam as i32, game);
     }
 
     update_pos(&victim);
 
     if attacktype >= TYPE_HIT && attacktype <= TYPE_SLASH {
-        if ch.equipment[EquipmentPosition::Wield].is_none() {
+        if ch.get_eq(EquipmentPosition::Wield).is_none() {
             dam_message(dam, Rc::as_ref(&ch), Rc::as_ref(&victim), TYPE_HIT);
         } else {
             dam_message(dam, Rc::as_ref(&ch), Rc::as_ref(&victim), attacktype);
@@ -211,12 +276,19 @@ pub fn damage(ch: Rc<CharData>, victim: Rc<CharData>, dam: i16,
     } else {
         match game.fight_messages.get(&attacktype) {
             Some(messages) => {
-                let nr = dice(1, messages.len() as u32);
-                let message = messages.get(nr as usize);
-                if !victim.is_npc() && victim.get_level() > 20 {
-                    send_msg(&message.unwrap().god_msg, Rc::as_ref(&ch),
-                        Rc::as_ref(&victim));
-                }
+                let message = &messages[game.dice(1, messages.len() as u32) as usize - 1];
+                let msg = if !victim.is_npc() && victim.get_level() > 20 {
+                    &message.god_msg
+                } else if dam != 0 {
+                    if victim.get_pos() == Position::Dead {
+                        &message.die_msg
+                    } else {
+                        &message.hit_msg
+                    }
+                } else {
+                    &message.miss_msg
+                };
+                send_msg(msg, Rc::as_ref(&ch), Rc::as_ref(&victim));
             },
             None => (),
         }
@@ -251,18 +323,27 @@ pub fn damage(ch: Rc<CharData>, victim: Rc<CharData>, dam: i16,
                 act("You wish that your wounds would stop BLEEDING that much!", false, &victim, None, None, None, None, VictimType::ToChar);
                 if victim.is_npc() {
                     if victim.specials.borrow().act.contains(SpecialActFlags::ACT_WIMPY) {
-                        do_flee(victim, "", 0, game);
+                        do_flee(Rc::clone(&victim), "", 0, game);
                     }
                 }
             }
         }
     }
+
+    if victim.get_pos() == Position::Dead {
+        if !victim.is_npc() {
+            log(&format!("{} killed by {} at {}", victim.get_name(), ch.get_name(),
+                victim.in_room.borrow().number));
+        }
+        die(&victim, game);
+    }
 }
 
 pub fn hit(ch: Rc<CharData>, victim: Rc<CharData>, attacktype: i32, game: &Game) {
+    let wield = ch.get_eq(EquipmentPosition::Wield);
     let mut wielded = None;
 
-    if ch.in_room != victim.in_room {
+    if !Rc::ptr_eq(&ch.in_room.borrow(), &victim.in_room.borrow()) {
         log("NOT SAME ROOM WHEN FIGHTING!");
         return;
     }
@@ -272,10 +353,10 @@ pub fn hit(ch: Rc<CharData>, victim: Rc<CharData>, attacktype: i32, game: &Game)
     //    held = ch.equipment[EquipmentPosition::Hold].unwrap();
     //}
 
-    let w_type = if ch.equipment[EquipmentPosition::Wield].is_some() &&
-            ch.equipment[EquipmentPosition::Wield].as_ref().unwrap().obj_flags.type_flag == ItemType::Weapon {
-        wielded = ch.equipment[EquipmentPosition::Wield].as_ref();
-        match wielded.unwrap().obj_flags.value[3] {
+    let w_type = if wield.is_some() &&
+            wield.as_ref().unwrap().get_item_type() == ItemType::Weapon {
+        wielded = wield.as_ref();
+        match wielded.unwrap().obj_flags.borrow().value[3] {
             0 | 1 | 2 => TYPE_WHIP,
             3 => TYPE_SLASH,
             4 | 5 | 6 => TYPE_CRUSH,
@@ -303,7 +384,7 @@ pub fn hit(ch: Rc<CharData>, victim: Rc<CharData>, attacktype: i32, game: &Game)
     calc_thaco -= STR_APP[ch.strength_apply_index()].tohit;
     calc_thaco -= ch.get_hitroll() as i16;
 
-    let diceroll = number(1, 20);
+    let diceroll = game.number(1, 20);
 
     let mut victim_ac = victim.get_ac() / 10;
 
@@ -326,14 +407,14 @@ pub fn hit(ch: Rc<CharData>, victim: Rc<CharData>, attacktype: i32, game: &Game)
 
         if !wielded.is_some() {
             if ch.is_npc() {
-                dam += dice(ch.specials.borrow().damnodice as u32,
+                dam += game.dice(ch.specials.borrow().damnodice as u32,
                     ch.specials.borrow().damsizedice as u32) as i16;
             } else {
-                dam += number(0, 2) as i16; // Max. 2 dam with bare hands
+                dam += game.number(0, 2) as i16; // Max. 2 dam with bare hands
             }
         } else {
-            dam += dice(wielded.unwrap().obj_flags.value[1] as u32,
-                wielded.unwrap().obj_flags.value[2] as u32) as i16;
+            let flags = wielded.unwrap().obj_flags.borrow();
+            dam += game.dice(flags.value[1] as u32, flags.value[2] as u32) as i16;
         }
 
         if victim.get_pos() < Position::Fighting {
@@ -355,4 +436,160 @@ pub fn hit(ch: Rc<CharData>, victim: Rc<CharData>, attacktype: i32, game: &Game)
             damage(Rc::clone(&ch), victim, dam, w_type, game);
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::clear_object;
+    use diku::interpreter::nanny;
+    use diku::testutil::*;
+    use diku::utility::MudRng;
+
+    fn expand(template: &str, ch: &str, vict: &str, obj: &str) -> String {
+        template.replace("$n", ch).replace("$N", vict).replace("$p", obj)
+            .replace("$e", "it").replace("$E", "it")
+            .replace("$m", "it").replace("$M", "it")
+            .replace("$s", "its").replace("$S", "its")
+    }
+
+    fn select<'a>(message: &'a MessageType, case: &str) -> &'a MsgType {
+        match case {
+            "die" => &message.die_msg,
+            "miss" => &message.miss_msg,
+            "hit" => &message.hit_msg,
+            _ => &message.god_msg,
+        }
+    }
+
+    // Empty messages are never sent, so only the non-empty ones are compared.
+    // A message that is empty for everyone proves nothing and never matches.
+    fn saw_first(seen: [&[String]; 3], expected: [String; 3]) -> bool {
+        expected.iter().any(|msg| !msg.is_empty()) &&
+            seen.iter().zip(expected.iter())
+                .all(|(lines, msg)| msg.is_empty() || lines.first() == Some(msg))
+    }
+
+    // Replays one attack and returns what attacker, victim and bystander saw
+    fn replay(game: &Game, attacktype: i32, case: &str) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let room = test_room(3001);
+        let ch = test_mob("the ogre", &room);
+        attach_descriptor(&ch);
+        let mut dagger = clear_object();
+        dagger.short_description = String::from("a dagger");
+        ch.equipment.borrow_mut()[EquipmentPosition::Wield] = Some(Rc::new(dagger));
+
+        let victim = test_char("Victim", &room);
+        let bystander = test_char("Bystander", &room);
+
+        let dam = match case {
+            "die" => 100,
+            "miss" => 0,
+            "hit" => 1,
+            _ => {
+                victim.player.borrow_mut().level = 21;
+                10
+            },
+        };
+
+        damage(Rc::clone(&ch), Rc::clone(&victim), dam, attacktype, game);
+
+        (output(&ch), output(&victim), output(&bystander))
+    }
+
+    #[test]
+    fn make_corpse_test() {
+        let mut game = test_game();
+        game.corpse_proto.short_description = String::from("the remains of %s");
+        let room = test_room(3001);
+        let ch = test_mob("the ogre", &room);
+        ch.points.borrow_mut().gold = 30;
+        let mut club = clear_object();
+        club.name = String::from("club");
+        ch.equipment.borrow_mut()[EquipmentPosition::Wield] = Some(Rc::new(club));
+
+        make_corpse(&ch, &game);
+        let corpse = room.contents.borrow()[0].clone();
+        assert!(corpse.is_corpse());
+        assert_eq!(corpse.short_description, "the remains of the ogre");
+        assert_eq!(corpse.description, "Corpse of the ogre is lying here.");
+        assert_eq!(corpse.obj_flags.borrow().timer, MAX_NPC_CORPSE_TIME);
+        let mut contents: Vec<String> = corpse.contains.borrow().iter().map(|obj| obj.name.clone()).collect();
+        contents.sort();
+        assert_eq!(contents, vec!["club", "coins gold"]);
+        assert_eq!(ch.get_gold(), 0);
+        assert!(ch.get_eq(EquipmentPosition::Wield).is_none());
+    }
+
+    #[test]
+    fn fight_messages_test() {
+        let game = test_game();
+        let mut types: Vec<&i32> = game.fight_messages.keys()
+            .filter(|&&t| t < TYPE_HIT || t > TYPE_SLASH).collect();
+        types.sort();
+        assert!(types.contains(&&SKILL_BACKSTAB));
+        assert!(types.contains(&&SPELL_FIREBALL));
+
+        for &attacktype in types {
+            for case in &["die", "miss", "hit", "god"] {
+                let (to_char, to_vict, to_room) = replay(&game, attacktype, case);
+                let matched = game.fight_messages[&attacktype].iter().any(|message| {
+                    let msg = select(message, case);
+                    saw_first([&to_char, &to_vict, &to_room], [
+                        expand(&msg.attacker_msg, "the ogre", "Victim", "a dagger"),
+                        expand(&msg.victim_msg, "the ogre", "Victim", "a dagger"),
+                        expand(&msg.room_msg, "the ogre", "Victim", "a dagger"),
+                    ])
+                });
+                assert!(matched, "attack {} ({}): {:?} / {:?} / {:?}",
+                    attacktype, case, to_char, to_vict, to_room);
+            }
+        }
+    }
+
+    #[test]
+    fn replay_fight_test() {
+        let fight = || {
+            let game = test_game();
+            *game.rng.borrow_mut() = MudRng::from_seed(7);
+            let room = test_room(3001);
+            let ch = test_mob("the ogre", &room);
+            ch.specials.borrow_mut().damnodice = 2;
+            ch.specials.borrow_mut().damsizedice = 4;
+            let victim = test_mob("the rabbit", &room);
+            victim.points.borrow_mut().hit = 1000;
+            for _ in 0..20 {
+                hit(Rc::clone(&ch), Rc::clone(&victim), TYPE_UNDEFINED, &game);
+            }
+            victim.get_hit()
+        };
+        let hit_points = fight();
+        assert!(hit_points < 1000);
+        assert_eq!(hit_points, fight());
+    }
+
+    #[test]
+    fn player_death_test() {
+        let mut game = test_game();
+        let room = test_room(3001);
+        let temple = test_room(START_ROOM);
+        game.world.insert(START_ROOM, Rc::clone(&temple));
+        let ogre = test_mob("the ogre", &room);
+        let victim = test_char("Victim", &room);
+        victim.points.borrow_mut().exp = 1000;
+        game.character_list.borrow_mut().push(Rc::clone(&victim));
+
+        damage(Rc::clone(&ogre), Rc::clone(&victim), 100, TYPE_HIT, &game);
+        let desc = Rc::clone(victim.desc.borrow().as_ref().unwrap());
+        assert!(desc.borrow().connected == ConnectionMode::Slct);
+        assert_eq!(output(&victim).last().map(String::as_str), Some(MENU));
+        assert!(room.people.borrow().iter().all(|k| !Rc::ptr_eq(k, &victim)));
+        assert!(game.character_list.borrow().is_empty());
+        assert_eq!(victim.get_exp(), 500);
+
+        // and from the menu back into the game
+        nanny(&desc, "1", &game);
+        assert!(desc.borrow().connected == ConnectionMode::Plying);
+        assert!(Rc::ptr_eq(&victim.in_room.borrow(), &temple));
+        assert!(victim.get_pos() == Position::Standing && victim.get_hit() > 0);
+    }
+}
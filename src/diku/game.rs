@@ -1,31 +1,47 @@
 use std::cell::RefCell;
-use std::cmp::max;
-use std::collections::HashMap;
-use std::fs::File;
+use std::cmp::{max, min};
+use std::collections::{HashMap, LinkedList};
+use std::fs::{File, OpenOptions};
+use std::io;
 use std::io::{BufReader, SeekFrom};
 use std::io::prelude::*;
 use std::str;
 use std::os::unix::io::RawFd;
 use std::rc::Rc;
-use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT};
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
+use std::thread::sleep;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use bincode::{deserialize_from, Infinite};
+use bincode::{deserialize_from, serialize_into, serialized_size, Infinite};
 use chan_signal;
 use chan_signal::Signal;
+use enum_map::EnumMap;
+use libc::time_t;
+use nix::unistd::close;
 
+use diku::act_comm::spam_update;
 use diku::act_social::{boot_pose_messages, boot_social_messages};
+use diku::comm::{close_sockets, copyover, new_descriptor, nonblock, process_commands, process_inputs, process_outputs,
+    send_to_all, shutdown_countdown};
 use diku::constants;
 use diku::fight::load_messages;
+use diku::handler::{affect_remove, affect_to_char, equip_char, unequip_char};
+use diku::interpreter::assign_command_pointers;
+use diku::limits::{point_update, set_title};
+use diku::magic::affect_update;
+use diku::mobact::mobile_activity;
 use diku::modify::{build_help_index};
+use diku::shop::{assign_the_shopkeepers, boot_the_shops};
 use diku::spec_assign::assign_mobiles;
+use diku::spell_parser::assign_spell_pointers;
 use diku::structs::*;
-use diku::utility::{current_line_number, dice, fread_string, log, mud_time_passed, read_char, read_number};
+use diku::utility::{current_mud_time, fread_string, log, read_char, read_number, MudRng};
+use diku::weather::weather_and_time;
 
 pub static TICS: AtomicUsize = ATOMIC_USIZE_INIT;
 
 impl Game {
-    pub fn new(lawful: bool, no_specials: bool) -> Game {
+    pub fn new(lawful: bool, no_specials: bool, pkill: bool, seed: Option<u32>) -> Game {
         log("Boot db -- BEGIN");
 
         log("Reading newsfile, credits, help-page, info and motd.");
@@ -66,7 +82,8 @@ impl Game {
 	    let player_table = build_player_index();
 
         log("Loading fight messages.");
-	    let fight_messages = load_messages();
+        let mess_f = File::open(constants::MESS_FILE).expect("read messages");
+	    let fight_messages = load_messages(&mut BufReader::new(mess_f));
 
         log("Loading social messages.");
 	    let soc_mess_list = boot_social_messages();
@@ -74,26 +91,42 @@ impl Game {
         log("Loading pose messages.");
 	    let pose_messages = boot_pose_messages();
 
+        log("Loading shops.");
+        let shop_index = boot_the_shops();
+
         log("Assigning function pointers:");
         if !no_specials {
             log("   Mobiles.");
             assign_mobiles(&mut mob_index);
+            log("   Shopkeepers.");
+            assign_the_shopkeepers(&mut mob_index, &shop_index);
         }
 
+        log("Assigning spell and skill levels.");
+        let spell_info = assign_spell_pointers();
+
+        log("Assigning command pointers.");
+        let cmd_info = assign_command_pointers();
+
         let mut game = Game {
-            descriptor_list: Vec::new(),
+            descriptor_list: RefCell::new(Vec::new()),
             lawful,
-            wizlock: false,
+            wizlock: RefCell::new(false),
             slow_death: false,
-            shutdown: false,
-            reboot: false,
+            shutdown: RefCell::new(false),
+            reboot: RefCell::new(false),
+            shutdown_timer: RefCell::new(None),
+            copyover: RefCell::new(false),
             no_specials,
-            weather_info: WeatherData {
+            pkill,
+            rng: RefCell::new(seed.map_or_else(MudRng::new, MudRng::from_seed)),
+            time_info: current_mud_time(),
+            weather_info: RefCell::new(WeatherData {
                 pressure: 0,
                 change: 0,
                 sky: Sky::Cloudless,
                 sunlight: Sunlight::Dark,
-            },
+            }),
             news,
             credits,
             motd,
@@ -106,13 +139,19 @@ impl Game {
             help_index,
             mob_index,
             obj_index,
-            player_table,
+            player_table: RefCell::new(player_table),
             zone_table,
             world,
+            character_list: RefCell::new(Vec::new()),
             combat_list: RefCell::new(Vec::new()),
+            object_list: RefCell::new(Vec::new()),
+            corpse_proto: boot_corpse_proto(),
             fight_messages,
+            spell_info,
+            cmd_info,
             soc_mess_list,
             pose_messages,
+            shop_index,
             shutdown_signal: chan_signal::notify(&[Signal::USR2]),
             hup_signal: chan_signal::notify(&[Signal::HUP, Signal::INT, Signal::TERM]),
             log_signal: chan_signal::notify(&[Signal::ALRM]),
@@ -125,14 +164,111 @@ impl Game {
         game
     }
 
-    pub fn game_loop(&mut self, s: RawFd) {
-        
+    // Returns the exit code telling the script whether to start us again
+    pub fn game_loop(&mut self, s: RawFd) -> i32 {
+        let opt_time = Duration::new(0, constants::OPT_USEC * 1000);
+        let mut pulse: u64 = 0;
+        nonblock(s);
+
+        while !*self.shutdown.borrow() {
+            sleep(opt_time);
+
+            // new links, and what the old ones have to say
+            new_descriptor(s, self);
+            process_inputs(self);
+            process_commands(self);
+
+            self.check_signals();
+
+            pulse += 1;
+            self.heartbeat(pulse);
+
+            process_outputs(self);
+
+            TICS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        save_time(&self.time_info);
+
+        if *self.copyover.borrow() {
+            copyover(s, self);
+
+            // the copyover failed, so have the script start us again
+            *self.reboot.borrow_mut() = true;
+        }
+
+        close_sockets(self);
+        if let Err(e) = close(s) {
+            log(&format!("Closing mother connection: {}", e));
+        }
+
+        if *self.reboot.borrow() {
+            log("Rebooting.");
+            constants::EXIT_REBOOT
+        } else {
+            log("Normal termination of game.");
+            constants::EXIT_SHUTDOWN
+        }
+    }
+
+    // Signals arrive on channels, and are looked at between pulses
+    fn check_signals(&self) {
+        let shutdown_signal = &self.shutdown_signal;
+        let hup_signal = &self.hup_signal;
+        let log_signal = &self.log_signal;
+
+        chan_select! {
+            default => {},
+            shutdown_signal.recv() -> signal => if signal.is_some() {
+                log("Received USR2 - shutdown request");
+                send_to_all(&format!("The game will shut down in {} seconds.\n\r", constants::SHUTDOWN_DELAY), self);
+                *self.reboot.borrow_mut() = false;
+                *self.shutdown_timer.borrow_mut() = Some(constants::SHUTDOWN_DELAY);
+            },
+            hup_signal.recv() -> signal => if signal.is_some() {
+                log("Received SIGHUP, SIGINT, or SIGTERM. Shutting down");
+                send_to_all("Shutting down immediately.\n\r", self);
+                *self.reboot.borrow_mut() = false;
+                *self.shutdown.borrow_mut() = true;
+            },
+            log_signal.recv() -> signal => if signal.is_some() {
+                log("Signal received. Ignoring.");
+            },
+        }
+    }
+
+    fn heartbeat(&mut self, pulse: u64) {
+        if pulse % constants::PASSES_PER_SEC == 0 {
+            shutdown_countdown(self);
+        }
+
+        if pulse % constants::PULSE_VIOLENCE == 0 {
+            spam_update(self);
+        }
+
+        if pulse % constants::PULSE_MOBILE == 0 {
+            mobile_activity(self);
+        }
+
+        if pulse % (constants::SECS_PER_MUD_HOUR * constants::PASSES_PER_SEC) == 0 {
+            weather_and_time(self);
+            save_time(&self.time_info);
+            affect_update(self);
+            point_update(self);
+        }
     }
 
     fn reset_time(&mut self) {
-        let beginning_of_time = UNIX_EPOCH + Duration::from_secs(650336715);
-        let time_info = mud_time_passed(SystemTime::now().duration_since(beginning_of_time).expect("Clock is before beginning of time."));
-        self.weather_info.sunlight = match time_info.hours {
+        self.time_info = load_time().unwrap_or_else(current_mud_time);
+        let time_info = self.time_info;
+        let pressure = 960 + if (7..=12).contains(&time_info.month) {
+            self.dice(1, 50) as i32
+        } else {
+            self.dice(1, 80) as i32
+        };
+
+        let weather_info = self.weather_info.get_mut();
+        weather_info.sunlight = match time_info.hours {
             5 => {
                 Sunlight::Sunrise
             }
@@ -151,20 +287,15 @@ impl Game {
             time_info.hours, time_info.day,
             time_info.month, time_info.year));
         
-        self.weather_info.pressure = 960;
-        self.weather_info.pressure += if time_info.month >= 7 && time_info.month <= 12 {
-            dice(1, 50) as i32
-        } else {
-            dice(1, 80) as i32
-        };
+        weather_info.pressure = pressure;
 
-        self.weather_info.change = 0;
+        weather_info.change = 0;
 
-        self.weather_info.sky = if self.weather_info.pressure <= 980 {
+        weather_info.sky = if weather_info.pressure <= 980 {
             Sky::Lightning
-        } else if self.weather_info.pressure <= 1000 {
+        } else if weather_info.pressure <= 1000 {
             Sky::Raining
-        } else if self.weather_info.pressure <= 1020 {
+        } else if weather_info.pressure <= 1020 {
             Sky::Cloudy
         } else {
             Sky::Cloudless
@@ -172,6 +303,207 @@ impl Game {
     }
 }
 
+// The time file holds the calendar over from the last game, or wherever the
+// gods have set it. Without one, the calendar is worked out from the clock.
+fn load_time() -> Option<TimeInfoData> {
+    let mut buf = String::new();
+    if File::open(constants::TIME_FILE).and_then(|mut fl| fl.read_to_string(&mut buf)).is_err() {
+        return None;
+    }
+
+    let time_info = parse_time(&buf);
+    if time_info.is_none() {
+        log(&format!("SYSERR: Format error in {}, using the clock.", constants::TIME_FILE));
+    }
+    time_info
+}
+
+// hours, day, month and year, counting from 0
+fn parse_time(buf: &str) -> Option<TimeInfoData> {
+    let fields: Vec<&str> = buf.split_whitespace().collect();
+    if fields.len() != 4 {
+        return None;
+    }
+
+    let time_info = TimeInfoData {
+        hours: fields[0].parse().ok()?,
+        day: fields[1].parse().ok()?,
+        month: fields[2].parse().ok()?,
+        year: fields[3].parse().ok()?,
+    };
+    if time_info.hours > 23 || time_info.day > 34 || time_info.month > 16 {
+        return None;
+    }
+    Some(time_info)
+}
+
+// the calendar is only kept if there's a time file to keep it in
+pub fn save_time(time_info: &TimeInfoData) {
+    let result = OpenOptions::new().write(true).truncate(true).open(constants::TIME_FILE)
+        .and_then(|mut fl| writeln!(fl, "{} {} {} {}", time_info.hours, time_info.day, time_info.month,
+            time_info.year));
+
+    match result {
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (),
+        Err(e) => log(&format!("SYSERR: Saving the time: {}", e)),
+        Ok(_) => (),
+    }
+}
+
+pub fn clear_char(in_room: Rc<RoomData>) -> CharData {
+    let now = SystemTime::now();
+
+    CharData {
+        nr: None,
+        in_room: RefCell::new(in_room),
+        player: RefCell::new(CharPlayerData {
+            name: String::new(),
+            short_descr: String::new(),
+            long_descr: String::new(),
+            description: String::new(),
+            title: "",
+            sex: Sex::Neutral,
+            class: Class::Warrior,
+            level: 0,
+            hometown: 0,
+            talks: [false; constants::MAX_TOUNGE],
+            time: TimeData { birth: now, logon: now, played: Duration::from_secs(0) },
+            tmptime: TimeData { birth: now, logon: now, played: Duration::from_secs(0) },
+            weight: 0,
+            height: 0,
+        }),
+        abilities: RefCell::new(CharAbilityData { str: 0, str_add: 0, intel: 0, wis: 0, dex: 0, con: 0 }),
+        tmpabilities: RefCell::new(CharAbilityData { str: 0, str_add: 0, intel: 0, wis: 0, dex: 0, con: 0 }),
+        points: RefCell::new(CharPointData {
+            mana: 0,
+            max_mana: 0,
+            hit: 0,
+            max_hit: 0,
+            mov: 0,
+            max_mov: 0,
+            armor: 100,
+            gold: 0,
+            exp: 0,
+            hitroll: 0,
+            damroll: 0,
+        }),
+        specials: RefCell::new(CharSpecialData {
+            fighting: None,
+            hunting: None,
+            affected_by: AffectedFlags::empty(),
+            position: Position::Standing,
+            default_pos: Position::Standing,
+            act: SpecialActFlags::empty(),
+            spells_to_learn: 0,
+            carry_weight: 0,
+            carry_items: 0,
+            timer: 0,
+            was_in_room: None,
+            apply_saving_throw: EnumMap::default(),
+            conditions: EnumMap::default(),
+            damnodice: 0,
+            damsizedice: 0,
+            last_direction: None,
+            attack_type: 0,
+            alignment: 0,
+        }),
+        skills: RefCell::new((0..constants::MAX_SKILLS).map(|_| CharSkillData { learned: 0, recognise: false }).collect()),
+        affected: RefCell::new(LinkedList::new()),
+        equipment: RefCell::new(EnumMap::default()),
+        carrying: RefCell::new(Vec::new()),
+        desc: RefCell::new(None),
+        followers: RefCell::new(Vec::new()),
+        master: RefCell::new(None),
+        channels: RefCell::new(ChannelData {
+            off: ChannelFlags::empty(),
+            ignore: Vec::new(),
+            reply_to: None,
+            spam: 0,
+            history: EnumMap::default(),
+        }),
+    }
+}
+
+// A new player, to be given class abilities and skills by do_start
+pub fn init_char(ch: &CharData, game: &Game) {
+    // if this is our first player --- he be God
+    if game.player_table.borrow().is_empty() {
+        ch.points.borrow_mut().exp = 7000000;
+        ch.player.borrow_mut().level = 24;
+    }
+    set_title(ch);
+
+    {
+        let mut player = ch.player.borrow_mut();
+        player.hometown = game.number(1, 4) as u16;
+        let now = SystemTime::now();
+        player.time = TimeData { birth: now, logon: now, played: Duration::from_secs(0) };
+        player.talks = [false; constants::MAX_TOUNGE];
+        // make favors for sex
+        let (weight, height) = if player.sex == Sex::Male {
+            (game.number(120, 180), game.number(160, 200))
+        } else {
+            (game.number(100, 160), game.number(150, 180))
+        };
+        player.weight = weight as u8;
+        player.height = height as u8;
+    }
+
+    *ch.abilities.borrow_mut() = CharAbilityData { str: 9, str_add: 0, intel: 9, wis: 9, dex: 9, con: 9 };
+    *ch.tmpabilities.borrow_mut() = *ch.abilities.borrow();
+
+    {
+        let mut points = ch.points.borrow_mut();
+        points.mana = points.max_mana;
+        points.hit = points.max_hit;
+        points.mov = points.max_mov;
+        points.armor = 100;
+    }
+
+    let god = ch.get_level() == 24;
+    for skill in ch.skills.borrow_mut().iter_mut() {
+        skill.learned = if god { 100 } else { 0 };
+        skill.recognise = false;
+    }
+
+    let mut specials = ch.specials.borrow_mut();
+    specials.affected_by = AffectedFlags::empty();
+    specials.spells_to_learn = 0;
+    for (_, save) in specials.apply_saving_throw.iter_mut() {
+        *save = 0;
+    }
+    for (_, cond) in specials.conditions.iter_mut() {
+        *cond = if god { -1 } else { 24 };
+    }
+}
+
+pub fn clear_object() -> ObjData {
+    ObjData {
+        item_number: None,
+        in_room: RefCell::new(None),
+        obj_flags: RefCell::new(ObjFlagData {
+            value: [0; 4],
+            type_flag: ItemType::Other,
+            wear_flags: WearFlags::empty(),
+            extra_flags: ItemExtraFlags::empty(),
+            weight: 0,
+            cost: 0,
+            cost_per_day: 0,
+            timer: 0,
+            bitvector: 0,
+        }),
+        affected: RefCell::new([ObjAffectedType::default(); constants::MAX_OBJ_AFFECT]),
+        name: String::new(),
+        description: String::new(),
+        short_description: String::new(),
+        action_description: String::new(),
+        ex_description: Vec::new(),
+        carried_by: RefCell::new(None),
+        in_obj: RefCell::new(None),
+        contains: RefCell::new(Vec::new()),
+    }
+}
+
 // generate index table for the player file
 fn build_player_index() -> HashMap<String, u64> {
     let file = File::open(constants::PLAYER_FILE).expect("build player index");
@@ -186,7 +518,7 @@ fn build_player_index() -> HashMap<String, u64> {
             Err(_) => break,
         };
         
-        let name = String::from(str::from_utf8(&dummy.name).unwrap().to_lowercase());
+        let name = String::from(str::from_utf8(&dummy.name).unwrap().trim_end_matches('\0').to_lowercase());
         player_table.insert(name, nr);
         nr += 1;
     }
@@ -194,8 +526,255 @@ fn build_player_index() -> HashMap<String, u64> {
     player_table
 }
 
+// copy a string into a fixed size, zero terminated field of the player file
+pub fn copy_to_store(src: &str, dst: &mut [u8]) {
+    let len = dst.len() - 1;
+    for (d, b) in dst.iter_mut().zip(src.bytes().take(len)) {
+        *d = b;
+    }
+}
+
+pub fn secs_since_epoch(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/* copy vital data from a players char-structure to the file structure */
+pub fn char_to_store(ch: &CharData) -> CharFileU {
+    // Unaffect everything a character can be affected by
+    let affected: Vec<AffectedType> = ch.affected.borrow().iter().cloned().collect();
+    for af in &affected {
+        affect_remove(ch, af);
+    }
+
+    let equipment: Vec<(EquipmentPosition, Rc<ObjData>)> = ch.equipment.borrow().iter()
+        .filter_map(|(pos, obj)| obj.as_ref().map(|obj| (pos, Rc::clone(obj)))).collect();
+    for &(pos, _) in &equipment {
+        unequip_char(ch, pos);
+    }
+
+    let now = SystemTime::now();
+    let player = ch.player.borrow();
+    let specials = ch.specials.borrow();
+    let skills = ch.skills.borrow();
+    let played = player.time.played + now.duration_since(player.time.logon).unwrap_or_default();
+
+    let mut st = CharFileU {
+        sex: player.sex as i8,
+        class: player.class as i8,
+        level: player.level as i8,
+        birth: secs_since_epoch(player.time.birth) as time_t,
+        played: played.as_secs() as i32,
+        weight: player.weight,
+        height: player.height,
+        title: [[0; 20]; 4],
+        hometown: player.hometown as i16,
+        description: [[0; 24]; 10],
+        talks: player.talks,
+        load_room: 0,
+        abilities: *ch.abilities.borrow(),
+        points: *ch.points.borrow(),
+        skills: ([CharSkillData { learned: 0, recognise: false }; 32],
+            [CharSkillData { learned: 0, recognise: false }; constants::MAX_SKILLS - 32]),
+        affected: [AffectedType {
+            spell_type: 0,
+            duration: 0,
+            modifier: 0,
+            location: AbilityModifier::None,
+            bitvector: AffectedFlags::empty(),
+        }; constants::MAX_AFFECT],
+        spells_to_learn: specials.spells_to_learn as i8,
+        alignmen: specials.alignment,
+        last_logon: secs_since_epoch(now),
+        act: specials.act.bits(),
+        channels: ch.channels.borrow().off.bits(),
+        ignore: [[0; 20]; constants::MAX_IGNORE],
+        name: [0; 20],
+        pwd: [0; 11],
+        apply_saving_throw: [0; 5],
+        conditions: [0; 3],
+    };
+
+    let mut title = [0; 80];
+    copy_to_store(player.title, &mut title);
+    for (line, chunk) in st.title.iter_mut().zip(title.chunks(20)) {
+        line.copy_from_slice(chunk);
+    }
+    let mut description = [0; 240];
+    copy_to_store(&player.description, &mut description);
+    for (line, chunk) in st.description.iter_mut().zip(description.chunks(24)) {
+        line.copy_from_slice(chunk);
+    }
+    copy_to_store(&player.name, &mut st.name);
+    for (line, name) in st.ignore.iter_mut().zip(ch.channels.borrow().ignore.iter()) {
+        copy_to_store(name, line);
+    }
+
+    for (i, skill) in skills.iter().enumerate().take(constants::MAX_SKILLS) {
+        if i < 32 {
+            st.skills.0[i] = *skill;
+        } else {
+            st.skills.1[i - 32] = *skill;
+        }
+    }
+    for (i, af) in affected.iter().enumerate().take(constants::MAX_AFFECT) {
+        st.affected[i] = *af;
+    }
+    for (i, (_, save)) in specials.apply_saving_throw.iter().enumerate() {
+        st.apply_saving_throw[i] = *save;
+    }
+    for (i, (_, cond)) in specials.conditions.iter().enumerate() {
+        st.conditions[i] = *cond as i32;
+    }
+
+    drop(player);
+    drop(specials);
+
+    // and put everything back on again
+    for (pos, obj) in equipment {
+        equip_char(ch, obj, pos);
+    }
+    for af in affected.iter().rev() {
+        affect_to_char(ch, af);
+    }
+
+    st
+}
+
+/* write the vital data of a player to the player file */
+pub fn save_char(ch: &CharData, load_room: u32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let mut st = char_to_store(ch);
+    st.load_room = load_room;
+
+    // the password is kept on the link, a link-dead player has the one on file
+    let desc = ch.desc.borrow().clone();
+    match desc {
+        Some(ref desc) => copy_to_store(&desc.borrow().pwd, &mut st.pwd),
+        None => match load_char(&ch.get_name(), game) {
+            Some((stored, _)) => st.pwd = stored.pwd,
+            None => {
+                log(&format!("SYSERR: Saving {}: no link and not in the player file", ch.get_name()));
+                return;
+            }
+        },
+    }
+
+    // new players go at the end of the file
+    let pos = {
+        let mut player_table = game.player_table.borrow_mut();
+        let nr = player_table.len() as u64;
+        *player_table.entry(ch.get_name().to_lowercase()).or_insert(nr)
+    };
+    if let Some(ref desc) = desc {
+        desc.borrow_mut().pos = pos as i32;
+    }
+
+    let size = serialized_size(&st);
+    // the player file is there from boot on
+    let result = OpenOptions::new().write(true).open(constants::PLAYER_FILE)
+        .and_then(|mut fl| {
+            fl.seek(SeekFrom::Start(pos * size))?;
+            serialize_into(&mut fl, &st, Infinite)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+        });
+
+    if let Err(e) = result {
+        log(&format!("SYSERR: Saving {}: {}", ch.get_name(), e));
+    }
+}
+
+/* read a player from the player file, along with his position in it */
+pub fn load_char(name: &str, game: &Game) -> Option<(CharFileU, u64)> {
+    let nr = match game.player_table.borrow().get(&name.to_lowercase()) {
+        Some(&nr) => nr,
+        None => return None,
+    };
+
+    let file = match File::open(constants::PLAYER_FILE) {
+        Ok(file) => file,
+        Err(e) => {
+            log(&format!("SYSERR: Loading {}: {}", name, e));
+            return None;
+        }
+    };
+    let mut reader = BufReader::new(file);
+
+    // the entries are all of a size, but it takes one to know it
+    for _ in 0..nr {
+        if deserialize_from::<_, CharFileU, _>(&mut reader, Infinite).is_err() {
+            return None;
+        }
+    }
+    deserialize_from(&mut reader, Infinite).ok().map(|st| (st, nr))
+}
+
+// read a string back out of a zero terminated field of the player file
+pub fn copy_from_store(src: &[u8]) -> String {
+    let len = src.iter().position(|&b| b == 0).unwrap_or(src.len());
+    String::from_utf8_lossy(&src[..len]).into_owned()
+}
+
+fn time_from_secs(secs: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_secs(max(secs, 0) as u64)
+}
+
+/* copy the data of the file structure back into a players char-structure */
+pub fn store_to_char(st: &CharFileU, ch: &CharData) {
+    {
+        let mut player = ch.player.borrow_mut();
+        player.sex = Sex::from(st.sex as u8);
+        player.class = Class::from(st.class as u8);
+        player.level = st.level as u8;
+        player.time.birth = time_from_secs(st.birth as i64);
+        player.time.played = Duration::from_secs(max(st.played, 0) as u64);
+        player.time.logon = SystemTime::now();
+        player.weight = st.weight;
+        player.height = st.height;
+        player.hometown = st.hometown as u16;
+        player.talks = st.talks;
+        player.name = copy_from_store(&st.name);
+        let description: Vec<u8> = st.description.iter().flat_map(|line| line.iter().cloned()).collect();
+        player.description = copy_from_store(&description);
+    }
+    // titles are not kept apart from the level they go with
+    set_title(ch);
+
+    *ch.abilities.borrow_mut() = st.abilities;
+    *ch.tmpabilities.borrow_mut() = st.abilities;
+    *ch.points.borrow_mut() = st.points;
+    *ch.skills.borrow_mut() = st.skills.0.iter().chain(st.skills.1.iter()).cloned().collect();
+
+    {
+        let mut specials = ch.specials.borrow_mut();
+        specials.spells_to_learn = st.spells_to_learn as u8;
+        specials.alignment = st.alignmen;
+        specials.act = SpecialActFlags::from_bits_truncate(st.act);
+        for ((_, save), &stored) in specials.apply_saving_throw.iter_mut().zip(st.apply_saving_throw.iter()) {
+            *save = stored;
+        }
+        for ((_, cond), &stored) in specials.conditions.iter_mut().zip(st.conditions.iter()) {
+            *cond = stored as i8;
+        }
+    }
+
+    {
+        let mut channels = ch.channels.borrow_mut();
+        channels.off = ChannelFlags::from_bits_truncate(st.channels);
+        channels.ignore = st.ignore.iter().map(|line| copy_from_store(line)).filter(|name| !name.is_empty())
+            .collect();
+    }
+
+    // the affects were taken off before saving, so put them back on
+    for af in st.affected.iter().filter(|af| af.spell_type != 0) {
+        affect_to_char(ch, af);
+    }
+}
+
 // generate index table for object or monster file
-fn generate_indices<R: Read + Seek>(reader: &mut BufReader<R>) -> HashMap<u32, IndexData> {
+pub fn generate_indices<R: Read + Seek>(reader: &mut BufReader<R>) -> HashMap<u32, IndexData> {
     let mut index = HashMap::new();
 
     // This is unneeded -sproctor
@@ -223,6 +802,193 @@ fn generate_indices<R: Read + Seek>(reader: &mut BufReader<R>) -> HashMap<u32, I
     index
 }
 
+// read an object from OBJ_FILE, and put it in the object list
+pub fn read_object(nr: u32, game: &Game) -> Option<Rc<ObjData>> {
+    let pos = game.obj_index.get(&nr)?.pos;
+    let mut reader = BufReader::new(&game.obj_f);
+    reader.seek(SeekFrom::Start(pos)).unwrap();
+
+    let mut obj = clear_object();
+    obj.item_number = Some(nr);
+
+    // *** string data ***
+    obj.name = fread_string(&mut reader);
+    obj.short_description = fread_string(&mut reader);
+    obj.description = fread_string(&mut reader);
+    obj.action_description = fread_string(&mut reader);
+
+    // *** numeric data ***
+    {
+        let mut flags = obj.obj_flags.borrow_mut();
+        flags.type_flag = ItemType::from(read_number::<&File, u8>(&mut reader, true).unwrap());
+        flags.extra_flags = ItemExtraFlags::from_bits_truncate(read_number(&mut reader, true).unwrap());
+        flags.wear_flags = WearFlags::from_bits_truncate(read_number(&mut reader, true).unwrap());
+        for value in flags.value.iter_mut() {
+            *value = read_number(&mut reader, true).unwrap();
+        }
+        flags.weight = read_number(&mut reader, true).unwrap();
+        flags.cost = max(read_number::<&File, i32>(&mut reader, true).unwrap(), 0) as u32;
+        flags.cost_per_day = max(read_number::<&File, i32>(&mut reader, true).unwrap(), 0) as u32;
+    }
+
+    // *** extra descriptions and affections ***
+    let mut affected = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        match line.trim() {
+            "E" => {
+                let keyword = fread_string(&mut reader);
+                let description = fread_string(&mut reader);
+                obj.ex_description.push(ExtraDescrData { keyword, description });
+            },
+            "A" if affected < constants::MAX_OBJ_AFFECT => {
+                let mut location = read_number::<&File, u8>(&mut reader, true).unwrap();
+                let modifier = read_number::<&File, i16>(&mut reader, true).unwrap();
+                if location > AbilityModifier::SavingSpell as u8 {
+                    log(&format!("SYSERR: Invalid apply {} on object #{}", location, nr));
+                    location = AbilityModifier::None as u8;
+                }
+                obj.affected.borrow_mut()[affected] = ObjAffectedType { location, modifier: modifier as u16 };
+                affected += 1;
+            },
+            _ => break,
+        }
+    }
+
+    let obj = Rc::new(obj);
+    game.object_list.borrow_mut().push(Rc::clone(&obj));
+    Some(obj)
+}
+
+// The next count whitespace separated words, read across as many lines as it
+// takes. Numbers may carry a sign, eg. "+200".
+fn read_words<R: Read>(reader: &mut BufReader<R>, count: usize) -> Vec<String> {
+    let mut words = Vec::new();
+    while words.len() < count {
+        let mut line = String::new();
+        if reader.read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        words.extend(line.split_whitespace().map(String::from));
+    }
+    words
+}
+
+fn parse_word<F: str::FromStr + Default>(word: Option<&String>) -> F {
+    word.and_then(|word| word.trim_start_matches('+').parse().ok()).unwrap_or_default()
+}
+
+// "XdY+Z"
+fn parse_dice(word: Option<&String>) -> (i32, i32, i32) {
+    let word = word.map(|word| &word[..]).unwrap_or("");
+    let (dice, bonus) = match word.find('+') {
+        Some(plus) => (&word[..plus], word[plus + 1..].parse().unwrap_or(0)),
+        None => (word, 0),
+    };
+    let mut dice = dice.splitn(2, 'd').map(|n| n.parse().unwrap_or(0));
+    (dice.next().unwrap_or(0), dice.next().unwrap_or(0), bonus)
+}
+
+// a position out of the file, a mob with a broken one just stands there
+fn parse_position(word: Option<&String>, nr: u32) -> Position {
+    match word.and_then(|word| word.parse::<u8>().ok()) {
+        Some(n) if n <= Position::Standing as u8 => Position::from(n),
+        _ => {
+            log(&format!("SYSERR: Invalid position {} on mob #{}", word.map_or("", |word| &word[..]), nr));
+            Position::Standing
+        }
+    }
+}
+
+// read a mobile from MOB_FILE, and put it in room and the character list
+pub fn read_mobile(nr: u32, room: &Rc<RoomData>, game: &Game) -> Option<Rc<CharData>> {
+    let pos = game.mob_index.get(&nr)?.pos;
+    let mut reader = BufReader::new(&game.mob_f);
+    reader.seek(SeekFrom::Start(pos)).unwrap();
+
+    let mut mob = clear_char(Rc::clone(room));
+    mob.nr = Some(nr);
+
+    // *** String data ***
+    {
+        let mut player = mob.player.borrow_mut();
+        player.name = fread_string(&mut reader);
+        player.short_descr = fread_string(&mut reader);
+        player.long_descr = fread_string(&mut reader);
+        player.description = fread_string(&mut reader);
+    }
+
+    // *** Numeric data ***
+    let words = read_words(&mut reader, 4);
+    let flags = SpecialActFlags::from_bits_truncate(parse_word(words.get(0)));
+    if words.get(3).map(|word| &word[..]) != Some("S") {
+        log(&format!("SYSERR: Mobile #{} is not in the simple format.", nr));
+        return None;
+    }
+
+    {
+        let mut specials = mob.specials.borrow_mut();
+        specials.act = flags | SpecialActFlags::ACT_ISNPC;
+        specials.affected_by = AffectedFlags::from_bits_truncate(parse_word(words.get(1)));
+        specials.alignment = parse_word(words.get(2));
+    }
+
+    // The new easy monsters
+    *mob.abilities.borrow_mut() = CharAbilityData { str: 11, str_add: 0, intel: 11, wis: 11, dex: 11, con: 11 };
+    *mob.tmpabilities.borrow_mut() = *mob.abilities.borrow();
+
+    let words = read_words(&mut reader, 5);
+    let level: u8 = parse_word(words.get(0));
+    let (hit_dice, hit_size, hit_bonus) = parse_dice(words.get(3));
+    let (dam_dice, dam_size, dam_bonus) = parse_dice(words.get(4));
+    {
+        let mut points = mob.points.borrow_mut();
+        points.hitroll = (20 - parse_word::<i32>(words.get(1))) as i8;
+        points.armor = 10 * parse_word::<i16>(words.get(2));
+        points.max_hit = min(game.dice(hit_dice as u32, hit_size as u32) as i32 + hit_bonus, i16::max_value() as i32)
+            as i16;
+        points.hit = points.max_hit;
+        points.damroll = dam_bonus as i8;
+        points.mana = 10;
+        points.max_mana = 10;
+        points.mov = 50;
+        points.max_mov = 50;
+    }
+
+    let words = read_words(&mut reader, 5);
+    {
+        let mut points = mob.points.borrow_mut();
+        points.gold = parse_word(words.get(0));
+        points.exp = parse_word(words.get(1));
+    }
+    {
+        let mut specials = mob.specials.borrow_mut();
+        specials.damnodice = dam_dice as i8;
+        specials.damsizedice = dam_size as i8;
+        specials.position = parse_position(words.get(2), nr);
+        specials.default_pos = parse_position(words.get(3), nr);
+        for (_, cond) in specials.conditions.iter_mut() {
+            *cond = -1;
+        }
+        for (_, save) in specials.apply_saving_throw.iter_mut() {
+            *save = max(20 - level as i16, 2);
+        }
+    }
+    {
+        let mut player = mob.player.borrow_mut();
+        player.sex = Sex::from(parse_word::<u8>(words.get(4)));
+        player.level = level;
+        player.weight = 200;
+        player.height = 198;
+    }
+
+    let mob = Rc::new(mob);
+    room.people.borrow_mut().push(Rc::clone(&mob));
+    game.character_list.borrow_mut().push(Rc::clone(&mob));
+    Some(mob)
+}
+
 fn file_to_string(name: &str) -> String {
     let mut file = File::open(name).expect("open file-to-string");
     let mut contents = String::new();
@@ -230,16 +996,83 @@ fn file_to_string(name: &str) -> String {
     contents
 }
 
+// The climate file gives zones weather of their own, one zone number and
+// climate a line. Every other zone is temperate.
+fn boot_climates() -> HashMap<u32, Climate> {
+    let mut climates = HashMap::new();
+
+    let mut buf = String::new();
+    if File::open(constants::CLIMATE_FILE).and_then(|mut fl| fl.read_to_string(&mut buf)).is_err() {
+        return climates;
+    }
+
+    for line in buf.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('*')) {
+        match parse_climate(line) {
+            Some((zone, climate)) => {
+                climates.insert(zone, climate);
+            }
+            None => log(&format!("SYSERR: Format error in {}: {}", constants::CLIMATE_FILE, line)),
+        }
+    }
+    climates
+}
+
+fn parse_climate(line: &str) -> Option<(u32, Climate)> {
+    let mut words = line.split_whitespace();
+    let zone = words.next()?.parse().ok()?;
+    let climate = match words.next()?.to_lowercase().as_str() {
+        "temperate" => Climate::Temperate,
+        "arid" => Climate::Arid,
+        "tropical" => Climate::Tropical,
+        "arctic" => Climate::Arctic,
+        _ => return None,
+    };
+    Some((zone, climate))
+}
+
+// The corpse file overrides the corpse make_corpse leaves behind, one field
+// and value a line. Fields that are left out keep their default.
+fn boot_corpse_proto() -> CorpseProto {
+    let mut buf = String::new();
+    if File::open(constants::CORPSE_FILE).and_then(|mut fl| fl.read_to_string(&mut buf)).is_err() {
+        return CorpseProto::default();
+    }
+    parse_corpse(&buf)
+}
+
+fn parse_corpse(buf: &str) -> CorpseProto {
+    let mut proto = CorpseProto::default();
+    for line in buf.lines().map(|line| line.trim()).filter(|line| !line.is_empty() && !line.starts_with('*')) {
+        let (field, value) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        let ok = match field.to_lowercase().as_str() {
+            "name" if !value.is_empty() => { proto.name = String::from(value); true }
+            "short" if !value.is_empty() => { proto.short_description = String::from(value); true }
+            "long" if !value.is_empty() => { proto.description = String::from(value); true }
+            "npc_timer" => value.parse().map(|t| proto.npc_timer = t).is_ok(),
+            "pc_timer" => value.parse().map(|t| proto.pc_timer = t).is_ok(),
+            _ => false,
+        };
+        if !ok {
+            log(&format!("SYSERR: Format error in {}: {}", constants::CORPSE_FILE, line));
+        }
+    }
+    proto
+}
+
 fn boot_zones() -> Vec<ZoneData> {
     let mut zone_table = Vec::new();
+    let climates = boot_climates();
 
     let file = File::open(constants::ZONE_FILE).expect("boot_zones");
     let mut reader = BufReader::new(file);
 
     loop {
-        // ignore #nn line
+        // the #nn line only matters for the climate
         assert_eq!(read_char(&mut reader), b'#', "Expected '#' in zone file");
-        read_number::<File, u32>(&mut reader, true).unwrap();
+        let number = read_number::<File, u32>(&mut reader, true).unwrap();
 
         let check = fread_string(&mut reader);
         if check.bytes().nth(0).unwrap() == b'$' {
@@ -290,12 +1123,13 @@ fn boot_zones() -> Vec<ZoneData> {
             top,
             reset_mode,
             cmd,
+            climate: climates.get(&number).cloned().unwrap_or(Climate::Temperate),
         })
     }
     zone_table
 }
 
-fn boot_world(zone_table: &Vec<ZoneData>) -> HashMap<u32, RoomData> {
+fn boot_world(zone_table: &Vec<ZoneData>) -> RoomTable {
     let file = File::open(constants::WORLD_FILE).expect("boot_world: could not open world file.");
     let mut reader = BufReader::new(file);
 
@@ -347,7 +1181,7 @@ fn boot_world(zone_table: &Vec<ZoneData>) -> HashMap<u32, RoomData> {
                 _ => panic!("Invalid value in room extra fields: {}", chk),
             }
         }
-        world.insert(virtual_nr, RoomData {
+        world.insert(virtual_nr, Rc::new(RoomData {
             number: virtual_nr,
             zone: zone as u16,
             sector_type,
@@ -360,7 +1194,7 @@ fn boot_world(zone_table: &Vec<ZoneData>) -> HashMap<u32, RoomData> {
             funct: None,
             contents: RefCell::new(Vec::new()),
             people: RefCell::new(Vec::new()),
-        });
+        }));
     }
     world
 }
@@ -384,7 +1218,7 @@ fn setup_dir<R: Read + Seek>(reader: &mut BufReader<R>) -> RoomDirectionData {
     RoomDirectionData {
         general_description,
         keyword,
-        exit_info,
+        exit_info: RefCell::new(exit_info),
         key,
         to_room,
      }
@@ -394,6 +1228,7 @@ fn setup_dir<R: Read + Seek>(reader: &mut BufReader<R>) -> RoomDirectionData {
 mod tests {
     use super::*;
     use std::io::Cursor;
+    use diku::testutil::*;
 
     #[test]
     fn fread_string_test() {
@@ -401,4 +1236,136 @@ mod tests {
         assert_eq!("test", fread_string(&mut BufReader::new(Cursor::new(String::from("test~foo\n")))));
         assert_eq!("test", fread_string(&mut BufReader::new(Cursor::new(String::from("test~")))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn read_mobile_test() {
+        let mut game = test_game();
+        game.mob_index = generate_indices(&mut BufReader::new(&game.mob_f));
+        let room = test_room(3001);
+
+        let baker = read_mobile(3001, &room, &game).unwrap();
+        assert_eq!(baker.get_name(), "baker");
+        assert_eq!(baker.player.borrow().short_descr, "the Baker");
+        assert!(baker.is_mob());
+        assert_eq!(baker.get_level(), 23);
+        assert_eq!(baker.get_hitroll(), 18);
+        assert_eq!(baker.get_ac(), 20);
+        assert_eq!(baker.get_max_hit(), 30001);
+        assert_eq!(baker.get_gold(), 2000);
+        assert_eq!(baker.get_exp(), 80000);
+        assert!(baker.get_sex() == Sex::Male);
+        assert!(baker.get_pos() == Position::Standing);
+        assert_eq!((baker.specials.borrow().damnodice, baker.specials.borrow().damsizedice), (1, 8));
+        assert_eq!(baker.get_damroll(), 12);
+        assert!(Rc::ptr_eq(&room.people.borrow()[0], &baker));
+        assert_eq!(game.character_list.borrow().len(), 1);
+
+        // alignments may be written with a sign
+        let mob = read_mobile(7046, &room, &game).unwrap();
+        assert_eq!(mob.get_alignment(), 200);
+
+        assert!(read_mobile(1234567, &room, &game).is_none());
+    }
+
+    #[test]
+    fn parse_position_test() {
+        assert!(parse_position(Some(&String::from("4")), 1) == Position::Sleeping);
+        assert!(parse_position(Some(&String::from("9")), 1) == Position::Standing);
+        assert!(parse_position(Some(&String::from("-1")), 1) == Position::Standing);
+        assert!(parse_position(None, 1) == Position::Standing);
+    }
+
+    #[test]
+    fn store_to_char_test() {
+        let room = test_room(3001);
+        let ch = test_char("Stored", &room);
+        {
+            let mut player = ch.player.borrow_mut();
+            player.sex = Sex::Female;
+            player.class = Class::Thief;
+            player.level = 5;
+            player.description = String::from("A rather quiet halfling.");
+        }
+        ch.points.borrow_mut().gold = 1234;
+        ch.skills.borrow_mut()[40].learned = 75;
+        ch.specials.borrow_mut().act = SpecialActFlags::PLR_LOG;
+        ch.specials.borrow_mut().conditions[Condition::Drunk] = 3;
+        ch.channels.borrow_mut().ignore.push(String::from("Pest"));
+        affect_to_char(&ch, &AffectedType {
+            spell_type: 1,
+            duration: 10,
+            modifier: 2,
+            location: AbilityModifier::Str,
+            bitvector: AffectedFlags::empty(),
+        });
+
+        let st = char_to_store(&ch);
+        let copy = clear_char(Rc::clone(&room));
+        store_to_char(&st, &copy);
+        assert_eq!(copy.get_name(), "Stored");
+        assert!(copy.get_sex() == Sex::Female);
+        assert!(copy.get_class() == Class::Thief);
+        assert_eq!(copy.get_level(), 5);
+        assert_eq!(copy.player.borrow().title, "the Sneak");
+        assert_eq!(copy.player.borrow().description, "A rather quiet halfling.");
+        assert_eq!(copy.get_gold(), 1234);
+        assert_eq!(copy.skills.borrow()[40].learned, 75);
+        assert_eq!(copy.specials.borrow().act, SpecialActFlags::PLR_LOG);
+        assert_eq!(copy.specials.borrow().conditions[Condition::Drunk], 3);
+        assert_eq!(copy.channels.borrow().ignore, vec!["Pest"]);
+        assert_eq!(copy.affected.borrow().len(), 1);
+        assert_eq!(copy.tmpabilities.borrow().str, 15);
+        assert_eq!(*copy.abilities.borrow(), *ch.abilities.borrow());
+    }
+
+    #[test]
+    fn channels_round_trip_test() {
+        let room = test_room(3001);
+        let ch = test_char("Quiet", &room);
+        ch.channels.borrow_mut().off = ChannelFlags::CHAN_GOSSIP;
+        ch.channels.borrow_mut().ignore = vec![String::from("Loud"), String::from("Louder")];
+
+        // through the player file format and back
+        let mut buf = Vec::new();
+        serialize_into(&mut buf, &char_to_store(&ch), Infinite).unwrap();
+        let st: CharFileU = deserialize_from(&mut &buf[..], Infinite).unwrap();
+        let copy = clear_char(Rc::clone(&room));
+        store_to_char(&st, &copy);
+        assert_eq!(copy.channels.borrow().off, ChannelFlags::CHAN_GOSSIP);
+        assert_eq!(copy.channels.borrow().ignore, vec!["Loud", "Louder"]);
+    }
+
+    #[test]
+    fn parse_time_test() {
+        assert_eq!(parse_time("13 4 16 1203\n"), Some(TimeInfoData { hours: 13, day: 4, month: 16, year: 1203 }));
+        assert_eq!(parse_time("24 4 16 1203"), None);
+        assert_eq!(parse_time("13 35 16 1203"), None);
+        assert_eq!(parse_time("13 4 17 1203"), None);
+        assert_eq!(parse_time("13 4 16"), None);
+        assert_eq!(parse_time("noon"), None);
+    }
+
+    #[test]
+    fn parse_climate_test() {
+        assert_eq!(parse_climate("30 temperate"), Some((30, Climate::Temperate)));
+        assert_eq!(parse_climate("50  Arid"), Some((50, Climate::Arid)));
+        assert_eq!(parse_climate("78 arctic  Redferne's larder"), Some((78, Climate::Arctic)));
+        assert_eq!(parse_climate("61 swampy"), None);
+        assert_eq!(parse_climate("tropical"), None);
+    }
+
+    #[test]
+    fn parse_corpse_test() {
+        let proto = parse_corpse("* bones instead of corpses\n\
+            name bones remains\n\
+            short the bones of %s\n\
+            long The bones of %s are scattered here.\n\
+            pc_timer 20\n\
+            npc_timer soon\n");
+        assert_eq!(proto.name, "bones remains");
+        assert_eq!(proto.short_description, "the bones of %s");
+        assert_eq!(proto.description, "The bones of %s are scattered here.");
+        assert_eq!(proto.pc_timer, 20);
+        assert_eq!(proto.npc_timer, constants::MAX_NPC_CORPSE_TIME);
+    }
+}
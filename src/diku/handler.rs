@@ -1,6 +1,14 @@
 
-use diku::constants::*;
+use std::cmp::{max, min};
+use std::ptr;
+use std::rc::Rc;
 use std::time::Duration;
+
+use diku::act_wizard::do_return;
+use diku::comm::{stop_snooping, write_to_output};
+use diku::constants::*;
+use diku::fight::stop_fighting;
+use diku::game::clear_object;
 use diku::structs::*;
 
 pub fn fname<'a>(namelist: &'a str) -> &'a str {
@@ -16,35 +24,21 @@ pub fn fname<'a>(namelist: &'a str) -> &'a str {
     namelist.get(0..point).unwrap()
 }
 
-fn isname(st: &str, namelist: &str) -> bool {
-    let mut pos = 0;
-    loop {
-        for c in st.chars() {
-            if pos >= namelist.len() {
-                return false;
-            }
-
-            let curname = namelist.chars().nth(pos).unwrap();
-            if curname == ' ' {
-                break;
-            }
-
-            if c.to_lowercase().to_string() != curname.to_lowercase().to_string() {
-                break;
-            }
+// true if st is one of the words of namelist, ignoring case
+pub fn isname(st: &str, namelist: &str) -> bool {
+    !st.is_empty() &&
+    namelist.split(|c: char| !c.is_alphabetic()).any(|name| name.eq_ignore_ascii_case(st))
+}
 
-            pos += 1;
-        }
-        if !namelist.chars().nth(pos).unwrap().is_alphabetic() {
-            return true;
-        }
-        while namelist.chars().nth(pos).unwrap().is_alphabetic() {
-            pos += 1;
-            if pos >= namelist.len() {
-                return false;
-            }
-        }
-        pos += 1;
+// "2.sword" gives (2, "sword"), "sword" gives (1, "sword"). None if the
+// number is not valid
+fn get_number(name: &str) -> Option<(usize, &str)> {
+    match name.find('.') {
+        Some(ppos) => match name[..ppos].parse::<usize>() {
+            Ok(number) if number > 0 => Some((number, &name[ppos + 1..])),
+            _ => None,
+        },
+        None => Some((1, name)),
     }
 }
 
@@ -91,44 +85,542 @@ fn affect_modify(ch: &CharData, loc: AbilityModifier, modifier: i8, bitv: Affect
     }
 }
 
+// Abilities may pass through odd values while affect_total takes everything
+// off and puts it back on, so only keep them inside what the field can hold.
 pub fn amod(dst: &mut u8, modify: i8) {
-    let result = *dst as i16 + modify as i16;
-    assert!(result >= 0 && result <= 25);
-    *dst = result as u8;
+    *dst = max(0, min(255, *dst as i16 + modify as i16)) as u8;
 }
 
-pub fn affect_from_char(ch: &CharData, skill: i32) {
-    for hjp in &ch.affected {
-        if hjp.spell_type == skill {
-            affect_remove(ch, &hjp);
+fn equipment_modify(ch: &CharData, add: bool) {
+    let equipment: Vec<Rc<ObjData>> = ch.equipment.borrow().iter()
+        .filter_map(|(_, obj)| obj.clone()).collect();
+
+    for obj in equipment {
+        obj_modify(ch, &obj, add);
+    }
+}
+
+/* This updates a character by subtracting everything he is affected by
+   restoring original abilities, and then affecting all again           */
+pub fn affect_total(ch: &CharData) {
+    let affected: Vec<AffectedType> = ch.affected.borrow().iter().cloned().collect();
+
+    equipment_modify(ch, false);
+    for af in &affected {
+        affect_modify(ch, af.location, af.modifier, af.bitvector, false);
+    }
+
+    *ch.tmpabilities.borrow_mut() = *ch.abilities.borrow();
+
+    equipment_modify(ch, true);
+    for af in &affected {
+        affect_modify(ch, af.location, af.modifier, af.bitvector, true);
+    }
+
+    // Make certain values are between 0..25, not < 0 and not > 25!
+    let limit = if ch.is_npc() { 25 } else { 18 };
+    let mut abilities = ch.tmpabilities.borrow_mut();
+    abilities.dex = min(abilities.dex, limit);
+    abilities.intel = min(abilities.intel, limit);
+    abilities.wis = min(abilities.wis, limit);
+    abilities.con = min(abilities.con, limit);
+    if ch.is_npc() {
+        abilities.str = min(abilities.str, limit);
+    } else if abilities.str > 18 {
+        let add = abilities.str_add as u16 + (abilities.str as u16 - 18) * 10;
+        abilities.str_add = min(add, 100) as u8;
+        abilities.str = 18;
+    }
+}
+
+/* Insert an affect_type in a char_data structure
+   Automatically sets apropriate bits and apply's */
+pub fn affect_to_char(ch: &CharData, af: &AffectedType) {
+    ch.affected.borrow_mut().push_front(af.clone());
+
+    affect_modify(ch, af.location, af.modifier, af.bitvector, true);
+    affect_total(ch);
+}
+
+/* Remove an affected_type structure from a char (called when duration
+   reaches zero). Frees mem and calls affect_location_apply            */
+pub fn affect_remove(ch: &CharData, af: &AffectedType) {
+    affect_modify(ch, af.location, af.modifier, af.bitvector, false);
+
+    {
+        let mut affected = ch.affected.borrow_mut();
+        if let Some(pos) = affected.iter().position(|hjp| hjp == af) {
+            let mut tail = affected.split_off(pos);
+            tail.pop_front();
+            affected.append(&mut tail);
         }
     }
+
+    affect_total(ch);
+}
+
+/* Call affect_remove with every spell of spelltype "skill" */
+pub fn affect_from_char(ch: &CharData, skill: i32) {
+    let matching: Vec<AffectedType> = ch.affected.borrow().iter()
+        .filter(|hjp| hjp.spell_type == skill).cloned().collect();
+
+    for hjp in &matching {
+        affect_remove(ch, hjp);
+    }
 }
 
 /* Return if a char is affected by a spell (SPELL_XXX), NULL indicates 
    not affected                                                        */
 pub fn affected_by_spell(ch: &CharData, skill: i32) -> bool {
-	for hjp in &ch.affected {
-        if hjp.spell_type == skill {
-            return true;
+    ch.affected.borrow().iter().any(|hjp| hjp.spell_type == skill)
+}
+
+/* Add af to ch, stacking it onto an affect of the same spell if there is
+   one. The durations and modifiers are summed, or averaged on request */
+pub fn affect_join(ch: &CharData, af: &AffectedType, avg_dur: bool, avg_mod: bool) {
+    let mut af = af.clone();
+    let old = ch.affected.borrow().iter().find(|hjp| hjp.spell_type == af.spell_type).cloned();
+
+    if let Some(hjp) = old {
+        af.duration += hjp.duration;
+        if avg_dur {
+            af.duration /= 2;
+        }
+
+        af.modifier = af.modifier.saturating_add(hjp.modifier);
+        if avg_mod {
+            af.modifier /= 2;
         }
+
+        affect_remove(ch, &hjp);
     }
-    return false;
-}
 
-fn affect_remove(ch: &CharData, af: &AffectedType) {
-    affect_modify(ch, af.location, af.modifier, af.bitvector, false);
+    affect_to_char(ch, &af);
 }
 
 pub fn char_from_room(ch: &CharData) {
-    let light = ch.equipment[EquipmentPosition::Light].as_ref();
+    let equipment = ch.equipment.borrow();
+    let light = equipment[EquipmentPosition::Light].as_ref();
     if light.is_some() {
-        if light.unwrap().obj_flags.type_flag == ItemType::Light {
-            if light.unwrap().obj_flags.value[2] != 0 {
-                *ch.in_room.light.borrow_mut() -= 1;
+        if light.unwrap().get_item_type() == ItemType::Light {
+            if light.unwrap().obj_flags.borrow().value[2] != 0 {
+                *ch.in_room.borrow().light.borrow_mut() -= 1;
             }
         }
     }
 
-    //ch.in_room.people.remove(ch);
+    ch.in_room.borrow().people.borrow_mut().retain(|k| !ptr::eq(k.as_ref(), ch));
+}
+
+// place a character in a room
+pub fn char_to_room(ch: &Rc<CharData>, room: &Rc<RoomData>) {
+    room.people.borrow_mut().push(Rc::clone(ch));
+    *ch.in_room.borrow_mut() = Rc::clone(room);
+
+    if let Some(ref light) = ch.equipment.borrow()[EquipmentPosition::Light] {
+        if light.get_item_type() == ItemType::Light && light.obj_flags.borrow().value[2] != 0 {
+            *room.light.borrow_mut() += 1;
+        }
+    }
+}
+
+// Extract a ch completely from the world, and leave his stuff behind
+pub fn extract_char(ch: &Rc<CharData>, game: &Game) {
+    let desc = ch.desc.borrow().clone();
+    if let Some(desc) = desc {
+        stop_snooping(&desc);
+        // a switched player goes back to his own body
+        if desc.borrow().original.is_some() {
+            do_return(Rc::clone(ch), "", 0, game);
+        }
+    }
+
+    let carrying = ch.carrying.borrow().clone();
+    for obj in carrying {
+        obj_from_char(&obj);
+        obj_to_room(obj, &ch.in_room.borrow());
+    }
+
+    let positions: Vec<EquipmentPosition> = ch.equipment.borrow().iter()
+        .filter(|&(_, e)| e.is_some()).map(|(pos, _)| pos).collect();
+    for pos in positions {
+        if let Some(obj) = unequip_char(ch, pos) {
+            obj_to_room(obj, &ch.in_room.borrow());
+        }
+    }
+
+    let fighters: Vec<Rc<CharData>> = game.combat_list.borrow().iter()
+        .filter(|k| k.specials.borrow().fighting.as_ref().map_or(false, |f| Rc::ptr_eq(f, ch)))
+        .cloned().collect();
+    for k in &fighters {
+        stop_fighting(k, game);
+    }
+
+    if ch.specials.borrow().fighting.is_some() {
+        stop_fighting(ch, game);
+    }
+
+    char_from_room(ch);
+    game.character_list.borrow_mut().retain(|k| !Rc::ptr_eq(k, ch));
+
+    // a player still linked goes back to the menu, and comes in again at the temple
+    let desc = ch.desc.borrow().clone();
+    if let Some(desc) = desc {
+        if let Some(room) = game.world.get(&START_ROOM) {
+            *ch.in_room.borrow_mut() = Rc::clone(room);
+        }
+        desc.borrow_mut().connected = ConnectionMode::Slct;
+        write_to_output(String::from(MENU), &desc);
+    }
+}
+
+fn obj_modify(ch: &CharData, obj: &ObjData, add: bool) {
+    let bitv = AffectedFlags::from_bits_truncate(obj.obj_flags.borrow().bitvector as u32);
+    let affected = *obj.affected.borrow();
+    for af in affected.iter() {
+        affect_modify(ch, AbilityModifier::from(af.location), af.modifier as i8, bitv, add);
+    }
+}
+
+pub fn equip_char(ch: &CharData, obj: Rc<ObjData>, pos: EquipmentPosition) {
+    assert!(ch.equipment.borrow()[pos].is_none());
+
+    obj_modify(ch, &obj, true);
+    ch.equipment.borrow_mut()[pos] = Some(obj);
+    affect_total(ch);
+}
+
+pub fn unequip_char(ch: &CharData, pos: EquipmentPosition) -> Option<Rc<ObjData>> {
+    let obj = ch.equipment.borrow_mut()[pos].take();
+    if let Some(ref obj) = obj {
+        obj_modify(ch, obj, false);
+        affect_total(ch);
+    }
+    obj
+}
+
+// give an object to a char
+pub fn obj_to_char(object: Rc<ObjData>, ch: &Rc<CharData>) {
+    *object.carried_by.borrow_mut() = Some(Rc::clone(ch));
+    *object.in_room.borrow_mut() = None;
+    {
+        let mut specials = ch.specials.borrow_mut();
+        specials.carry_weight += object.get_obj_weight();
+        specials.carry_items += 1;
+    }
+    ch.carrying.borrow_mut().push(object);
+}
+
+// take an object from a char
+pub fn obj_from_char(object: &Rc<ObjData>) {
+    let carrier = object.carried_by.borrow_mut().take();
+    if let Some(ch) = carrier {
+        ch.carrying.borrow_mut().retain(|o| !Rc::ptr_eq(o, object));
+        let mut specials = ch.specials.borrow_mut();
+        specials.carry_weight -= object.get_obj_weight();
+        specials.carry_items -= 1;
+    }
+}
+
+// put an object in a room
+pub fn obj_to_room(object: Rc<ObjData>, room: &Rc<RoomData>) {
+    *object.in_room.borrow_mut() = Some(Rc::clone(room));
+    *object.carried_by.borrow_mut() = None;
+    room.contents.borrow_mut().push(object);
+}
+
+// Take an object from a room
+pub fn obj_from_room(object: &Rc<ObjData>) {
+    let room = object.in_room.borrow_mut().take();
+    if let Some(room) = room {
+        room.contents.borrow_mut().retain(|o| !Rc::ptr_eq(o, object));
+    }
+}
+
+// put an object in an object (quaint)
+pub fn obj_to_obj(object: Rc<ObjData>, obj_to: &Rc<ObjData>) {
+    let weight = object.get_obj_weight();
+    *object.in_obj.borrow_mut() = Some(Rc::clone(obj_to));
+    obj_to.contains.borrow_mut().push(object);
+
+    let mut tmp_obj = Some(Rc::clone(obj_to));
+    while let Some(obj) = tmp_obj {
+        obj.obj_flags.borrow_mut().weight += weight;
+        tmp_obj = obj.in_obj.borrow().clone();
+    }
+}
+
+// remove an object from an object
+pub fn obj_from_obj(object: &Rc<ObjData>) {
+    let obj_from = object.in_obj.borrow_mut().take();
+    if let Some(obj_from) = obj_from {
+        obj_from.contains.borrow_mut().retain(|o| !Rc::ptr_eq(o, object));
+
+        let weight = object.get_obj_weight();
+        let mut tmp_obj = Some(obj_from);
+        while let Some(obj) = tmp_obj {
+            obj.obj_flags.borrow_mut().weight -= weight;
+            tmp_obj = obj.in_obj.borrow().clone();
+        }
+    }
+}
+
+// Change the weight of an object, and of whatever carries or contains it
+pub fn weight_change_object(obj: &ObjData, weight: i32) {
+    obj.obj_flags.borrow_mut().weight += weight;
+    if let Some(ref ch) = *obj.carried_by.borrow() {
+        ch.specials.borrow_mut().carry_weight += weight;
+    } else if let Some(ref in_obj) = *obj.in_obj.borrow() {
+        weight_change_object(in_obj, weight);
+    }
+}
+
+// Extract an object from the world
+pub fn extract_obj(object: &Rc<ObjData>, game: &Game) {
+    if object.in_room.borrow().is_some() {
+        obj_from_room(object);
+    } else if object.carried_by.borrow().is_some() {
+        obj_from_char(object);
+    } else if object.in_obj.borrow().is_some() {
+        obj_from_obj(object);
+    }
+
+    let contents = object.contains.borrow().clone();
+    for obj in &contents {
+        extract_obj(obj, game);
+    }
+
+    game.object_list.borrow_mut().retain(|o| !Rc::ptr_eq(o, object));
+}
+
+pub fn create_money(amount: i32, game: &Game) -> Rc<ObjData> {
+    assert!(amount > 0, "Try to create negative money.");
+
+    let mut obj = clear_object();
+    if amount == 1 {
+        obj.name = String::from("coin gold");
+        obj.short_description = String::from("a gold coin");
+        obj.description = String::from("One miserable gold coin is lying here.");
+        obj.ex_description.push(ExtraDescrData {
+            keyword: String::from("coin gold"),
+            description: String::from("It's just one miserable little gold coin."),
+        });
+    } else {
+        obj.name = String::from("coins gold");
+        obj.short_description = String::from("gold coins");
+        obj.description = String::from("A pile of gold coins is lying here.");
+        let description = if amount < 10 {
+            format!("There is {} coins.", amount)
+        } else if amount < 100 {
+            format!("There is about {} coins.", 10 * (amount / 10))
+        } else if amount < 1000 {
+            format!("It looks to be about {} coins.", 100 * (amount / 100))
+        } else if amount < 100000 {
+            format!("You guess there is, maybe, {} coins.",
+                1000 * ((amount / 1000) + game.number(0, amount / 1000)))
+        } else {
+            String::from("There is A LOT of coins.")
+        };
+        obj.ex_description.push(ExtraDescrData {
+            keyword: String::from("coins gold"),
+            description,
+        });
+    }
+
+    {
+        let mut flags = obj.obj_flags.borrow_mut();
+        flags.type_flag = ItemType::Money;
+        flags.wear_flags = WearFlags::ITEM_TAKE;
+        flags.value[0] = amount;
+        flags.cost = amount as u32;
+    }
+
+    let obj = Rc::new(obj);
+    game.object_list.borrow_mut().push(Rc::clone(&obj));
+    obj
+}
+
+/* search a given list for an object, and return a pointer to that object */
+pub fn get_obj_in_list_vis(ch: &CharData, name: &str, list: &[Rc<ObjData>]) -> Option<Rc<ObjData>> {
+    let (number, tmp) = get_number(name)?;
+
+    list.iter()
+        .filter(|i| isname(tmp, &i.name) && ch.can_see_obj(i))
+        .nth(number - 1)
+        .cloned()
+}
+
+/* search the entire world for an object, and return a pointer  */
+pub fn get_obj_vis(ch: &CharData, name: &str, game: &Game) -> Option<Rc<ObjData>> {
+    // scan items carried
+    if let Some(i) = get_obj_in_list_vis(ch, name, &ch.carrying.borrow()) {
+        return Some(i);
+    }
+
+    // scan room
+    if let Some(i) = get_obj_in_list_vis(ch, name, &ch.in_room.borrow().contents.borrow()) {
+        return Some(i);
+    }
+
+    // ok.. no luck yet. scan the entire obj list
+    get_obj_in_list_vis(ch, name, &game.object_list.borrow())
+}
+
+pub fn get_char_room_vis(ch: &CharData, name: &str) -> Option<Rc<CharData>> {
+    let (number, tmp) = get_number(name)?;
+
+    ch.in_room.borrow().people.borrow().iter()
+        .filter(|i| isname(tmp, &i.get_name()) && ch.can_see(i))
+        .nth(number - 1)
+        .cloned()
+}
+
+pub fn get_char_vis(ch: &CharData, name: &str, game: &Game) -> Option<Rc<CharData>> {
+    // check location
+    if let Some(i) = get_char_room_vis(ch, name) {
+        return Some(i);
+    }
+
+    let (number, tmp) = get_number(name)?;
+
+    game.character_list.borrow().iter()
+        .filter(|i| isname(tmp, &i.get_name()) && ch.can_see(i))
+        .nth(number - 1)
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::spells::*;
+    use diku::testutil::*;
+
+    fn affect(spell_type: i32, duration: i16, modifier: i8, location: AbilityModifier) -> AffectedType {
+        AffectedType {
+            spell_type,
+            duration,
+            modifier,
+            location,
+            bitvector: AffectedFlags::empty(),
+        }
+    }
+
+    #[test]
+    fn affect_lifecycle_test() {
+        let room = test_room(3001);
+        let ch = test_char("Affected", &room);
+
+        let mut af = affect(SPELL_CURSE, 5, -1, AbilityModifier::Hitroll);
+        af.bitvector = AffectedFlags::AFF_CURSE;
+        affect_to_char(&ch, &af);
+        affect_to_char(&ch, &affect(SPELL_CURSE, 5, 1, AbilityModifier::SavingPara));
+        affect_to_char(&ch, &affect(SPELL_STRENGTH, 5, 2, AbilityModifier::Str));
+        assert_eq!(ch.points.borrow().hitroll, -1);
+        assert_eq!(ch.specials.borrow().apply_saving_throw[SavingThrowModifier::Para], 1);
+        assert_eq!(ch.get_str(), 15);
+        assert!(ch.is_affected(AffectedFlags::AFF_CURSE));
+
+        affect_from_char(&ch, SPELL_CURSE);
+        assert!(!affected_by_spell(&ch, SPELL_CURSE));
+        assert_eq!(ch.affected.borrow().len(), 1);
+        assert_eq!(ch.points.borrow().hitroll, 0);
+        assert_eq!(ch.specials.borrow().apply_saving_throw[SavingThrowModifier::Para], 0);
+        assert!(!ch.is_affected(AffectedFlags::AFF_CURSE));
+        assert_eq!(ch.get_str(), 15);
+
+        affect_from_char(&ch, SPELL_STRENGTH);
+        assert!(ch.affected.borrow().is_empty());
+        assert_eq!(ch.get_str(), 13);
+    }
+
+    #[test]
+    fn affect_join_test() {
+        let room = test_room(3001);
+        let ch = test_char("Joined", &room);
+
+        // durations and modifiers add up
+        affect_join(&ch, &affect(SPELL_POISON, 4, -2, AbilityModifier::Str), false, false);
+        affect_join(&ch, &affect(SPELL_POISON, 6, -2, AbilityModifier::Str), false, false);
+        assert_eq!(ch.affected.borrow().len(), 1);
+        assert_eq!(ch.affected.borrow().front().unwrap().duration, 10);
+        assert_eq!(ch.get_str(), 9);
+
+        // or are averaged
+        affect_join(&ch, &affect(SPELL_STRENGTH, 10, 2, AbilityModifier::Str), true, true);
+        affect_join(&ch, &affect(SPELL_STRENGTH, 4, 4, AbilityModifier::Str), true, true);
+        let strength = ch.affected.borrow().iter().find(|af| af.spell_type == SPELL_STRENGTH).cloned().unwrap();
+        assert_eq!((strength.duration, strength.modifier), (7, 3));
+        assert_eq!(ch.get_str(), 12);
+    }
+
+    #[test]
+    fn affect_total_test() {
+        let room = test_room(3001);
+        let ch = test_char("Strong", &room);
+        let mob = test_mob("a strong mob", &room);
+
+        let ring = Rc::new(clear_object());
+        ring.affected.borrow_mut()[0] = ObjAffectedType { location: AbilityModifier::Dex as u8, modifier: 2 };
+        ring.affected.borrow_mut()[1] = ObjAffectedType { location: AbilityModifier::Ac as u8, modifier: -10i16 as u16 };
+        ring.obj_flags.borrow_mut().bitvector = AffectedFlags::AFF_DETECT_INVISIBLE.bits() as u64;
+        let armor = ch.points.borrow().armor;
+
+        equip_char(&ch, Rc::clone(&ring), EquipmentPosition::FingerR);
+        assert_eq!(ch.get_dex(), 15);
+        assert_eq!(ch.points.borrow().armor, armor - 10);
+        assert!(ch.is_affected(AffectedFlags::AFF_DETECT_INVISIBLE));
+
+        // players top out at 18/100, the rest goes to str_add
+        affect_to_char(&ch, &affect(SPELL_STRENGTH, 5, 7, AbilityModifier::Str));
+        assert_eq!((ch.get_str(), ch.get_add()), (18, 20));
+        affect_to_char(&ch, &affect(SPELL_STRENGTH, 5, 20, AbilityModifier::Str));
+        assert_eq!((ch.get_str(), ch.get_add()), (18, 100));
+
+        // taking the ring off leaves only the spells
+        assert!(unequip_char(&ch, EquipmentPosition::FingerR).is_some());
+        assert_eq!(ch.get_dex(), 13);
+        assert_eq!(ch.points.borrow().armor, armor);
+        assert!(!ch.is_affected(AffectedFlags::AFF_DETECT_INVISIBLE));
+
+        affect_to_char(&mob, &affect(SPELL_STRENGTH, 5, 30, AbilityModifier::Str));
+        assert_eq!(mob.get_str(), 25);
+    }
+
+    #[test]
+    fn unknown_apply_test() {
+        let room = test_room(3001);
+        let ch = test_char("Wearer", &room);
+        let ring = Rc::new(clear_object());
+        ring.affected.borrow_mut()[0] = ObjAffectedType { location: 99, modifier: 5 };
+
+        equip_char(&ch, ring, EquipmentPosition::FingerR);
+        assert_eq!(*ch.abilities.borrow(), *ch.tmpabilities.borrow());
+        assert!(unequip_char(&ch, EquipmentPosition::FingerR).is_some());
+    }
+
+    #[test]
+    fn extract_char_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Leaver", &room);
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        let mut bag = clear_object();
+        bag.name = String::from("bag");
+        obj_to_char(Rc::new(bag), &ch);
+        let mut ring = clear_object();
+        ring.name = String::from("ring");
+        ring.affected.borrow_mut()[0] = ObjAffectedType { location: AbilityModifier::Dex as u8, modifier: 2 };
+        equip_char(&ch, Rc::new(ring), EquipmentPosition::FingerR);
+        assert_eq!(ch.get_dex(), 15);
+
+        extract_char(&ch, &game);
+        let mut left: Vec<String> = room.contents.borrow().iter().map(|obj| obj.name.clone()).collect();
+        left.sort();
+        assert_eq!(left, vec!["bag", "ring"]);
+        assert!(ch.equipment.borrow().iter().all(|(_, e)| e.is_none()));
+        assert_eq!(ch.get_dex(), 13);
+        assert!(room.people.borrow().is_empty());
+        assert!(game.character_list.borrow().is_empty());
+    }
 }
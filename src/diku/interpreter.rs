@@ -1,9 +1,30 @@
-use std::cmp::max;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
 use std::rc::Rc;
 
-use enum_map::EnumMap;
-
+use diku::act_comm::*;
+use diku::act_info::{do_help, do_time, do_weather};
+use diku::act_movement::do_pick;
+use diku::act_obj2::{do_drink, do_eat, do_fill, do_pour};
+use diku::act_offensive::*;
+use diku::act_other::*;
+use diku::act_social::{do_action, do_pose, find_action};
+use diku::act_wizard::*;
+use diku::comm::{act, close_socket, process_output, send_to_char, write_to_output};
+use diku::constants::{MAX_NAME_LENGTH, MENU, START_ROOM, VOID_ROOM, WELC_MESSG};
+use diku::game::{clear_char, copy_from_store, init_char, load_char, save_char, store_to_char};
+use diku::handler::{char_from_room, char_to_room};
+use diku::limits::do_start;
+use diku::reception::load_char_objs;
+use diku::spell_parser::do_cast;
 use diku::structs::*;
+use diku::utility::log;
+
+// command numbers, as handed to the special procedures
+pub const CMD_PRACTICE: i32 = 164;
+pub const CMD_PRACTISE: i32 = 170;
+pub const CMD_REBOOT: i32 = 222;
 
 const FILL: &'static [&'static str] = &[
     "in",
@@ -25,9 +46,11 @@ fn search_block(arg: &str, list: &[&str], exact: bool) -> Option<usize> {
             }
         }
     } else {
-        let length = max(word.len(), 1);
+        if word.is_empty() {
+            return None;
+        }
         for (i, &item) in list.iter().enumerate() {
-            if word == item[0..length] {
+            if item.starts_with(&word) {
                 return Some(i);
             }
         }
@@ -40,36 +63,467 @@ pub fn fill_word(argument: &str) -> bool {
     search_block(argument, FILL, true).is_some()
 }
 
+/* find the first sub-argument of a string, return pointer to first char in
+   primary argument, following the sub-arg			            */
+pub fn one_argument(argument: &str) -> (String, &str) {
+    let mut rest = argument;
+    loop {
+        rest = rest.trim_start();
+        let end = rest.find(' ').unwrap_or(rest.len());
+        let first_arg = rest[..end].to_lowercase();
+        rest = &rest[end..];
+        if !fill_word(&first_arg) {
+            return (first_arg, rest);
+        }
+    }
+}
+
+/* The first two arguments of a string, fill words are skipped */
+pub fn argument_interpreter(argument: &str) -> (String, String) {
+    let (first_arg, rest) = one_argument(argument);
+    let (second_arg, _) = one_argument(rest);
+    (first_arg, second_arg)
+}
+
+fn commando(cmd_info: &mut Vec<CommandInfo>, name: &'static str, number: i32, minimum_position: Position,
+        command_pointer: CommandProcedure, minimum_level: u8) {
+    cmd_info.push(CommandInfo {
+        name,
+        number,
+        command_pointer,
+        minimum_position,
+        minimum_level,
+    });
+}
+
+// Abbreviations match the first command in this order, so it follows the
+// numbering of the original command list. Socials are looked up by name when
+// no command matches.
+pub fn assign_command_pointers() -> Vec<CommandInfo> {
+    let mut info = Vec::new();
+    let i = &mut info;
+
+    commando(i, "drink", 11, Position::Resting, do_drink, 0);
+    commando(i, "eat", 12, Position::Resting, do_eat, 0);
+    commando(i, "say", 17, Position::Resting, do_say, 0);
+    commando(i, "shout", 18, Position::Resting, do_shout, 0);
+    commando(i, "tell", 19, Position::Resting, do_tell, 0);
+    commando(i, "help", 38, Position::Dead, do_help, 0);
+    commando(i, "emote", 40, Position::Sleeping, do_emote, 0);
+    commando(i, "force", 47, Position::Sleeping, do_force, 23);
+    commando(i, "transfer", 48, Position::Sleeping, do_trans, 22);
+    commando(i, "buy", 56, Position::Standing, do_not_here, 0);
+    commando(i, "sell", 57, Position::Standing, do_not_here, 0);
+    commando(i, "value", 58, Position::Standing, do_not_here, 0);
+    commando(i, "list", 59, Position::Standing, do_not_here, 0);
+    commando(i, "goto", 61, Position::Sleeping, do_goto, 21);
+    commando(i, "weather", 62, Position::Resting, do_weather, 0);
+    commando(i, "pour", 64, Position::Standing, do_pour, 0);
+    // the cut-off names come first, so that only the full word brings the game down
+    commando(i, "shutdow", 82, Position::Dead, do_shutdow, 24);
+    commando(i, "shutdown", 66, Position::Dead, do_shutdown, 24);
+    commando(i, "stat", 74, Position::Dead, do_stat, 21);
+    commando(i, "time", 76, Position::Dead, do_time, 0);
+    commando(i, "load", 77, Position::Dead, do_load, 22);
+    commando(i, "purge", 78, Position::Dead, do_purge, 22);
+    commando(i, "whisper", 83, Position::Resting, do_whisper, 0);
+    commando(i, "cast", 84, Position::Sitting, do_cast, 1);
+    commando(i, "at", 85, Position::Dead, do_at, 21);
+    commando(i, "snoop", 90, Position::Dead, do_snoop, 22);
+    commando(i, "advance", 95, Position::Dead, do_advance, 24);
+    commando(i, "flee", 151, Position::Fighting, do_flee, 1);
+    commando(i, "sneak", 152, Position::Standing, do_sneak, 1);
+    commando(i, "hide", 153, Position::Resting, do_hide, 1);
+    commando(i, "backstab", 154, Position::Standing, do_backstab, 1);
+    commando(i, "pick", 155, Position::Standing, do_pick, 1);
+    commando(i, "steal", 156, Position::Standing, do_steal, 1);
+    commando(i, "bash", 157, Position::Fighting, do_bash, 1);
+    commando(i, "rescue", 158, Position::Fighting, do_rescue, 1);
+    commando(i, "kick", 159, Position::Fighting, do_kick, 1);
+    commando(i, "practice", CMD_PRACTICE, Position::Resting, do_practice, 1);
+    commando(i, "'", 169, Position::Resting, do_say, 0);
+    commando(i, "practise", CMD_PRACTISE, Position::Resting, do_practice, 1);
+    commando(i, ":", 177, Position::Sleeping, do_emote, 0);
+    commando(i, "restore", 203, Position::Dead, do_restore, 22);
+    commando(i, "return", 204, Position::Dead, do_return, 0);
+    commando(i, "switch", 205, Position::Dead, do_switch, 23);
+    commando(i, "pose", 209, Position::Standing, do_pose, 0);
+    commando(i, "gossip", 214, Position::Resting, do_gossip, 0);
+    commando(i, "reply", 215, Position::Resting, do_reply, 0);
+    commando(i, "channels", 216, Position::Dead, do_channels, 0);
+    commando(i, "ignore", 217, Position::Dead, do_ignore, 0);
+    commando(i, "history", 218, Position::Dead, do_history, 0);
+    commando(i, "fill", 219, Position::Standing, do_fill, 0);
+    commando(i, "set", 220, Position::Dead, do_set, 23);
+    commando(i, "freeze", 221, Position::Dead, do_freeze, 22);
+    commando(i, "reboo", 224, Position::Dead, do_shutdow, 24);
+    commando(i, "reboot", CMD_REBOOT, Position::Dead, do_shutdown, 24);
+    commando(i, "wizlock", 223, Position::Dead, do_wizlock, 23);
+    commando(i, "copyover", 225, Position::Dead, do_copyover, 24);
+
+    info
+}
+
+fn position_message(pos: Position) -> &'static str {
+    match pos {
+        Position::Dead => "Lie still; you are DEAD!!! :-( \n\r",
+        Position::Incap | Position::MortallyW => "You are in a pretty bad shape, unable to do anything!\n\r",
+        Position::Stunned => "All you can do right now, is think about the stars!\n\r",
+        Position::Sleeping => "In your dreams, or what?\n\r",
+        Position::Resting => "Nah... You feel too relaxed to do that..\n\r",
+        Position::Sitting => "Maybe you should get on your feet first?\n\r",
+        Position::Fighting | Position::Standing => "No way! You are fighting for your life!\n\r",
+    }
+}
+
+// Commands above the level of ch are not even recognised
+pub fn command_interpreter(ch: Rc<CharData>, argument: &str, game: &Game) {
+    let argument = argument.trim_start();
+    if argument.is_empty() {
+        return;
+    }
+
+    // A single character that is not a letter is a command of its own, eg. "'hello"
+    let end = match argument.chars().next() {
+        Some(c) if !c.is_alphanumeric() => c.len_utf8(),
+        _ => argument.find(' ').unwrap_or(argument.len()),
+    };
+    let word = argument[..end].to_lowercase();
+    let rest = &argument[end..];
+
+    if !ch.is_npc() {
+        let act = ch.specials.borrow().act;
+        if act.contains(SpecialActFlags::PLR_FREEZE) {
+            send_to_char("You're totally frozen!\n\r", &ch);
+            return;
+        }
+        if act.contains(SpecialActFlags::PLR_LOG) {
+            log(&format!("{}: {}", ch.get_name(), argument));
+        }
+    }
+
+    let level = ch.get_level();
+    let command = game.cmd_info.iter()
+        .find(|info| info.name.starts_with(&word[..]) && level >= info.minimum_level)
+        .map(|info| (info.number, info.command_pointer, info.minimum_position))
+        .or_else(|| find_action(&word, game).map(|nr| (nr, do_action as CommandProcedure, Position::Resting)));
+    let (number, pointer, position) = match command {
+        Some(command) => command,
+        None => {
+            send_to_char("Huh?!?\n\r", &ch);
+            return;
+        }
+    };
+
+    if ch.get_pos() < position {
+        send_to_char(position_message(ch.get_pos()), &ch);
+        return;
+    }
+
+    if !special(Rc::clone(&ch), number, rest, game) {
+        pointer(ch, rest, number, game);
+    }
+}
+
+// A player whose link died is still in the game, and takes over the body
+// again when logging in.
+pub fn reconnect(d: &Rc<RefCell<DescriptorData>>, name: &str, game: &Game) -> bool {
+    let ch = game.character_list.borrow().iter()
+        .find(|ch| !ch.is_npc() && ch.desc.borrow().is_none() && ch.get_name().eq_ignore_ascii_case(name) &&
+            !game.descriptor_list.borrow().iter()
+                .any(|k| k.borrow().original.as_ref().map_or(false, |original| Rc::ptr_eq(original, ch))))
+        .cloned();
+    let ch = match ch {
+        Some(ch) => ch,
+        None => return false,
+    };
+
+    *ch.desc.borrow_mut() = Some(Rc::clone(d));
+    d.borrow_mut().character = Some(Rc::clone(&ch));
+    d.borrow_mut().connected = ConnectionMode::Plying;
+    send_to_char("Reconnecting.\n\r", &ch);
+
+    ch.specials.borrow_mut().timer = 0;
+    let was_in_room = ch.specials.borrow_mut().was_in_room.take();
+    if let Some(room) = was_in_room {
+        char_from_room(&ch);
+        char_to_room(&ch, &game.world[&room]);
+    }
+
+    act("$n has reconnected.", true, &ch, None, None, None, None, VictimType::ToRoom);
+    log(&format!("{}[{}] has reconnected.", ch.get_name(), d.borrow().host));
+    true
+}
+
+#[link(name = "crypt")]
+extern "C" {
+    fn crypt(key: *const c_char, salt: *const c_char) -> *mut c_char;
+}
+
+// Passwords are kept as the first ten characters of their crypt()
+fn crypt_password(pwd: &str, salt: &str) -> String {
+    let (key, salt) = match (CString::new(pwd), CString::new(salt)) {
+        (Ok(key), Ok(salt)) => (key, salt),
+        _ => return String::new(),
+    };
+    let crypted = unsafe { crypt(key.as_ptr(), salt.as_ptr()) };
+    if crypted.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(crypted) }.to_string_lossy().chars().take(10).collect()
+}
+
+fn password_ok(arg: &str, pwd: &str) -> bool {
+    !pwd.is_empty() && crypt_password(arg, pwd) == pwd
+}
+
+// A name is letters only, and is written with a capital
+fn parse_name(arg: &str) -> Option<String> {
+    let arg = arg.trim();
+    if arg.is_empty() || arg.len() > MAX_NAME_LENGTH || !arg.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let name = arg.to_lowercase();
+    Some(name[..1].to_uppercase() + &name[1..])
+}
+
+fn send_to_q(txt: &str, d: &RefCell<DescriptorData>) {
+    write_to_output(String::from(txt), d);
+}
+
+// Takes a link from the name prompt into the game
+pub fn nanny(d: &Rc<RefCell<DescriptorData>>, arg: &str, game: &Game) {
+    let arg = arg.trim();
+    let connected = d.borrow().connected;
+    match connected {
+        ConnectionMode::Nme => { // wait for input of name
+            if arg.is_empty() {
+                close_socket(d, game);
+                return;
+            }
+            let name = match parse_name(arg) {
+                Some(name) => name,
+                None => {
+                    send_to_q("Illegal name, please try another.\n\rName: ", d);
+                    return;
+                }
+            };
+
+            let ch = match load_char(&name, game) {
+                Some((st, nr)) => {
+                    let room = game.world.get(&st.load_room).or_else(|| game.world.get(&START_ROOM))
+                        .unwrap_or_else(|| &game.world[&VOID_ROOM]);
+                    let ch = Rc::new(clear_char(Rc::clone(room)));
+                    store_to_char(&st, &ch);
+                    d.borrow_mut().pwd = copy_from_store(&st.pwd);
+                    d.borrow_mut().pos = nr as i32;
+                    send_to_q("Password: ", d);
+                    d.borrow_mut().connected = ConnectionMode::PwdNrm;
+                    ch
+                }
+                None => { // player unknown gotta make a new
+                    let room = game.world.get(&START_ROOM).unwrap_or_else(|| &game.world[&VOID_ROOM]);
+                    let ch = Rc::new(clear_char(Rc::clone(room)));
+                    ch.player.borrow_mut().name = name.clone();
+                    send_to_q(&format!("Did I get that right, {} (Y/N)? ", name), d);
+                    d.borrow_mut().connected = ConnectionMode::NmeCnf;
+                    ch
+                }
+            };
+            *ch.desc.borrow_mut() = Some(Rc::clone(d));
+            d.borrow_mut().character = Some(ch);
+        }
+        ConnectionMode::NmeCnf => { // wait for conf. of new name
+            match arg.chars().next().map(|c| c.to_ascii_lowercase()) {
+                Some('y') => {
+                    if *game.wizlock.borrow() {
+                        send_to_q("Sorry, no new characters while the game is wizlocked.\n\r", d);
+                        process_output(d);
+                        close_socket(d, game);
+                        return;
+                    }
+                    let name = d.borrow().character.as_ref().map(|ch| ch.get_name()).unwrap_or_default();
+                    send_to_q(&format!("New character.\n\rGive me a password for {}: ", name), d);
+                    d.borrow_mut().connected = ConnectionMode::PwdGet;
+                }
+                Some('n') => {
+                    send_to_q("Ok, what IS it, then? ", d);
+                    d.borrow_mut().character = None;
+                    d.borrow_mut().connected = ConnectionMode::Nme;
+                }
+                _ => send_to_q("Please type Yes or No? ", d),
+            }
+        }
+        ConnectionMode::PwdNrm => { // get pwd for known player
+            if arg.is_empty() {
+                close_socket(d, game);
+                return;
+            }
+            let name = d.borrow().character.as_ref().map(|ch| ch.get_name()).unwrap_or_default();
+            if !password_ok(arg, &d.borrow().pwd) {
+                send_to_q("Wrong password.\n\r", d);
+                log(&format!("Bad password for {}[{}].", name, d.borrow().host));
+                process_output(d);
+                close_socket(d, game);
+                return;
+            }
+
+            // a link-dead player takes up where he left off
+            if reconnect(d, &name, game) {
+                return;
+            }
+
+            log(&format!("{}[{}] has connected.", name, d.borrow().host));
+            send_to_q(&game.motd, d);
+            send_to_q("\n\r\n*** PRESS RETURN: ", d);
+            d.borrow_mut().connected = ConnectionMode::RMOTD;
+        }
+        ConnectionMode::PwdGet => { // get pwd for new player
+            if arg.is_empty() || arg.len() > 10 {
+                send_to_q("Illegal password.\n\rPassword: ", d);
+                return;
+            }
+            let name = d.borrow().character.as_ref().map(|ch| ch.get_name()).unwrap_or_default();
+            let pwd = crypt_password(arg, &name);
+            d.borrow_mut().pwd = pwd;
+            send_to_q("Please retype password: ", d);
+            d.borrow_mut().connected = ConnectionMode::PwdCnf;
+        }
+        ConnectionMode::PwdCnf => { // get confirmation of new pwd
+            if !password_ok(arg, &d.borrow().pwd) {
+                send_to_q("Passwords don't match.\n\rRetype password: ", d);
+                d.borrow_mut().connected = ConnectionMode::PwdGet;
+                return;
+            }
+            send_to_q("What is your sex (M/F) ? ", d);
+            d.borrow_mut().connected = ConnectionMode::QSex;
+        }
+        ConnectionMode::QSex => { // query sex of new user
+            let sex = match arg.chars().next().map(|c| c.to_ascii_lowercase()) {
+                Some('m') => Sex::Male,
+                Some('f') => Sex::Female,
+                _ => {
+                    send_to_q("That's not a sex..\n\rWhat IS your sex? :", d);
+                    return;
+                }
+            };
+            if let Some(ref ch) = d.borrow().character {
+                ch.player.borrow_mut().sex = sex;
+            }
+            send_to_q("\n\rSelect a class:\n\rCleric\n\rThief\n\rWarrior\n\rMagic-user\n\rClass :", d);
+            d.borrow_mut().connected = ConnectionMode::QClass;
+        }
+        ConnectionMode::QClass => { // query class of new user
+            let class = match arg.chars().next().map(|c| c.to_ascii_lowercase()) {
+                Some('m') => Class::MagicUser,
+                Some('c') => Class::Cleric,
+                Some('f') | Some('w') => Class::Warrior,
+                Some('t') => Class::Thief,
+                _ => {
+                    send_to_q("\n\rThat's not a class.\n\rClass:", d);
+                    return;
+                }
+            };
+            let ch = d.borrow().character.clone().unwrap();
+            ch.player.borrow_mut().class = class;
+            init_char(&ch, game);
+            save_char(&ch, START_ROOM, game);
+            log(&format!("{} [{}] new player.", ch.get_name(), d.borrow().host));
+            send_to_q(&game.motd, d);
+            send_to_q("\n\r\n*** PRESS RETURN: ", d);
+            d.borrow_mut().connected = ConnectionMode::RMOTD;
+        }
+        ConnectionMode::RMOTD => { // read CR after printing motd
+            send_to_q(MENU, d);
+            d.borrow_mut().connected = ConnectionMode::Slct;
+        }
+        ConnectionMode::Slct => { // get selection from main menu
+            match arg {
+                "0" => close_socket(d, game),
+                "1" => {
+                    // while wizlocked only gods may enter
+                    let mortal = d.borrow().character.as_ref().map_or(true, |ch| ch.get_level() < 21);
+                    if *game.wizlock.borrow() && mortal {
+                        send_to_q("Sorry, the game is wizlocked.\n\r", d);
+                        process_output(d);
+                        close_socket(d, game);
+                    } else {
+                        enter_game(d, game);
+                    }
+                }
+                _ => {
+                    send_to_q("Wrong option.\n\r", d);
+                    send_to_q(MENU, d);
+                }
+            }
+        }
+        _ => {
+            log("SYSERR: Nanny: illegal state of con'ness");
+            close_socket(d, game);
+        }
+    }
+}
+
+fn enter_game(d: &Rc<RefCell<DescriptorData>>, game: &Game) {
+    let ch = d.borrow().character.clone().unwrap();
+    d.borrow_mut().connected = ConnectionMode::Plying;
+
+    send_to_char(WELC_MESSG, &ch);
+    if ch.get_level() == 0 {
+        do_start(&ch, game);
+    }
+
+    // someone killed since logging in gets up again
+    ch.specials.borrow_mut().position = Position::Standing;
+    if ch.get_hit() <= 0 {
+        ch.points.borrow_mut().hit = 1;
+    }
+
+    let room = Rc::clone(&ch.in_room.borrow());
+    char_to_room(&ch, &room);
+    game.character_list.borrow_mut().push(Rc::clone(&ch));
+    load_char_objs(&ch, game);
+    act("$n has entered the game.", true, &ch, None, None, None, None, VictimType::ToRoom);
+}
+
+// corpses and money have no index entry, and so no special procedure
+fn obj_func(obj: &ObjData, game: &Game) -> Option<SpecialProcedure> {
+    obj.item_number.and_then(|nr| game.obj_index.get(&nr)).and_then(|index| index.func)
+}
+
+// The procedures may move people and things about, so the lists are copied
+// before they are called.
 pub fn special(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
 
     // special in room?
-    if ch.in_room.funct.is_some() &&
-    (ch.in_room.funct.unwrap())(Rc::clone(&ch), cmd, arg, game) {
+    let funct = ch.in_room.borrow().funct;
+    if funct.is_some() && funct.unwrap()(Rc::clone(&ch), cmd, arg, game) {
         return true;
     }
 
     // special in equipment list?
-    for (_, e) in ch.equipment.iter() {
-        if e.is_some() {
-            let func = game.obj_index.get(&e.as_ref().unwrap().item_number).unwrap().func;
-            if func.is_some() && func.unwrap()(Rc::clone(&ch), cmd, arg, game) {
-                return true;
-            }
+    let equipment: Vec<Rc<ObjData>> = ch.equipment.borrow().iter().filter_map(|(_, e)| e.clone()).collect();
+    for e in &equipment {
+        let func = obj_func(e, game);
+        if func.is_some() && func.unwrap()(Rc::clone(&ch), cmd, arg, game) {
+            return true;
         }
     }
 
     // special in inventory?
-    for i in &ch.carrying {
-        let func = game.obj_index.get(&i.item_number).unwrap().func;
+    let carrying = ch.carrying.borrow().clone();
+    for i in &carrying {
+        let func = obj_func(i, game);
         if func.is_some() && func.unwrap()(Rc::clone(&ch), cmd, arg, game) {
             return true;
         }
     }
 
     // special in mobile present?
-    for k in ch.in_room.people.borrow().iter() {
+    let people = ch.in_room.borrow().people.borrow().clone();
+    for k in &people {
         if k.is_mob() {
-            let func = game.mob_index.get(&k.nr.unwrap()).unwrap().func;
+            let func = game.mob_index.get(&k.nr.unwrap()).and_then(|index| index.func);
             if func.is_some() && func.unwrap()(Rc::clone(&ch), cmd, arg, game) {
                 return true;
             }
@@ -77,12 +531,198 @@ pub fn special(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
     }
 
     // special in object present?
-    for i in ch.in_room.contents.borrow().iter() {
-        let func = game.obj_index.get(&i.item_number).unwrap().func;
+    let contents = ch.in_room.borrow().contents.borrow().clone();
+    for i in &contents {
+        let func = obj_func(i, game);
         if func.is_some() && func.unwrap()(Rc::clone(&ch), cmd, arg, game) {
             return true;
         }
     }
 
     return false;
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::spells::SKILL_STEAL;
+    use diku::testutil::*;
+
+    #[test]
+    fn argument_test() {
+        assert_eq!(one_argument(" at the Temple"), (String::from("temple"), ""));
+        assert_eq!(argument_interpreter("get all from bag"), (String::from("get"), String::from("all")));
+    }
+
+    #[test]
+    fn command_interpreter_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Speaker", &room);
+        let listener = test_char("Listener", &room);
+
+        command_interpreter(Rc::clone(&ch), "sa hello", &game);
+        command_interpreter(Rc::clone(&ch), "'hi there", &game);
+        assert_eq!(output(&listener), vec!["Speaker says 'hello'", "Speaker says 'hi there'"]);
+
+        command_interpreter(Rc::clone(&ch), "xyzzy", &game);
+        command_interpreter(Rc::clone(&ch), "buy bread", &game);
+        assert_eq!(output(&ch), vec!["Huh?!?\n\r", "Sorry, but you cannot do that here!\n\r"]);
+
+        ch.specials.borrow_mut().position = Position::Sleeping;
+        command_interpreter(Rc::clone(&ch), "say hello", &game);
+        command_interpreter(Rc::clone(&ch), "help sleep", &game);
+        assert_eq!(output(&ch), vec!["In your dreams, or what?\n\r", "No help available.\n\r"]);
+    }
+
+    #[test]
+    fn level_test() {
+        let mut game = test_game();
+        let room = test_room(3001);
+        game.world.insert(3001, Rc::clone(&room));
+        let ch = test_char("Mortal", &room);
+
+        // The commands of the gods are unknown to mortals
+        command_interpreter(Rc::clone(&ch), "goto 3001", &game);
+        command_interpreter(Rc::clone(&ch), "got", &game);
+        command_interpreter(Rc::clone(&ch), "g", &game);
+        assert_eq!(output(&ch), vec!["Huh?!?\n\r", "Huh?!?\n\r", "Gossip? Yes, but WHAT??\n\r"]);
+
+        ch.player.borrow_mut().level = 21;
+        command_interpreter(Rc::clone(&ch), "goto", &game);
+        command_interpreter(Rc::clone(&ch), "load obj 1", &game);
+        assert_eq!(output(&ch), vec!["You must supply a room number or a name.\n\r", "Huh?!?\n\r"]);
+    }
+
+    // Drains what was queued for a link, that may have no char yet
+    fn sent(d: &RefCell<DescriptorData>) -> String {
+        let mut d = d.borrow_mut();
+        let mut messages = String::new();
+        while let Some(message) = d.output.pop_back() {
+            messages.push_str(&message);
+        }
+        messages
+    }
+
+    #[test]
+    fn parse_name_test() {
+        assert_eq!(parse_name(" gandalf "), Some(String::from("Gandalf")));
+        assert_eq!(parse_name("BILBO"), Some(String::from("Bilbo")));
+        assert_eq!(parse_name("R2D2"), None);
+        assert_eq!(parse_name("Fredegar Bolger"), None);
+        assert_eq!(parse_name("Abcdefghijklmnop"), None);
+    }
+
+    #[test]
+    fn new_player_test() {
+        let mut game = test_game();
+        let room = test_room(START_ROOM);
+        game.world.insert(START_ROOM, Rc::clone(&room));
+        // somebody else was here first, and is the god
+        game.player_table.borrow_mut().insert(String::from("odin"), 0);
+
+        let d = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+        game.descriptor_list.borrow_mut().push(Rc::clone(&d));
+        nanny(&d, "4711", &game);
+        assert_eq!(sent(&d), "Illegal name, please try another.\n\rName: ");
+        nanny(&d, "newbie", &game);
+        assert_eq!(sent(&d), "Did I get that right, Newbie (Y/N)? ");
+        nanny(&d, "yes", &game);
+        nanny(&d, "secret", &game);
+        nanny(&d, "public", &game);
+        assert_eq!(sent(&d), "New character.\n\rGive me a password for Newbie: Please retype password: \
+            Passwords don't match.\n\rRetype password: ");
+        nanny(&d, "secret", &game);
+        nanny(&d, "secret", &game);
+        nanny(&d, "f", &game);
+        nanny(&d, "thief", &game);
+        assert!(d.borrow().connected == ConnectionMode::RMOTD);
+        nanny(&d, "", &game);
+        nanny(&d, "2", &game);
+        sent(&d);
+        nanny(&d, "1", &game);
+
+        let ch = d.borrow().character.clone().unwrap();
+        assert!(d.borrow().connected == ConnectionMode::Plying);
+        assert!(game.character_list.borrow().iter().any(|k| Rc::ptr_eq(k, &ch)));
+        assert!(Rc::ptr_eq(&ch.in_room.borrow(), &room));
+        assert!(ch.get_sex() == Sex::Female && ch.get_class() == Class::Thief);
+        assert_eq!(ch.get_level(), 1);
+        assert_eq!(ch.skills.borrow()[SKILL_STEAL as usize].learned, 15);
+        assert!(ch.get_hit() > 0);
+        assert_eq!(d.borrow().pwd, crypt_password("secret", "Newbie"));
+        assert!(sent(&d).starts_with(WELC_MESSG));
+    }
+
+    #[test]
+    fn wizlock_login_test() {
+        let mut game = test_game();
+        let room = test_room(START_ROOM);
+        game.world.insert(START_ROOM, Rc::clone(&room));
+        *game.wizlock.borrow_mut() = true;
+
+        // at the menu, as if just logged in
+        let at_menu = |name: &str, level: u8| {
+            let d = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+            let ch = Rc::new(clear_char(Rc::clone(&room)));
+            ch.player.borrow_mut().name = String::from(name);
+            ch.player.borrow_mut().level = level;
+            *ch.desc.borrow_mut() = Some(Rc::clone(&d));
+            d.borrow_mut().character = Some(ch);
+            d.borrow_mut().connected = ConnectionMode::Slct;
+            game.descriptor_list.borrow_mut().push(Rc::clone(&d));
+            d
+        };
+
+        let d = at_menu("Mortal", 20);
+        nanny(&d, "1", &game);
+        assert!(!game.descriptor_list.borrow().iter().any(|k| Rc::ptr_eq(k, &d)));
+        assert!(game.character_list.borrow().is_empty());
+        assert!(room.people.borrow().is_empty());
+
+        let d = at_menu("Odin", 21);
+        nanny(&d, "1", &game);
+        assert!(d.borrow().connected == ConnectionMode::Plying);
+        assert_eq!(game.character_list.borrow().len(), 1);
+
+        // nobody new gets made either
+        let d = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+        game.descriptor_list.borrow_mut().push(Rc::clone(&d));
+        nanny(&d, "newbie", &game);
+        nanny(&d, "y", &game);
+        assert!(!game.descriptor_list.borrow().iter().any(|k| Rc::ptr_eq(k, &d)));
+    }
+
+    #[test]
+    fn login_reconnect_test() {
+        let game = test_game();
+        let room = test_room(START_ROOM);
+        let ch = test_char("Linky", &room);
+        *ch.desc.borrow_mut() = None;
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        // as if Linky was just read from the player file
+        let login = || {
+            let d = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+            let loaded = Rc::new(clear_char(Rc::clone(&room)));
+            loaded.player.borrow_mut().name = String::from("Linky");
+            *loaded.desc.borrow_mut() = Some(Rc::clone(&d));
+            d.borrow_mut().character = Some(loaded);
+            d.borrow_mut().pwd = crypt_password("secret", "Linky");
+            d.borrow_mut().connected = ConnectionMode::PwdNrm;
+            game.descriptor_list.borrow_mut().push(Rc::clone(&d));
+            d
+        };
+
+        let d = login();
+        nanny(&d, "guess", &game);
+        assert_eq!(sent(&d), "");
+        assert!(!game.descriptor_list.borrow().iter().any(|k| Rc::ptr_eq(k, &d)));
+        assert!(ch.desc.borrow().is_none());
+
+        let d = login();
+        nanny(&d, "secret", &game);
+        assert!(Rc::ptr_eq(d.borrow().character.as_ref().unwrap(), &ch));
+        assert!(Rc::ptr_eq(ch.desc.borrow().as_ref().unwrap(), &d));
+        assert_eq!(output(&ch), vec!["Reconnecting.\n\r"]);
+    }
+}
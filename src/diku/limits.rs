@@ -1,9 +1,15 @@
 use std::cmp::{max, min};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
 
-use diku::comm::send_to_char;
+use diku::comm::{act, close_socket, send_to_char};
 use diku::constants::*;
+use diku::fight::{damage, stop_fighting, update_pos};
+use diku::game::save_char;
+use diku::handler::{char_from_room, char_to_room, extract_char, extract_obj, obj_from_obj, obj_to_char,
+    obj_to_obj, obj_to_room};
+use diku::spells::{SKILL_BACKSTAB, SKILL_HIDE, SKILL_PICK_LOCK, SKILL_SNEAK, SKILL_STEAL, SPELL_POISON, TYPE_SUFFERING};
 use diku::structs::*;
-use diku::utility::number;
 
 // When age < 15 return the value p0
 // When age in 15..29 calculate the line between p1 & p2
@@ -31,7 +37,7 @@ fn graf(age: u16, p0: u16, p1: u16, p2: u16, p3: u16, p4: u16, p5: u16, p6: u16)
 /* Which is NOT the same as the ch->points.max_xxxx !!!          */
 pub fn mana_limit(ch: &CharData) -> i16 {
     let max = if !ch.is_npc() {
-        (100) /* + (graf(age(ch).year, 0,0,10,30,50,70,60)); */
+        100 + ch.points.borrow().max_mana /* + (graf(age(ch).year, 0,0,10,30,50,70,60)); */
     } else {
         100
     };
@@ -68,54 +74,615 @@ pub fn hit_limit(ch: &CharData) -> i16 {
     max
 }
 
-fn advance_level(ch: &CharData) {
-    let mut add_hp = CON_APP[ch.get_con() as usize].hitp;
+// Tiredness makes you regain less, rest makes you regain more
+fn position_gain(ch: &CharData, gain: i16, sleeping: i16, resting: i16, sitting: i16) -> i16 {
+    match ch.get_pos() {
+        Position::Sleeping => gain + (gain >> sleeping),
+        Position::Resting => gain + (gain >> resting),
+        Position::Sitting => gain + (gain >> sitting),
+        _ => gain,
+    }
+}
+
+// Poison and an empty stomach slow everything down
+fn condition_gain(ch: &CharData, gain: i16) -> i16 {
+    let mut gain = gain;
+
+    if ch.is_affected(AffectedFlags::AFF_POISON) {
+        gain >>= 2;
+    }
+
+    if ch.get_cond(Condition::Full) == 0 || ch.get_cond(Condition::Thirst) == 0 {
+        gain >>= 2;
+    }
 
-    add_hp += match ch.get_class() {
-        Class::MagicUser => number(3, 8),
-        Class::Cleric => number(5, 10),
-        Class::Thief => number(7, 13),
-        Class::Warrior => number(10, 15),
-    } as i16;
+    gain
 }
 
-fn set_title(ch: &CharData) {
-    ch.player.borrow_mut().title = if ch.get_sex() == Sex::Male {
-        TITLES[ch.get_class() as usize - 1][ch.get_level() as usize].title_m } else {
-        TITLES[ch.get_class() as usize - 1][ch.get_level() as usize].title_f };
+pub fn mana_gain(ch: &CharData) -> i16 {
+    let gain = if ch.is_npc() {
+        /* Neat and fast */
+        ch.get_level() as i16
+    } else {
+        let mut gain = graf(ch.get_age(), 2, 4, 6, 8, 10, 12, 16) as i16;
+
+        /* Class calculations */
+        gain = position_gain(ch, gain, 0, 1, 2);
+
+        if ch.get_class() == Class::MagicUser || ch.get_class() == Class::Cleric {
+            gain += gain;
+        }
+
+        gain
+    };
+
+    condition_gain(ch, gain)
 }
 
-pub fn gain_exp(ch: &CharData, gain: i32) {
-    let mut gain = gain;
-    let mut is_altered = false;
-
-    if ch.is_npc() || (ch.get_level() < 21 && ch.get_level() > 0) {
-        if gain > 0 {
-            gain = min(100_000, gain);
-            ch.points.borrow_mut().exp += gain;
-            if !ch.is_npc() {
-                let mut i: u8 = 0;
-                while TITLES[ch.get_class() as usize - 1][i as usize].exp <= ch.get_exp() {
-                    if i > ch.get_level() {
-                        send_to_char("You raise a level\n\r", &ch);
-                        ch.player.borrow_mut().level = i;
-                        advance_level(ch);
-                        is_altered = true;
-                    }
+pub fn hit_gain(ch: &CharData) -> i16 {
+    let gain = if ch.is_npc() {
+        /* Neat and fast */
+        ch.get_level() as i16
+    } else {
+        let mut gain = graf(ch.get_age(), 2, 5, 10, 18, 6, 4, 2) as i16;
+
+        /* Class/Level calculations */
+        gain = max(0, gain + CON_APP[ch.get_con() as usize].hitp);
+
+        gain = position_gain(ch, gain, 1, 2, 3);
+
+        if ch.get_class() == Class::MagicUser || ch.get_class() == Class::Cleric {
+            gain >>= 1;
+        }
+
+        gain
+    };
+
+    condition_gain(ch, gain)
+}
+
+pub fn move_gain(ch: &CharData) -> i16 {
+    if ch.is_npc() {
+        /* Neat and fast */
+        return ch.get_level() as i16;
+    }
+
+    let gain = graf(ch.get_age(), 12, 18, 22, 21, 14, 10, 6) as i16;
+
+    condition_gain(ch, position_gain(ch, gain, 1, 2, 3))
+}
+
+// What a level is worth to each class: the hit point and mana dice, and the
+// average used when a level is lost again
+fn class_gains(class: Class) -> ((i32, i32), (i32, i32)) {
+    match class {
+        Class::MagicUser => ((3, 8), (4, 8)),
+        Class::Cleric => ((5, 10), (3, 7)),
+        Class::Thief => ((7, 13), (0, 0)),
+        Class::Warrior => ((10, 15), (0, 0)),
+    }
+}
+
+fn practices_per_level(ch: &CharData) -> u8 {
+    let bonus = WIS_APP[ch.get_wis() as usize].bonus;
+    let practices = if ch.get_class() == Class::MagicUser || ch.get_class() == Class::Cleric {
+        max(2, bonus)
+    } else {
+        min(2, max(1, bonus))
+    };
+    practices as u8
+}
+
+pub fn advance_level(ch: &CharData, game: &Game) {
+    let ((hp_from, hp_to), (mana_from, mana_to)) = class_gains(ch.get_class());
+    let add_hp = CON_APP[ch.get_con() as usize].hitp + game.number(hp_from, hp_to) as i16;
+    let add_mana = game.number(mana_from, mana_to) as i16;
+
+    {
+        let mut points = ch.points.borrow_mut();
+        points.max_hit += max(1, add_hp);
+        points.max_mana += add_mana;
+    }
+
+    let practices = practices_per_level(ch);
+    let mut specials = ch.specials.borrow_mut();
+    specials.spells_to_learn = specials.spells_to_learn.saturating_add(practices);
+
+    if ch.get_level() > 20 {
+        for (_, cond) in specials.conditions.iter_mut() {
+            *cond = -1;
+        }
+    }
+}
+
+// Take away what advance_level gave, as far as we can tell what that was
+fn lose_level(ch: &CharData) {
+    let ((hp_from, hp_to), (mana_from, mana_to)) = class_gains(ch.get_class());
+    let lose_hp = max(1, CON_APP[ch.get_con() as usize].hitp + ((hp_from + hp_to) / 2) as i16);
+    let lose_mana = ((mana_from + mana_to) / 2) as i16;
+
+    {
+        let mut points = ch.points.borrow_mut();
+        points.max_hit = max(1, points.max_hit - lose_hp);
+        points.max_mana = max(0, points.max_mana - lose_mana);
+        points.hit = min(points.hit, points.max_hit);
+    }
+
+    let practices = practices_per_level(ch);
+    let mut specials = ch.specials.borrow_mut();
+    specials.spells_to_learn = specials.spells_to_learn.saturating_sub(practices);
+}
+
+pub fn set_title(ch: &CharData) {
+    let title = &TITLES[ch.get_class() as usize - 1][ch.get_level() as usize];
+    ch.player.borrow_mut().title = if ch.get_sex() == Sex::Female { title.title_f } else { title.title_m };
+}
+
+// Five rolls of 4d6 with the lowest die dropped, the best going to what
+// matters most to the class
+pub fn roll_abilities(ch: &CharData, game: &Game) {
+    let mut table: Vec<u8> = (0..5).map(|_| {
+        let mut rolls: Vec<i32> = (0..4).map(|_| game.number(1, 6)).collect();
+        rolls.sort();
+        (rolls[1] + rolls[2] + rolls[3]) as u8
+    }).collect();
+    table.sort_by(|a, b| b.cmp(a));
+
+    {
+        let mut abilities = ch.abilities.borrow_mut();
+        abilities.str_add = 0;
+        match ch.get_class() {
+            Class::MagicUser => {
+                abilities.intel = table[0];
+                abilities.wis = table[1];
+                abilities.dex = table[2];
+                abilities.str = table[3];
+                abilities.con = table[4];
+            }
+            Class::Cleric => {
+                abilities.wis = table[0];
+                abilities.intel = table[1];
+                abilities.str = table[2];
+                abilities.dex = table[3];
+                abilities.con = table[4];
+            }
+            Class::Thief => {
+                abilities.dex = table[0];
+                abilities.str = table[1];
+                abilities.con = table[2];
+                abilities.intel = table[3];
+                abilities.wis = table[4];
+            }
+            Class::Warrior => {
+                abilities.str = table[0];
+                abilities.dex = table[1];
+                abilities.con = table[2];
+                abilities.wis = table[3];
+                abilities.intel = table[4];
+                if abilities.str == 18 {
+                    abilities.str_add = game.number(0, 100) as u8;
                 }
             }
         }
+    }
+    *ch.tmpabilities.borrow_mut() = *ch.abilities.borrow();
+}
+
+// A new character steps into the game for the first time
+pub fn do_start(ch: &CharData, game: &Game) {
+    send_to_char("Welcome. This is now your character in DikuMud,\n\rYou can now earn XP, and lots more...\n\r", ch);
+
+    ch.player.borrow_mut().level = 1;
+    ch.points.borrow_mut().exp = 1;
+    set_title(ch);
+    roll_abilities(ch, game);
+    ch.points.borrow_mut().max_hit = 10; // These are BASE numbers
+
+    if ch.get_class() == Class::Thief {
+        let mut skills = ch.skills.borrow_mut();
+        skills[SKILL_SNEAK as usize].learned = 10;
+        skills[SKILL_HIDE as usize].learned = 5;
+        skills[SKILL_STEAL as usize].learned = 15;
+        skills[SKILL_BACKSTAB as usize].learned = 10;
+        skills[SKILL_PICK_LOCK as usize].learned = 10;
+    }
+
+    advance_level(ch, game);
+
+    let (hit, mana, mov) = (hit_limit(ch), mana_limit(ch), move_limit(ch));
+    {
+        let mut points = ch.points.borrow_mut();
+        points.hit = hit;
+        points.mana = mana;
+        points.mov = mov;
+    }
+    {
+        let mut specials = ch.specials.borrow_mut();
+        specials.conditions[Condition::Thirst] = 24;
+        specials.conditions[Condition::Full] = 24;
+        specials.conditions[Condition::Drunk] = 0;
+    }
+
+    let mut player = ch.player.borrow_mut();
+    player.time.played = Duration::from_secs(0);
+    player.time.logon = SystemTime::now();
+}
+
+// Move a player to the level its experience is worth, never above max_level
+// and never below level 1. Returns true if the level changed.
+fn check_level(ch: &CharData, max_level: u8, game: &Game) -> bool {
+    let titles = &TITLES[ch.get_class() as usize - 1];
+    let start = ch.get_level();
+
+    while ch.get_level() < max_level && titles[ch.get_level() as usize + 1].exp <= ch.get_exp() {
+        send_to_char("You raise a level\n\r", ch);
+        ch.player.borrow_mut().level += 1;
+        advance_level(ch, game);
+    }
+
+    while ch.get_level() > 1 && ch.get_level() <= max_level &&
+            titles[ch.get_level() as usize].exp > ch.get_exp() {
+        send_to_char("You lose a level!\n\r", ch);
+        lose_level(ch);
+        ch.player.borrow_mut().level -= 1;
+    }
+
+    if ch.get_level() != start {
+        set_title(ch);
+        true
+    } else {
+        false
+    }
+}
+
+// Mortals gain at most 100000 and lose at most 500000 experience at a time,
+// and can't become immortal by gaining experience
+pub fn gain_exp(ch: &CharData, gain: i32, game: &Game) {
+    if !ch.is_npc() && (ch.get_level() >= 21 || ch.get_level() == 0) {
+        return;
+    }
+
+    let gain = if gain > 0 { min(100_000, gain) } else { max(-500_000, gain) };
+    let exp = max(0, ch.get_exp() + gain);
+    ch.points.borrow_mut().exp = exp;
+
+    if !ch.is_npc() {
+        check_level(ch, 20, game);
+    }
+}
+
+// Used by the gods, there are no limits to what can be given or taken away
+pub fn gain_exp_regardless(ch: &CharData, gain: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let exp = max(0, ch.get_exp().saturating_add(gain));
+    ch.points.borrow_mut().exp = exp;
+
+    check_level(ch, TITLES[0].len() as u8 - 1, game);
+}
+
+// The object has run out of time; tell whoever can see it and spill its
+// contents where the object itself was.
+fn obj_decay(j: &Rc<ObjData>, game: &Game) {
+    let (hands_msg, room_msg) = if j.is_corpse() {
+        ("$p decay in your hands.", "A quivering hoard of maggots consume $p.")
+    } else {
+        ("$p crumbles to dust in your hands.", "$p crumbles to dust.")
+    };
+
+    let carried_by = j.carried_by.borrow().clone();
+    let in_room = j.in_room.borrow().clone();
+    let in_obj = j.in_obj.borrow().clone();
+
+    if let Some(ref ch) = carried_by {
+        act(hands_msg, false, ch, Some(j), None, None, None, VictimType::ToChar);
+    } else if let Some(ref room) = in_room {
+        let first = room.people.borrow().first().cloned();
+        if let Some(ch) = first {
+            act(room_msg, true, &ch, Some(j), None, None, None, VictimType::ToRoom);
+            act(room_msg, true, &ch, Some(j), None, None, None, VictimType::ToChar);
+        }
+    }
+
+    let contents = j.contains.borrow().clone();
+    for jj in contents {
+        obj_from_obj(&jj);
+        if let Some(ref obj) = in_obj {
+            obj_to_obj(jj, obj);
+        } else if let Some(ref ch) = carried_by {
+            obj_to_char(jj, ch);
+        } else if let Some(ref room) = in_room {
+            obj_to_room(jj, room);
+        } else {
+            extract_obj(&jj, game);
+        }
+    }
+
+    extract_obj(j, game);
+}
+
+pub fn gain_condition(ch: &CharData, condition: Condition, value: i8) {
+    if ch.get_cond(condition) == -1 {
+        // No change
+        return;
+    }
+
+    let intoxicated = ch.get_cond(Condition::Drunk) > 0;
+
+    let cond = max(0, min(24, ch.get_cond(condition) as i16 + value as i16)) as i8;
+    ch.specials.borrow_mut().conditions[condition] = cond;
+
+    if cond != 0 {
+        return;
+    }
+
+    match condition {
+        Condition::Full => send_to_char("You are hungry.\n\r", ch),
+        Condition::Thirst => send_to_char("You are thirsty.\n\r", ch),
+        Condition::Drunk => if intoxicated {
+            send_to_char("You are now sober.\n\r", ch);
+        },
+    }
+}
+
+// Players that have not typed anything for a while are moved to the void,
+// and after a long while saved and thrown out of the game.
+fn check_idling(ch: &Rc<CharData>, game: &Game) {
+    let timer = {
+        let mut specials = ch.specials.borrow_mut();
+        specials.timer += 1;
+        specials.timer
+    };
+
+    if timer <= 8 {
+        return;
+    }
+
+    if ch.specials.borrow().was_in_room.is_none() {
+        ch.specials.borrow_mut().was_in_room = Some(ch.in_room.borrow().number);
+        let fighting = ch.specials.borrow().fighting.clone();
+        if let Some(victim) = fighting {
+            stop_fighting(&victim, game);
+            stop_fighting(ch, game);
+        }
+        act("$n disappears into the void.", true, ch, None, None, None, None, VictimType::ToRoom);
+        send_to_char("You have been idle, and are pulled into a void.\n\r", ch);
+        char_from_room(ch);
+        char_to_room(ch, &game.world[&VOID_ROOM]);
+    } else if timer > 48 {
+        let load_room = ch.specials.borrow().was_in_room.unwrap();
+        save_char(ch, load_room, game);
+        let desc = ch.desc.borrow().clone();
+        if let Some(desc) = desc {
+            close_socket(&desc, game);
+        }
+        extract_char(ch, game);
+    }
+}
+
+// Update PCs, NPCs, and objects
+pub fn point_update(game: &Game) {
+    // characters
+    let characters = game.character_list.borrow().clone();
+    for i in &characters {
+        if i.is_affected(AffectedFlags::AFF_POISON) && i.get_pos() > Position::Dead {
+            damage(Rc::clone(i), Rc::clone(i), 2, SPELL_POISON, game);
+        }
+
+        let position = i.get_pos();
+        if position >= Position::Stunned {
+            let hit = min(i.get_hit() + hit_gain(i), hit_limit(i));
+            let mana = min(i.get_mana() + mana_gain(i), mana_limit(i));
+            let mov = min(i.get_move() + move_gain(i), move_limit(i));
+            let mut points = i.points.borrow_mut();
+            points.hit = hit;
+            points.mana = mana;
+            points.mov = mov;
+        }
+
+        if position == Position::Stunned {
+            update_pos(i);
+        } else if position == Position::Incap {
+            damage(Rc::clone(i), Rc::clone(i), 1, TYPE_SUFFERING, game);
+        } else if !i.is_npc() && position == Position::MortallyW {
+            damage(Rc::clone(i), Rc::clone(i), 2, TYPE_SUFFERING, game);
+        }
+
+        if !i.is_npc() && i.get_pos() > Position::Dead && i.get_level() < 22 {
+            check_idling(i, game);
+        }
+
+        gain_condition(i, Condition::Full, -1);
+        gain_condition(i, Condition::Drunk, -1);
+        gain_condition(i, Condition::Thirst, -1);
+    }
+
+    // objects
+    let objects = game.object_list.borrow().clone();
+    for j in &objects {
+        let timer = j.obj_flags.borrow().timer;
+        if timer > 0 {
+            j.obj_flags.borrow_mut().timer = timer - 1;
+            if timer == 1 {
+                obj_decay(j, game);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::testutil::*;
+
+    #[test]
+    fn gain_test() {
+        let room = test_room(3001);
+        let ch = test_char("Resting", &room);
+
+        // (class, position, hit, mana, move) for a 17 year old with con 13
+        let cases = [
+            (Class::Warrior, Position::Standing, 5, 4, 18),
+            (Class::Warrior, Position::Sitting, 5, 5, 20),
+            (Class::Warrior, Position::Resting, 6, 6, 22),
+            (Class::Warrior, Position::Sleeping, 7, 8, 27),
+            (Class::MagicUser, Position::Standing, 2, 8, 18),
+            (Class::Cleric, Position::Sleeping, 3, 16, 27),
+            (Class::Thief, Position::Resting, 6, 6, 22),
+        ];
+
+        for &(class, position, hit, mana, mov) in cases.iter() {
+            ch.player.borrow_mut().class = class;
+            ch.specials.borrow_mut().position = position;
+            assert_eq!((hit_gain(&ch), mana_gain(&ch), move_gain(&ch)), (hit, mana, mov),
+                "class {} position {}", class as u8, position as u8);
+        }
+
+        // starving or poisoned chars regain a quarter
+        ch.player.borrow_mut().class = Class::Warrior;
+        ch.specials.borrow_mut().position = Position::Sleeping;
+        ch.specials.borrow_mut().conditions[Condition::Thirst] = 0;
+        assert_eq!((hit_gain(&ch), mana_gain(&ch), move_gain(&ch)), (1, 2, 6));
+        ch.specials.borrow_mut().affected_by.insert(AffectedFlags::AFF_POISON);
+        assert_eq!((hit_gain(&ch), mana_gain(&ch), move_gain(&ch)), (0, 0, 1));
+
+        // a good constitution heals faster
+        ch.specials.borrow_mut().conditions[Condition::Thirst] = 24;
+        ch.specials.borrow_mut().affected_by.remove(AffectedFlags::AFF_POISON);
+        ch.tmpabilities.borrow_mut().con = 18;
+        assert_eq!(hit_gain(&ch), 12);
+
+        let mob = test_mob("a mob", &room);
+        mob.player.borrow_mut().level = 12;
+        assert_eq!((hit_gain(&mob), mana_gain(&mob), move_gain(&mob)), (12, 12, 12));
+    }
+
+    #[test]
+    fn point_update_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Healing", &room);
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+        ch.player.borrow_mut().class = Class::Warrior;
+        ch.points.borrow_mut().hit = 10;
+
+        point_update(&game);
+        assert_eq!(ch.get_hit(), 15);
+        assert_eq!(ch.get_move(), 18);
+
+        // never above the limit
+        for _ in 0..10 {
+            ch.specials.borrow_mut().timer = 0;
+            point_update(&game);
+        }
+        assert_eq!(ch.get_hit(), hit_limit(&ch));
+        assert_eq!(ch.get_move(), move_limit(&ch));
+    }
+
+    #[test]
+    fn gain_exp_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let classes = [
+            (Class::MagicUser, 3, 8),
+            (Class::Cleric, 5, 10),
+            (Class::Thief, 7, 13),
+            (Class::Warrior, 10, 15),
+        ];
+
+        for &(class, hp_from, hp_to) in classes.iter() {
+            let (hp_from, hp_to) = (hp_from + CON_APP[13].hitp, hp_to + CON_APP[13].hitp);
+            let titles = &TITLES[class as usize - 1];
+            let ch = test_char("Adventurer", &room);
+            ch.player.borrow_mut().class = class;
+            ch.player.borrow_mut().sex = Sex::Female;
+            ch.points.borrow_mut().exp = 1;
+            set_title(&ch);
+            assert_eq!(ch.get_title(), titles[1].title_f);
 
-        if gain < 0 {
-            gain = max(-500_000, gain);
-            ch.points.borrow_mut().exp += gain;
-            if ch.get_exp() < 0 {
-                ch.points.borrow_mut().exp = 0;
+            // one level at a time
+            gain_exp(&ch, titles[2].exp - 1, &game);
+            assert_eq!(ch.get_level(), 2);
+            assert_eq!(ch.get_title(), titles[2].title_f);
+            let max_hit = ch.points.borrow().max_hit;
+            assert!(max_hit >= 20 + hp_from && max_hit <= 20 + hp_to, "{}", max_hit);
+            assert_eq!(output(&ch), vec!["You raise a level\n\r"]);
+
+            // several levels at once
+            gain_exp(&ch, titles[5].exp - ch.get_exp(), &game);
+            assert_eq!(ch.get_level(), 5);
+            assert_eq!(ch.get_title(), titles[5].title_f);
+            assert_eq!(output(&ch).len(), 3);
+            assert!(ch.points.borrow().max_hit >= 20 + 4 * hp_from);
+            // everybody gets two practices a level with wis 13
+            assert_eq!(ch.specials.borrow().spells_to_learn, 8);
+            if class == Class::MagicUser || class == Class::Cleric {
+                assert!(ch.points.borrow().max_mana > 0);
+                assert_eq!(mana_limit(&ch), 100 + ch.points.borrow().max_mana);
+            } else {
+                assert_eq!(ch.points.borrow().max_mana, 0);
             }
+
+            // and back down again
+            gain_exp(&ch, -(ch.get_exp() - titles[3].exp), &game);
+            assert_eq!(ch.get_level(), 3);
+            assert_eq!(ch.get_title(), titles[3].title_f);
+            assert_eq!(output(&ch), vec!["You lose a level!\n\r", "You lose a level!\n\r"]);
+            assert_eq!(ch.specials.borrow().spells_to_learn, 4);
+
+            // never more than 100000 at a time, and never immortal
+            ch.points.borrow_mut().exp = titles[20].exp - 1;
+            ch.player.borrow_mut().level = 19;
+            gain_exp(&ch, 500_000, &game);
+            assert_eq!(ch.get_exp(), titles[20].exp + 99_999);
+            assert_eq!(ch.get_level(), 20);
+            ch.points.borrow_mut().exp = titles[24].exp;
+            gain_exp(&ch, 1, &game);
+            assert_eq!(ch.get_level(), 20);
+
+            // but the gods may do as they please
+            output(&ch);
+            gain_exp_regardless(&ch, 0, &game);
+            assert_eq!(ch.get_level(), 24);
+            assert_eq!(ch.get_title(), titles[24].title_f);
+            assert_eq!(ch.get_cond(Condition::Full), -1);
+            gain_exp(&ch, -1_000_000, &game);
+            assert_eq!(ch.get_exp(), titles[24].exp + 1);
+            gain_exp_regardless(&ch, -titles[24].exp - 1, &game);
+            assert_eq!((ch.get_level(), ch.get_exp()), (1, 0));
+        }
+    }
+
+    #[test]
+    fn idle_test() {
+        let mut game = test_game();
+        game.world.insert(VOID_ROOM, test_room(VOID_ROOM));
+        let room = test_room(3001);
+        let ch = test_char("Idler", &room);
+        let watcher = test_char("Watcher", &room);
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        for _ in 0..8 {
+            point_update(&game);
         }
+        assert_eq!(ch.in_room.borrow().number, 3001);
+        assert!(output(&ch).is_empty());
 
-        if is_altered {
-            set_title(ch);
+        point_update(&game);
+        assert_eq!(ch.in_room.borrow().number, VOID_ROOM);
+        assert_eq!(ch.specials.borrow().was_in_room, Some(3001));
+        assert_eq!(output(&ch), vec!["You have been idle, and are pulled into a void.\n\r"]);
+        assert_eq!(output(&watcher), vec!["Idler disappears into the void."]);
+
+        // immortals are never pulled away
+        let god = test_char("God", &room);
+        god.player.borrow_mut().level = 22;
+        game.character_list.borrow_mut().push(Rc::clone(&god));
+        for _ in 0..10 {
+            point_update(&game);
         }
+        assert_eq!(god.in_room.borrow().number, 3001);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1255 @@
+use std::cmp::{max, min};
+use std::rc::Rc;
+
+use diku::comm::{act, send_to_char};
+use diku::constants::*;
+use diku::fight::{damage, update_pos};
+use diku::handler::{affect_from_char, affect_join, affect_remove, affect_to_char, affected_by_spell, char_from_room,
+    char_to_room, extract_obj, fname, isname, obj_to_room, weight_change_object};
+use diku::game::clear_object;
+use diku::limits::{gain_exp, hit_limit};
+use diku::spell_parser::{add_follower, stop_follower};
+use diku::spells::*;
+use diku::structs::*;
+use diku::utility::{circle_follow, sprintbit};
+use diku::weather::local_sky;
+
+// The signature shared by all spell_xxx functions. victim and obj are the
+// targets found by do_cast, or chosen by the potion, scroll, wand or staff
+pub type SpellFn = fn(u8, &Rc<CharData>, Option<&Rc<CharData>>, Option<&Rc<ObjData>>, &Game);
+
+// The number a d20 must beat for ch to save. Players start from the table
+// for their class and level, everyone adds the modifiers from affects and
+// equipment. Negative apply_saving_throw makes saving throw better!
+pub fn saving_throw(ch: &CharData, save_type: SavingThrowModifier) -> i16 {
+    let mut save = ch.specials.borrow().apply_saving_throw[save_type];
+
+    if !ch.is_npc() {
+        let level = min(ch.get_level() as usize, 20);
+        save += SAVING_THROWS[ch.get_class() as usize - 1][save_type as usize][level];
+    }
+
+    max(1, save)
+}
+
+pub fn saves_spell(ch: &CharData, save_type: SavingThrowModifier, game: &Game) -> bool {
+    if !ch.is_npc() && ch.get_level() > 20 {
+        return true;
+    }
+
+    saving_throw(ch, save_type) < game.number(1, 20) as i16
+}
+
+// Called once every mud hour. Counts down the spells on everybody and takes
+// off those that have run out, telling the char when the last one of a
+// spell goes.
+pub fn affect_update(game: &Game) {
+    let characters = game.character_list.borrow().clone();
+    for i in &characters {
+        let mut expired = Vec::new();
+        {
+            let mut affected = i.affected.borrow_mut();
+            let durations: Vec<(i32, i16)> = affected.iter()
+                .map(|af| (af.spell_type, af.duration)).collect();
+
+            for (n, af) in affected.iter_mut().enumerate() {
+                if af.duration >= 1 {
+                    af.duration -= 1;
+                } else if af.duration == -1 {
+                    // No action, GODs only! unlimited
+                } else {
+                    let last = match durations.get(n + 1) {
+                        Some(&(next_type, next_duration)) =>
+                            next_type != af.spell_type || next_duration > 0,
+                        None => true,
+                    };
+                    expired.push((af.clone(), last));
+                }
+            }
+        }
+
+        for (af, last) in expired {
+            if last && af.spell_type > 0 && (af.spell_type as usize) < SPELL_WEAR_OFF_MSG.len() {
+                let msg = SPELL_WEAR_OFF_MSG[af.spell_type as usize];
+                if !msg.is_empty() {
+                    send_to_char(&format!("{}\n\r", msg), i);
+                }
+            }
+            affect_remove(i, &af);
+        }
+    }
+}
+
+fn new_affect(spell_type: i32, duration: i16, modifier: i8, location: AbilityModifier,
+        bitvector: AffectedFlags) -> AffectedType {
+    AffectedType {
+        spell_type,
+        duration,
+        modifier,
+        location,
+        bitvector,
+    }
+}
+
+// Damage between half and double dam_each for the casters level
+fn damage_by_level(dam_each: &[i16], level: u8, game: &Game) -> i16 {
+    let each = dam_each[min(level as usize, dam_each.len() - 1)] as i32;
+    game.number(each >> 1, each << 1) as i16
+}
+
+// Offensive Spells
+
+pub fn spell_magic_missile(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 7] = [0, 3, 3, 4, 4, 5, 6];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_MAGIC_MISSILE, game);
+}
+
+pub fn spell_chill_touch(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 9] = [0, 0, 0, 8, 7, 9, 9, 12, 13];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if !saves_spell(victim, SavingThrowModifier::Spell, game) {
+        affect_join(victim, &new_affect(SPELL_CHILL_TOUCH, 6, -1, AbilityModifier::Str,
+            AffectedFlags::empty()), true, false);
+    } else {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_CHILL_TOUCH, game);
+}
+
+pub fn spell_burning_hands(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 9] = [0, 0, 0, 0, 0, 19, 17, 20, 19];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_BURNING_HANDS, game);
+}
+
+pub fn spell_shocking_grasp(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 12] = [0, 0, 0, 0, 0, 0, 0, 41, 33, 29, 27, 25];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_SHOCKING_GRASP, game);
+}
+
+pub fn spell_lightning_bolt(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 15] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 59, 46, 39, 35, 38, 36];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_LIGHTNING_BOLT, game);
+}
+
+pub fn spell_colour_spray(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 17] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 79, 60, 57, 51, 47, 44];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_COLOUR_SPRAY, game);
+}
+
+pub fn spell_fireball(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    const DAM_EACH: [i16; 21] = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        143, 122, 115, 107, 103, 100];
+    let victim = victim.unwrap();
+
+    let mut dam = damage_by_level(&DAM_EACH, level, game);
+    if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam >>= 1;
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_FIREBALL, game);
+}
+
+pub fn spell_energy_drain(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if !saves_spell(victim, SavingThrowModifier::Spell, game) {
+        {
+            let mut specials = ch.specials.borrow_mut();
+            specials.alignment = max(-1000, specials.alignment - 200);
+        }
+        if victim.get_level() <= 2 {
+            damage(Rc::clone(ch), Rc::clone(victim), 100, SPELL_ENERGY_DRAIN, game); // Kill the sucker
+        } else {
+            let xp = game.number(level as i32 >> 1, level as i32) * 1000;
+            gain_exp(victim, -xp, game);
+
+            let dam = game.dice(1, 10) as i16;
+            {
+                let mut points = victim.points.borrow_mut();
+                points.mana >>= 1;
+                points.mov >>= 1;
+            }
+            ch.points.borrow_mut().hit += dam;
+
+            send_to_char("Your life energy is drained!\n\r", victim);
+            damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_ENERGY_DRAIN, game);
+        }
+    } else {
+        damage(Rc::clone(ch), Rc::clone(victim), 0, SPELL_ENERGY_DRAIN, game); // Miss
+    }
+}
+
+pub fn spell_earthquake(level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let dam = game.dice(1, 8) as i16 + level as i16;
+
+    send_to_char("The earth trembles beneath your feet!\n\r", ch);
+    act("$n makes the earth tremble and shiver\n\rYou fall, and hit yourself!",
+        false, ch, None, None, None, None, VictimType::ToRoom);
+
+    let room = Rc::clone(&ch.in_room.borrow());
+    let people = room.people.borrow().clone();
+    for tmp_victim in people.iter().filter(|v| !Rc::ptr_eq(v, ch)) {
+        if tmp_victim.get_pos() > Position::Dead {
+            damage(Rc::clone(ch), Rc::clone(tmp_victim), dam, SPELL_EARTHQUAKE, game);
+        }
+    }
+
+    for tmp_victim in game.character_list.borrow().iter() {
+        let in_room = tmp_victim.in_room.borrow();
+        if !Rc::ptr_eq(&in_room, &room) && in_room.zone == room.zone {
+            send_to_char("The earth trembles and shivers.\n\r", tmp_victim);
+        }
+    }
+}
+
+pub fn spell_dispel_evil(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = if ch.is_evil() {
+        ch
+    } else {
+        victim.unwrap()
+    };
+
+    if !Rc::ptr_eq(victim, ch) && victim.is_good() {
+        act("God protects $N.", false, ch, None, Some(victim), None, None, VictimType::ToChar);
+        return;
+    }
+
+    let dam = if victim.get_level() < level || Rc::ptr_eq(victim, ch) {
+        100
+    } else {
+        let dam = game.dice(level as u32, 4) as i16;
+        if saves_spell(victim, SavingThrowModifier::Spell, game) {
+            dam >> 1
+        } else {
+            dam
+        }
+    };
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_DISPEL_EVIL, game);
+}
+
+pub fn spell_call_lightning(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+    let mut dam = game.dice(min(level, 15) as u32, 8) as i16;
+
+    if ch.outside() && local_sky(&ch.in_room.borrow(), game) >= Sky::Raining {
+        if saves_spell(victim, SavingThrowModifier::Spell, game) {
+            dam >>= 1;
+        }
+        damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_CALL_LIGHTNING, game);
+    }
+}
+
+pub fn spell_harm(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    let mut dam = victim.get_hit() - game.dice(1, 4) as i16;
+    if dam < 0 {
+        dam = 0; // Kill the suffering bastard
+    } else if saves_spell(victim, SavingThrowModifier::Spell, game) {
+        dam = min(50, dam / 2);
+    }
+
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_HARM, game);
+}
+
+// Breath weapons hit for up to a quarter of the dragons hit points
+fn breath_damage(ch: &CharData, victim: &CharData, game: &Game) -> i16 {
+    let hpch = max(10, ch.get_hit() as i32);
+    let dam = game.number(0, hpch >> 2) as i16;
+
+    if saves_spell(victim, SavingThrowModifier::Breath, game) {
+        dam >> 1
+    } else {
+        dam
+    }
+}
+
+// Destroys one of the items the victim carries that fragile accepts
+fn breath_destroy(ch: &CharData, victim: &Rc<CharData>, fragile: &[ItemType], message: &str,
+        game: &Game) {
+    if game.number(0, 21) < ch.get_level() as i32 &&
+    !saves_spell(victim, SavingThrowModifier::Breath, game) {
+        let burn = victim.carrying.borrow().iter()
+            .find(|obj| fragile.contains(&obj.get_item_type()) && game.number(0, 2) == 0)
+            .cloned();
+        if let Some(burn) = burn {
+            act(message, false, victim, Some(&burn), None, None, None, VictimType::ToChar);
+            extract_obj(&burn, game);
+        }
+    }
+}
+
+pub fn spell_fire_breath(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    let dam = breath_damage(ch, victim, game);
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_FIRE_BREATH, game);
+
+    // And now for the damage on inventory
+    if victim.get_pos() > Position::Dead {
+        breath_destroy(ch, victim, &[ItemType::Scroll, ItemType::Wand, ItemType::Staff,
+            ItemType::Note], "$o burns.", game);
+    }
+}
+
+pub fn spell_frost_breath(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    let dam = breath_damage(ch, victim, game);
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_FROST_BREATH, game);
+
+    // And now for the damage on inventory
+    if victim.get_pos() > Position::Dead {
+        breath_destroy(ch, victim, &[ItemType::DrinkCon, ItemType::Food, ItemType::Potion],
+            "$o shatters.", game);
+    }
+}
+
+pub fn spell_acid_breath(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    let dam = breath_damage(ch, victim, game);
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_ACID_BREATH, game);
+}
+
+pub fn spell_gas_breath(_level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let people = ch.in_room.borrow().people.borrow().clone();
+
+    for victim in people.iter().filter(|v| !Rc::ptr_eq(v, ch)) {
+        if victim.get_pos() > Position::Dead {
+            let dam = breath_damage(ch, victim, game);
+            damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_GAS_BREATH, game);
+        }
+    }
+}
+
+pub fn spell_lightning_breath(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    let dam = breath_damage(ch, victim, game);
+    damage(Rc::clone(ch), Rc::clone(victim), dam, SPELL_LIGHTNING_BREATH, game);
+}
+
+// Healing Spells
+
+pub fn spell_cure_blind(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    if affected_by_spell(victim, SPELL_BLINDNESS) {
+        affect_from_char(victim, SPELL_BLINDNESS);
+
+        send_to_char("Your vision returns!\n\r", victim);
+    }
+}
+
+fn heal(victim: &CharData, healpoints: i16) {
+    {
+        let mut points = victim.points.borrow_mut();
+        points.hit = min(hit_limit(victim), points.hit + healpoints);
+    }
+
+    send_to_char("You feel better!\n\r", victim);
+
+    update_pos(victim);
+}
+
+pub fn spell_cure_critic(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    heal(victim.unwrap(), game.dice(3, 8) as i16 + 3);
+}
+
+pub fn spell_cure_light(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    heal(victim.unwrap(), game.dice(1, 8) as i16);
+}
+
+pub fn spell_heal(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, game: &Game) {
+    spell_cure_blind(level, ch, victim, obj, game);
+
+    let victim = victim.unwrap();
+    {
+        let limit = hit_limit(victim);
+        let mut points = victim.points.borrow_mut();
+        points.hit += 100;
+        if points.hit >= limit {
+            points.hit = limit - game.dice(1, 4) as i16;
+        }
+    }
+
+    update_pos(victim);
+
+    send_to_char("A warm feeling fills your body.\n\r", victim);
+}
+
+pub fn spell_remove_poison(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(victim) = victim {
+        if affected_by_spell(victim, SPELL_POISON) {
+            affect_from_char(victim, SPELL_POISON);
+            act("A warm feeling runs through your body!", false, victim, None, None, None, None,
+                VictimType::ToChar);
+            act("$N looks better.", false, ch, None, Some(victim), None, None, VictimType::ToRoom);
+        }
+    } else {
+        let obj = obj.unwrap();
+        let item_type = obj.get_item_type();
+        if item_type == ItemType::DrinkCon || item_type == ItemType::Food {
+            obj.obj_flags.borrow_mut().value[3] = 0;
+            act("The $p steams briefly.", false, ch, Some(obj), None, None, None,
+                VictimType::ToChar);
+        }
+    }
+}
+
+// Protective and detection spells
+
+pub fn spell_armor(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    if !affected_by_spell(victim, SPELL_ARMOR) {
+        affect_to_char(victim, &new_affect(SPELL_ARMOR, 24, -20, AbilityModifier::Ac,
+            AffectedFlags::empty()));
+        send_to_char("You feel someone protecting you.\n\r", victim);
+    }
+}
+
+pub fn spell_bless(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(obj) = obj {
+        if 5 * ch.get_level() as i32 > obj.get_obj_weight() && ch.get_pos() != Position::Fighting &&
+        !obj.is_obj_state(ItemExtraFlags::ITEM_EVIL) {
+            obj.obj_flags.borrow_mut().extra_flags.insert(ItemExtraFlags::ITEM_BLESS);
+            act("$p briefly glows.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+        }
+    } else {
+        let victim = victim.unwrap();
+        if victim.get_pos() != Position::Fighting && !affected_by_spell(victim, SPELL_BLESS) {
+            send_to_char("You feel righteous.\n\r", victim);
+            affect_to_char(victim, &new_affect(SPELL_BLESS, 6, 1, AbilityModifier::Hitroll,
+                AffectedFlags::empty()));
+            // Make better
+            affect_to_char(victim, &new_affect(SPELL_BLESS, 6, -1, AbilityModifier::SavingSpell,
+                AffectedFlags::empty()));
+        }
+    }
+}
+
+pub fn spell_strength(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    act("You feel stronger.", false, victim, None, None, None, None, VictimType::ToChar);
+
+    let modifier = if level > 18 { 2 } else { 1 };
+    affect_join(victim, &new_affect(SPELL_STRENGTH, level as i16, modifier,
+        AbilityModifier::Str, AffectedFlags::empty()), true, true);
+}
+
+pub fn spell_sanctuary(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    if !affected_by_spell(victim, SPELL_SANCTUARY) {
+        act("$n is surrounded by a white aura.", true, victim, None, None, None, None,
+            VictimType::ToRoom);
+        act("You start glowing.", true, victim, None, None, None, None, VictimType::ToChar);
+
+        let duration = if level < 21 { 3 } else { level as i16 };
+        affect_to_char(victim, &new_affect(SPELL_SANCTUARY, duration, 0, AbilityModifier::None,
+            AffectedFlags::AFF_SANCTUARY));
+    }
+}
+
+pub fn spell_protection_from_evil(_level: u8, _ch: &Rc<CharData>,
+        victim: Option<&Rc<CharData>>, _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    if !affected_by_spell(victim, SPELL_PROTECT_FROM_EVIL) {
+        affect_to_char(victim, &new_affect(SPELL_PROTECT_FROM_EVIL, 24, 0, AbilityModifier::None,
+            AffectedFlags::AFF_PROTECT_EVIL));
+        send_to_char("You have a righteous feeling!\n\r", victim);
+    }
+}
+
+pub fn spell_invisibility(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(obj) = obj {
+        if !obj.is_obj_state(ItemExtraFlags::ITEM_INVISIBLE) {
+            act("$p turns invisible.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+            act("$p turns invisible.", true, ch, Some(obj), None, None, None, VictimType::ToRoom);
+            obj.obj_flags.borrow_mut().extra_flags.insert(ItemExtraFlags::ITEM_INVISIBLE);
+        }
+    } else { // Then it is a PC | NPC
+        let victim = victim.unwrap();
+        if !affected_by_spell(victim, SPELL_INVISIBLE) {
+            act("$n slowly fade out of existence.", true, victim, None, None, None, None,
+                VictimType::ToRoom);
+            send_to_char("You vanish.\n\r", victim);
+
+            affect_to_char(victim, &new_affect(SPELL_INVISIBLE, 24, -40, AbilityModifier::Ac,
+                AffectedFlags::AFF_INVISIBLE));
+        }
+    }
+}
+
+fn detect(spell_type: i32, duration: i16, bitvector: AffectedFlags, victim: &CharData) {
+    if affected_by_spell(victim, spell_type) {
+        return;
+    }
+
+    affect_to_char(victim, &new_affect(spell_type, duration, 0, AbilityModifier::None, bitvector));
+
+    send_to_char("Your eyes tingle.\n\r", victim);
+}
+
+pub fn spell_detect_evil(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    detect(SPELL_DETECT_EVIL, level as i16 * 5, AffectedFlags::AFF_DETECT_EVIL, victim.unwrap());
+}
+
+pub fn spell_detect_invisibility(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    detect(SPELL_DETECT_INVISIBLE, level as i16 * 5, AffectedFlags::AFF_DETECT_INVISIBLE,
+        victim.unwrap());
+}
+
+pub fn spell_detect_magic(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    detect(SPELL_DETECT_MAGIC, level as i16 * 5, AffectedFlags::AFF_DETECT_MAGIC, victim.unwrap());
+}
+
+pub fn spell_detect_poison(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(victim) = victim {
+        if Rc::ptr_eq(victim, ch) {
+            if victim.is_affected(AffectedFlags::AFF_POISON) {
+                send_to_char("You can sense poison in your blood.\n\r", ch);
+            } else {
+                send_to_char("You feel healthy.\n\r", ch);
+            }
+        } else if victim.is_affected(AffectedFlags::AFF_POISON) {
+            act("You sense that $E is poisoned.", false, ch, None, Some(victim), None, None,
+                VictimType::ToChar);
+        } else {
+            act("You sense that $E is healthy.", false, ch, None, Some(victim), None, None,
+                VictimType::ToChar);
+        }
+    } else { // It's an object
+        let obj = obj.unwrap();
+        let item_type = obj.get_item_type();
+        if item_type == ItemType::DrinkCon || item_type == ItemType::Food {
+            if obj.obj_flags.borrow().value[3] != 0 {
+                act("Poisonous fumes are revealed.", false, ch, None, None, None, None,
+                    VictimType::ToChar);
+            } else {
+                send_to_char("It looks very delicious.\n\r", ch);
+            }
+        }
+    }
+}
+
+pub fn spell_sense_life(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let victim = victim.unwrap();
+
+    if !affected_by_spell(victim, SPELL_SENSE_LIFE) {
+        send_to_char("Your feel your awareness improve.\n\r", ch);
+
+        affect_to_char(victim, &new_affect(SPELL_SENSE_LIFE, 5 * level as i16, 0,
+            AbilityModifier::None, AffectedFlags::AFF_SENSE_LIFE));
+    }
+}
+
+// Spells against the victim, which the victim may save against
+
+pub fn spell_blindness(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if saves_spell(victim, SavingThrowModifier::Spell, game) || affected_by_spell(victim, SPELL_BLINDNESS) {
+        return;
+    }
+
+    act("$n seems to be blinded!", true, victim, None, None, None, None, VictimType::ToRoom);
+    send_to_char("You have been blinded!\n\r", victim);
+
+    affect_to_char(victim, &new_affect(SPELL_BLINDNESS, 1, -4, AbilityModifier::Hitroll,
+        AffectedFlags::AFF_BLIND));
+    affect_to_char(victim, &new_affect(SPELL_BLINDNESS, 1, 40, AbilityModifier::Ac,
+        AffectedFlags::AFF_BLIND));
+}
+
+pub fn spell_curse(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, game: &Game) {
+    if let Some(obj) = obj {
+        {
+            let mut obj_flags = obj.obj_flags.borrow_mut();
+            obj_flags.extra_flags.insert(ItemExtraFlags::ITEM_EVIL | ItemExtraFlags::ITEM_NODROP);
+
+            // LOWER ATTACK DICE BY -1
+            if obj_flags.type_flag == ItemType::Weapon {
+                obj_flags.value[2] -= 1;
+            }
+        }
+        act("$p glows red.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+    } else {
+        let victim = victim.unwrap();
+        if saves_spell(victim, SavingThrowModifier::Spell, game) || affected_by_spell(victim, SPELL_CURSE) {
+            return;
+        }
+
+        affect_to_char(victim, &new_affect(SPELL_CURSE, 24 * 7, -1, AbilityModifier::Hitroll,
+            AffectedFlags::AFF_CURSE)); // 7 Days
+        // Make worse
+        affect_to_char(victim, &new_affect(SPELL_CURSE, 24 * 7, 1, AbilityModifier::SavingPara,
+            AffectedFlags::AFF_CURSE));
+
+        act("$n briefly reveal a red aura!", false, victim, None, None, None, None,
+            VictimType::ToRoom);
+        act("You feel very uncomfortable.", false, victim, None, None, None, None,
+            VictimType::ToChar);
+    }
+}
+
+pub fn spell_remove_curse(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(obj) = obj {
+        if obj.is_obj_state(ItemExtraFlags::ITEM_EVIL) || obj.is_obj_state(ItemExtraFlags::ITEM_NODROP) {
+            act("$p briefly glows blue.", true, ch, Some(obj), None, None, None, VictimType::ToChar);
+
+            obj.obj_flags.borrow_mut().extra_flags
+                .remove(ItemExtraFlags::ITEM_EVIL | ItemExtraFlags::ITEM_NODROP);
+        }
+    } else { // Then it is a PC | NPC
+        let victim = victim.unwrap();
+        if affected_by_spell(victim, SPELL_CURSE) {
+            act("$n briefly glows red, then blue.", false, victim, None, None, None, None,
+                VictimType::ToRoom);
+            act("You feel better.", false, victim, None, None, None, None, VictimType::ToChar);
+            affect_from_char(victim, SPELL_CURSE);
+        }
+    }
+}
+
+pub fn spell_poison(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, game: &Game) {
+    if let Some(victim) = victim {
+        if !saves_spell(victim, SavingThrowModifier::Para, game) {
+            affect_join(victim, &new_affect(SPELL_POISON, level as i16 * 2, -2,
+                AbilityModifier::Str, AffectedFlags::AFF_POISON), false, false);
+            send_to_char("You feel very sick.\n\r", victim);
+        }
+    } else { // Object poison
+        let obj = obj.unwrap();
+        let item_type = obj.get_item_type();
+        if item_type == ItemType::DrinkCon || item_type == ItemType::Food {
+            obj.obj_flags.borrow_mut().value[3] = 1;
+        }
+    }
+}
+
+pub fn spell_sleep(level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if !saves_spell(victim, SavingThrowModifier::Spell, game) {
+        affect_join(victim, &new_affect(SPELL_SLEEP, 4 + level as i16, 0,
+            AbilityModifier::None, AffectedFlags::AFF_SLEEP), false, false);
+
+        if victim.get_pos() > Position::Sleeping {
+            act("You feel very sleepy ..... zzzzzz", false, victim, None, None, None, None,
+                VictimType::ToChar);
+            act("$n go to sleep.", true, victim, None, None, None, None, VictimType::ToRoom);
+            victim.specials.borrow_mut().position = Position::Sleeping;
+        }
+    }
+}
+
+pub fn spell_charm_person(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if Rc::ptr_eq(victim, ch) {
+        send_to_char("You like yourself even better!\n\r", ch);
+        return;
+    }
+
+    if !victim.is_affected(AffectedFlags::AFF_CHARM) && !ch.is_affected(AffectedFlags::AFF_CHARM) &&
+    level >= victim.get_level() {
+        if circle_follow(victim, ch) {
+            send_to_char("Sorry, following in circles can not be allowed.\n\r", ch);
+            return;
+        }
+
+        if saves_spell(victim, SavingThrowModifier::Para, game) {
+            return;
+        }
+
+        if victim.master.borrow().is_some() {
+            stop_follower(victim);
+        }
+
+        add_follower(victim, ch);
+
+        let duration = match victim.get_int() {
+            0 => 24 * 18,
+            int => 24 * 18 / int as i16,
+        };
+        affect_to_char(victim, &new_affect(SPELL_CHARM_PERSON, duration, 0, AbilityModifier::None,
+            AffectedFlags::AFF_CHARM));
+
+        act("Isn't $n just such a nice fellow?", false, ch, None, Some(victim), None, None,
+            VictimType::ToVict);
+    }
+}
+
+// Transportation spells
+
+pub fn spell_teleport(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let ch = victim.unwrap_or(ch);
+
+    let mut rooms: Vec<&u32> = game.world.iter()
+        .filter(|&(_, room)| !room.room_flags.contains(RoomFlags::PRIVATE))
+        .map(|(nr, _)| nr)
+        .collect();
+    if rooms.is_empty() {
+        return;
+    }
+    rooms.sort();
+    let to_room = &game.world[rooms[game.number(0, rooms.len() as i32 - 1) as usize]];
+
+    act("$n slowly fade out of existence.", false, ch, None, None, None, None, VictimType::ToRoom);
+    char_from_room(ch);
+    char_to_room(ch, to_room);
+    act("$n slowly fade in to existence.", false, ch, None, None, None, None, VictimType::ToRoom);
+}
+
+pub fn spell_word_of_recall(_level: u8, _ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if victim.is_npc() {
+        return;
+    }
+
+    // loc_nr = GET_HOME(ch);
+    let location = match game.world.get(&3001) {
+        Some(location) => location,
+        None => {
+            send_to_char("You are completely lost.\n\r", victim);
+            return;
+        },
+    };
+
+    // a location has been found.
+    act("$n disappears.", true, victim, None, None, None, None, VictimType::ToRoom);
+    char_from_room(victim);
+    char_to_room(victim, location);
+    act("$n appears in the middle of the room.", true, victim, None, None, None, None,
+        VictimType::ToRoom);
+}
+
+pub fn spell_summon(level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let victim = victim.unwrap();
+
+    if victim.get_level() > min(20, level + 3) {
+        send_to_char("You failed.\n\r", ch);
+        return;
+    }
+
+    if victim.is_npc() && saves_spell(victim, SavingThrowModifier::Spell, game) {
+        send_to_char("You failed.\n\r", ch);
+        return;
+    }
+
+    act("$n disappears suddenly.", true, victim, None, None, None, None, VictimType::ToRoom);
+
+    let target = Rc::clone(&ch.in_room.borrow());
+    char_from_room(victim);
+    char_to_room(victim, &target);
+
+    act("$n arrives suddenly.", true, victim, None, None, None, None, VictimType::ToRoom);
+    act("$n has summoned you!", false, ch, None, Some(victim), None, None, VictimType::ToVict);
+}
+
+// Object spells
+
+pub fn spell_locate_object(level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, game: &Game) {
+    let name = fname(&obj.unwrap().name);
+    let mut j = level >> 1;
+
+    for i in game.object_list.borrow().iter() {
+        if j == 0 {
+            break;
+        }
+        if !isname(name, &i.name) {
+            continue;
+        }
+
+        let buf = if let Some(ref carried_by) = *i.carried_by.borrow() {
+            format!("{} carried by {}.\n\r", i.short_description, ch.pers(carried_by))
+        } else if let Some(ref in_obj) = *i.in_obj.borrow() {
+            format!("{} in {}.\n\r", i.short_description, in_obj.short_description)
+        } else if let Some(ref in_room) = *i.in_room.borrow() {
+            format!("{} in {}.\n\r", i.short_description, in_room.name)
+        } else {
+            format!("{} in use but uncertain.\n\r", i.short_description)
+        };
+        send_to_char(&buf, ch);
+        j -= 1;
+    }
+
+    if j == 0 {
+        send_to_char("You are very confused.\n\r", ch);
+    }
+    if j == level >> 1 {
+        send_to_char("No such object.\n\r", ch);
+    }
+}
+
+pub fn spell_enchant_weapon(level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    let obj = obj.unwrap();
+
+    if obj.get_item_type() != ItemType::Weapon || obj.is_obj_state(ItemExtraFlags::ITEM_MAGIC) {
+        return;
+    }
+
+    {
+        let mut affected = obj.affected.borrow_mut();
+        if affected.iter().any(|af| af.location != AbilityModifier::None as u8) {
+            return;
+        }
+
+        let modifier = if level > 20 { 2 } else { 1 };
+        affected[0].location = AbilityModifier::Hitroll as u8;
+        affected[0].modifier = modifier;
+        affected[1].location = AbilityModifier::Damroll as u8;
+        affected[1].modifier = modifier;
+    }
+
+    obj.obj_flags.borrow_mut().extra_flags.insert(ItemExtraFlags::ITEM_MAGIC);
+
+    if ch.is_good() {
+        obj.obj_flags.borrow_mut().extra_flags.insert(ItemExtraFlags::ITEM_ANTI_EVIL);
+        act("$p glows blue.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+    } else if ch.is_evil() {
+        obj.obj_flags.borrow_mut().extra_flags.insert(ItemExtraFlags::ITEM_ANTI_GOOD);
+        act("$p glows red.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+    } else {
+        act("$p glows yellow.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+    }
+}
+
+pub fn spell_identify(_level: u8, ch: &Rc<CharData>, victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, _game: &Game) {
+    if let Some(obj) = obj {
+        send_to_char("You feel informed:\n\r", ch);
+
+        let obj_flags = obj.obj_flags.borrow();
+        send_to_char(&format!("Object '{}', Item type: {}\n\r", obj.name,
+            ITEM_TYPES[obj_flags.type_flag as usize]), ch);
+
+        if obj_flags.bitvector != 0 {
+            send_to_char("Item will give you following abilities:  ", ch);
+            send_to_char(&format!("{}\n\r", sprintbit(obj_flags.bitvector, &AFFECTED_BITS)), ch);
+        }
+
+        send_to_char("Item is: ", ch);
+        send_to_char(&format!("{}\n\r", sprintbit(obj_flags.extra_flags.bits() as u64,
+            &EXTRA_BITS)), ch);
+
+        send_to_char(&format!("Weight: {}, Value: {}\n\r", obj_flags.weight, obj_flags.cost), ch);
+
+        let spell_name = |nr: i32| if nr >= 1 && nr as usize <= SPELLS.len() {
+            format!("{}\n\r", SPELLS[nr as usize - 1])
+        } else {
+            String::new()
+        };
+
+        let value = obj_flags.value;
+        match obj_flags.type_flag {
+            ItemType::Scroll | ItemType::Potion => {
+                send_to_char(&format!("Level {} spells of:\n\r", value[0]), ch);
+                for &nr in &value[1..4] {
+                    send_to_char(&spell_name(nr), ch);
+                }
+            },
+            ItemType::Wand | ItemType::Staff => {
+                send_to_char(&format!("Has {} charges, with {} charges left.\n\r",
+                    value[1], value[2]), ch);
+                send_to_char(&format!("Level {} spell of:\n\r", value[0]), ch);
+                send_to_char(&spell_name(value[3]), ch);
+            },
+            ItemType::Weapon =>
+                send_to_char(&format!("Damage Dice is '{}D{}'\n\r", value[1], value[2]), ch),
+            ItemType::Armor =>
+                send_to_char(&format!("AC-apply is {}\n\r", value[0]), ch),
+            _ => (),
+        }
+
+        let mut found = false;
+        for af in obj.affected.borrow().iter() {
+            if af.location != AbilityModifier::None as u8 && af.modifier != 0 {
+                if !found {
+                    send_to_char("Can affect you as :\n\r", ch);
+                    found = true;
+                }
+
+                send_to_char(&format!("    Affects : {} By {}\n\r",
+                    APPLY_TYPES.get(af.location as usize).unwrap_or(&"UNDEFINED"), af.modifier), ch);
+            }
+        }
+    } else { // victim
+        let victim = victim.unwrap();
+        if !victim.is_npc() {
+            let age = victim.age();
+            send_to_char(&format!("{} Years,  {} Months,  {} Days,  {} Hours old.\n\r",
+                age.year, age.month, age.day, age.hours), ch);
+
+            send_to_char(&format!("Height {}cm  Weight {}pounds \n\r",
+                victim.get_height(), victim.get_weight()), ch);
+
+            send_to_char(&format!("Str {}/{},  Int {},  Wis {},  Dex {},  Con {}\n\r",
+                victim.get_str(), victim.get_add(), victim.get_int(), victim.get_wis(),
+                victim.get_dex(), victim.get_con()), ch);
+        } else {
+            send_to_char("You learn nothing new.\n\r", ch);
+        }
+    }
+}
+
+// Creation spells
+
+pub fn spell_create_food(level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        _obj: Option<&Rc<ObjData>>, game: &Game) {
+    let mut tmp_obj = clear_object();
+    tmp_obj.name = String::from("mushroom");
+    tmp_obj.short_description = String::from("A Magic Mushroom");
+    tmp_obj.description = String::from("A really delicious looking magic mushroom lies here.");
+    {
+        let mut obj_flags = tmp_obj.obj_flags.borrow_mut();
+        obj_flags.type_flag = ItemType::Food;
+        obj_flags.wear_flags = WearFlags::ITEM_TAKE;
+        obj_flags.value[0] = 5 + level as i32;
+        obj_flags.weight = 1;
+        obj_flags.cost = 10;
+        obj_flags.cost_per_day = 1;
+    }
+
+    let tmp_obj = Rc::new(tmp_obj);
+    game.object_list.borrow_mut().push(Rc::clone(&tmp_obj));
+    obj_to_room(Rc::clone(&tmp_obj), &ch.in_room.borrow());
+
+    act("$p suddenly appears.", true, ch, Some(&tmp_obj), None, None, None, VictimType::ToRoom);
+    act("$p suddenly appears.", true, ch, Some(&tmp_obj), None, None, None, VictimType::ToChar);
+}
+
+pub fn spell_create_water(level: u8, ch: &Rc<CharData>, _victim: Option<&Rc<CharData>>,
+        obj: Option<&Rc<ObjData>>, game: &Game) {
+    let obj = obj.unwrap();
+
+    if obj.get_item_type() != ItemType::DrinkCon {
+        return;
+    }
+
+    let value = obj.obj_flags.borrow().value;
+    if value[2] != Liquid::Water as i32 && value[1] != 0 {
+        obj.obj_flags.borrow_mut().value[2] = Liquid::Slime as i32;
+    } else {
+        let water = 2 * level as i32 * if local_sky(&ch.in_room.borrow(), game) >= Sky::Raining { 2 } else { 1 };
+
+        // Calculate water it can contain, or water created
+        let water = min(value[0] - value[1], water);
+
+        if water > 0 {
+            {
+                let mut obj_flags = obj.obj_flags.borrow_mut();
+                obj_flags.value[2] = Liquid::Water as i32;
+                obj_flags.value[1] += water;
+            }
+            weight_change_object(obj, water);
+            act("$p is filled.", false, ch, Some(obj), None, None, None, VictimType::ToChar);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::spell_parser::do_cast;
+    use diku::testutil::*;
+
+    fn caster(name: &str, class: Class, spell: i32, room: &Rc<RoomData>) -> Rc<CharData> {
+        let ch = test_char(name, room);
+        ch.player.borrow_mut().class = class;
+        ch.player.borrow_mut().level = 20;
+        ch.points.borrow_mut().mana = 100;
+        ch.skills.borrow_mut()[spell as usize].learned = 100;
+        ch
+    }
+
+    #[test]
+    fn saving_throw_test() {
+        let room = test_room(3001);
+        let ch = test_char("Saver", &room);
+        let mob = test_mob("a mob", &room);
+
+        let cases = [
+            (Class::MagicUser, 1, SavingThrowModifier::Para, 0, 14),
+            (Class::MagicUser, 20, SavingThrowModifier::Rod, 0, 5),
+            (Class::Cleric, 1, SavingThrowModifier::Para, 0, 10),
+            (Class::Cleric, 19, SavingThrowModifier::Para, 0, 2),
+            (Class::Thief, 5, SavingThrowModifier::Petri, 0, 11),
+            (Class::Thief, 10, SavingThrowModifier::Breath, 0, 14),
+            (Class::Warrior, 1, SavingThrowModifier::Breath, 0, 17),
+            (Class::Warrior, 13, SavingThrowModifier::Spell, 0, 7),
+            (Class::Warrior, 1, SavingThrowModifier::Spell, -1, 15),
+            (Class::Cleric, 20, SavingThrowModifier::Para, -5, 1),
+            (Class::MagicUser, 1, SavingThrowModifier::Spell, 3, 15),
+        ];
+
+        for &(class, level, save_type, modifier, expected) in cases.iter() {
+            ch.player.borrow_mut().class = class;
+            ch.player.borrow_mut().level = level;
+            ch.specials.borrow_mut().apply_saving_throw[save_type] = modifier;
+            assert_eq!(saving_throw(&ch, save_type), expected,
+                "class {} level {} {:?} modifier {}", class as u8, level, save_type, modifier);
+            ch.specials.borrow_mut().apply_saving_throw[save_type] = 0;
+        }
+
+        // mobiles only have their modifiers
+        for &(modifier, expected) in [(0, 1), (-4, 1), (6, 6)].iter() {
+            mob.specials.borrow_mut().apply_saving_throw[SavingThrowModifier::Spell] = modifier;
+            assert_eq!(saving_throw(&mob, SavingThrowModifier::Spell), expected);
+        }
+    }
+
+    #[test]
+    fn saves_spell_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Saver", &room);
+
+        // immortals always save
+        ch.player.borrow_mut().level = 21;
+        ch.specials.borrow_mut().apply_saving_throw[SavingThrowModifier::Spell] = 30;
+        assert!((0..100).all(|_| saves_spell(&ch, SavingThrowModifier::Spell, &game)));
+
+        // nobody else saves when a 20 is needed
+        ch.player.borrow_mut().level = 1;
+        assert!((0..100).all(|_| !saves_spell(&ch, SavingThrowModifier::Spell, &game)));
+
+        // and a 1 always fails
+        ch.specials.borrow_mut().apply_saving_throw[SavingThrowModifier::Spell] = -30;
+        let saves = (0..1000).filter(|_| saves_spell(&ch, SavingThrowModifier::Spell, &game)).count();
+        assert!(saves > 900 && saves < 1000, "saved {} times out of 1000", saves);
+    }
+
+    #[test]
+    fn affect_update_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Blessed", &room);
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        spell_bless(1, &ch, Some(&ch), None, &game);
+        affect_to_char(&ch, &new_affect(SPELL_SANCTUARY, -1, 0, AbilityModifier::None,
+            AffectedFlags::AFF_SANCTUARY));
+        output(&ch);
+
+        for _ in 0..6 {
+            affect_update(&game);
+        }
+        assert!(output(&ch).is_empty());
+        assert_eq!(ch.points.borrow().hitroll, 1);
+
+        // both bless affects go, with a single message
+        affect_update(&game);
+        assert_eq!(output(&ch), vec!["You feel less righteous.\n\r"]);
+        assert!(!affected_by_spell(&ch, SPELL_BLESS));
+        assert_eq!(ch.points.borrow().hitroll, 0);
+        assert_eq!(ch.specials.borrow().apply_saving_throw[SavingThrowModifier::Spell], 0);
+
+        // unlimited affects never wear off
+        affect_update(&game);
+        assert!(ch.is_affected(AffectedFlags::AFF_SANCTUARY));
+    }
+
+    #[test]
+    fn armor_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let cleric = caster("Cleric", Class::Cleric, SPELL_ARMOR, &room);
+        let armor = cleric.get_ac();
+
+        do_cast(Rc::clone(&cleric), " 'armor' cleric", 0, &game);
+
+        assert_eq!(cleric.get_ac(), armor - 20);
+        assert!(affected_by_spell(&cleric, SPELL_ARMOR));
+        assert_eq!(output(&cleric), vec!["Ok.\n\r", "You feel someone protecting you.\n\r"]);
+        assert_eq!(cleric.get_mana(), 95);
+
+        // Only one armor spell at a time
+        do_cast(Rc::clone(&cleric), " 'armor' cleric", 0, &game);
+        assert_eq!(cleric.get_ac(), armor - 20);
+        assert_eq!(output(&cleric), vec!["Ok.\n\r", "Nothing seems to happen.\n\r"]);
+    }
+
+    #[test]
+    fn magic_missile_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let mage = caster("Mage", Class::MagicUser, SPELL_MAGIC_MISSILE, &room);
+        let victim = test_mob("the fido", &room);
+        victim.player.borrow_mut().name = String::from("fido");
+        // an immortal gains no experience from the fight
+        mage.player.borrow_mut().level = 21;
+
+        do_cast(Rc::clone(&mage), "'magic missile' fido", 0, &game);
+
+        // 6 each at level 21, halved if saved against
+        let dam = 20 - victim.get_hit();
+        assert!(dam >= 1 && dam <= 12, "magic missile did {} damage", dam);
+        assert!(Rc::ptr_eq(mage.specials.borrow().fighting.as_ref().unwrap(), &victim));
+    }
+
+    #[test]
+    fn cast_errors_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let mage = caster("Mage", Class::MagicUser, SPELL_SLEEP, &room);
+        mage.player.borrow_mut().level = 10;
+
+        do_cast(Rc::clone(&mage), "", 0, &game);
+        do_cast(Rc::clone(&mage), "sleep", 0, &game);
+        do_cast(Rc::clone(&mage), "'sleep", 0, &game);
+        do_cast(Rc::clone(&mage), "'foo'", 0, &game);
+        do_cast(Rc::clone(&mage), "'sleep' mage", 0, &game);
+        mage.player.borrow_mut().level = 20;
+        do_cast(Rc::clone(&mage), "'sleep' nobody", 0, &game);
+        do_cast(Rc::clone(&mage), "'sleep'", 0, &game);
+
+        assert_eq!(output(&mage), vec![
+            "Cast which what where?\n\r",
+            "Magic must always be enclosed by the holy magic symbols : '\n\r",
+            "Magic must always be enclosed by the holy magic symbols : '\n\r",
+            "Your lips do not move, no magic appears.\n\r",
+            "Sorry, you can't do that.\n\r",
+            "Nobody here by that name.\n\r",
+            "Who should the spell be cast upon?\n\r",
+        ]);
+        assert_eq!(mage.get_mana(), 100);
+    }
+
+    #[test]
+    fn create_food_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let cleric = caster("Cleric", Class::Cleric, SPELL_CREATE_FOOD, &room);
+
+        do_cast(Rc::clone(&cleric), "'create food'", 0, &game);
+
+        let contents = room.contents.borrow();
+        assert_eq!(contents.len(), 1);
+        assert_eq!(contents[0].short_description, "A Magic Mushroom");
+        assert_eq!(contents[0].obj_flags.borrow().value[0], 25);
+        assert_eq!(game.object_list.borrow().len(), 1);
+    }
+
+    #[test]
+    fn control_weather_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let cleric = caster("Cleric", Class::Cleric, SPELL_CONTROL_WEATHER, &room);
+
+        do_cast(Rc::clone(&cleric), " 'control weather' better", 0, &game);
+        let change = game.weather_info.borrow().change;
+        assert!(change >= 6 && change <= 24, "control weather changed it by {}", change);
+        do_cast(Rc::clone(&cleric), " 'control weather' sideways", 0, &game);
+        assert_eq!(output(&cleric), vec!["Ok.\n\r", "Ok.\n\r", "Do you want it to get better or worse?\n\r"]);
+        assert_eq!(game.weather_info.borrow().change, change);
+    }
+
+    #[test]
+    fn call_lightning_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let cleric = caster("Cleric", Class::Cleric, SPELL_CALL_LIGHTNING, &room);
+        cleric.player.borrow_mut().level = 21;
+        let victim = test_mob("the fido", &room);
+        victim.player.borrow_mut().name = String::from("fido");
+        victim.points.borrow_mut().max_hit = 1000;
+        victim.points.borrow_mut().hit = 1000;
+
+        // no lightning from a clear sky
+        do_cast(Rc::clone(&cleric), "'call lightning' fido", 0, &game);
+        assert_eq!(victim.get_hit(), 1000);
+
+        game.weather_info.borrow_mut().sky = Sky::Raining;
+        do_cast(Rc::clone(&cleric), "'call lightning' fido", 0, &game);
+        assert!(victim.get_hit() < 1000);
+    }
+}
@@ -0,0 +1,270 @@
+use std::rc::Rc;
+
+use diku::act_movement::do_simple_move;
+use diku::comm::act;
+use diku::fight::hit;
+use diku::handler::{obj_from_room, obj_to_char};
+use diku::spells::TYPE_UNDEFINED;
+use diku::structs::*;
+use diku::utility::log;
+
+// A charmed mob goes wherever its master went, if that is next door
+fn follow_master(ch: &Rc<CharData>, game: &Game) {
+    let master_room = match *ch.master.borrow() {
+        Some(ref master) => master.in_room.borrow().number,
+        None => return,
+    };
+
+    if ch.in_room.borrow().number == master_room || ch.get_pos() != Position::Standing {
+        return;
+    }
+
+    let door = (0..6).map(Direction::from)
+        .find(|&door| ch.can_go(door) && ch.exit(door).unwrap().to_room == Some(master_room));
+    if let Some(door) = door {
+        do_simple_move(Rc::clone(ch), door, true, game);
+    }
+}
+
+fn scavenge(ch: &Rc<CharData>, game: &Game) {
+    if ch.in_room.borrow().contents.borrow().is_empty() || game.number(0, 10) != 0 {
+        return;
+    }
+
+    let mut max = 1;
+    let mut best_obj = None;
+    for obj in ch.in_room.borrow().contents.borrow().iter() {
+        if ch.can_get_obj(obj) && obj.obj_flags.borrow().cost > max {
+            max = obj.obj_flags.borrow().cost;
+            best_obj = Some(Rc::clone(obj));
+        }
+    }
+
+    if let Some(obj) = best_obj {
+        obj_from_room(&obj);
+        obj_to_char(Rc::clone(&obj), ch);
+        act("$n gets $p.", false, ch, Some(&obj), None, None, None, VictimType::ToRoom);
+    }
+}
+
+fn wander(ch: &Rc<CharData>, game: &Game) {
+    let door = game.number(0, 45);
+    if door > 5 {
+        return;
+    }
+    let door = Direction::from(door as u8);
+
+    if !ch.can_go(door) {
+        return;
+    }
+
+    let to_room = &game.world[&ch.exit(door).unwrap().to_room.unwrap()];
+    if to_room.room_flags.intersects(RoomFlags::NO_MOB | RoomFlags::DEATH) {
+        return;
+    }
+
+    // Never wander the same way twice in a row, it skips a turn instead
+    if ch.specials.borrow().last_direction == Some(door) {
+        ch.specials.borrow_mut().last_direction = None;
+    } else if !ch.specials.borrow().act.contains(SpecialActFlags::ACT_STAY_ZONE) ||
+            to_room.zone == ch.in_room.borrow().zone {
+        ch.specials.borrow_mut().last_direction = Some(door);
+        do_simple_move(Rc::clone(ch), door, false, game);
+    }
+}
+
+fn aggress(ch: &Rc<CharData>, game: &Game) {
+    let wimpy = ch.specials.borrow().act.contains(SpecialActFlags::ACT_WIMPY);
+
+    let victim = ch.in_room.borrow().people.borrow().iter()
+        .find(|i| !i.is_npc() && ch.can_see(i) && i.get_level() < 21 && (!wimpy || !i.awake()))
+        .cloned();
+    if let Some(victim) = victim {
+        hit(Rc::clone(ch), victim, TYPE_UNDEFINED, game);
+    }
+}
+
+pub fn mobile_activity(game: &Game) {
+    let mobs: Vec<Rc<CharData>> = game.character_list.borrow().iter()
+        .filter(|ch| ch.is_npc())
+        .cloned()
+        .collect();
+
+    for ch in mobs {
+        // killed earlier in this pass
+        if ch.get_pos() == Position::Dead {
+            continue;
+        }
+
+        // Examine call for special procedure
+        if ch.specials.borrow().act.contains(SpecialActFlags::ACT_SPEC) && !game.no_specials {
+            let func = ch.nr.and_then(|nr| game.mob_index.get(&nr)).and_then(|index| index.func);
+            match func {
+                Some(func) => if func(Rc::clone(&ch), 0, "", game) {
+                    continue;
+                },
+                None => {
+                    log("Attempting to call a non-existing MOB func. (mobact.rs)");
+                    ch.specials.borrow_mut().act.remove(SpecialActFlags::ACT_SPEC);
+                }
+            }
+        }
+
+        if !ch.awake() || ch.specials.borrow().fighting.is_some() {
+            continue;
+        }
+
+        if ch.is_affected(AffectedFlags::AFF_CHARM) {
+            follow_master(&ch, game);
+            continue;
+        }
+
+        let act = ch.specials.borrow().act;
+
+        if act.contains(SpecialActFlags::ACT_SCAVENGER) {
+            scavenge(&ch, game);
+        }
+
+        if !act.contains(SpecialActFlags::ACT_SENTINEL) && ch.get_pos() == Position::Standing {
+            wander(&ch, game);
+        }
+
+        if act.contains(SpecialActFlags::ACT_AGGRESSIVE) {
+            aggress(&ch, game);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::clear_object;
+    use diku::handler::obj_to_room;
+    use diku::testutil::*;
+
+    fn mob(short_descr: &str, room: &Rc<RoomData>, act: SpecialActFlags, game: &Game) -> Rc<CharData> {
+        let mob = test_mob(short_descr, room);
+        mob.specials.borrow_mut().act.insert(act);
+        mob.specials.borrow_mut().damnodice = 1;
+        mob.specials.borrow_mut().damsizedice = 2;
+        game.character_list.borrow_mut().push(Rc::clone(&mob));
+        mob
+    }
+
+    fn treasure(name: &str, cost: u32) -> Rc<ObjData> {
+        let mut obj = clear_object();
+        obj.name = String::from(name);
+        obj.short_description = format!("a {}", name);
+        obj.obj_flags.borrow_mut().wear_flags = WearFlags::ITEM_TAKE;
+        obj.obj_flags.borrow_mut().cost = cost;
+        Rc::new(obj)
+    }
+
+    // Two rooms joined east to west; the second one can be made off limits
+    fn corridor(game: &mut Game, zone: u16, flags: RoomFlags) -> (Rc<RoomData>, Rc<RoomData>) {
+        let west = with_exit(test_room(3001), Direction::East, 3002, ExitFlags::empty());
+        let mut east = Rc::try_unwrap(with_exit(test_room(3002), Direction::West, 3001, ExitFlags::empty()))
+            .ok().unwrap();
+        east.zone = zone;
+        east.room_flags = flags;
+        let east = Rc::new(east);
+        game.world.insert(3001, Rc::clone(&west));
+        game.world.insert(3002, Rc::clone(&east));
+        (west, east)
+    }
+
+    #[test]
+    fn scavenger_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let mob = mob("the rat", &room, SpecialActFlags::ACT_SCAVENGER | SpecialActFlags::ACT_SENTINEL, &game);
+        obj_to_room(treasure("pebble", 1), &room);
+        obj_to_room(treasure("ruby", 500), &room);
+        obj_to_room(treasure("coin", 20), &room);
+
+        for _ in 0..200 {
+            if !mob.carrying.borrow().is_empty() {
+                break;
+            }
+            mobile_activity(&game);
+        }
+        assert_eq!(mob.carrying.borrow().len(), 1);
+        assert_eq!(mob.carrying.borrow()[0].name, "ruby");
+    }
+
+    #[test]
+    fn wander_test() {
+        let mut game = test_game();
+        let (west, east) = corridor(&mut game, 0, RoomFlags::empty());
+        let sentinel = mob("the guard", &west, SpecialActFlags::ACT_SENTINEL, &game);
+        let wanderer = mob("the cat", &west, SpecialActFlags::empty(), &game);
+
+        for _ in 0..200 {
+            if Rc::ptr_eq(&wanderer.in_room.borrow(), &east) {
+                break;
+            }
+            mobile_activity(&game);
+        }
+        assert!(Rc::ptr_eq(&wanderer.in_room.borrow(), &east));
+        assert!(wanderer.specials.borrow().last_direction == Some(Direction::East));
+        assert!(Rc::ptr_eq(&sentinel.in_room.borrow(), &west));
+    }
+
+    #[test]
+    fn off_limits_test() {
+        for &(zone, flags, act) in &[(0, RoomFlags::NO_MOB, SpecialActFlags::empty()),
+                (0, RoomFlags::DEATH, SpecialActFlags::empty()),
+                (1, RoomFlags::empty(), SpecialActFlags::ACT_STAY_ZONE)] {
+            let mut game = test_game();
+            let (west, _) = corridor(&mut game, zone, flags);
+            let mob = mob("the cat", &west, act, &game);
+
+            for _ in 0..200 {
+                mobile_activity(&game);
+            }
+            assert!(Rc::ptr_eq(&mob.in_room.borrow(), &west));
+        }
+    }
+
+    #[test]
+    fn aggressive_test() {
+        let game = test_game();
+        let room = test_room(3001);
+        let ch = test_char("Victim", &room);
+        let coward = mob("the jackal", &room, SpecialActFlags::ACT_SENTINEL | SpecialActFlags::ACT_AGGRESSIVE |
+            SpecialActFlags::ACT_WIMPY, &game);
+
+        // A wimpy mob only dares to attack sleepers
+        mobile_activity(&game);
+        assert!(coward.specials.borrow().fighting.is_none());
+        ch.specials.borrow_mut().position = Position::Sleeping;
+        mobile_activity(&game);
+        assert!(Rc::ptr_eq(coward.specials.borrow().fighting.as_ref().unwrap(), &ch));
+
+        let bold = mob("the wolf", &room, SpecialActFlags::ACT_SENTINEL | SpecialActFlags::ACT_AGGRESSIVE, &game);
+        mobile_activity(&game);
+        assert!(Rc::ptr_eq(bold.specials.borrow().fighting.as_ref().unwrap(), &ch));
+    }
+
+    #[test]
+    fn charmed_test() {
+        let mut game = test_game();
+        let (west, east) = corridor(&mut game, 0, RoomFlags::empty());
+        let master = test_char("Master", &east);
+        let pet = mob("the dog", &west, SpecialActFlags::ACT_AGGRESSIVE, &game);
+        pet.specials.borrow_mut().affected_by.insert(AffectedFlags::AFF_CHARM);
+        *pet.master.borrow_mut() = Some(Rc::clone(&master));
+        let stranger = test_char("Stranger", &west);
+
+        mobile_activity(&game);
+        assert!(Rc::ptr_eq(&pet.in_room.borrow(), &east));
+        assert!(pet.specials.borrow().fighting.is_none());
+        assert!(stranger.specials.borrow().fighting.is_none());
+
+        // A special procedure that does not exist is switched off
+        game.no_specials = false;
+        pet.specials.borrow_mut().act.insert(SpecialActFlags::ACT_SPEC);
+        mobile_activity(&game);
+        assert!(!pet.specials.borrow().act.contains(SpecialActFlags::ACT_SPEC));
+    }
+}
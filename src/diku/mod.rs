@@ -1,7 +1,11 @@
 pub mod act_comm;
+pub mod act_info;
 pub mod act_movement;
+pub mod act_obj2;
 pub mod act_offensive;
+pub mod act_other;
 pub mod act_social;
+pub mod act_wizard;
 pub mod comm;
 pub mod constants;
 pub mod fight;
@@ -9,11 +13,20 @@ pub mod game;
 pub mod handler;
 pub mod interpreter;
 pub mod limits;
+pub mod magic;
+pub mod mobact;
 pub mod modify;
+pub mod reception;
+pub mod shop;
 pub mod signals;
 pub mod spec_assign;
 pub mod spec_procs;
 pub mod spell_parser;
 pub mod spells;
+pub mod spells1;
+pub mod spells2;
 pub mod structs;
 pub mod utility;
+pub mod weather;
+#[cfg(test)]
+pub mod testutil;
@@ -0,0 +1,227 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::BufReader;
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use bincode::{deserialize_from, serialize_into, Infinite};
+
+use diku::constants::{MAX_OBJ_SAVE, OBJ_SAVE_FILE};
+use diku::game::{copy_from_store, copy_to_store, read_object, secs_since_epoch};
+use diku::handler::obj_to_char;
+use diku::structs::*;
+use diku::utility::log;
+
+// an object and everything inside it, in the order they are stored
+fn objs_in(obj: &Rc<ObjData>, objs: &mut Vec<Rc<ObjData>>) {
+    objs.push(Rc::clone(obj));
+    for inside in obj.contains.borrow().iter() {
+        objs_in(inside, objs);
+    }
+}
+
+// What a char has, worn and carried, as it goes in the object file.
+// Containers are emptied and corpses and money are left out.
+fn objs_to_store(ch: &CharData) -> ObjFileU {
+    let mut objs = Vec::new();
+    for obj in ch.equipment.borrow().iter().filter_map(|(_, e)| e.as_ref()) {
+        objs_in(obj, &mut objs);
+    }
+    for obj in ch.carrying.borrow().iter() {
+        objs_in(obj, &mut objs);
+    }
+    objs.retain(|obj| obj.item_number.map_or(false, |nr| nr <= i16::max_value() as u32));
+    if objs.len() > MAX_OBJ_SAVE {
+        log(&format!("{} has {} objects, only {} are kept.", ch.get_name(), objs.len(), MAX_OBJ_SAVE));
+    }
+
+    let mut st = ObjFileU::default();
+    copy_to_store(&ch.get_name(), &mut st.owner);
+    st.gold_left = ch.get_gold();
+    st.last_update = secs_since_epoch(SystemTime::now());
+    for elem in st.objects.iter_mut() {
+        elem.item_number = -1;
+    }
+    for (elem, obj) in st.objects.iter_mut().zip(&objs) {
+        let flags = obj.obj_flags.borrow();
+        elem.item_number = obj.item_number.unwrap() as i16;
+        elem.value = flags.value;
+        elem.extra_flags = flags.extra_flags.bits() as i32;
+        elem.weight = flags.weight;
+        elem.timer = flags.timer as i32;
+        elem.bitvector = flags.bitvector as i32;
+        elem.affected = *obj.affected.borrow();
+        st.total_cost += flags.cost_per_day as i32;
+    }
+    st
+}
+
+// gives a char back the objects from the file, all of them carried
+fn store_to_objs(st: &ObjFileU, ch: &Rc<CharData>, game: &Game) {
+    for elem in st.objects.iter().filter(|elem| elem.item_number >= 0) {
+        let obj = match read_object(elem.item_number as u32, game) {
+            Some(obj) => obj,
+            None => {
+                log(&format!("SYSERR: Object #{} of {} no longer exists", elem.item_number, ch.get_name()));
+                continue;
+            }
+        };
+        {
+            let mut flags = obj.obj_flags.borrow_mut();
+            flags.value = elem.value;
+            flags.extra_flags = ItemExtraFlags::from_bits_truncate(elem.extra_flags as u16);
+            flags.weight = elem.weight;
+            flags.timer = elem.timer as u32;
+            flags.bitvector = elem.bitvector as u32 as u64;
+        }
+        *obj.affected.borrow_mut() = elem.affected;
+        obj_to_char(obj, ch);
+    }
+}
+
+// every entry in the object file, it ends where an entry will not read
+fn read_obj_file(path: &str) -> io::Result<Vec<ObjFileU>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut entries = Vec::new();
+    while let Ok(st) = deserialize_from(&mut reader, Infinite) {
+        entries.push(st);
+    }
+    Ok(entries)
+}
+
+// Replaces the entry of a player, or takes it out when there is no new one
+fn update_obj_file(path: &str, name: &str, st: Option<ObjFileU>) -> io::Result<()> {
+    let mut entries = read_obj_file(path)?;
+    entries.retain(|entry| !copy_from_store(&entry.owner).eq_ignore_ascii_case(name));
+    entries.extend(st);
+
+    // the object file is there from boot on
+    let mut fl = OpenOptions::new().write(true).truncate(true).open(path)?;
+    for entry in &entries {
+        serialize_into(&mut fl, entry, Infinite).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn find_obj_file(path: &str, name: &str) -> io::Result<Option<ObjFileU>> {
+    Ok(read_obj_file(path)?.into_iter().find(|entry| copy_from_store(&entry.owner).eq_ignore_ascii_case(name)))
+}
+
+// Writes down the objects of a player, to be had back on the next login
+pub fn save_obj(ch: &CharData) {
+    save_obj_to(ch, OBJ_SAVE_FILE);
+}
+
+fn save_obj_to(ch: &CharData, path: &str) {
+    if ch.is_npc() {
+        return;
+    }
+    if let Err(e) = update_obj_file(path, &ch.get_name(), Some(objs_to_store(ch))) {
+        log(&format!("SYSERR: Saving objects of {}: {}", ch.get_name(), e));
+    }
+}
+
+// Gives a player the saved objects, once
+pub fn load_char_objs(ch: &Rc<CharData>, game: &Game) {
+    load_char_objs_from(ch, OBJ_SAVE_FILE, game);
+}
+
+fn load_char_objs_from(ch: &Rc<CharData>, path: &str, game: &Game) {
+    let name = ch.get_name();
+    let st = match find_obj_file(path, &name) {
+        Ok(Some(st)) => st,
+        Ok(None) => return,
+        Err(e) => {
+            log(&format!("SYSERR: Loading objects of {}: {}", name, e));
+            return;
+        }
+    };
+
+    store_to_objs(&st, ch, game);
+    if let Err(e) = update_obj_file(path, &name, None) {
+        log(&format!("SYSERR: Removing objects of {}: {}", name, e));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use diku::game::generate_indices;
+    use diku::handler::{equip_char, obj_to_obj};
+    use diku::testutil::*;
+
+    #[test]
+    fn obj_file_test() {
+        let mut game = test_game();
+        game.obj_index = generate_indices(&mut BufReader::new(&game.obj_f));
+        let room = test_room(3001);
+        let ch = test_char("Packer", &room);
+
+        let bag = read_object(3032, &game).unwrap();
+        obj_to_obj(read_object(3010, &game).unwrap(), &bag);
+        obj_to_char(Rc::clone(&bag), &ch);
+        let worn = read_object(3070, &game).unwrap();
+        worn.obj_flags.borrow_mut().timer = 7;
+        equip_char(&ch, worn, EquipmentPosition::Head);
+
+        let path = env::temp_dir().join(format!("pcobjs.obj.{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        File::create(path).unwrap();
+        let mut other = ObjFileU::default();
+        copy_to_store("Other", &mut other.owner);
+        update_obj_file(path, "Other", Some(other)).unwrap();
+        update_obj_file(path, "Packer", Some(objs_to_store(&ch))).unwrap();
+
+        let st = find_obj_file(path, "packer").unwrap().unwrap();
+        let numbers: Vec<i16> = st.objects.iter().map(|elem| elem.item_number).take(4).collect();
+        assert_eq!(numbers, vec![3070, 3032, 3010, -1]);
+
+        let unpacker = test_char("Unpacker", &room);
+        store_to_objs(&st, &unpacker, &game);
+        assert_eq!(unpacker.carrying.borrow().len(), 3);
+        assert_eq!(unpacker.carrying.borrow()[0].obj_flags.borrow().timer, 7);
+        assert_eq!(st.gold_left, ch.get_gold());
+
+        update_obj_file(path, "Packer", None).unwrap();
+        assert!(find_obj_file(path, "Packer").unwrap().is_none());
+        assert!(find_obj_file(path, "Other").unwrap().is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn save_and_load_test() {
+        let mut game = test_game();
+        game.obj_index = generate_indices(&mut BufReader::new(&game.obj_f));
+        let room = test_room(3001);
+        let ch = test_char("Keeper", &room);
+
+        let bag = read_object(3032, &game).unwrap();
+        obj_to_obj(read_object(3010, &game).unwrap(), &bag);
+        obj_to_char(bag, &ch);
+        equip_char(&ch, read_object(3070, &game).unwrap(), EquipmentPosition::Head);
+        // mobiles keep nothing
+        let mob = test_mob("the hoarder", &room);
+        obj_to_char(read_object(3010, &game).unwrap(), &mob);
+
+        let path = env::temp_dir().join(format!("pcobjs.obj.saved.{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        File::create(path).unwrap();
+        save_obj_to(&ch, path);
+        save_obj_to(&mob, path);
+        assert_eq!(read_obj_file(path).unwrap().len(), 1);
+
+        // on the next login everything is carried
+        let again = test_char("Keeper", &room);
+        load_char_objs_from(&again, path, &game);
+        let mut numbers: Vec<u32> = again.carrying.borrow().iter().map(|obj| obj.item_number.unwrap()).collect();
+        numbers.sort();
+        assert_eq!(numbers, vec![3010, 3032, 3070]);
+
+        // and only once
+        let twice = test_char("Keeper", &room);
+        load_char_objs_from(&twice, path, &game);
+        assert!(twice.carrying.borrow().is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::prelude::*;
+use std::rc::Rc;
+
+use diku::act_comm::do_say;
+use diku::comm::{act, send_to_char};
+use diku::constants::{LIQUIDS, MAX_PROD, MAX_TRADE, SHOP_FILE};
+use diku::game::read_object;
+use diku::fight::hit;
+use diku::handler::{extract_obj, fname, get_char_room_vis, get_obj_in_list_vis, isname, obj_from_char, obj_to_char};
+use diku::interpreter::one_argument;
+use diku::spells::TYPE_UNDEFINED;
+use diku::structs::*;
+use diku::utility::{fread_string, log, read_line_trim};
+
+// command numbers of the shop commands
+pub const CMD_BUY: i32 = 56;
+pub const CMD_SELL: i32 = 57;
+pub const CMD_VALUE: i32 = 58;
+pub const CMD_LIST: i32 = 59;
+// and the attacks a keeper may answer
+const CMD_BACKSTAB: i32 = 154;
+const CMD_BASH: i32 = 157;
+const CMD_KICK: i32 = 159;
+
+// The shop messages are written "%s message", where %s once was the name of
+// the customer to tell it to
+fn keeper_tell(keeper: &CharData, ch: &CharData, message: &str, amount: i32) {
+    let message = message.trim_start_matches("%s").trim().replace("%d", &amount.to_string());
+    act(&format!("$n tells you '{}'", message), false, keeper, None, Some(ch), None, None, VictimType::ToVict);
+}
+
+fn is_ok(keeper: &Rc<CharData>, ch: &CharData, shop: &ShopData, hour: u8, game: &Game) -> bool {
+    if shop.open1 > hour {
+        do_say(Rc::clone(keeper), "Come back later!", 17, game);
+        return false;
+    } else if shop.close1 < hour {
+        if shop.open2 > hour {
+            do_say(Rc::clone(keeper), "Sorry, we have closed, but come back later.", 17, game);
+            return false;
+        } else if shop.close2 < hour {
+            do_say(Rc::clone(keeper), "Sorry, come back tomorrow.", 17, game);
+            return false;
+        }
+    }
+
+    if !keeper.can_see(ch) {
+        do_say(Rc::clone(keeper), "I don't trade with someone I can't see!", 17, game);
+        return false;
+    }
+
+    if !trades_with_char(ch, shop) {
+        do_say(Rc::clone(keeper), "I don't trade with your kind!", 17, game);
+        return false;
+    }
+
+    true
+}
+
+fn trades_with_char(ch: &CharData, shop: &ShopData) -> bool {
+    let refused = match ch.get_class() {
+        Class::MagicUser => TradeFlags::TRADE_NOMAGIC_USER,
+        Class::Cleric => TradeFlags::TRADE_NOCLERIC,
+        Class::Thief => TradeFlags::TRADE_NOTHIEF,
+        Class::Warrior => TradeFlags::TRADE_NOWARRIOR,
+    };
+    !(shop.with_who.contains(refused) ||
+        (ch.is_good() && shop.with_who.contains(TradeFlags::TRADE_NOGOOD)) ||
+        (ch.is_evil() && shop.with_who.contains(TradeFlags::TRADE_NOEVIL)) ||
+        (ch.is_neutral() && shop.with_who.contains(TradeFlags::TRADE_NONEUTRAL)))
+}
+
+fn trade_with(item: &ObjData, shop: &ShopData) -> bool {
+    let flags = item.obj_flags.borrow();
+    flags.cost >= 1 && shop.buy_types.contains(&flags.type_flag)
+}
+
+fn shop_producing(item: &ObjData, shop: &ShopData) -> bool {
+    item.item_number.map_or(false, |nr| shop.producing.contains(&nr))
+}
+
+fn price(item: &ObjData, profit: f32) -> i32 {
+    (item.obj_flags.borrow().cost as f32 * profit) as i32
+}
+
+// A shop never runs out of what it produces
+fn restock(keeper: &Rc<CharData>, shop: &ShopData, game: &Game) {
+    for &nr in shop.producing.iter() {
+        if !keeper.carrying.borrow().iter().any(|obj| obj.item_number == Some(nr)) {
+            match read_object(nr, game) {
+                Some(obj) => obj_to_char(obj, keeper),
+                None => log(&format!("SYSERR: Shop produces non-existing object {}.", nr)),
+            }
+        }
+    }
+}
+
+fn shopping_buy(arg: &str, ch: &Rc<CharData>, keeper: &Rc<CharData>, shop: &ShopData, hour: u8, game: &Game) {
+    if !is_ok(keeper, ch, shop, hour, game) {
+        return;
+    }
+
+    let (argm, _) = one_argument(arg);
+    if argm.is_empty() {
+        keeper_tell(keeper, ch, "What do you want to buy??", 0);
+        return;
+    }
+
+    restock(keeper, shop, game);
+    let item = get_obj_in_list_vis(ch, &argm, &keeper.carrying.borrow());
+    let item = match item {
+        Some(item) if item.obj_flags.borrow().cost > 0 => item,
+        _ => {
+            keeper_tell(keeper, ch, &shop.no_such_item1, 0);
+            return;
+        }
+    };
+
+    let cost = price(&item, shop.profit_buy);
+    if ch.get_gold() < cost && ch.get_level() < 22 {
+        keeper_tell(keeper, ch, &shop.missing_cash2, cost);
+        if shop.temper1 == 1 {
+            act("$n smokes on $s joint.", false, keeper, None, None, None, None, VictimType::ToRoom);
+        }
+        return;
+    }
+
+    if ch.is_carrying_n() + 1 > ch.can_carry_n() {
+        send_to_char(&format!("{} : You can't carry that many items.\n\r", fname(&item.name)), ch);
+        return;
+    }
+
+    if ch.is_carrying_w() + item.get_obj_weight() > ch.can_carry_w() {
+        send_to_char(&format!("{} : You can't carry that much weight.\n\r", fname(&item.name)), ch);
+        return;
+    }
+
+    act("$n buys $p.", false, ch, Some(&item), None, None, None, VictimType::ToRoom);
+    keeper_tell(keeper, ch, &shop.message_buy, cost);
+    send_to_char(&format!("You now have {}.\n\r", item.short_description), ch);
+
+    if ch.get_level() < 22 {
+        ch.points.borrow_mut().gold -= cost;
+    }
+    keeper.points.borrow_mut().gold += cost;
+
+    // Produced items are handed out as fresh copies
+    let item = if shop_producing(&item, shop) {
+        read_object(item.item_number.unwrap(), game).unwrap()
+    } else {
+        obj_from_char(&item);
+        item
+    };
+    obj_to_char(item, ch);
+}
+
+fn shopping_sell(arg: &str, ch: &Rc<CharData>, keeper: &Rc<CharData>, shop: &ShopData, hour: u8, game: &Game) {
+    if !is_ok(keeper, ch, shop, hour, game) {
+        return;
+    }
+
+    let (argm, _) = one_argument(arg);
+    if argm.is_empty() {
+        keeper_tell(keeper, ch, "What do you want to sell??", 0);
+        return;
+    }
+
+    let item = match get_obj_in_list_vis(ch, &argm, &ch.carrying.borrow()) {
+        Some(item) => item,
+        None => {
+            keeper_tell(keeper, ch, &shop.no_such_item2, 0);
+            return;
+        }
+    };
+
+    if !trade_with(&item, shop) {
+        keeper_tell(keeper, ch, &shop.do_not_buy, 0);
+        return;
+    }
+
+    let cost = price(&item, shop.profit_sell);
+    if keeper.get_gold() < cost {
+        keeper_tell(keeper, ch, &shop.missing_cash1, cost);
+        return;
+    }
+
+    act("$n sells $p.", false, ch, Some(&item), None, None, None, VictimType::ToRoom);
+    keeper_tell(keeper, ch, &shop.message_sell, cost);
+    send_to_char(&format!("The shopkeeper now has {}.\n\r", item.short_description), ch);
+
+    ch.points.borrow_mut().gold += cost;
+    keeper.points.borrow_mut().gold -= cost;
+
+    // One of each is enough, and trash is not kept at all
+    let in_stock = keeper.carrying.borrow().iter().any(|obj| isname(&argm, &obj.name));
+    if in_stock || item.get_item_type() == ItemType::Trash {
+        extract_obj(&item, game);
+    } else {
+        obj_from_char(&item);
+        obj_to_char(item, keeper);
+    }
+}
+
+fn shopping_value(arg: &str, ch: &Rc<CharData>, keeper: &Rc<CharData>, shop: &ShopData, hour: u8, game: &Game) {
+    if !is_ok(keeper, ch, shop, hour, game) {
+        return;
+    }
+
+    let (argm, _) = one_argument(arg);
+    if argm.is_empty() {
+        keeper_tell(keeper, ch, "What do you want me to valuate??", 0);
+        return;
+    }
+
+    let item = match get_obj_in_list_vis(ch, &argm, &ch.carrying.borrow()) {
+        Some(item) => item,
+        None => {
+            keeper_tell(keeper, ch, &shop.no_such_item2, 0);
+            return;
+        }
+    };
+
+    if !trade_with(&item, shop) {
+        keeper_tell(keeper, ch, &shop.do_not_buy, 0);
+        return;
+    }
+
+    keeper_tell(keeper, ch, "I'll give you %d gold coins for that!", price(&item, shop.profit_sell));
+}
+
+fn shopping_list(ch: &Rc<CharData>, keeper: &Rc<CharData>, shop: &ShopData, hour: u8, game: &Game) {
+    if !is_ok(keeper, ch, shop, hour, game) {
+        return;
+    }
+
+    restock(keeper, shop, game);
+
+    let mut buf = String::from("You can buy:\n\r");
+    let mut found_obj = false;
+    for item in keeper.carrying.borrow().iter() {
+        let flags = item.obj_flags.borrow();
+        if !ch.can_see_obj(item) || flags.cost == 0 {
+            continue;
+        }
+
+        found_obj = true;
+        let name = if flags.type_flag == ItemType::DrinkCon && flags.value[1] != 0 {
+            format!("{} of {}", item.short_description, LIQUIDS[flags.value[2] as usize].name)
+        } else {
+            item.short_description.clone()
+        };
+        let mut chars = name.chars();
+        if let Some(first) = chars.next() {
+            buf.extend(first.to_uppercase());
+            buf.push_str(chars.as_str());
+        }
+        buf.push_str(&format!(" for {} gold coins.\n\r", (flags.cost as f32 * shop.profit_buy) as i32));
+    }
+
+    if !found_obj {
+        buf.push_str("Nothing!\n\r");
+    }
+
+    send_to_char(&buf, ch);
+}
+
+// The special procedure of every shopkeeper
+pub fn shop_keeper(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
+    let attack = cmd == CMD_BACKSTAB || cmd == CMD_BASH || cmd == CMD_KICK;
+    if cmd != CMD_BUY && cmd != CMD_SELL && cmd != CMD_VALUE && cmd != CMD_LIST && !attack {
+        return false;
+    }
+
+    let room = Rc::clone(&ch.in_room.borrow());
+    let shop = match game.shop_index.iter().find(|shop| shop.in_room == room.number) {
+        Some(shop) => shop,
+        None => return false,
+    };
+    let keeper = room.people.borrow().iter()
+        .find(|k| k.is_npc() && k.nr == Some(shop.keeper))
+        .cloned();
+    let keeper = match keeper {
+        Some(keeper) => keeper,
+        None => return false,
+    };
+
+    if attack {
+        return shop_attacked(&ch, arg, &keeper, shop, game);
+    }
+
+    let hour = game.time_info.hours;
+    match cmd {
+        CMD_BUY => shopping_buy(arg, &ch, &keeper, shop, hour, game),
+        CMD_SELL => shopping_sell(arg, &ch, &keeper, shop, hour, game),
+        CMD_VALUE => shopping_value(arg, &ch, &keeper, shop, hour, game),
+        _ => shopping_list(&ch, &keeper, shop, hour, game),
+    }
+
+    true
+}
+
+// A keeper with temper2 set does not wait to be hit in his own shop
+fn shop_attacked(ch: &Rc<CharData>, arg: &str, keeper: &Rc<CharData>, shop: &ShopData, game: &Game) -> bool {
+    let (name, _) = one_argument(arg);
+    let victim = get_char_room_vis(ch, &name);
+    if shop.temper2 != 1 || !victim.map_or(false, |victim| Rc::ptr_eq(&victim, keeper)) ||
+            keeper.specials.borrow().fighting.is_some() {
+        return false;
+    }
+
+    act("$N tells you 'Don't you ever try that in my shop!'", false, ch, None, Some(keeper), None, None,
+        VictimType::ToChar);
+    hit(Rc::clone(keeper), Rc::clone(ch), TYPE_UNDEFINED, game);
+    true
+}
+
+fn read_int<R: Read>(reader: &mut BufReader<R>) -> i32 {
+    read_line_trim(reader).parse().expect("shop file number")
+}
+
+// Item types are numbered from 1 in the shop file, unused slots hold 0 or -1
+fn read_shop<R: Read>(reader: &mut BufReader<R>) -> ShopData {
+    let producing = (0..MAX_PROD).map(|_| read_int(reader))
+        .filter(|&nr| nr >= 0)
+        .map(|nr| nr as u32)
+        .collect();
+    let profit_buy = read_line_trim(reader).parse().expect("shop file profit");
+    let profit_sell = read_line_trim(reader).parse().expect("shop file profit");
+    let buy_types = (0..MAX_TRADE).map(|_| read_int(reader))
+        .filter(|&t| t > 0)
+        .map(|t| ItemType::from(t as u8))
+        .collect();
+
+    ShopData {
+        producing,
+        profit_buy,
+        profit_sell,
+        buy_types,
+        no_such_item1: fread_string(reader),
+        no_such_item2: fread_string(reader),
+        do_not_buy: fread_string(reader),
+        missing_cash1: fread_string(reader),
+        missing_cash2: fread_string(reader),
+        message_buy: fread_string(reader),
+        message_sell: fread_string(reader),
+        temper1: read_int(reader),
+        temper2: read_int(reader),
+        keeper: read_int(reader) as u32,
+        with_who: TradeFlags::from_bits_truncate(read_int(reader) as u8),
+        in_room: read_int(reader) as u32,
+        open1: read_int(reader) as u8,
+        close1: read_int(reader) as u8,
+        open2: read_int(reader) as u8,
+        close2: read_int(reader) as u8,
+    }
+}
+
+pub fn read_shops<R: Read>(reader: &mut BufReader<R>) -> Vec<ShopData> {
+    let mut shops = Vec::new();
+
+    loop {
+        let buf = read_line_trim(reader);
+        if buf.starts_with('#') {
+            shops.push(read_shop(reader));
+        } else if buf.starts_with('$') || buf.is_empty() {
+            break;
+        }
+    }
+
+    shops
+}
+
+pub fn boot_the_shops() -> Vec<ShopData> {
+    let file = File::open(SHOP_FILE).expect("Error in boot shop");
+    read_shops(&mut BufReader::new(file))
+}
+
+pub fn assign_the_shopkeepers(mob_index: &mut HashMap<u32, IndexData>, shop_index: &[ShopData]) {
+    for shop in shop_index.iter() {
+        match mob_index.get_mut(&shop.keeper) {
+            Some(index) => index.func = Some(shop_keeper),
+            None => log(&format!("SYSERR: Shopkeeper {} does not exist.", shop.keeper)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::game::{clear_char, clear_object, generate_indices};
+    use diku::interpreter::special;
+    use diku::testutil::*;
+
+    fn shops() -> Vec<ShopData> {
+        read_shops(&mut BufReader::new(File::open("lib/tinyworld.shp").expect("test shop file")))
+    }
+
+    // The baker's shop of Midgaard with the baker behind the counter
+    fn bakery() -> (Game, Rc<RoomData>, Rc<CharData>) {
+        let mut game = test_game();
+        game.obj_index = generate_indices(&mut BufReader::new(&game.obj_f));
+        game.shop_index.push(shops().remove(0));
+
+        let room = test_room(3009);
+        let mut keeper = clear_char(Rc::clone(&room));
+        keeper.nr = Some(3001);
+        keeper.player.borrow_mut().short_descr = String::from("the Baker");
+        keeper.specials.borrow_mut().act = SpecialActFlags::ACT_ISNPC;
+        keeper.points.borrow_mut().gold = 100;
+        let keeper = Rc::new(keeper);
+        room.people.borrow_mut().push(Rc::clone(&keeper));
+
+        (game, room, keeper)
+    }
+
+    fn sword(cost: u32) -> Rc<ObjData> {
+        let mut obj = clear_object();
+        obj.name = String::from("sword");
+        obj.short_description = String::from("a sword");
+        obj.obj_flags.borrow_mut().type_flag = ItemType::Weapon;
+        obj.obj_flags.borrow_mut().wear_flags = WearFlags::ITEM_TAKE;
+        obj.obj_flags.borrow_mut().cost = cost;
+        Rc::new(obj)
+    }
+
+    #[test]
+    fn read_shops_test() {
+        let shops = shops();
+        assert_eq!(shops.len(), 17);
+        assert_eq!(shops[0].producing, vec![3010, 3011]);
+        assert_eq!(shops[0].profit_buy, 1.1);
+        assert!(shops[0].buy_types.is_empty());
+        assert_eq!(shops[0].keeper, 3001);
+        assert_eq!(shops[0].in_room, 3009);
+        assert_eq!(shops[0].close1, 28);
+        assert_eq!(shops[0].message_buy, "%s That'll be %d coins.");
+    }
+
+    #[test]
+    fn buy_test() {
+        let (game, room, keeper) = bakery();
+        let ch = test_char("Buyer", &room);
+        ch.points.borrow_mut().gold = 20;
+
+        assert!(shop_keeper(Rc::clone(&ch), CMD_LIST, "", &game));
+        assert_eq!(output(&ch), vec!["You can buy:\n\rA bread for 11 gold coins.\n\r\
+            A danish pastry for 5 gold coins.\n\r"]);
+
+        shop_keeper(Rc::clone(&ch), CMD_BUY, " bread", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'That'll be 11 coins.'", "You now have a bread.\n\r"]);
+        assert_eq!(ch.get_gold(), 9);
+        assert_eq!(keeper.get_gold(), 111);
+        assert_eq!(ch.carrying.borrow()[0].item_number, Some(3010));
+
+        // The baker bakes as much bread as anyone wants
+        shop_keeper(Rc::clone(&ch), CMD_BUY, " bread", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'If you have no money, you'll have to go!'"]);
+        assert_eq!(keeper.carrying.borrow().len(), 2);
+
+        shop_keeper(Rc::clone(&ch), CMD_BUY, " cake", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'Haven't got that on storage - try list!'"]);
+        assert!(!shop_keeper(Rc::clone(&ch), 17, " bread", &game));
+    }
+
+    #[test]
+    fn sell_test() {
+        let (mut game, room, keeper) = bakery();
+        let ch = test_char("Seller", &room);
+        obj_to_char(sword(50), &ch);
+
+        shop_keeper(Rc::clone(&ch), CMD_SELL, " sword", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'I don't buy!'"]);
+
+        game.shop_index[0].buy_types.push(ItemType::Weapon);
+        shop_keeper(Rc::clone(&ch), CMD_VALUE, " sword", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'I'll give you 50 gold coins for that!'"]);
+
+        shop_keeper(Rc::clone(&ch), CMD_SELL, " sword", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'Oups - 50 a minor bug - please report!'",
+            "The shopkeeper now has a sword.\n\r"]);
+        assert_eq!(ch.get_gold(), 50);
+        assert_eq!(keeper.get_gold(), 50);
+        assert_eq!(keeper.carrying.borrow()[0].name, "sword");
+
+        // The keeper cannot pay more than he has
+        game.shop_index[0].missing_cash1 = String::from("%s I can't afford that!");
+        obj_to_char(sword(80), &ch);
+        shop_keeper(Rc::clone(&ch), CMD_SELL, " sword", &game);
+        assert_eq!(output(&ch), vec!["the Baker tells you 'I can't afford that!'"]);
+        assert_eq!(ch.carrying.borrow().len(), 1);
+    }
+
+    #[test]
+    fn closed_test() {
+        let (mut game, room, _) = bakery();
+        let ch = test_char("Early", &room);
+        game.shop_index[0].open1 = 25;
+
+        shop_keeper(Rc::clone(&ch), CMD_LIST, "", &game);
+        assert_eq!(output(&ch), vec!["the Baker says 'Come back later!'"]);
+    }
+
+    #[test]
+    fn with_who_test() {
+        let (mut game, room, _) = bakery();
+        let ch = test_char("Sneaky", &room);
+        ch.player.borrow_mut().class = Class::Thief;
+        ch.specials.borrow_mut().alignment = -500;
+
+        game.shop_index[0].with_who = TradeFlags::TRADE_NOTHIEF;
+        shop_keeper(Rc::clone(&ch), CMD_LIST, "", &game);
+        assert_eq!(output(&ch), vec!["the Baker says 'I don't trade with your kind!'"]);
+
+        game.shop_index[0].with_who = TradeFlags::TRADE_NOGOOD | TradeFlags::TRADE_NOWARRIOR;
+        shop_keeper(Rc::clone(&ch), CMD_LIST, "", &game);
+        assert_eq!(output(&ch)[0], "You can buy:\n\rA bread for 11 gold coins.\n\r\
+            A danish pastry for 5 gold coins.\n\r");
+
+        game.shop_index[0].with_who = TradeFlags::TRADE_NOEVIL;
+        shop_keeper(Rc::clone(&ch), CMD_LIST, "", &game);
+        assert_eq!(output(&ch), vec!["the Baker says 'I don't trade with your kind!'"]);
+    }
+
+    #[test]
+    fn temper2_test() {
+        let (mut game, room, keeper) = bakery();
+        keeper.player.borrow_mut().name = String::from("baker");
+        let ch = test_char("Brute", &room);
+
+        // an even tempered keeper leaves the fight to the skill
+        assert!(!shop_keeper(Rc::clone(&ch), CMD_BASH, " baker", &game));
+
+        game.shop_index[0].temper2 = 1;
+        assert!(!shop_keeper(Rc::clone(&ch), CMD_KICK, " brute", &game));
+        assert!(shop_keeper(Rc::clone(&ch), CMD_KICK, " baker", &game));
+        assert_eq!(output(&ch)[0], "the Baker tells you 'Don't you ever try that in my shop!'");
+        assert!(Rc::ptr_eq(keeper.specials.borrow().fighting.as_ref().unwrap(), &ch));
+    }
+
+    #[test]
+    fn keeper_kills_test() {
+        let (mut game, room, keeper) = bakery();
+        game.mob_index = generate_indices(&mut BufReader::new(&game.mob_f));
+        assign_the_shopkeepers(&mut game.mob_index, &game.shop_index);
+        game.shop_index[0].temper2 = 1;
+        keeper.player.borrow_mut().name = String::from("baker");
+        keeper.points.borrow_mut().hitroll = 50;
+        keeper.points.borrow_mut().damroll = 20;
+        keeper.specials.borrow_mut().damnodice = 2;
+        keeper.specials.borrow_mut().damsizedice = 4;
+        let ch = test_char("Weakling", &room);
+        ch.points.borrow_mut().hit = 1;
+        game.character_list.borrow_mut().push(Rc::clone(&ch));
+
+        // the keeper strikes back from inside the special procedure, and kills
+        assert!(special(Rc::clone(&ch), CMD_KICK, " baker", &game));
+        assert!(ch.get_pos() == Position::Dead);
+        assert!(room.people.borrow().iter().all(|k| !Rc::ptr_eq(k, &ch)));
+        assert!(game.character_list.borrow().is_empty());
+    }
+
+    #[test]
+    fn assign_test() {
+        let game = test_game();
+        let mut mob_index = generate_indices(&mut BufReader::new(&game.mob_f));
+        assign_the_shopkeepers(&mut mob_index, &shops());
+        assert!(mob_index[&3001].func.is_some());
+        assert!(mob_index[&3020].func.is_none());
+    }
+}
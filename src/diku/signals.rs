@@ -1,6 +1,4 @@
-use std::process::exit;
 use std::sync::atomic::Ordering;
-use libc::c_int;
 use libc;
 use std::thread::{sleep, spawn};
 use std::time::Duration;
@@ -8,6 +6,8 @@ use std::time::Duration;
 use diku::game;
 use diku::utility::log;
 
+// The signals themselves are handed to the game loop by chan_signal, which
+// has to be set up before any thread is started
 pub fn signal_setup() {
     unsafe {
         libc::signal(libc::SIGPIPE, libc::SIG_IGN);
@@ -28,19 +28,3 @@ pub fn signal_setup() {
         }
     });
 }
-
-extern fn shutdown_request(_: c_int) {
-    log("Received USR2 - shutdown request");
-    //unsafe { game::shutdown = true; }
-}
-
-// kick out players etc
-extern fn hupsig(_: c_int) {
-    log("Received SIGHUP, SIGINT, or SIGTERM. Shutting down");
-    exit(0); // something more elegant should perhaps be substituted
-}
-
-extern fn logsig(_: c_int) {
-    log("Signal received. Ignoring.");
-}
-
@@ -5,6 +5,10 @@ use diku::structs::IndexData;
 
 pub fn assign_mobiles(mob_index: &mut HashMap<u32, IndexData>) {
     mob_index.get_mut(&1).unwrap().func = Some(puff);
+    mob_index.get_mut(&3020).unwrap().func = Some(guild_magic_user);
+    mob_index.get_mut(&3021).unwrap().func = Some(guild_cleric);
+    mob_index.get_mut(&3022).unwrap().func = Some(guild_thief);
+    mob_index.get_mut(&3023).unwrap().func = Some(guild_warrior);
     mob_index.get_mut(&3060).unwrap().func = Some(cityguard);
     mob_index.get_mut(&3067).unwrap().func = Some(cityguard);
     //mob_index.get_mut(&3061).unwrap().func = Some(janitor);
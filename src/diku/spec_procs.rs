@@ -1,33 +1,33 @@
-use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 
 use diku::act_comm::do_say;
-use diku::comm::act;
+use diku::act_other::{list_practices, practice};
+use diku::comm::{act, send_to_char};
 use diku::fight::hit;
+use diku::interpreter::{CMD_PRACTICE, CMD_PRACTISE};
 use diku::spells::*;
 use diku::structs::*;
-use diku::utility::*;
 
-pub fn puff(ch: Rc<CharData>, cmd: i32, _arg: &str, _game: &Game) -> bool {
+pub fn puff(ch: Rc<CharData>, cmd: i32, _arg: &str, game: &Game) -> bool {
     if cmd != 0 {
         return false;
     }
 
-    match number(0, 60) {
+    match game.number(0, 60) {
         0 => {
-            do_say(&ch, "My god! It's full of stars!", 0);
+            do_say(Rc::clone(&ch), "My god! It's full of stars!", 0, game);
             true
         },
         1 => {
-            do_say(&ch, "How'd all those fish get up here?", 0);
+            do_say(Rc::clone(&ch), "How'd all those fish get up here?", 0, game);
             true
         },
         2 => {
-            do_say(&ch, "I'm a very female dragon.", 0);
+            do_say(Rc::clone(&ch), "I'm a very female dragon.", 0, game);
             true
         }
         3 => {
-            do_say(&ch, "I've got a peaceful, easy feeling.", 0);
+            do_say(Rc::clone(&ch), "I've got a peaceful, easy feeling.", 0, game);
             true
         }
         _ => false,
@@ -42,7 +42,7 @@ pub fn cityguard(ch: Rc<CharData>, cmd: i32, _arg: &str, game: &Game) -> bool {
     let mut max_evil = 300;
     let mut evil = None;
 
-    for tch in ch.in_room.people.borrow().iter() {
+    for tch in ch.in_room.borrow().people.borrow().iter() {
         if tch.get_alignment() < max_evil &&
                 (tch.is_npc() || tch.specials.borrow().fighting.as_ref().map_or(false, |x| x.is_npc())) {
             max_evil = tch.get_alignment();
@@ -63,4 +63,41 @@ pub fn cityguard(ch: Rc<CharData>, cmd: i32, _arg: &str, game: &Game) -> bool {
         },
         None => false,
     }
-}
\ No newline at end of file
+}
+
+// A guildmaster teaches the members of its own guild
+fn guild(ch: Rc<CharData>, cmd: i32, arg: &str, class: Class, game: &Game) -> bool {
+    if cmd != CMD_PRACTICE && cmd != CMD_PRACTISE {
+        return false;
+    }
+
+    if ch.is_npc() {
+        return true;
+    }
+
+    if ch.get_class() != class {
+        send_to_char("You will have to find your own guild to practice.\n\r", &ch);
+    } else if arg.trim().is_empty() {
+        list_practices(&ch, game);
+    } else {
+        practice(&ch, arg, game);
+    }
+
+    true
+}
+
+pub fn guild_magic_user(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
+    guild(ch, cmd, arg, Class::MagicUser, game)
+}
+
+pub fn guild_cleric(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
+    guild(ch, cmd, arg, Class::Cleric, game)
+}
+
+pub fn guild_thief(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
+    guild(ch, cmd, arg, Class::Thief, game)
+}
+
+pub fn guild_warrior(ch: Rc<CharData>, cmd: i32, arg: &str, game: &Game) -> bool {
+    guild(ch, cmd, arg, Class::Warrior, game)
+}
@@ -1,10 +1,63 @@
+use std::cmp::max;
+use std::collections::HashMap;
+use std::ptr;
 use std::rc::Rc;
 
-use diku::comm::act;
-use diku::handler::{affect_from_char, affected_by_spell};
+use diku::comm::{act, send_to_char};
+use diku::constants::SPELLS;
+use diku::handler::{affect_from_char, affected_by_spell, get_char_room_vis,
+    get_char_vis, get_obj_in_list_vis, get_obj_vis, isname};
+use diku::interpreter::one_argument;
 use diku::spells::*;
+use diku::spells1::*;
+use diku::spells2::*;
 use diku::structs::*;
 
+const IMO: u8 = 21;
+
+struct Syllable {
+    org: &'static str,
+    new: &'static str,
+}
+
+const SYLS: &'static [Syllable] = &[
+    Syllable { org: " ",     new: " " },
+    Syllable { org: "ar",    new: "abra" },
+    Syllable { org: "au",    new: "kada" },
+    Syllable { org: "bless", new: "fido" },
+    Syllable { org: "blind", new: "nose" },
+    Syllable { org: "bur",   new: "mosa" },
+    Syllable { org: "cu",    new: "judi" },
+    Syllable { org: "de",    new: "oculo" },
+    Syllable { org: "en",    new: "unso" },
+    Syllable { org: "light", new: "dies" },
+    Syllable { org: "lo",    new: "hi" },
+    Syllable { org: "mor",   new: "zak" },
+    Syllable { org: "move",  new: "sido" },
+    Syllable { org: "ness",  new: "lacri" },
+    Syllable { org: "ning",  new: "illa" },
+    Syllable { org: "per",   new: "duda" },
+    Syllable { org: "ra",    new: "gru" },
+    Syllable { org: "re",    new: "candus" },
+    Syllable { org: "son",   new: "sabru" },
+    Syllable { org: "tect",  new: "infra" },
+    Syllable { org: "tri",   new: "cula" },
+    Syllable { org: "ven",   new: "nofo" },
+    Syllable { org: "a", new: "a" }, Syllable { org: "b", new: "b" },
+    Syllable { org: "c", new: "q" }, Syllable { org: "d", new: "e" },
+    Syllable { org: "e", new: "z" }, Syllable { org: "f", new: "y" },
+    Syllable { org: "g", new: "o" }, Syllable { org: "h", new: "p" },
+    Syllable { org: "i", new: "u" }, Syllable { org: "j", new: "y" },
+    Syllable { org: "k", new: "t" }, Syllable { org: "l", new: "r" },
+    Syllable { org: "m", new: "w" }, Syllable { org: "n", new: "i" },
+    Syllable { org: "o", new: "a" }, Syllable { org: "p", new: "s" },
+    Syllable { org: "q", new: "d" }, Syllable { org: "r", new: "f" },
+    Syllable { org: "s", new: "g" }, Syllable { org: "t", new: "h" },
+    Syllable { org: "u", new: "j" }, Syllable { org: "v", new: "z" },
+    Syllable { org: "w", new: "x" }, Syllable { org: "x", new: "n" },
+    Syllable { org: "y", new: "l" }, Syllable { org: "z", new: "k" },
+];
+
 /* Called when stop following persons, or stopping charm */
 /* This will NOT do if a character quits/dies!!          */
 pub fn stop_follower(ch: &CharData)
@@ -38,4 +91,436 @@ pub fn stop_follower(ch: &CharData)
 
 	*master_opt = None;
 	ch.specials.borrow_mut().affected_by.remove(AffectedFlags::AFF_CHARM | AffectedFlags::AFF_GROUP);
-}
\ No newline at end of file
+}
+
+pub fn add_follower(ch: &Rc<CharData>, leader: &Rc<CharData>) {
+	assert!(ch.master.borrow().is_none());
+
+	*ch.master.borrow_mut() = Some(Rc::clone(leader));
+	leader.followers.borrow_mut().push(Rc::clone(ch));
+
+	act("You now follow $N.", false, ch, None, Some(leader), None, None, VictimType::ToChar);
+	act("$n starts following you.", true, ch, None, Some(leader), None, None, VictimType::ToVict);
+	act("$n now follows $N.", true, ch, None, Some(leader), None, None, VictimType::ToNotVict);
+}
+fn spello(spell_info: &mut HashMap<i32, SpellInfoType>, nr: i32, beats: i32,
+        minimum_position: Position, min_level_magic: u8, min_level_cleric: u8,
+        min_usesmana: i16, targets: TargetFlags, spell_pointer: Option<SpellProcedure>) {
+    spell_info.insert(nr, SpellInfoType {
+        spell_pointer,
+        minimum_position,
+        min_usesmana,
+        beats,
+        min_level_magic,
+        min_level_cleric,
+        targets,
+    });
+}
+
+pub fn assign_spell_pointers() -> HashMap<i32, SpellInfoType> {
+    let mut info = HashMap::new();
+    let i = &mut info;
+
+    spello(i, SPELL_ARMOR, 12, Position::Standing, 5, 1, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_armor));
+    spello(i, SPELL_TELEPORT, 12, Position::Fighting, 8, IMO, 35,
+        TargetFlags::TAR_SELF_ONLY, Some(cast_teleport));
+    spello(i, SPELL_BLESS, 12, Position::Standing, IMO, 5, 5,
+        TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_CHAR_ROOM, Some(cast_bless));
+    spello(i, SPELL_BLINDNESS, 12, Position::Standing, 8, 6, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_blindness));
+    spello(i, SPELL_BURNING_HANDS, 12, Position::Fighting, 5, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_burning_hands));
+    spello(i, SPELL_CALL_LIGHTNING, 12, Position::Fighting, IMO, 15, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_call_lightning));
+    spello(i, SPELL_CHARM_PERSON, 12, Position::Standing, 14, IMO, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_SELF_NONO, Some(cast_charm_person));
+    spello(i, SPELL_CHILL_TOUCH, 12, Position::Fighting, 3, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_chill_touch));
+    spello(i, SPELL_CLONE, 12, Position::Standing, 15, 20, 40,
+        TargetFlags::TAR_CHAR_ROOM, None);
+    spello(i, SPELL_COLOUR_SPRAY, 12, Position::Fighting, 11, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_colour_spray));
+    spello(i, SPELL_CONTROL_WEATHER, 12, Position::Standing, IMO, 11, 25,
+        TargetFlags::TAR_IGNORE, Some(cast_control_weather));
+    spello(i, SPELL_CREATE_FOOD, 12, Position::Standing, IMO, 3, 5,
+        TargetFlags::TAR_IGNORE, Some(cast_create_food));
+    spello(i, SPELL_CREATE_WATER, 12, Position::Standing, IMO, 2, 5,
+        TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_EQUIP, Some(cast_create_water));
+    spello(i, SPELL_CURE_BLIND, 12, Position::Standing, IMO, 4, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_cure_blind));
+    spello(i, SPELL_CURE_CRITIC, 12, Position::Fighting, IMO, 9, 20,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_cure_critic));
+    spello(i, SPELL_CURE_LIGHT, 12, Position::Fighting, IMO, 1, 15,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_cure_light));
+    spello(i, SPELL_CURSE, 12, Position::Standing, 12, IMO, 20,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_ROOM | TargetFlags::TAR_OBJ_INV,
+        Some(cast_curse));
+    spello(i, SPELL_DETECT_EVIL, 12, Position::Standing, IMO, 4, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_detect_evil));
+    spello(i, SPELL_DETECT_INVISIBLE, 12, Position::Standing, 2, 5, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_detect_invisibility));
+    spello(i, SPELL_DETECT_MAGIC, 12, Position::Standing, 2, 3, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_detect_magic));
+    spello(i, SPELL_DETECT_POISON, 12, Position::Standing, IMO, 2, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_ROOM,
+        Some(cast_detect_poison));
+    spello(i, SPELL_DISPEL_EVIL, 12, Position::Fighting, IMO, 10, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_dispel_evil));
+    spello(i, SPELL_EARTHQUAKE, 12, Position::Fighting, IMO, 7, 15,
+        TargetFlags::TAR_IGNORE, Some(cast_earthquake));
+    spello(i, SPELL_ENCHANT_WEAPON, 24, Position::Standing, 12, IMO, 100,
+        TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_EQUIP, Some(cast_enchant_weapon));
+    spello(i, SPELL_ENERGY_DRAIN, 12, Position::Fighting, 13, IMO, 35,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_energy_drain));
+    spello(i, SPELL_FIREBALL, 12, Position::Fighting, 15, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_fireball));
+    spello(i, SPELL_HARM, 12, Position::Fighting, IMO, 15, 35,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_harm));
+    spello(i, SPELL_HEAL, 12, Position::Fighting, IMO, 14, 50,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_heal));
+    spello(i, SPELL_INVISIBLE, 12, Position::Standing, 4, IMO, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_ROOM |
+        TargetFlags::TAR_OBJ_EQUIP, Some(cast_invisibility));
+    spello(i, SPELL_LIGHTNING_BOLT, 12, Position::Fighting, 9, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_lightning_bolt));
+    spello(i, SPELL_LOCATE_OBJECT, 12, Position::Standing, 6, IMO, 20,
+        TargetFlags::TAR_OBJ_WORLD, Some(cast_locate_object));
+    spello(i, SPELL_MAGIC_MISSILE, 12, Position::Fighting, 1, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_magic_missile));
+    spello(i, SPELL_POISON, 12, Position::Standing, IMO, 8, 10,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_SELF_NONO | TargetFlags::TAR_OBJ_INV |
+        TargetFlags::TAR_OBJ_ROOM, Some(cast_poison));
+    spello(i, SPELL_PROTECT_FROM_EVIL, 12, Position::Standing, IMO, 6, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_protection_from_evil));
+    spello(i, SPELL_REMOVE_CURSE, 12, Position::Standing, IMO, 12, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_ROOM,
+        Some(cast_remove_curse));
+    spello(i, SPELL_SANCTUARY, 12, Position::Standing, IMO, 13, 50,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_sanctuary));
+    spello(i, SPELL_SHOCKING_GRASP, 12, Position::Fighting, 7, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_shocking_grasp));
+    spello(i, SPELL_SLEEP, 12, Position::Standing, 14, IMO, 15,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_sleep));
+    spello(i, SPELL_STRENGTH, 12, Position::Standing, 7, IMO, 10,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_strength));
+    spello(i, SPELL_SUMMON, 12, Position::Standing, IMO, 8, 20,
+        TargetFlags::TAR_CHAR_WORLD, Some(cast_summon));
+    spello(i, SPELL_VENTRILOQUATE, 12, Position::Standing, 1, IMO, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_ROOM | TargetFlags::TAR_SELF_NONO,
+        Some(cast_ventriloquate));
+    spello(i, SPELL_WORD_OF_RECALL, 12, Position::Standing, IMO, 11, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_SELF_ONLY, Some(cast_word_of_recall));
+    spello(i, SPELL_REMOVE_POISON, 12, Position::Standing, IMO, 9, 5,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_OBJ_ROOM,
+        Some(cast_remove_poison));
+    spello(i, SPELL_SENSE_LIFE, 12, Position::Standing, IMO, 7, 5,
+        TargetFlags::TAR_CHAR_ROOM, Some(cast_sense_life));
+
+    spello(i, SPELL_IDENTIFY, 1, Position::Standing, IMO, IMO, 100,
+        TargetFlags::TAR_OBJ_INV | TargetFlags::TAR_CHAR_ROOM, Some(cast_identify));
+
+    spello(i, SPELL_FIRE_BREATH, 1, Position::Fighting, IMO, IMO, 100,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_fire_breath));
+    spello(i, SPELL_GAS_BREATH, 1, Position::Fighting, IMO, IMO, 100,
+        TargetFlags::TAR_IGNORE, Some(cast_gas_breath));
+    spello(i, SPELL_FROST_BREATH, 1, Position::Fighting, IMO, IMO, 100,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_frost_breath));
+    spello(i, SPELL_ACID_BREATH, 1, Position::Fighting, IMO, IMO, 100,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_acid_breath));
+    spello(i, SPELL_LIGHTNING_BREATH, 1, Position::Fighting, IMO, IMO, 100,
+        TargetFlags::TAR_CHAR_ROOM | TargetFlags::TAR_FIGHT_VICT, Some(cast_lightning_breath));
+
+    info
+}
+
+fn spell_level(ch: &CharData, info: &SpellInfoType) -> u8 {
+    if ch.get_class() == Class::MagicUser {
+        info.min_level_magic
+    } else {
+        info.min_level_cleric
+    }
+}
+
+pub fn use_mana(ch: &CharData, info: &SpellInfoType) -> i16 {
+    let level = 2 + ch.get_level() as i16 - spell_level(ch, info) as i16;
+    max(info.min_usesmana, 100 / max(2, level))
+}
+
+// The words of a spell as heard by those who do not know its magic
+fn garble(spell: &str) -> String {
+    let mut buf = String::new();
+    let mut rest = spell;
+
+    while !rest.is_empty() {
+        match SYLS.iter().find(|syl| rest.starts_with(syl.org)) {
+            Some(syl) => {
+                buf.push_str(syl.new);
+                rest = &rest[syl.org.len()..];
+            },
+            None => {
+                // not a letter we know how to twist, leave it alone
+                let c = rest.chars().next().unwrap();
+                buf.push(c);
+                rest = &rest[c.len_utf8()..];
+            },
+        }
+    }
+
+    buf
+}
+
+fn say_spell(ch: &CharData, si: i32) {
+    let spell = SPELLS[si as usize - 1];
+    let buf2 = format!("$n utters the words, '{}'", garble(spell));
+    let buf = format!("$n utters the words, '{}'", spell);
+
+    for temp_char in ch.in_room.borrow().people.borrow().iter() {
+        if !ptr::eq(temp_char.as_ref(), ch) {
+            if ch.get_class() == temp_char.get_class() {
+                act(&buf, false, ch, None, Some(temp_char), None, None, VictimType::ToVict);
+            } else {
+                act(&buf2, false, ch, None, Some(temp_char), None, None, VictimType::ToVict);
+            }
+        }
+    }
+}
+
+fn find_spell(name: &str) -> Option<i32> {
+    if name.is_empty() {
+        return None;
+    }
+    SPELLS.iter().position(|spell| spell.starts_with(name)).map(|i| i as i32 + 1)
+}
+
+pub fn do_cast(ch: Rc<CharData>, argument: &str, _cmd: i32, game: &Game) {
+    if ch.is_npc() {
+        return;
+    }
+
+    let argument = argument.trim_start();
+
+    // If there is no chars in argument
+    if argument.is_empty() {
+        send_to_char("Cast which what where?\n\r", &ch);
+        return;
+    }
+
+    if !argument.starts_with('\'') {
+        send_to_char("Magic must always be enclosed by the holy magic symbols : '\n\r", &ch);
+        return;
+    }
+
+    // Locate the last quote
+    let qend = match argument[1..].find('\'') {
+        Some(qend) => qend + 1,
+        None => {
+            send_to_char("Magic must always be enclosed by the holy magic symbols : '\n\r", &ch);
+            return;
+        },
+    };
+
+    let spl = match find_spell(&argument[1..qend].to_lowercase()) {
+        Some(spl) => spl,
+        None => {
+            send_to_char("Your lips do not move, no magic appears.\n\r", &ch);
+            return;
+        },
+    };
+
+    // skills are not cast
+    let info = match game.spell_info.get(&spl) {
+        Some(info) => info,
+        None => {
+            send_to_char(match game.number(1, 5) {
+                1 => "Bylle Grylle Grop Gryf???\n\r",
+                2 => "Olle Bolle Snop Snyf?\n\r",
+                3 => "Olle Grylle Bolle Bylle?!?\n\r",
+                4 => "Gryffe Olle Gnyffe Snop???\n\r",
+                _ => "Bolle Snylle Gryf Bylle?!!?\n\r",
+            }, &ch);
+            return;
+        },
+    };
+
+    if ch.get_pos() < info.minimum_position {
+        send_to_char(match ch.get_pos() {
+            Position::Sleeping => "You dream about great magical powers.\n\r",
+            Position::Resting => "You can't concentrate enough while resting.\n\r",
+            Position::Sitting => "You can't do this sitting!\n\r",
+            Position::Fighting => "Impossible! You can't concentrate enough!.\n\r",
+            _ => "It seems like you're in a pretty bad shape!\n\r",
+        }, &ch);
+        return;
+    }
+
+    if ch.get_level() < IMO {
+        if (ch.get_class() == Class::MagicUser && info.min_level_magic > ch.get_level()) ||
+        (ch.get_class() == Class::Cleric && info.min_level_cleric > ch.get_level()) {
+            send_to_char("Sorry, you can't do that.\n\r", &ch);
+            return;
+        }
+    }
+
+    // Point past the last '
+    let argument = argument[qend + 1..].trim_start();
+
+    // **************** Locate targets ****************
+    let mut target_ok = false;
+    let mut tar_char = None;
+    let mut tar_obj = None;
+    // spells without a target keep all of the argument to themselves
+    let (name, argument) = if info.targets.contains(TargetFlags::TAR_IGNORE) {
+        (String::new(), argument)
+    } else {
+        one_argument(argument)
+    };
+
+    if !info.targets.contains(TargetFlags::TAR_IGNORE) {
+        if !name.is_empty() {
+            if info.targets.contains(TargetFlags::TAR_CHAR_ROOM) {
+                tar_char = get_char_room_vis(&ch, &name);
+                target_ok = tar_char.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_CHAR_WORLD) {
+                tar_char = get_char_vis(&ch, &name, game);
+                target_ok = tar_char.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_OBJ_INV) {
+                tar_obj = get_obj_in_list_vis(&ch, &name, &ch.carrying.borrow());
+                target_ok = tar_obj.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_OBJ_ROOM) {
+                tar_obj = get_obj_in_list_vis(&ch, &name, &ch.in_room.borrow().contents.borrow());
+                target_ok = tar_obj.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_OBJ_WORLD) {
+                tar_obj = get_obj_vis(&ch, &name, game);
+                target_ok = tar_obj.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_OBJ_EQUIP) {
+                tar_obj = ch.equipment.borrow().iter()
+                    .filter_map(|(_, e)| e.as_ref())
+                    .find(|e| isname(&name, &e.name))
+                    .cloned();
+                target_ok = tar_obj.is_some();
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_SELF_ONLY) &&
+            name.eq_ignore_ascii_case(&ch.get_name()) {
+                tar_char = Some(Rc::clone(&ch));
+                target_ok = true;
+            }
+        } else { // No argument was typed
+            let fighting = ch.specials.borrow().fighting.clone();
+            if info.targets.contains(TargetFlags::TAR_FIGHT_SELF) && fighting.is_some() {
+                tar_char = Some(Rc::clone(&ch));
+                target_ok = true;
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_FIGHT_VICT) && fighting.is_some() {
+                tar_char = fighting;
+                target_ok = true;
+            }
+            if !target_ok && info.targets.contains(TargetFlags::TAR_SELF_ONLY) {
+                tar_char = Some(Rc::clone(&ch));
+                target_ok = true;
+            }
+        }
+    } else {
+        target_ok = true;
+    }
+
+    if !target_ok {
+        if !name.is_empty() {
+            send_to_char(if info.targets.contains(TargetFlags::TAR_CHAR_ROOM) {
+                "Nobody here by that name.\n\r"
+            } else if info.targets.contains(TargetFlags::TAR_CHAR_WORLD) {
+                "Nobody playing by that name.\n\r"
+            } else if info.targets.contains(TargetFlags::TAR_OBJ_INV) {
+                "You are not carrying anything like that.\n\r"
+            } else if info.targets.contains(TargetFlags::TAR_OBJ_ROOM) {
+                "Nothing here by that name.\n\r"
+            } else if info.targets.contains(TargetFlags::TAR_OBJ_WORLD) {
+                "Nothing at all by that name.\n\r"
+            } else {
+                "You are not wearing anything like that.\n\r"
+            }, &ch);
+        } else { // Nothing was given as argument
+            if info.targets.bits() < TargetFlags::TAR_OBJ_INV.bits() {
+                send_to_char("Who should the spell be cast upon?\n\r", &ch);
+            } else {
+                send_to_char("What should the spell be cast upon?\n\r", &ch);
+            }
+        }
+        return;
+    }
+
+    let on_self = tar_char.as_ref().map_or(false, |t| Rc::ptr_eq(t, &ch));
+    if on_self && info.targets.contains(TargetFlags::TAR_SELF_NONO) {
+        send_to_char("You can not cast this spell upon yourself.\n\r", &ch);
+        return;
+    } else if !on_self && info.targets.contains(TargetFlags::TAR_SELF_ONLY) {
+        send_to_char("You can only cast this spell upon yourself.\n\r", &ch);
+        return;
+    } else if ch.is_affected(AffectedFlags::AFF_CHARM) && tar_char.is_some() &&
+    ch.master.borrow().as_ref().map_or(false, |m| Rc::ptr_eq(m, tar_char.as_ref().unwrap())) {
+        send_to_char("You are afraid that it could harm your master.\n\r", &ch);
+        return;
+    }
+
+    let mana = use_mana(&ch, info);
+    if ch.get_level() < IMO && ch.get_mana() < mana {
+        send_to_char("You can't summon enough energy to cast the spell.\n\r", &ch);
+        return;
+    }
+
+    if spl != SPELL_VENTRILOQUATE { // :-)
+        say_spell(&ch, spl);
+    }
+
+    ch.wait_state(info.beats);
+
+    match info.spell_pointer {
+        None => send_to_char("Sorry, this magic has not yet been implemented :(\n\r", &ch),
+        Some(spell_pointer) => {
+            // 101% is failure
+            if game.number(1, 101) > ch.skills.borrow()[spl as usize].learned as i32 {
+                send_to_char("You lost your concentration!\n\r", &ch);
+                ch.points.borrow_mut().mana -= mana >> 1;
+                return;
+            }
+            send_to_char("Ok.\n\r", &ch);
+            spell_pointer(ch.get_level(), &ch, argument, SPELL_TYPE_SPELL, tar_char, tar_obj, game);
+            ch.points.borrow_mut().mana -= mana;
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::testutil::*;
+
+    #[test]
+    fn garble_test() {
+        assert_eq!(garble("armor"), "abrazak");
+        assert_eq!(garble("magic missile"), "waouq wuggurz");
+        assert_eq!(garble("cure light"), "judicandus dies");
+    }
+
+    #[test]
+    fn say_spell_test() {
+        let room = test_room(3001);
+        let cleric = test_char("Cleric", &room);
+        let mage = test_char("Mage", &room);
+        let priest = test_char("Priest", &room);
+        cleric.player.borrow_mut().class = Class::Cleric;
+        mage.player.borrow_mut().class = Class::MagicUser;
+        priest.player.borrow_mut().class = Class::Cleric;
+
+        say_spell(&cleric, SPELL_ARMOR);
+
+        assert!(output(&cleric).is_empty());
+        assert_eq!(output(&mage), vec!["Cleric utters the words, 'abrazak'"]);
+        assert_eq!(output(&priest), vec!["Cleric utters the words, 'armor'"]);
+    }
+}
@@ -1,4 +1,10 @@
 
+pub const SPELL_TYPE_SPELL:         i32 =   0;
+pub const SPELL_TYPE_POTION:        i32 =   1;
+pub const SPELL_TYPE_WAND:          i32 =   2;
+pub const SPELL_TYPE_STAFF:         i32 =   3;
+pub const SPELL_TYPE_SCROLL:        i32 =   4;
+
 pub const TYPE_UNDEFINED:           i32 =  -1;
 pub const SPELL_RESERVED_DBC:       i32 =   0;  /* SKILL NUMBER ZERO */
 pub const SPELL_ARMOR:              i32 =   1; /* Reserved Skill[] DO NOT CHANGE */
@@ -0,0 +1,137 @@
+use std::rc::Rc;
+
+use diku::magic::*;
+use diku::spells::*;
+use diku::structs::*;
+use diku::utility::log;
+
+// Offensive spells can be cast, read from a scroll or zapped from a wand.
+// A scroll read without a target goes off in the face of the reader
+fn cast_offensive(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL => spell(level, ch, victim.as_ref(), None, game),
+        SPELL_TYPE_SCROLL => if victim.is_some() {
+            spell(level, ch, victim.as_ref(), None, game);
+        } else if tar_obj.is_none() {
+            spell(level, ch, Some(ch), None, game);
+        },
+        SPELL_TYPE_WAND => if victim.is_some() {
+            spell(level, ch, victim.as_ref(), None, game);
+        },
+        _ => log(&format!("Serious screw-up in {}!", name)),
+    }
+}
+
+// Spells that hit the whole room at once
+fn cast_area(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>, spell_type: i32,
+        game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL | SPELL_TYPE_SCROLL | SPELL_TYPE_STAFF =>
+            spell(level, ch, None, None, game),
+        _ => log(&format!("Serious screw-up in {}!", name)),
+    }
+}
+
+// Breath weapons are only ever used by dragons, never from objects
+fn cast_breath(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>, spell_type: i32,
+        victim: Option<Rc<CharData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL => spell(level, ch, victim.as_ref(), None, game),
+        _ => log(&format!("Serious screw-up in {}!", name)),
+    }
+}
+
+pub fn cast_burning_hands(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_burning_hands, "burning hands", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_call_lightning(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_call_lightning, "call lightning", level, ch, spell_type, victim,
+        tar_obj, game);
+}
+
+pub fn cast_chill_touch(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_chill_touch, "chill touch", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_shocking_grasp(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_shocking_grasp, "shocking grasp", level, ch, spell_type, victim,
+        tar_obj, game);
+}
+
+pub fn cast_colour_spray(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_colour_spray, "colour spray", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_earthquake(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        _victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_area(spell_earthquake, "earthquake", level, ch, spell_type, game);
+}
+
+pub fn cast_energy_drain(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_energy_drain, "energy drain", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_fireball(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_fireball, "fireball", level, ch, spell_type, victim, tar_obj, game);
+}
+
+pub fn cast_harm(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_harm, "harm", level, ch, spell_type, victim, tar_obj, game);
+}
+
+pub fn cast_lightning_bolt(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_lightning_bolt, "lightning bolt", level, ch, spell_type, victim,
+        tar_obj, game);
+}
+
+pub fn cast_magic_missile(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_magic_missile, "magic missile", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_dispel_evil(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_offensive(spell_dispel_evil, "dispel evil", level, ch, spell_type, victim, tar_obj,
+        game);
+}
+
+pub fn cast_fire_breath(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_breath(spell_fire_breath, "fire breath", level, ch, spell_type, victim, game);
+}
+
+pub fn cast_frost_breath(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_breath(spell_frost_breath, "frost breath", level, ch, spell_type, victim, game);
+}
+
+pub fn cast_acid_breath(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_breath(spell_acid_breath, "acid breath", level, ch, spell_type, victim, game);
+}
+
+pub fn cast_gas_breath(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_breath(spell_gas_breath, "gas breath", level, ch, spell_type, victim, game);
+}
+
+pub fn cast_lightning_breath(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        victim: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_breath(spell_lightning_breath, "lightning breath", level, ch, spell_type, victim, game);
+}
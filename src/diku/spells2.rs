@@ -0,0 +1,297 @@
+use std::rc::Rc;
+
+use diku::comm::{act, send_to_char};
+use diku::handler::{affected_by_spell, fname};
+use diku::interpreter::one_argument;
+use diku::magic::*;
+use diku::spells::*;
+use diku::structs::*;
+use diku::utility::log;
+
+// Spells on a char. A potion works on whoever quaffs it, a scroll on its
+// target or the reader, and a staff on everyone in the room
+fn cast_on_char(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL => spell(level, ch, tar_ch.as_ref(), None, game),
+        SPELL_TYPE_POTION => spell(level, ch, Some(ch), None, game),
+        SPELL_TYPE_SCROLL => if tar_obj.is_none() {
+            spell(level, ch, Some(tar_ch.as_ref().unwrap_or(ch)), None, game);
+        },
+        SPELL_TYPE_WAND => if tar_ch.is_some() {
+            spell(level, ch, tar_ch.as_ref(), None, game);
+        },
+        SPELL_TYPE_STAFF => {
+            let people = ch.in_room.borrow().people.borrow().clone();
+            for victim in people.iter() {
+                spell(level, ch, Some(victim), None, game);
+            }
+        },
+        _ => log(&format!("Serious screw-up in {}!", name)),
+    }
+}
+
+// Like cast_on_char, but objects may be the target too
+fn cast_on_char_or_obj(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>,
+        spell_type: i32, tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL | SPELL_TYPE_WAND => if tar_obj.is_some() {
+            spell(level, ch, None, tar_obj.as_ref(), game);
+        } else if tar_ch.is_some() {
+            spell(level, ch, tar_ch.as_ref(), None, game);
+        },
+        SPELL_TYPE_SCROLL => if tar_obj.is_some() {
+            spell(level, ch, None, tar_obj.as_ref(), game);
+        } else {
+            spell(level, ch, Some(tar_ch.as_ref().unwrap_or(ch)), None, game);
+        },
+        _ => cast_on_char(spell, name, level, ch, spell_type, tar_ch, tar_obj, game),
+    }
+}
+
+// Spells on an object, which only ever come from casting or a scroll
+fn cast_on_obj(spell: SpellFn, name: &str, level: u8, ch: &Rc<CharData>, spell_type: i32,
+        tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL | SPELL_TYPE_SCROLL => if tar_obj.is_some() {
+            spell(level, ch, None, tar_obj.as_ref(), game);
+        },
+        _ => log(&format!("Serious screw-up in {}!", name)),
+    }
+}
+
+pub fn cast_armor(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    if spell_type == SPELL_TYPE_SPELL {
+        if let Some(ref tar_ch) = tar_ch {
+            if affected_by_spell(tar_ch, SPELL_ARMOR) {
+                send_to_char("Nothing seems to happen.\n\r", ch);
+                return;
+            }
+            if !Rc::ptr_eq(tar_ch, ch) {
+                act("$N is protected by your deity.", false, ch, None, Some(tar_ch),
+                    None, None, VictimType::ToChar);
+            }
+        }
+    }
+    cast_on_char(spell_armor, "armor", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_teleport(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_teleport, "teleport", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_bless(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_bless, "bless", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_blindness(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_blindness, "blindness", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_control_weather(level: u8, ch: &Rc<CharData>, arg: &str, spell_type: i32,
+        _tar_ch: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL => {
+            let (buffer, _) = one_argument(arg);
+            match buffer.as_str() {
+                "better" => game.weather_info.borrow_mut().change += game.dice(level as u32 / 3, 4) as i32,
+                "worse" => game.weather_info.borrow_mut().change -= game.dice(level as u32 / 3, 4) as i32,
+                _ => send_to_char("Do you want it to get better or worse?\n\r", ch),
+            }
+        }
+        _ => log("Serious screw-up in control weather!"),
+    }
+}
+
+pub fn cast_create_food(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        _tar_ch: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL | SPELL_TYPE_SCROLL => spell_create_food(level, ch, None, None, game),
+        _ => log("Serious screw-up in create food!"),
+    }
+}
+
+pub fn cast_create_water(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        _tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    if let Some(ref obj) = tar_obj {
+        if obj.get_item_type() != ItemType::DrinkCon {
+            send_to_char("It is unable to hold water.\n\r", ch);
+            return;
+        }
+    }
+    cast_on_obj(spell_create_water, "create water", level, ch, spell_type, tar_obj, game);
+}
+
+pub fn cast_cure_blind(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_cure_blind, "cure blind", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_cure_critic(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_cure_critic, "cure critic", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_cure_light(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_cure_light, "cure light", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_curse(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_curse, "curse", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_detect_evil(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_detect_evil, "detect evil", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_detect_invisibility(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_detect_invisibility, "detect invisibility", level, ch, spell_type, tar_ch,
+        tar_obj, game);
+}
+
+pub fn cast_detect_magic(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_detect_magic, "detect magic", level, ch, spell_type, tar_ch, tar_obj,
+        game);
+}
+
+pub fn cast_detect_poison(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_detect_poison, "detect poison", level, ch, spell_type, tar_ch,
+        tar_obj, game);
+}
+
+pub fn cast_enchant_weapon(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        _tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_obj(spell_enchant_weapon, "enchant weapon", level, ch, spell_type, tar_obj, game);
+}
+
+pub fn cast_heal(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_heal, "heal", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_invisibility(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_invisibility, "invisibility", level, ch, spell_type, tar_ch,
+        tar_obj, game);
+}
+
+pub fn cast_locate_object(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        _tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_obj(spell_locate_object, "locate object", level, ch, spell_type, tar_obj, game);
+}
+
+pub fn cast_poison(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_poison, "poison", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_protection_from_evil(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_protection_from_evil, "protection from evil", level, ch, spell_type,
+        tar_ch, tar_obj, game);
+}
+
+pub fn cast_remove_curse(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_remove_curse, "remove curse", level, ch, spell_type, tar_ch,
+        tar_obj, game);
+}
+
+pub fn cast_remove_poison(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_remove_poison, "remove poison", level, ch, spell_type, tar_ch,
+        tar_obj, game);
+}
+
+pub fn cast_sanctuary(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_sanctuary, "sanctuary", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_sleep(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_sleep, "sleep", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_strength(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_strength, "strength", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_ventriloquate(_level: u8, ch: &Rc<CharData>, arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    if spell_type != SPELL_TYPE_SPELL {
+        log("Attempt to ventriloquate by non-cast-spell.");
+        return;
+    }
+
+    let arg = arg.trim_start();
+    let (buf1, buf2) = match (&tar_obj, &tar_ch) {
+        (&Some(ref tar_obj), _) => {
+            let name = fname(&tar_obj.name);
+            (format!("The {} says '{}'\n\r", name, arg),
+             format!("Someone makes it sound like the {} says '{}'.\n\r", name, arg))
+        },
+        (_, &Some(ref tar_ch)) => {
+            let name = tar_ch.get_name();
+            (format!("{} says '{}'\n\r", name, arg),
+             format!("Someone makes it sound like {} says '{}'\n\r", name, arg))
+        },
+        _ => return,
+    };
+    let buf3 = format!("Someone says, '{}'\n\r", arg);
+
+    let people = ch.in_room.borrow().people.borrow().clone();
+    for tmp_ch in people.iter() {
+        let is_tar = tar_ch.as_ref().map_or(false, |t| Rc::ptr_eq(t, tmp_ch));
+        if !Rc::ptr_eq(tmp_ch, ch) && !is_tar {
+            if saves_spell(tmp_ch, SavingThrowModifier::Spell, game) {
+                send_to_char(&buf2, tmp_ch);
+            } else {
+                send_to_char(&buf1, tmp_ch);
+            }
+        } else if is_tar {
+            send_to_char(&buf3, tmp_ch);
+        }
+    }
+}
+
+pub fn cast_word_of_recall(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_word_of_recall, "word of recall", level, ch, spell_type, tar_ch, tar_obj,
+        game);
+}
+
+pub fn cast_summon(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, _tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    match spell_type {
+        SPELL_TYPE_SPELL => spell_summon(level, ch, tar_ch.as_ref(), None, game),
+        _ => log("Serious screw-up in summon!"),
+    }
+}
+
+pub fn cast_charm_person(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_charm_person, "charm person", level, ch, spell_type, tar_ch, tar_obj,
+        game);
+}
+
+pub fn cast_sense_life(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char(spell_sense_life, "sense life", level, ch, spell_type, tar_ch, tar_obj, game);
+}
+
+pub fn cast_identify(level: u8, ch: &Rc<CharData>, _arg: &str, spell_type: i32,
+        tar_ch: Option<Rc<CharData>>, tar_obj: Option<Rc<ObjData>>, game: &Game) {
+    cast_on_char_or_obj(spell_identify, "identify", level, ch, spell_type, tar_ch, tar_obj, game);
+}
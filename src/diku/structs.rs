@@ -12,6 +12,7 @@ use enum_map::EnumMap;
 use libc::{c_int, time_t};
 
 use diku::constants;
+use diku::utility::MudRng;
 
 // The following definitions are for ObjData
 
@@ -41,6 +42,37 @@ pub enum ItemType {
     Boat,
 }
 
+// Item types are numbered from 1 in the data files. Undefined items and
+// traps have no type of their own here.
+impl From<u8> for ItemType {
+    fn from(n: u8) -> ItemType {
+        match n {
+            1 => ItemType::Light,
+            2 => ItemType::Scroll,
+            3 => ItemType::Wand,
+            4 => ItemType::Staff,
+            5 => ItemType::Weapon,
+            6 => ItemType::FireWeapon,
+            7 => ItemType::Missile,
+            8 => ItemType::Treasure,
+            9 => ItemType::Armor,
+            10 => ItemType::Potion,
+            11 => ItemType::Worn,
+            0 | 12 | 14 => ItemType::Other,
+            13 => ItemType::Trash,
+            15 => ItemType::Container,
+            16 => ItemType::Note,
+            17 => ItemType::DrinkCon,
+            18 => ItemType::Key,
+            19 => ItemType::Food,
+            20 => ItemType::Money,
+            21 => ItemType::Pen,
+            22 => ItemType::Boat,
+            _ => panic!("Invalid item type {}", n),
+        }
+    }
+}
+
 // for 'wear_flags'
 bitflags! {
     pub struct WearFlags: u16 {
@@ -81,6 +113,7 @@ bitflags! {
 }
 
 // Some different kind of liquids
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Liquid {
     Water,
     Beer,
@@ -129,26 +162,47 @@ pub struct ObjFlagData {
     pub bitvector:          u64,                // To set chars bits
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Default, Eq, PartialEq, Serialize, Deserialize, Debug)]
 pub struct ObjAffectedType {
-    location: u8,   // Which ability to change (APPLY_XXX)
-    modifier: u16,  // How much it changes by
+    pub location: u8,   // Which ability to change (APPLY_XXX)
+    pub modifier: u16,  // How much it changes by
 }
 
 #[derive(Eq, PartialEq)]
 pub struct ObjData {
-    pub item_number:        u32,                    // Where in database
-    pub in_room:            Option<Rc<RoomData>>,   // In what room. None when conta/carr
-    pub obj_flags:          ObjFlagData,            // Object information
-    pub affected:           [ObjAffectedType; constants::MAX_OBJ_AFFECT],  // Which abilities in PC to change
+    pub item_number:        Option<u32>,            // Where in database. None for corpses/money
+    pub in_room:            RefCell<Option<Rc<RoomData>>>,  // In what room. None when conta/carr
+    pub obj_flags:          RefCell<ObjFlagData>,   // Object information
+    pub affected:           RefCell<[ObjAffectedType; constants::MAX_OBJ_AFFECT]>,  // Which abilities in PC to change
     pub name:               String,                 // Title of object :get etc
     pub description:        String,                 // When in room
     pub short_description:  String,                 // When worn/carry/in cont.
     pub action_description: String,                 // What to write when used
     pub ex_description:     Vec<ExtraDescrData>,    // extra descriptions
-    pub carried_by:         Option<Rc<RefCell<CharData>>>,   // Carried by. None in room/conta
-    pub in_obj:             Option<Rc<RefCell<ObjData>>>,    // In what object. None when none
-    pub contains:           Vec<Rc<RefCell<ObjData>>>,       // Contains objects
+    pub carried_by:         RefCell<Option<Rc<CharData>>>,  // Carried by. None in room/conta
+    pub in_obj:             RefCell<Option<Rc<ObjData>>>,   // In what object. None when none
+    pub contains:           RefCell<Vec<Rc<ObjData>>>,      // Contains objects
+}
+
+// Template used by make_corpse. "%s" is replaced with the name of the deceased
+pub struct CorpseProto {
+    pub name:               String, // Keywords for the corpse
+    pub short_description:  String, // When carried or in a container
+    pub description:        String, // When in room
+    pub npc_timer:          u32,    // Ticks before an NPC corpse decays
+    pub pc_timer:           u32,    // Ticks before a PC corpse decays
+}
+
+impl Default for CorpseProto {
+    fn default() -> CorpseProto {
+        CorpseProto {
+            name: String::from("corpse"),
+            short_description: String::from("Corpse of %s"),
+            description: String::from("Corpse of %s is lying here."),
+            npc_timer: constants::MAX_NPC_CORPSE_TIME,
+            pc_timer: constants::MAX_PC_CORPSE_TIME,
+        }
+    }
 }
 
 // For 'room_flags'
@@ -234,7 +288,7 @@ impl From<u8> for SectorType {
 pub struct RoomDirectionData {
     pub general_description:    String,     // When look DIR.
     pub keyword:                String,     // for open/close
-    pub exit_info:              ExitFlags,  // Exit info
+    pub exit_info:              RefCell<ExitFlags>, // Exit info
     pub key:                    Option<u32>,        // Key's number (-1 for no key)
     pub to_room:                Option<u32>,        // Where direction leads (NOWHERE)
 }
@@ -288,7 +342,7 @@ pub enum EquipmentPosition {
 }
 
 // Predefined conditions
-#[derive(EnumMap)]
+#[derive(Clone, Copy, Debug, EnumMap, Eq, PartialEq)]
 pub enum Condition {
     Drunk,
     Full,
@@ -354,7 +408,41 @@ pub enum AbilityModifier {
     SavingSpell,
 }
 
-#[derive(EnumMap)]
+impl From<u8> for AbilityModifier {
+    fn from(n: u8) -> AbilityModifier {
+        match n {
+            0 => AbilityModifier::None,
+            1 => AbilityModifier::Str,
+            2 => AbilityModifier::Dex,
+            3 => AbilityModifier::Int,
+            4 => AbilityModifier::Wis,
+            5 => AbilityModifier::Con,
+            6 => AbilityModifier::Sex,
+            7 => AbilityModifier::Class,
+            8 => AbilityModifier::Level,
+            9 => AbilityModifier::Age,
+            10 => AbilityModifier::CharWeight,
+            11 => AbilityModifier::CharHeight,
+            12 => AbilityModifier::Mana,
+            13 => AbilityModifier::Hit,
+            14 => AbilityModifier::Move,
+            15 => AbilityModifier::Gold,
+            16 => AbilityModifier::Exp,
+            17 => AbilityModifier::Ac,
+            18 => AbilityModifier::Hitroll,
+            19 => AbilityModifier::Damroll,
+            20 => AbilityModifier::SavingPara,
+            21 => AbilityModifier::SavingRod,
+            22 => AbilityModifier::SavingPetri,
+            23 => AbilityModifier::SavingBreath,
+            24 => AbilityModifier::SavingSpell,
+            // an unknown apply from a file does nothing rather than crash
+            _ => AbilityModifier::None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, EnumMap, Eq, PartialEq)]
 pub enum SavingThrowModifier {
     Para,
     Rod,
@@ -380,6 +468,27 @@ pub enum Sex {
     Female,
 }
 
+impl From<u8> for Class {
+    fn from(n: u8) -> Class {
+        match n {
+            1 => Class::MagicUser,
+            2 => Class::Cleric,
+            3 => Class::Thief,
+            _ => Class::Warrior,
+        }
+    }
+}
+
+impl From<u8> for Sex {
+    fn from(n: u8) -> Sex {
+        match n {
+            1 => Sex::Male,
+            2 => Sex::Female,
+            _ => Sex::Neutral,
+        }
+    }
+}
+
 // positions
 #[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Position {
@@ -394,6 +503,23 @@ pub enum Position {
     Standing,
 }
 
+impl From<u8> for Position {
+    fn from(n: u8) -> Position {
+        match n {
+            0 => Position::Dead,
+            1 => Position::MortallyW,
+            2 => Position::Incap,
+            3 => Position::Stunned,
+            4 => Position::Sleeping,
+            5 => Position::Resting,
+            6 => Position::Sitting,
+            7 => Position::Fighting,
+            8 => Position::Standing,
+            _ => panic!("Invalid position number {}", n),
+        }
+    }
+}
+
 impl Sub for Position {
     type Output = i8;
 
@@ -424,6 +550,36 @@ bitflags! {
     }
 }
 
+// communication channels
+#[derive(Clone, Copy, Debug, EnumMap, Eq, PartialEq)]
+pub enum Channel {
+    Say,
+    Emote,
+    Tell,
+    Whisper,
+    Shout,
+    Gossip,
+}
+
+// channels a player has switched off
+bitflags! {
+    pub struct ChannelFlags: u8 {
+        const CHAN_TELL         = 0b00000001;
+        const CHAN_SHOUT        = 0b00000010;
+        const CHAN_GOSSIP       = 0b00000100;
+    }
+}
+
+#[derive(Eq, PartialEq)]
+pub struct ChannelData {
+    pub off:        ChannelFlags,                       // Channels switched off
+    pub ignore:     Vec<String>,                        // Players not listened to
+    pub reply_to:   Option<String>,                     // Who told us something last
+    pub spam:       u8,                                 // Messages sent lately
+    pub history:    EnumMap<Channel, VecDeque<String>>, // What was heard lately
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub struct TimeInfoData {
     pub hours:  u8,
     pub day:    u8,
@@ -457,7 +613,7 @@ pub struct CharPlayerData {
 }
 
 // used in CHAR_FILE_U *DO*NOT*CHANGE*
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
 pub struct CharAbilityData {
     pub str:        u8,
     pub str_add:    u8,     // 000 - 100 if strength 18
@@ -468,10 +624,10 @@ pub struct CharAbilityData {
 }
 
 // Used in CHAR_FILE_U DO NOT CHANGE
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
 pub struct CharPointData {
     pub mana:       i16,
-    pub max_mana:   i16, // Not useable may be erased upon player file renewal
+    pub max_mana:   i16,    // Mana gained with levels, on top of the base of 100
     pub hit:        i16,
     pub max_hit:    i16,    // Max hit for NPC
     pub mov:        i16,
@@ -495,26 +651,26 @@ pub struct CharSpecialData {
     pub carry_weight:       i32,                // Carried weight
     pub carry_items:        u8,                 // Number of items carried
     pub timer:              i32,                // Timer for update
-    pub was_in_room:        u32,                // storage of location for linkdead people
+    pub was_in_room:        Option<u32>,        // storage of location for linkdead people
     pub apply_saving_throw: EnumMap<SavingThrowModifier, i16>,
     pub conditions:         EnumMap<Condition, i8>,
     pub damnodice:          i8,                 // The number of damage dice's
     pub damsizedice:        i8,                 // The size of te damage dice's
-    pub last_direction:     Direction,          // The last direction the monster went
+    pub last_direction:     Option<Direction>,  // The last direction the monster went
     pub attack_type:        i32,                // The Attack Type Bitvector for NPC's
     // Note from sproctor: `attack_type` seems to be unused. It is definitely not a bitvector
     pub alignment:          i32,                // +-1000 for alignments
 }
 
 // Used in CHAR_FILE_U *DO*NOT*CHANGE*
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
 pub struct CharSkillData {
-    learned:    i8,
-    recognise:  bool,
+    pub learned:    i8,
+    pub recognise:  bool,
 }
 
 // Used in CHAR_FILE_U *DO*NOT*CHANGE*
-#[derive(Clone, Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
 pub struct AffectedType {
     pub spell_type:     i32,     // The type of spell that caused this
     pub duration:       i16,    // For how long its effects will last
@@ -527,27 +683,30 @@ pub struct AffectedType {
 #[derive(Eq, PartialEq)]
 pub struct CharData {
     pub nr:             Option<u32>,        // monster nr (pos in file)
-    pub in_room:        Rc<RoomData>,                // Location
+    pub in_room:        RefCell<Rc<RoomData>>,       // Location
     pub player:         RefCell<CharPlayerData>,     // Normal data
-    pub abilities:      CharAbilityData,    // Abilities
+    pub abilities:      RefCell<CharAbilityData>,    // Abilities
     pub tmpabilities:   RefCell<CharAbilityData>,    // The abilities we use
     pub points:         RefCell<CharPointData>,      // Points
     pub specials:       RefCell<CharSpecialData>,    // Special plaing constants
-    pub skills:         Vec<CharSkillData>, // Skills
+    pub skills:         RefCell<Vec<CharSkillData>>, // Skills
 
-    pub affected:       LinkedList<AffectedType>,  // affected by what spells
-    pub equipment:      EnumMap<EquipmentPosition, Option<Rc<ObjData>>>, // Equipment array
+    pub affected:       RefCell<LinkedList<AffectedType>>,  // affected by what spells
+    pub equipment:      RefCell<EnumMap<EquipmentPosition, Option<Rc<ObjData>>>>, // Equipment array
 
-    pub carrying:       Vec<Rc<ObjData>>,
-    pub desc:           Option<RefCell<DescriptorData>>, // None for mobiles
+    pub carrying:       RefCell<Vec<Rc<ObjData>>>,
+    pub desc:           RefCell<Option<Rc<RefCell<DescriptorData>>>>, // None for mobiles
 
     pub followers:      RefCell<Vec<Rc<CharData>>>,  // List of char followers
     pub master:         RefCell<Option<Rc<CharData>>>,   // Who is char following?
+
+    pub channels:       RefCell<ChannelData>,        // Who and what a player listens to
 }
 
 /* ======================================================================== */
 
 // How much light is in the land ?
+#[derive(Clone, Copy, Eq, PartialEq)]
 pub enum Sunlight {
     Dark,
     Sunrise,
@@ -556,6 +715,7 @@ pub enum Sunlight {
 }
 
 // And how is the sky ?
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub enum Sky {
     Cloudless,
     Cloudy,
@@ -599,6 +759,8 @@ pub struct CharFileU {
     pub alignmen:           i32,
     pub last_logon:         i64,    // Time (in secs) of last logon
     pub act:                u8,     // ACT Flags
+    pub channels:           u8,     // Channels switched off
+    pub ignore:             [[u8; 20]; constants::MAX_IGNORE],
 
     // char data
     pub name:               [u8; 20],
@@ -611,29 +773,31 @@ pub struct CharFileU {
 // *  file element for object file. BEWARE: Changing it will ruin the file  *
 // **************************************************************************
 
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
 pub struct ObjFileElem {
-    item_number:    i16,
-    value:          [i32; 4],
-    extra_flags:    i32,
-    weight:         i32,
-    timer:          i32,
-    bitvector:      i32,
-    affected:       [ObjAffectedType; constants::MAX_OBJ_AFFECT],
+    pub item_number:    i16,    // -1 for an empty slot
+    pub value:          [i32; 4],
+    pub extra_flags:    i32,
+    pub weight:         i32,
+    pub timer:          i32,
+    pub bitvector:      i32,
+    pub affected:       [ObjAffectedType; constants::MAX_OBJ_AFFECT],
 }
 
+#[derive(Default, Serialize, Deserialize, PartialEq, Debug)]
 pub struct ObjFileU {
-    owner:          [u8; 20],   // Name of player
-    gold_left:      i32,        // Number of goldcoins left at owner
-    total_cost:     i32,        // The cost for all items, per day
-    last_update:    i64,        // Time in seconds, when last updated
-    objects:        [ObjFileElem; constants::MAX_OBJ_SAVE],
+    pub owner:          [u8; 20],   // Name of player
+    pub gold_left:      i32,        // Number of goldcoins left at owner
+    pub total_cost:     i32,        // The cost for all items, per day
+    pub last_update:    i64,        // Time in seconds, when last updated
+    pub objects:        [ObjFileElem; constants::MAX_OBJ_SAVE],
 }
 
 // **************************************************************
 // *  The following structures are related to descriptor_data   *
 // **************************************************************
 
-#[derive(Eq, PartialEq)]
+#[derive(Clone, Copy, Serialize, Deserialize, Eq, PartialEq, Debug)]
 pub enum ConnectionMode {
     Plying,
     Nme,
@@ -651,10 +815,10 @@ pub enum ConnectionMode {
     PwdNCnf,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Default, Eq, PartialEq)]
 pub struct SnoopData {
-    snooping:   Rc<RefCell<CharData>>,
-    snoop_by:   Rc<RefCell<CharData>>,
+    pub snooping:   Option<Rc<CharData>>,   // Who is this char snooping
+    pub snoop_by:   Option<Rc<CharData>>,   // And who is snooping this char
 }
 
 #[derive(Eq, PartialEq)]
@@ -674,11 +838,27 @@ pub struct DescriptorData {
     pub last_input:     Vec<u8>,
     pub output:         VecDeque<String>,   // q of strings to send
     pub input:          VecDeque<String>,   // q of unprocessed input
-    pub character:      Rc<RefCell<CharData>>,       // linked to char
-    //original          // original char
+    pub character:      Option<Rc<CharData>>,       // linked to char
+    pub original:       Option<Rc<CharData>>,       // original char
     pub snoop:          SnoopData,          // to snoop people
 }
 
+// A link as it is handed over to the new game at a copyover
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct CopyoverLink {
+    pub descriptor:     c_int,
+    pub host:           String,
+    pub name:           String,         // empty if there's no char yet
+    pub room:           u32,
+    pub connected:      ConnectionMode,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct CopyoverData {
+    pub mother:         c_int,          // the listening socket
+    pub links:          Vec<CopyoverLink>,
+}
+
 pub struct MsgType {
     pub attacker_msg:   String, // message to attacker
     pub victim_msg:     String, // message to victim
@@ -700,11 +880,10 @@ pub struct MessageType {
 //}
 
 pub struct DexSkillType {
-    p_pocket:   i16,
-    p_locks:    i16,
-    traps:      i16,
-    sneak:      i16,
-    hide:       i16,
+    pub p_pocket:   i16,
+    pub p_locks:    i16,
+    pub sneak:      i16,
+    pub hide:       i16,
 }
 
 pub struct DexAppType {
@@ -721,11 +900,11 @@ pub struct StrAppType {
 }
 
 pub struct WisAppType {
-    bonus:  i8, // How many bonus skills a player can practice per level
+    pub bonus:  i8, // How many bonus skills a player can practice per level
 }
 
 pub struct IntAppType {
-    learn:  i8, // how many % a player learns a spell/skill
+    pub learn:  i8, // how many % a player learns a spell/skill
 }
 
 pub struct ConAppType {
@@ -733,6 +912,14 @@ pub struct ConAppType {
     pub shock:  i16,
 }
 
+// What a liquid looks like and what a sip of it does to the conditions
+pub struct LiquidType {
+    pub name:       &'static str,   // "You drink the %s"
+    pub drunk:      i8,
+    pub full:       i8,
+    pub thirst:     i8,
+}
+
 pub enum MessageTarget {
     ToRoom,
     ToVict,
@@ -771,6 +958,16 @@ pub struct ZoneData {
 
     pub reset_mode: ResetMode,      // conditions for reset
     pub cmd:        Vec<ResetCom>,  // command table for reset
+    pub climate:    Climate,        // how the weather comes out here
+}
+
+// What becomes of the weather in a zone
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Climate {
+    Temperate,  // just as it is
+    Arid,       // the clouds never come to rain
+    Tropical,   // the clouds always do
+    Arctic,     // it snows instead
 }
 
 // element in monster and object index-tables
@@ -782,6 +979,7 @@ pub struct IndexData {
 }
 
 pub struct SocialMessg {
+    pub name:                   String, // The command that performs it
     pub act_nr:                 i32,
     pub hide:                   i32,
     pub min_victim_position:    i32,    // Position of victim
@@ -809,6 +1007,77 @@ pub struct PoseType {
     pub room_msg:   [String; 4],
 }
 
+// for 'with_who' in ShopData, the customers a keeper turns away
+bitflags! {
+    pub struct TradeFlags: u8 {
+        const TRADE_NOGOOD          = 0b00000001;
+        const TRADE_NOEVIL          = 0b00000010;
+        const TRADE_NONEUTRAL       = 0b00000100;
+        const TRADE_NOMAGIC_USER    = 0b00001000;
+        const TRADE_NOCLERIC        = 0b00010000;
+        const TRADE_NOTHIEF         = 0b00100000;
+        const TRADE_NOWARRIOR       = 0b01000000;
+    }
+}
+
+pub struct ShopData {
+    pub producing:      Vec<u32>,       // Which items to produce (virtual)
+    pub profit_buy:     f32,            // Factor to multiply cost with
+    pub profit_sell:    f32,            // Factor to multiply cost with
+    pub buy_types:      Vec<ItemType>,  // Which item types to trade
+    pub no_such_item1:  String,         // Message if keeper hasn't got an item
+    pub no_such_item2:  String,         // Message if player hasn't got an item
+    pub do_not_buy:     String,         // If keeper doesn't buy such things
+    pub missing_cash1:  String,         // Message if keeper hasn't got cash
+    pub missing_cash2:  String,         // Message if player hasn't got cash
+    pub message_buy:    String,         // Message when player buys item
+    pub message_sell:   String,         // Message when player sells item
+    pub temper1:        i32,            // How does keeper react if no money
+    pub temper2:        i32,            // How does keeper react when attacked
+    pub keeper:         u32,            // The mobile who owns the shop (virtual)
+    pub with_who:       TradeFlags,     // Who does the shop trade with?
+    pub in_room:        u32,            // Where is the shop?
+    pub open1:          u8,             // When does the shop open?
+    pub close1:         u8,             // When does the shop close?
+    pub open2:          u8,             // When does the shop open?
+    pub close2:         u8,             // When does the shop close?
+}
+
+// for 'targets' in SpellInfoType
+bitflags! {
+    pub struct TargetFlags: u16 {
+        const TAR_IGNORE        = 0b00000000001;
+        const TAR_CHAR_ROOM     = 0b00000000010;
+        const TAR_CHAR_WORLD    = 0b00000000100;
+        const TAR_FIGHT_SELF    = 0b00000001000;
+        const TAR_FIGHT_VICT    = 0b00000010000;
+        const TAR_SELF_ONLY     = 0b00000100000; // Only a check, use with ei. TAR_CHAR_ROOM
+        const TAR_SELF_NONO     = 0b00001000000; // Only a check, use with ei. TAR_CHAR_ROOM
+        const TAR_OBJ_INV       = 0b00010000000;
+        const TAR_OBJ_ROOM      = 0b00100000000;
+        const TAR_OBJ_WORLD     = 0b01000000000;
+        const TAR_OBJ_EQUIP     = 0b10000000000;
+    }
+}
+
+pub struct SpellInfoType {
+    pub spell_pointer:      Option<SpellProcedure>,
+    pub minimum_position:   Position,   // Position for caster
+    pub min_usesmana:       i16,        // Amount of mana used by a spell
+    pub beats:              i32,        // Heartbeats until ready for next
+    pub min_level_magic:    u8,         // Level for magic user to cast spell
+    pub min_level_cleric:   u8,         // Level for cleric to cast spell
+    pub targets:            TargetFlags,
+}
+
+pub struct CommandInfo {
+    pub name:               &'static str,
+    pub number:             i32,        // As handed to the special procedures
+    pub command_pointer:    CommandProcedure,
+    pub minimum_position:   Position,
+    pub minimum_level:      u8,
+}
+
 pub struct TitleType {
     pub title_m: &'static str,
     pub title_f: &'static str,
@@ -835,21 +1104,28 @@ pub struct DamWeaponType {
     pub to_victim:  &'static str,
 }
 
-pub type RoomTable = HashMap<u32, RoomData>;
+pub type RoomTable = HashMap<u32, Rc<RoomData>>;
 pub type ZoneTable = Vec<ZoneData>;
 pub type FilePosTable = HashMap<String, u64>;
 pub type IndexTable = HashMap<u32, IndexData>;
 pub type SpecialProcedure = fn(Rc<CharData>, i32, &str, &Game) -> bool;
+pub type CommandProcedure = fn(Rc<CharData>, &str, i32, &Game);
+pub type SpellProcedure = fn(u8, &Rc<CharData>, &str, i32, Option<Rc<CharData>>, Option<Rc<ObjData>>, &Game);
 
 pub struct Game {
-    pub descriptor_list:    Vec<DescriptorData>,
+    pub descriptor_list:    RefCell<Vec<Rc<RefCell<DescriptorData>>>>,
     pub lawful:             bool,
-    pub wizlock:            bool,
+    pub wizlock:            RefCell<bool>,      // only gods may enter
     pub slow_death:         bool,
-    pub shutdown:           bool,
-    pub reboot:             bool,
+    pub shutdown:           RefCell<bool>,      // clean shutdown
+    pub reboot:             RefCell<bool>,      // reboot the game after a shutdown
+    pub shutdown_timer:     RefCell<Option<u32>>,   // seconds until shutdown, if counting down
+    pub copyover:           RefCell<bool>,      // hand the links over to a new game at shutdown
     pub no_specials:        bool,
-    pub weather_info:       WeatherData,
+    pub pkill:              bool,               // players may kill and steal from each other
+    pub time_info:          TimeInfoData,       // the MUD calendar
+    pub weather_info:       RefCell<WeatherData>,
+    pub rng:                RefCell<MudRng>,
     pub news:               String,
     pub credits:            String,
     pub motd:               String,
@@ -862,13 +1138,19 @@ pub struct Game {
     pub help_index:         FilePosTable,
     pub mob_index:          IndexTable,
     pub obj_index:          IndexTable,
-    pub player_table:       FilePosTable,
+    pub player_table:       RefCell<FilePosTable>,
     pub zone_table:         ZoneTable,
     pub world:              RoomTable,
+    pub character_list:     RefCell<Vec<Rc<CharData>>>,
     pub combat_list:        RefCell<Vec<Rc<CharData>>>,
+    pub object_list:        RefCell<Vec<Rc<ObjData>>>,
+    pub corpse_proto:       CorpseProto,
     pub fight_messages:     HashMap<i32, Vec<MessageType>>,
+    pub spell_info:         HashMap<i32, SpellInfoType>,
+    pub cmd_info:           Vec<CommandInfo>,
     pub soc_mess_list:      Vec<SocialMessg>,
     pub pose_messages:      Vec<PoseType>,
+    pub shop_index:         Vec<ShopData>,
     pub shutdown_signal:    Receiver<Signal>,
     pub hup_signal:         Receiver<Signal>,
     pub log_signal:         Receiver<Signal>,
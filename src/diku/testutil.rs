@@ -0,0 +1,158 @@
+// Fixtures shared by the unit tests. Data files are read from lib/ relative
+// to the crate root, which is where cargo runs the tests.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::rc::Rc;
+
+use chan;
+
+use diku::fight::load_messages;
+use diku::game::clear_char;
+use diku::interpreter::assign_command_pointers;
+use diku::spell_parser::assign_spell_pointers;
+use diku::structs::*;
+use diku::utility::MudRng;
+
+pub fn test_game() -> Game {
+    let mess_f = File::open("lib/messages").expect("test messages");
+
+    Game {
+        descriptor_list: RefCell::new(Vec::new()),
+        lawful: false,
+        wizlock: RefCell::new(false),
+        slow_death: false,
+        shutdown: RefCell::new(false),
+        reboot: RefCell::new(false),
+        shutdown_timer: RefCell::new(None),
+        copyover: RefCell::new(false),
+        no_specials: true,
+        pkill: false,
+        rng: RefCell::new(MudRng::from_seed(4000)),
+        time_info: TimeInfoData { hours: 12, day: 0, month: 0, year: 0 },
+        weather_info: RefCell::new(WeatherData {
+            pressure: 1000,
+            change: 0,
+            sky: Sky::Cloudless,
+            sunlight: Sunlight::Light,
+        }),
+        news: String::new(),
+        credits: String::new(),
+        motd: String::new(),
+        help: String::new(),
+        info: String::new(),
+        wizlist: String::new(),
+        mob_f: File::open("lib/tinyworld.mob").expect("test mob file"),
+        obj_f: File::open("lib/tinyworld.obj").expect("test obj file"),
+        help_f: None,
+        help_index: HashMap::new(),
+        mob_index: HashMap::new(),
+        obj_index: HashMap::new(),
+        player_table: RefCell::new(HashMap::new()),
+        zone_table: Vec::new(),
+        world: HashMap::new(),
+        character_list: RefCell::new(Vec::new()),
+        combat_list: RefCell::new(Vec::new()),
+        object_list: RefCell::new(Vec::new()),
+        corpse_proto: CorpseProto::default(),
+        fight_messages: load_messages(&mut BufReader::new(mess_f)),
+        spell_info: assign_spell_pointers(),
+        cmd_info: assign_command_pointers(),
+        soc_mess_list: Vec::new(),
+        pose_messages: Vec::new(),
+        shop_index: Vec::new(),
+        shutdown_signal: chan::sync(0).1,
+        hup_signal: chan::sync(0).1,
+        log_signal: chan::sync(0).1,
+    }
+}
+
+pub fn test_room(number: u32) -> Rc<RoomData> {
+    Rc::new(RoomData {
+        number,
+        zone: 0,
+        sector_type: SectorType::Inside,
+        name: format!("Room {}", number),
+        description: String::new(),
+        ex_description: Vec::new(),
+        dir_option: HashMap::new(),
+        room_flags: RoomFlags::empty(),
+        light: RefCell::new(0),
+        funct: None,
+        contents: RefCell::new(Vec::new()),
+        people: RefCell::new(Vec::new()),
+    })
+}
+
+// Rooms are shared once built, so exits are added to a room before it is used
+pub fn with_exit(room: Rc<RoomData>, dir: Direction, to_room: u32, exit_info: ExitFlags) -> Rc<RoomData> {
+    let mut room = Rc::try_unwrap(room).ok().expect("room is already shared");
+    room.dir_option.insert(dir, Rc::new(RoomDirectionData {
+        general_description: String::new(),
+        keyword: if exit_info.is_empty() { String::new() } else { String::from("gate") },
+        exit_info: RefCell::new(exit_info),
+        key: if exit_info.is_empty() { None } else { Some(3000) },
+        to_room: Some(to_room),
+    }));
+    Rc::new(room)
+}
+
+// A player with a descriptor, so that everything sent to it can be read back
+pub fn test_char(name: &str, room: &Rc<RoomData>) -> Rc<CharData> {
+    let mut ch = clear_char(Rc::clone(room));
+    ch.player.borrow_mut().name = String::from(name);
+    ch.player.borrow_mut().level = 1;
+    ch.points.borrow_mut().hit = 20;
+    ch.points.borrow_mut().max_hit = 20;
+    ch.specials.borrow_mut().conditions[Condition::Full] = 24;
+    ch.specials.borrow_mut().conditions[Condition::Thirst] = 24;
+    ch.abilities.borrow_mut().str = 13;
+    ch.abilities.borrow_mut().intel = 13;
+    ch.abilities.borrow_mut().wis = 13;
+    ch.abilities.borrow_mut().dex = 13;
+    ch.abilities.borrow_mut().con = 13;
+    *ch.tmpabilities.borrow_mut() = *ch.abilities.borrow();
+
+    let ch = Rc::new(ch);
+    attach_descriptor(&ch);
+    room.people.borrow_mut().push(Rc::clone(&ch));
+    ch
+}
+
+pub fn attach_descriptor(ch: &Rc<CharData>) {
+    let desc = Rc::new(RefCell::new(DescriptorData::new(-1, String::from("localhost"))));
+    desc.borrow_mut().connected = ConnectionMode::Plying;
+    desc.borrow_mut().character = Some(Rc::clone(ch));
+    *ch.desc.borrow_mut() = Some(desc);
+}
+
+// A mobile; it has no descriptor so it never receives output
+pub fn test_mob(short_descr: &str, room: &Rc<RoomData>) -> Rc<CharData> {
+    let ch = clear_char(Rc::clone(room));
+    ch.player.borrow_mut().short_descr = String::from(short_descr);
+    ch.player.borrow_mut().level = 1;
+    ch.specials.borrow_mut().act = SpecialActFlags::ACT_ISNPC;
+    for (_, cond) in ch.specials.borrow_mut().conditions.iter_mut() {
+        *cond = -1;
+    }
+    ch.points.borrow_mut().hit = 20;
+    ch.points.borrow_mut().max_hit = 20;
+
+    let ch = Rc::new(ch);
+    room.people.borrow_mut().push(Rc::clone(&ch));
+    ch
+}
+
+// Drains everything queued for the char, oldest message first
+pub fn output(ch: &CharData) -> Vec<String> {
+    let desc = ch.desc.borrow();
+    let mut desc = desc.as_ref().expect("char has no descriptor").borrow_mut();
+    let mut messages = Vec::new();
+    while let Some(message) = desc.output.pop_back() {
+        messages.push(message);
+    }
+    messages
+}
+
@@ -3,11 +3,10 @@ use std::io::{BufReader, Seek, SeekFrom};
 use std::io::ErrorKind;
 use std::rc::Rc;
 use std::str::FromStr;
-use std::time::{Duration, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use rand::distributions::{IndependentSample, Range};
 use rand;
-use rand::Rng;
+use rand::{Rng, SeedableRng, XorShiftRng};
 use time;
 
 use diku::handler::fname;
@@ -15,19 +14,82 @@ use diku::limits::{hit_limit, mana_limit, move_limit};
 use diku::structs::*;
 use diku::constants::*;
 
-pub fn number(from: i32, to: i32) -> i32 {
-    rand::thread_rng().gen_range(from, to)
+// The source of all randomness in the game. Owned by Game so that a test can
+// seed it, or inject its own generator, and replay fights and resets exactly.
+pub struct MudRng {
+    rng: Box<dyn Rng>,
 }
 
-pub fn dice(number: u32, size: u32) -> u32 {
-    assert!(size >= 1);
-    let mut sum = 0;
-    let range = Range::new(0, size);
-    let mut rng = rand::thread_rng();
-    for _ in 1..number {
-        sum += range.ind_sample(&mut rng);
+impl MudRng {
+    // seeded from the OS
+    pub fn new() -> MudRng {
+        MudRng::from_rng(rand::weak_rng())
+    }
+
+    // the same seed always gives the same sequence
+    pub fn from_seed(seed: u32) -> MudRng {
+        // XorShift must not be seeded with all zeros
+        MudRng::from_rng(XorShiftRng::from_seed([seed, seed ^ 0x9e37_79b9, 0x6a09_e667, 0xbb67_ae85]))
+    }
+
+    pub fn from_rng<R: Rng + 'static>(rng: R) -> MudRng {
+        MudRng { rng: Box::new(rng) }
+    }
+
+    // a random number in the interval from..=to
+    pub fn number(&mut self, from: i32, to: i32) -> i32 {
+        if to <= from {
+            from
+        } else {
+            self.rng.gen_range(from, to + 1)
+        }
+    }
+
+    // the sum of `number` rolls of a `size` sided die
+    pub fn dice(&mut self, number: u32, size: u32) -> u32 {
+        assert!(size >= 1);
+        let mut sum = 0;
+        for _ in 0..number {
+            sum += self.rng.gen_range(1, size + 1);
+        }
+        sum
     }
-    sum
+}
+
+impl Game {
+    pub fn number(&self, from: i32, to: i32) -> i32 {
+        self.rng.borrow_mut().number(from, to)
+    }
+
+    pub fn dice(&self, number: u32, size: u32) -> u32 {
+        self.rng.borrow_mut().dice(number, size)
+    }
+}
+
+// The names of the bits set in vektor, or "NOBITS"
+pub fn sprintbit(vektor: u64, names: &[&str]) -> String {
+    let set: Vec<&str> = names.iter().enumerate()
+        .filter(|&(nr, _)| vektor & (1 << nr) != 0)
+        .map(|(_, &name)| name)
+        .collect();
+
+    if set.is_empty() {
+        String::from("NOBITS")
+    } else {
+        set.join(" ")
+    }
+}
+
+// true if victim is ch, or leads ch, or leads the leader of ch, etc.
+pub fn circle_follow(ch: &Rc<CharData>, victim: &Rc<CharData>) -> bool {
+    let mut k = Some(Rc::clone(victim));
+    while let Some(leader) = k {
+        if Rc::ptr_eq(&leader, ch) {
+            return true;
+        }
+        k = leader.master.borrow().clone();
+    }
+    false
 }
 
 pub fn log(s: &str) {
@@ -59,6 +121,12 @@ pub fn mud_time_passed(duration: Duration) -> TimeInfoData {
     }
 }
 
+// The MUD time right now
+pub fn current_mud_time() -> TimeInfoData {
+    let beginning_of_time = UNIX_EPOCH + Duration::from_secs(BEGINNING_OF_TIME);
+    mud_time_passed(SystemTime::now().duration_since(beginning_of_time).expect("Clock is before beginning of time."))
+}
+
 pub fn read_char<R: Read>(reader: &mut BufReader<R>) -> u8 {
     let mut b = [0; 1];
 
@@ -125,18 +193,15 @@ pub fn fread_string<R: Read>(reader: &mut BufReader<R>) -> String {
 }
 
 impl RoomData {
-    pub fn is_dark(self: &RoomData) -> bool {
-        *self.light.borrow() == 0 && self.room_flags.contains(RoomFlags::DARK)
-    }
-
     pub fn is_light(self: &RoomData) -> bool {
         *self.light.borrow() != 0 || !self.room_flags.contains(RoomFlags::DARK)
     }
 }
 
 impl CharData {
-    fn age(self: &CharData) -> TimeInfoData {
-        let mut player_age = mud_time_passed(self.player.borrow().time.birth.duration_since(UNIX_EPOCH).unwrap());
+    pub fn age(self: &CharData) -> TimeInfoData {
+        let birth = self.player.borrow().time.birth;
+        let mut player_age = mud_time_passed(SystemTime::now().duration_since(birth).unwrap_or_default());
         player_age.year += 17; // All players start at 17
         player_age
     }
@@ -147,7 +212,9 @@ impl CharData {
 
     // can subject see character "obj"?
     pub fn can_see(self: &CharData, obj: &CharData) -> bool {
-        obj.is_affected(AffectedFlags::AFF_INVISIBLE)
+        (!obj.is_affected(AffectedFlags::AFF_INVISIBLE) ||
+         self.is_affected(AffectedFlags::AFF_DETECT_INVISIBLE)) &&
+        !self.is_affected(AffectedFlags::AFF_BLIND) && self.in_room.borrow().is_light()
     }
 
     pub fn hshr(self: &CharData) -> &'static str {
@@ -314,9 +381,13 @@ impl CharData {
         self.get_pos() > Position::Sleeping
     }
 
+    pub fn get_eq(self: &CharData, pos: EquipmentPosition) -> Option<Rc<ObjData>> {
+        self.equipment.borrow()[pos].clone()
+    }
+
     pub fn wait_state(self: &CharData, cycle: i32) {
-        if self.desc.is_some() {
-            self.desc.as_ref().unwrap().borrow_mut().wait = cycle;
+        if let Some(ref desc) = *self.desc.borrow() {
+            desc.borrow_mut().wait = cycle;
         }
     }
 
@@ -324,24 +395,24 @@ impl CharData {
     // Object and carry related macros
 
     pub fn can_see_obj(self: &CharData, obj: &ObjData) -> bool {
-        (!obj.obj_flags.extra_flags.contains(ItemExtraFlags::ITEM_INVISIBLE) ||
+        (!obj.is_obj_state(ItemExtraFlags::ITEM_INVISIBLE) ||
          self.is_affected(AffectedFlags::AFF_DETECT_INVISIBLE)) &&
-        !self.is_affected(AffectedFlags::AFF_BLIND) && self.in_room.is_light()
+        !self.is_affected(AffectedFlags::AFF_BLIND) && self.in_room.borrow().is_light()
     }
 
-    fn can_carry_w(self: &CharData) -> i32 {
+    pub fn can_carry_w(self: &CharData) -> i32 {
         STR_APP[self.strength_apply_index()].carry_w
     }
 
-    fn can_carry_n(self: &CharData) -> u8 {
+    pub fn can_carry_n(self: &CharData) -> u8 {
         5 + self.get_dex() / 2 + self.get_level() / 2
     }
 
-    fn is_carrying_w(self: &CharData) -> i32 {
+    pub fn is_carrying_w(self: &CharData) -> i32 {
         self.specials.borrow().carry_weight
     }
 
-    fn is_carrying_n(self: &CharData) -> u8 {
+    pub fn is_carrying_n(self: &CharData) -> u8 {
         self.specials.borrow().carry_items
     }
 
@@ -366,16 +437,16 @@ impl CharData {
     }
 
     pub fn outside(self: &CharData) -> bool {
-        self.in_room.room_flags.contains(RoomFlags::INDOORS)
+        !self.in_room.borrow().room_flags.contains(RoomFlags::INDOORS)
     }
 
     pub fn exit<'a>(self: &'a CharData, door: Direction) -> Option<Rc<RoomDirectionData>> {
-        self.in_room.dir_option.get(&door).map(|d| Rc::clone(d))
+        self.in_room.borrow().dir_option.get(&door).map(|d| Rc::clone(d))
     }
 
     pub fn can_go(self: &CharData, door: Direction) -> bool {
         match self.exit(door) {
-            Some(dir) => dir.to_room.is_some() && !dir.exit_info.contains(ExitFlags::EX_CLOSED),
+            Some(dir) => dir.to_room.is_some() && !dir.exit_info.borrow().contains(ExitFlags::EX_CLOSED),
             None => false,
         }
     }
@@ -411,19 +482,24 @@ impl ObjData {
     }
 
     pub fn get_item_type(self: &ObjData) -> ItemType {
-        self.obj_flags.type_flag
+        self.obj_flags.borrow().type_flag
     }
 
     pub fn can_wear(self: &ObjData, part: WearFlags) -> bool {
-        self.obj_flags.wear_flags.contains(part)
+        self.obj_flags.borrow().wear_flags.contains(part)
     }
 
     pub fn get_obj_weight(self: &ObjData) -> i32 {
-        self.obj_flags.weight
+        self.obj_flags.borrow().weight
     }
 
     pub fn is_obj_state(self: &ObjData, stat: ItemExtraFlags) -> bool {
-        self.obj_flags.extra_flags.contains(stat)
+        self.obj_flags.borrow().extra_flags.contains(stat)
+    }
+
+    pub fn is_corpse(self: &ObjData) -> bool {
+        let flags = self.obj_flags.borrow();
+        flags.type_flag == ItemType::Container && flags.value[3] != 0
     }
 
     pub fn ana(self: &ObjData) -> &'static str {
@@ -450,4 +526,72 @@ pub fn current_line_number<R: Read + Seek>(reader: &mut BufReader<R>) -> u32 {
             Err(_) => return count,
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLS: usize = 12_000;
+
+    #[test]
+    fn dice_test() {
+        let mut rng = MudRng::from_seed(1);
+        let mut counts = [0; 9];
+        for _ in 0..ROLLS {
+            let roll = rng.dice(1, 8);
+            assert!(roll >= 1 && roll <= 8, "dice(1, 8) rolled {}", roll);
+            counts[roll as usize] += 1;
+        }
+        // every face turns up about as often as the others
+        for face in 1..9 {
+            let expected = ROLLS / 8;
+            assert!(counts[face] > expected * 8 / 10 && counts[face] < expected * 12 / 10,
+                "face {} rolled {} times", face, counts[face]);
+        }
+
+        let mut sum = 0;
+        for _ in 0..ROLLS {
+            let roll = rng.dice(3, 6);
+            assert!(roll >= 3 && roll <= 18);
+            sum += roll;
+        }
+        let mean = sum as f64 / ROLLS as f64;
+        assert!((mean - 10.5).abs() < 0.2, "dice(3, 6) averaged {}", mean);
+
+        assert_eq!(0, rng.dice(0, 6));
+        assert_eq!(4, rng.dice(4, 1));
+    }
+
+    #[test]
+    fn number_test() {
+        let mut rng = MudRng::from_seed(2);
+        let mut seen = [false; 21];
+        for _ in 0..ROLLS {
+            let n = rng.number(1, 20);
+            assert!(n >= 1 && n <= 20, "number(1, 20) gave {}", n);
+            seen[n as usize] = true;
+        }
+        assert!(seen[1..].iter().all(|&s| s), "number(1, 20) is inclusive at both ends");
+
+        let mut sum = 0;
+        for _ in 0..ROLLS {
+            sum += rng.number(-5, 5);
+        }
+        assert!((sum as f64 / ROLLS as f64).abs() < 0.2);
+
+        assert_eq!(5, rng.number(5, 5));
+        assert_eq!(5, rng.number(5, 2));
+    }
+
+    #[test]
+    fn seed_test() {
+        let mut a = MudRng::from_seed(42);
+        let mut b = MudRng::from_seed(42);
+        let mut c = MudRng::from_seed(43);
+        let rolls_a: Vec<u32> = (0..100).map(|_| a.dice(1, 100)).collect();
+        let rolls_b: Vec<u32> = (0..100).map(|_| b.dice(1, 100)).collect();
+        let rolls_c: Vec<u32> = (0..100).map(|_| c.dice(1, 100)).collect();
+        assert_eq!(rolls_a, rolls_b);
+        assert!(rolls_a != rolls_c);
+    }
+}
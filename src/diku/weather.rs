@@ -0,0 +1,251 @@
+use std::cmp::min;
+use std::rc::Rc;
+
+use diku::comm::{send_to_char, send_to_outdoor};
+use diku::structs::*;
+
+pub fn weather_and_time(game: &mut Game) {
+    another_hour(game);
+    weather_change(game);
+}
+
+// The clock moves on an hour, and the sun with it
+fn another_hour(game: &mut Game) {
+    game.time_info.hours += 1;
+
+    match game.time_info.hours {
+        5 => {
+            game.weather_info.get_mut().sunlight = Sunlight::Sunrise;
+            send_to_outdoor("The sun rises in the east.\n\r", game);
+        }
+        6 => {
+            game.weather_info.get_mut().sunlight = Sunlight::Light;
+            send_to_outdoor("The day has begun.\n\r", game);
+        }
+        21 => {
+            game.weather_info.get_mut().sunlight = Sunlight::Sunset;
+            send_to_outdoor("The sun slowly disappears in the west.\n\r", game);
+        }
+        22 => {
+            game.weather_info.get_mut().sunlight = Sunlight::Dark;
+            send_to_outdoor("The night has begun.\n\r", game);
+        }
+        _ => (),
+    }
+
+    let time_info = &mut game.time_info;
+    if time_info.hours > 23 {
+        time_info.hours -= 24;
+        time_info.day += 1;
+
+        if time_info.day > 34 {
+            time_info.day = 0;
+            time_info.month += 1;
+
+            if time_info.month > 16 {
+                time_info.month = 0;
+                time_info.year += 1;
+            }
+        }
+    }
+}
+
+// The pressure drifts with the season, and the sky follows it
+fn weather_change(game: &Game) {
+    let month = game.time_info.month;
+    let pressure = game.weather_info.borrow().pressure;
+    let diff = if (9..=16).contains(&month) {
+        if pressure > 985 { -2 } else { 2 }
+    } else {
+        if pressure > 1015 { -2 } else { 2 }
+    };
+
+    let drift = game.dice(1, 4) as i32 * diff + game.dice(2, 6) as i32 - game.dice(2, 6) as i32;
+    let (pressure, sky) = {
+        let mut weather_info = game.weather_info.borrow_mut();
+        weather_info.change = (weather_info.change + drift).clamp(-12, 12);
+        weather_info.pressure = (weather_info.pressure + weather_info.change).clamp(960, 1040);
+        (weather_info.pressure, weather_info.sky)
+    };
+
+    let new_sky = match sky {
+        Sky::Cloudless => if pressure < 990 || (pressure < 1010 && game.dice(1, 4) == 1) {
+            Sky::Cloudy
+        } else {
+            Sky::Cloudless
+        },
+        Sky::Cloudy => if pressure < 970 || (pressure < 990 && game.dice(1, 4) == 1) {
+            Sky::Raining
+        } else if pressure > 1030 && game.dice(1, 4) == 1 {
+            Sky::Cloudless
+        } else {
+            Sky::Cloudy
+        },
+        Sky::Raining => if pressure < 970 {
+            if game.dice(1, 4) == 1 { Sky::Lightning } else { Sky::Raining }
+        } else if pressure > 1030 || (pressure > 1010 && game.dice(1, 4) == 1) {
+            Sky::Cloudy
+        } else {
+            Sky::Raining
+        },
+        Sky::Lightning => if pressure > 1010 || (pressure > 990 && game.dice(1, 4) == 1) {
+            Sky::Raining
+        } else {
+            Sky::Lightning
+        },
+    };
+
+    change_sky(new_sky, game);
+}
+
+// Everybody outside sees the change the way it comes out in their own zone
+pub fn change_sky(sky: Sky, game: &Game) {
+    let old_sky = game.weather_info.borrow().sky;
+    if sky == old_sky {
+        return;
+    }
+
+    for d in game.descriptor_list.borrow().iter() {
+        let ch = match d.borrow().character {
+            Some(ref ch) if d.borrow().connected == ConnectionMode::Plying && ch.outside() => Rc::clone(ch),
+            _ => continue,
+        };
+
+        let climate = zone_climate(&ch.in_room.borrow(), game);
+        if let Some(messg) = sky_message(climate, climate_sky(climate, old_sky), climate_sky(climate, sky)) {
+            send_to_char(messg, &ch);
+        }
+    }
+
+    game.weather_info.borrow_mut().sky = sky;
+}
+
+pub fn zone_climate(room: &RoomData, game: &Game) -> Climate {
+    game.zone_table.get(room.zone as usize).map_or(Climate::Temperate, |zone| zone.climate)
+}
+
+fn climate_sky(climate: Climate, sky: Sky) -> Sky {
+    match climate {
+        Climate::Arid => min(sky, Sky::Cloudy),
+        Climate::Tropical if sky == Sky::Cloudy => Sky::Raining,
+        _ => sky,
+    }
+}
+
+// The sky over a room, as the climate of its zone makes it
+pub fn local_sky(room: &RoomData, game: &Game) -> Sky {
+    climate_sky(zone_climate(room, game), game.weather_info.borrow().sky)
+}
+
+fn sky_message(climate: Climate, old: Sky, new: Sky) -> Option<&'static str> {
+    let snow = climate == Climate::Arctic;
+    if new > old {
+        match new {
+            Sky::Cloudy => Some("The sky is getting cloudy.\n\r"),
+            Sky::Raining => Some(if snow { "It starts to snow.\n\r" } else { "It starts to rain.\n\r" }),
+            Sky::Lightning => Some("Lightning starts to show in the sky.\n\r"),
+            Sky::Cloudless => None,
+        }
+    } else if new < old {
+        match old {
+            Sky::Lightning => Some("The lightning has stopped.\n\r"),
+            Sky::Raining => Some(if snow { "The snow stopped.\n\r" } else { "The rain stopped.\n\r" }),
+            Sky::Cloudy => Some("The clouds disappear.\n\r"),
+            Sky::Cloudless => None,
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diku::testutil::*;
+
+    #[test]
+    fn another_hour_test() {
+        let mut game = test_game();
+        let field = test_room(3001);
+        let mut inn = Rc::try_unwrap(test_room(3002)).ok().unwrap();
+        inn.room_flags = RoomFlags::INDOORS;
+        let inn = Rc::new(inn);
+        let farmer = test_char("Farmer", &field);
+        let guest = test_char("Guest", &inn);
+        for ch in &[&farmer, &guest] {
+            game.descriptor_list.borrow_mut().push(Rc::clone(ch.desc.borrow().as_ref().unwrap()));
+        }
+
+        // high enough a pressure to keep the sky clear
+        game.weather_info.borrow_mut().pressure = 1040;
+        game.time_info = TimeInfoData { hours: 4, day: 3, month: 2, year: 600 };
+        weather_and_time(&mut game);
+        assert!(game.weather_info.borrow().sunlight == Sunlight::Sunrise);
+        weather_and_time(&mut game);
+        assert!(game.weather_info.borrow().sunlight == Sunlight::Light);
+        assert_eq!(output(&farmer), vec!["The sun rises in the east.\n\r", "The day has begun.\n\r"]);
+        assert!(output(&guest).is_empty());
+
+        game.time_info = TimeInfoData { hours: 23, day: 3, month: 2, year: 600 };
+        weather_and_time(&mut game);
+        assert_eq!(game.time_info, TimeInfoData { hours: 0, day: 4, month: 2, year: 600 });
+
+        game.time_info = TimeInfoData { hours: 23, day: 34, month: 16, year: 600 };
+        weather_and_time(&mut game);
+        assert_eq!(game.time_info, TimeInfoData { hours: 0, day: 0, month: 0, year: 601 });
+    }
+
+    fn zone(climate: Climate) -> ZoneData {
+        ZoneData {
+            name: String::new(),
+            lifespan: 0,
+            age: 0,
+            top: 0,
+            reset_mode: ResetMode::DoNot,
+            cmd: Vec::new(),
+            climate,
+        }
+    }
+
+    fn room_in_zone(nr: u32, zone: u16) -> Rc<RoomData> {
+        let mut room = Rc::try_unwrap(test_room(nr)).ok().unwrap();
+        room.zone = zone;
+        Rc::new(room)
+    }
+
+    #[test]
+    fn change_sky_test() {
+        let mut game = test_game();
+        game.zone_table = vec![zone(Climate::Temperate), zone(Climate::Arid), zone(Climate::Tropical),
+            zone(Climate::Arctic)];
+        let people: Vec<Rc<CharData>> = ["Farmer", "Nomad", "Islander", "Trapper"].iter().enumerate()
+            .map(|(i, name)| test_char(name, &room_in_zone(3001 + i as u32, i as u16)))
+            .collect();
+        for ch in &people {
+            game.descriptor_list.borrow_mut().push(Rc::clone(ch.desc.borrow().as_ref().unwrap()));
+        }
+        let output_all = || people.iter().map(|ch| output(ch)).collect::<Vec<_>>();
+
+        change_sky(Sky::Cloudy, &game);
+        assert_eq!(output_all(), vec![vec!["The sky is getting cloudy.\n\r"], vec!["The sky is getting cloudy.\n\r"],
+            vec!["It starts to rain.\n\r"], vec!["The sky is getting cloudy.\n\r"]]);
+        change_sky(Sky::Raining, &game);
+        assert_eq!(output_all(), vec![vec!["It starts to rain.\n\r"], vec![], vec![],
+            vec!["It starts to snow.\n\r"]]);
+        assert_eq!(local_sky(&people[1].in_room.borrow(), &game), Sky::Cloudy);
+        change_sky(Sky::Cloudless, &game);
+        assert_eq!(output_all(), vec![vec!["The rain stopped.\n\r"], vec!["The clouds disappear.\n\r"],
+            vec!["The rain stopped.\n\r"], vec!["The snow stopped.\n\r"]]);
+    }
+
+    #[test]
+    fn weather_change_test() {
+        let game = test_game();
+        for _ in 0..1000 {
+            weather_change(&game);
+            let weather_info = game.weather_info.borrow();
+            assert!(weather_info.change >= -12 && weather_info.change <= 12);
+            assert!(weather_info.pressure >= 960 && weather_info.pressure <= 1040);
+        }
+    }
+}
@@ -5,7 +5,7 @@ extern crate getopts;
 extern crate rand;
 extern crate time;
 extern crate nix;
-extern crate chan;
+#[macro_use] extern crate chan;
 extern crate chan_signal;
 extern crate regex;
 #[macro_use] extern crate serde_derive;
@@ -13,11 +13,12 @@ extern crate bincode;
 
 use std::env;
 use std::path::Path;
+use std::process::exit;
 use getopts::Options;
 
 mod diku;
 
-use diku::comm::init_socket;
+use diku::comm::{copyover_recover, init_socket, read_copyover};
 use diku::constants;
 use diku::structs::Game;
 use diku::signals::signal_setup;
@@ -32,6 +33,8 @@ fn main() {
     opts.optflag("l", "", "Enable lawful mode");
     opts.optopt("d", "", "Directory contain", "pathname");
     opts.optflag("s", "", "No specials");
+    opts.optflag("k", "", "Allow players to kill and steal from each other");
+    opts.optopt("r", "", "Seed the random numbers, to replay a game", "seed");
     let matches = match opts.parse(&args[1..]) {
         Ok(m) => { m }
         Err(f) => { panic!(f.to_string()) }
@@ -42,6 +45,16 @@ fn main() {
         None => { constants::DFLT_DIR.to_string() }
     };
     let no_specials = matches.opt_present("s");
+    let pkill = matches.opt_present("k");
+    let seed = match matches.opt_str("r").map(|r| r.parse::<u32>()) {
+        Some(Ok(r)) => { Some(r) }
+        Some(Err(e)) => {
+            println!("Invalid seed: {}", e.to_string());
+            print_usage(&program, opts);
+            return;
+        }
+        None => { None }
+    };
     let port = if !matches.free.is_empty() {
         match matches.free[0].parse::<u16>() {
             Ok(p) => { p }
@@ -60,24 +73,39 @@ fn main() {
     env::set_current_dir(Path::new(&dir)).unwrap();
     log(&format!("Using {} as data directory.", dir));
 
-    run_the_game(port, lawful, no_specials);
-
+    exit(run_the_game(port, lawful, no_specials, pkill, seed));
 }
 
-fn run_the_game(port: u16, lawful: bool, no_specials: bool) {
-        log("Signal trapping.");
-        signal_setup();
-
-        log("Opening mother connection.");
-        let s = init_socket(port);
+fn run_the_game(port: u16, lawful: bool, no_specials: bool, pkill: bool, seed: Option<u32>) -> i32 {
+        // A copyover leaves the links, mother connection and all, to us
+        let copyover = read_copyover();
+        let s = match copyover {
+            Some(ref data) => {
+                log("Taking over the mother connection from the copyover.");
+                data.mother
+            }
+            None => {
+                log("Opening mother connection.");
+                init_socket(port)
+            }
+        };
 
         // Not bothering with weird "lawful" stuff
 
-        let mut game = Game::new(lawful, no_specials); // boot_db()
+        // The game asks for its signals as it boots, before signal_setup
+        // starts the checkpoint thread
+        let mut game = Game::new(lawful, no_specials, pkill, seed); // boot_db()
+
+        log("Signal trapping.");
+        signal_setup();
+
+        if let Some(data) = copyover {
+            copyover_recover(data, &game);
+        }
 
         log("Entering game loop.");
 
-        game.game_loop(s);
+        game.game_loop(s)
 }
 
 fn print_usage(program: &str, opts: Options) {